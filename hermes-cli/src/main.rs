@@ -0,0 +1,303 @@
+//! A command line tool to watch, record and replay the messages exchanged on a hermes MQTT bus and
+//! publish new ones.
+
+mod watch;
+
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use hermes::*;
+use hermes_mqtt::record::{read_recording, replay as replay_recording, Recorder, Timing};
+use hermes_mqtt::topics::{FromPath, HermesTopic, ToPath};
+use hermes_mqtt::{HandlerOptions, MqttHermesProtocolHandler, MqttOptions, Payload, PayloadCodec, TlsOptions};
+use structopt::StructOpt;
+
+/// The MQTT client sends the messages from its own thread, leave it some time to do so before
+/// exiting
+const PUBLISH_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "hermes-cli",
+    about = "Watch, record, replay and publish hermes messages on an MQTT bus"
+)]
+struct Opt {
+    #[structopt(flatten)]
+    connection: ConnectionOpt,
+    #[structopt(subcommand)]
+    command: Command,
+}
+
+/// The configuration of the MQTT client, mirrors the one available to guest languages
+#[derive(Debug, StructOpt)]
+struct ConnectionOpt {
+    /// Address of the MQTT broker in the form `host:port`
+    #[structopt(short = "b", long = "broker", default_value = "localhost:1883")]
+    broker_address: String,
+    /// Username to use on the broker
+    #[structopt(long)]
+    username: Option<String>,
+    /// Password to use on the broker
+    #[structopt(long)]
+    password: Option<String>,
+    /// Hostname to use for the TLS configuration, setting a value enables TLS
+    #[structopt(long)]
+    tls_hostname: Option<String>,
+    /// CA files to use if TLS is enabled
+    #[structopt(long, parse(from_os_str))]
+    tls_ca_file: Vec<PathBuf>,
+    /// CA paths to use if TLS is enabled
+    #[structopt(long, parse(from_os_str))]
+    tls_ca_path: Vec<PathBuf>,
+    /// Client key to use if TLS is enabled, requires a client cert
+    #[structopt(long, parse(from_os_str))]
+    tls_client_key: Option<PathBuf>,
+    /// Client cert to use if TLS is enabled, requires a client key
+    #[structopt(long, parse(from_os_str))]
+    tls_client_cert: Option<PathBuf>,
+    /// Disable the root store if TLS is enabled
+    #[structopt(long)]
+    tls_disable_root_store: bool,
+    /// Prefix of the topics of the assistant, for brokers shared by several assistants
+    #[structopt(long)]
+    topic_prefix: Option<String>,
+    /// Codec of the payloads published: json, msgpack or cbor. The ones received are decoded
+    /// whatever their codec
+    #[structopt(long, default_value = "json")]
+    payload_codec: PayloadCodec,
+}
+
+impl ConnectionOpt {
+    fn mqtt_options(&self) -> MqttOptions {
+        let mut options = MqttOptions::new(hermes_mqtt::get_mqtt_id(), self.broker_address.clone());
+        options.username = self.username.clone();
+        options.password = self.password.clone();
+        if let Some(ref hostname) = self.tls_hostname {
+            let mut tls = TlsOptions::new(hostname.clone());
+            tls.disable_root_store = self.tls_disable_root_store;
+            tls.cafile = self.tls_ca_file.clone();
+            tls.capath = self.tls_ca_path.clone();
+            if let (Some(ref key), Some(ref cert)) = (&self.tls_client_key, &self.tls_client_cert) {
+                tls.client_certs_key = Some((cert.clone(), key.clone()));
+            }
+            options.tls = Some(tls)
+        }
+        options
+    }
+
+    fn handler_options(&self) -> HandlerOptions {
+        HandlerOptions {
+            topic_prefix: self.topic_prefix.clone(),
+            payload_codec: self.payload_codec,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Print the messages exchanged on the bus, decoded into their ontology type
+    Watch(watch::WatchOpt),
+    /// Record the messages exchanged on the bus in a file, one JSON object per line, until
+    /// interrupted
+    Record {
+        /// The file to record to, the messages are printed on the standard output if absent
+        #[structopt(parse(from_os_str))]
+        file: Option<PathBuf>,
+    },
+    /// Replay a recording made with the `record` command
+    Replay {
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+        /// Replay faster (or slower) than recorded, `2` replays the recording twice as fast
+        #[structopt(long)]
+        speed: Option<f64>,
+        /// Publish all the messages without waiting between them
+        #[structopt(long, conflicts_with = "speed")]
+        immediate: bool,
+    },
+    /// Publish a message given as JSON on a hermes topic
+    Publish {
+        /// The topic to publish on, like `hermes/tts/say`
+        topic: String,
+        /// The payload, `-` to read it from the standard input. Topics without payload don't need
+        /// one and the binary ones expect the bytes base64 encoded
+        payload: Option<String>,
+    },
+    /// Ask the text to speech to say something
+    Say {
+        text: String,
+        #[structopt(short = "s", long = "site", default_value = "default")]
+        site_id: String,
+        #[structopt(long)]
+        lang: Option<String>,
+        #[structopt(long = "session")]
+        session_id: Option<String>,
+    },
+    /// Play a wav file
+    Play {
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+        #[structopt(short = "s", long = "site", default_value = "default")]
+        site_id: String,
+    },
+    /// Start a session expecting a response from the user, or a notification
+    StartSession {
+        /// The text to say when starting the session
+        #[structopt(long)]
+        text: Option<String>,
+        #[structopt(short = "s", long = "site")]
+        site_id: Option<String>,
+        /// Restrict the intents of the user response to these ones
+        #[structopt(short = "i", long = "intent")]
+        intent_filter: Vec<String>,
+        #[structopt(long)]
+        custom_data: Option<String>,
+        /// Start a notification, which doesn't expect a response, the text is then mandatory
+        #[structopt(long)]
+        notification: bool,
+    },
+    /// End a session
+    EndSession {
+        session_id: String,
+        /// The text to say before ending the session
+        #[structopt(long)]
+        text: Option<String>,
+    },
+    /// Simulate the detection of the hotword
+    Hotword {
+        #[structopt(short = "s", long = "site", default_value = "default")]
+        site_id: String,
+        #[structopt(long = "model", default_value = "default")]
+        model_id: String,
+    },
+}
+
+fn publish(handler: &MqttHermesProtocolHandler, topic: &str, payload: Option<String>) -> HermesResult<()> {
+    let topic = HermesTopic::from_path(topic)
+        .ok_or_else(|| HermesError::InvalidArgument(format!("{} is not a hermes topic", topic)))?;
+    let json = match payload.as_deref() {
+        Some("-") => {
+            let mut json = String::new();
+            std::io::stdin().read_to_string(&mut json)?;
+            json
+        }
+        Some(json) => json.to_string(),
+        None => String::new(),
+    };
+    let payload = Payload::from_json(&topic, json.trim().as_bytes())
+        .map_err(|e| HermesError::InvalidArgument(format!("Invalid payload for topic {}: {}", topic, e)))?;
+    handler.publish_raw(&topic.as_path(), payload.encode(handler.payload_codec())?)
+}
+
+fn record(handler: &MqttHermesProtocolHandler, file: Option<PathBuf>) -> HermesResult<()> {
+    let _recorder = match file {
+        Some(file) => {
+            let writer =
+                File::create(&file).map_err(|e| HermesError::other(format!("Could not create {:?}: {}", file, e)))?;
+            Recorder::start(handler, writer)?
+        }
+        None => Recorder::start(handler, std::io::stdout())?,
+    };
+    loop {
+        std::thread::park();
+    }
+}
+
+fn replay(handler: &MqttHermesProtocolHandler, file: PathBuf, speed: Option<f64>, immediate: bool) -> HermesResult<()> {
+    let reader = File::open(&file).map_err(|e| HermesError::other(format!("Could not open {:?}: {}", file, e)))?;
+    let messages = read_recording(BufReader::new(reader))?;
+    let timing = match (immediate, speed) {
+        (true, _) => Timing::Immediate,
+        (false, Some(speed)) => Timing::Accelerated(speed),
+        (false, None) => Timing::Original,
+    };
+    replay_recording(handler, &messages, timing)
+}
+
+fn run(opt: Opt) -> HermesResult<()> {
+    let handler = MqttHermesProtocolHandler::new_with_handler_options(
+        opt.connection.mqtt_options(),
+        opt.connection.handler_options(),
+    )?;
+    match opt.command {
+        Command::Watch(watch) => return watch.run(&handler),
+        Command::Record { file } => return record(&handler, file),
+        Command::Replay { file, speed, immediate } => replay(&handler, file, speed, immediate)?,
+        Command::Publish { topic, payload } => publish(&handler, &topic, payload)?,
+        Command::Say {
+            text,
+            site_id,
+            lang,
+            session_id,
+        } => handler.tts().publish_say(SayMessage {
+            text,
+            lang,
+            id: Some(new_request_id()),
+            site_id,
+            session_id,
+        })?,
+        Command::Play { file, site_id } => handler.audio_server().publish_play_bytes(PlayBytesMessage {
+            id: new_request_id(),
+            wav_bytes: std::fs::read(&file)
+                .map_err(|e| HermesError::other(format!("Could not read {:?}: {}", file, e)))?,
+            site_id,
+        })?,
+        Command::StartSession {
+            text,
+            site_id,
+            intent_filter,
+            custom_data,
+            notification,
+        } => {
+            let init = if notification {
+                SessionInit::Notification {
+                    text: text.ok_or_else(|| HermesError::InvalidArgument("A notification needs a text".into()))?,
+                }
+            } else {
+                SessionInit::Action {
+                    text,
+                    intent_filter: if intent_filter.is_empty() {
+                        None
+                    } else {
+                        Some(intent_filter)
+                    },
+                    can_be_enqueued: true,
+                    send_intent_not_recognized: false,
+                }
+            };
+            handler.dialogue().publish_start_session(StartSessionMessage {
+                init,
+                custom_data,
+                site_id,
+            })?
+        }
+        Command::EndSession { session_id, text } => handler
+            .dialogue()
+            .publish_end_session(EndSessionMessage { session_id, text })?,
+        Command::Hotword { site_id, model_id } => handler.hotword_backend().publish_detected(
+            site_id.clone(),
+            HotwordDetectedMessage {
+                site_id,
+                model_id,
+                model_version: None,
+                model_type: Some(HotwordModelType::Universal),
+                current_sensitivity: None,
+                detection_signal_ms: None,
+                end_signal_ms: None,
+            },
+        )?,
+    }
+    std::thread::sleep(PUBLISH_DELAY);
+    Ok(())
+}
+
+fn main() {
+    env_logger::init();
+    if let Err(e) = run(Opt::from_args()) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,180 @@
+use std::io::Write;
+
+use hermes::{HermesError, HermesResult};
+use hermes_mqtt::topics::{AudioServerCommand, FromPath, HermesTopic};
+use hermes_mqtt::{MqttHermesProtocolHandler, Payload};
+use serde_json::{json, Value};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct WatchOpt {
+    /// Print a JSON object per line for each message instead of pretty printing them
+    #[structopt(long)]
+    json: bool,
+    /// Only print the messages of these components (like `asr`, `dialogueManager` or `intent`)
+    #[structopt(short = "c", long = "component")]
+    components: Vec<String>,
+    /// Only print the messages related to these sites
+    #[structopt(short = "s", long = "site")]
+    sites: Vec<String>,
+    /// Only print the messages related to these sessions
+    #[structopt(long = "session")]
+    sessions: Vec<String>,
+    /// Also print the audio frames, they are skipped by default as there are a lot of them
+    #[structopt(long)]
+    audio: bool,
+}
+
+/// The bytes field of the payloads published as binary, along with their size
+fn binary_field(payload: &Payload) -> Option<(&'static str, usize)> {
+    match *payload {
+        Payload::AudioFrame(ref frame) => Some(("wavFrame", frame.wav_frame.len())),
+        Payload::PlayBytes(ref bytes) => Some(("wavBytes", bytes.wav_bytes.len())),
+        Payload::RegisterSound(ref sound) => Some(("wavSound", sound.wav_sound.len())),
+        _ => None,
+    }
+}
+
+/// The JSON representation of a payload, binary fields are replaced by their size
+fn summary(payload: &Payload) -> HermesResult<Value> {
+    let mut value = serde_json::to_value(payload)?;
+    if let Some((field, size)) = binary_field(payload) {
+        value[field] = format!("<{} bytes>", size).into();
+    }
+    Ok(value)
+}
+
+/// The component part of a hermes topic, like `asr` in `hermes/asr/textCaptured`
+fn component(topic: &str) -> Option<&str> {
+    topic.split('/').nth(1)
+}
+
+fn field<'a>(payload: &'a Value, name: &str) -> Option<&'a str> {
+    payload.get(name).and_then(Value::as_str)
+}
+
+impl WatchOpt {
+    fn is_audio_frame(topic: &str) -> bool {
+        matches!(
+            HermesTopic::from_path(topic),
+            Some(HermesTopic::AudioServer(_, AudioServerCommand::AudioFrame))
+        )
+    }
+
+    /// Whether a message passes the filters, `payload` is `None` when it could not be decoded
+    fn accepts(&self, topic: &str, payload: Option<&Value>) -> bool {
+        let accepts = |values: &[String], value: Option<&str>| {
+            values.is_empty() || value.map_or(false, |value| values.iter().any(|it| it.eq_ignore_ascii_case(value)))
+        };
+        (self.audio || !Self::is_audio_frame(topic))
+            && accepts(&self.components, component(topic))
+            && accepts(&self.sites, payload.and_then(|it| field(it, "siteId")))
+            && accepts(&self.sessions, payload.and_then(|it| field(it, "sessionId")))
+    }
+
+    fn format(&self, topic: &str, bytes: &[u8]) -> Option<String> {
+        let time = chrono::Local::now();
+        let value = HermesTopic::from_path(topic)
+            .ok_or_else(|| HermesError::InvalidArgument("Unknown hermes topic".into()))
+            .and_then(|it| Payload::decode(&it, bytes))
+            .and_then(|it| {
+                if self.json {
+                    Ok(serde_json::to_value(&it)?)
+                } else {
+                    summary(&it)
+                }
+            })
+            .map_err(|e| e.to_string());
+        if !self.accepts(topic, value.as_ref().ok()) {
+            return None;
+        }
+
+        let raw = String::from_utf8_lossy(bytes);
+        let line = match (self.json, value) {
+            (true, Ok(payload)) => json!({ "time": time.to_rfc3339(), "topic": topic, "payload": payload }).to_string(),
+            (true, Err(error)) => {
+                json!({ "time": time.to_rfc3339(), "topic": topic, "error": error, "raw": raw }).to_string()
+            }
+            (false, Ok(Value::Null)) => format!("[{}] {}", time.format("%H:%M:%S%.3f"), topic),
+            (false, Ok(payload)) => format!(
+                "[{}] {}\n{}",
+                time.format("%H:%M:%S%.3f"),
+                topic,
+                serde_json::to_string_pretty(&payload).unwrap_or_default()
+            ),
+            (false, Err(error)) => format!(
+                "[{}] {}\ncould not decode payload: {}\n{}",
+                time.format("%H:%M:%S%.3f"),
+                topic,
+                error,
+                raw.chars().take(512).collect::<String>()
+            ),
+        };
+        Some(line)
+    }
+
+    pub fn run(self, handler: &MqttHermesProtocolHandler) -> HermesResult<()> {
+        let _subscription = handler.subscribe_raw("hermes/#", move |topic, bytes| {
+            if let Some(line) = self.format(topic, bytes) {
+                let _ = writeln!(std::io::stdout().lock(), "{}", line);
+            }
+        })?;
+        loop {
+            std::thread::park();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watch(args: &[&str]) -> WatchOpt {
+        WatchOpt::from_iter(std::iter::once("watch").chain(args.iter().cloned()))
+    }
+
+    #[test]
+    fn filters_work() {
+        let say = br#"{"text": "hello", "siteId": "kitchen", "sessionId": "abc"}"#;
+        assert!(watch(&[]).format("hermes/tts/say", say).is_some());
+        assert!(watch(&["-c", "tts", "-c", "asr"])
+            .format("hermes/tts/say", say)
+            .is_some());
+        assert!(watch(&["-c", "asr"]).format("hermes/tts/say", say).is_none());
+        assert!(watch(&["-s", "kitchen"]).format("hermes/tts/say", say).is_some());
+        assert!(watch(&["-s", "bedroom"]).format("hermes/tts/say", say).is_none());
+        assert!(watch(&["--session", "abc"]).format("hermes/tts/say", say).is_some());
+        assert!(watch(&["--session", "abc"])
+            .format("hermes/hotword/toggleOn", b"")
+            .is_none());
+    }
+
+    #[test]
+    fn audio_frames_are_skipped_by_default() {
+        let topic = "hermes/audioServer/kitchen/audioFrame";
+        assert!(watch(&[]).format(topic, &[0, 159, 255]).is_none());
+        assert!(watch(&["--audio", "-s", "kitchen"])
+            .format(topic, &[0, 159, 255])
+            .is_some());
+    }
+
+    #[test]
+    fn binary_payloads_are_summarized() {
+        let line = watch(&[])
+            .format("hermes/audioServer/kitchen/playBytes/abc", &[0, 159, 255])
+            .unwrap();
+        assert!(line.contains(r#""wavBytes": "<3 bytes>""#));
+        let line = watch(&["--json"])
+            .format("hermes/audioServer/kitchen/playBytes/abc", &[0, 159, 255])
+            .unwrap();
+        assert!(line.contains(r#""wavBytes":"AJ//""#));
+    }
+
+    #[test]
+    fn undecodable_payloads_are_printed_raw() {
+        let line = watch(&["--json"]).format("hermes/tts/say", b"not json").unwrap();
+        let value: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["raw"], "not json");
+        assert!(value["error"].is_string());
+    }
+}
@@ -0,0 +1,1105 @@
+//! A reference dialogue manager implemented on top of the hermes facades.
+//!
+//! It drives the voice interactions on each site: a detected hotword (or a `StartSessionMessage`)
+//! starts a session, the ASR captures what the user says and the NLU turns it into an intent that
+//! is published for the lambdas, which can then continue or end the session. Only one session runs
+//! at a time on a site, the others are queued.
+
+mod session;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use hermes::*;
+use log::*;
+
+use crate::session::*;
+
+/// The name under which the dialogue manager advertises its protocol version
+const COMPONENT: &str = "hermes-dialogue";
+
+/// The configuration of a `DialogueManager`
+#[derive(Debug, Clone)]
+pub struct DialogueConfig {
+    /// The site used when a session is started without one
+    pub default_site_id: String,
+    /// How long to wait for a component or a lambda before ending a session with a `Timeout`
+    pub session_timeout: Duration,
+    /// How often the running sessions are checked for timeouts
+    pub timeout_check_interval: Duration,
+    /// How many alternative intents to request from the NLU and forward in the intents, if any
+    pub intent_alternatives: Option<usize>,
+}
+
+impl Default for DialogueConfig {
+    fn default() -> Self {
+        Self {
+            default_site_id: "default".into(),
+            session_timeout: Duration::from_secs(15),
+            timeout_check_interval: Duration::from_millis(100),
+            intent_alternatives: None,
+        }
+    }
+}
+
+/// A dialogue manager, it handles the sessions until it is dropped
+pub struct DialogueManager {
+    _subscriptions: Vec<Subscription>,
+}
+
+impl DialogueManager {
+    pub fn new(handler: &HermesProtocolHandler, config: DialogueConfig) -> HermesResult<Self> {
+        let timeout_check_interval = config.timeout_check_interval;
+        let inner = Arc::new(Inner {
+            config,
+            facades: Facades {
+                dialogue: handler.dialogue_backend(),
+                hotword: handler.hotword(),
+                asr: handler.asr(),
+                nlu: handler.nlu(),
+                tts: handler.tts(),
+                handshake: handler.handshake(),
+                site: handler.site(),
+            },
+            state: Mutex::new(State {
+                enabled: true,
+                sites: HashMap::new(),
+                intents: IntentConfig::default(),
+            }),
+        });
+
+        let facades = &inner.facades;
+        let subscriptions = vec![
+            facades
+                .dialogue
+                .subscribe_start_session(handle(&inner, Inner::on_start_session))?,
+            facades
+                .dialogue
+                .subscribe_continue_session(handle(&inner, Inner::on_continue_session))?,
+            facades
+                .dialogue
+                .subscribe_end_session(handle(&inner, Inner::on_end_session))?,
+            facades
+                .dialogue
+                .subscribe_configure(handle(&inner, Inner::on_configure))?,
+            facades
+                .dialogue
+                .subscribe_toggle_on(handle0(&inner, |_, state| state.toggle(true)))?,
+            facades
+                .dialogue
+                .subscribe_toggle_off(handle0(&inner, |_, state| state.toggle(false)))?,
+            facades
+                .dialogue
+                .subscribe_version_request(handle0(&inner, Inner::on_version_request))?,
+            facades
+                .dialogue
+                .subscribe_session_state_request(handle0(&inner, Inner::on_session_state_request))?,
+            facades
+                .hotword
+                .subscribe_all_detected(handle(&inner, Inner::on_hotword_detected))?,
+            facades
+                .asr
+                .subscribe_text_captured(handle(&inner, Inner::on_text_captured))?,
+            facades
+                .nlu
+                .subscribe_intent_parsed(handle(&inner, Inner::on_intent_parsed))?,
+            facades
+                .nlu
+                .subscribe_intent_not_recognized(handle(&inner, Inner::on_intent_not_recognized))?,
+            facades
+                .nlu
+                .subscribe_slot_parsed(handle(&inner, Inner::on_slot_parsed))?,
+            facades
+                .tts
+                .subscribe_say_finished(handle(&inner, Inner::on_say_finished))?,
+            facades
+                .handshake
+                .subscribe_handshake_request(handle0(&inner, Inner::on_handshake_request))?,
+            facades
+                .handshake
+                .subscribe_handshakes(handle(&inner, Inner::on_handshake))?,
+            facades.site.subscribe_sites(handle(&inner, Inner::on_sites))?,
+        ];
+
+        // advertise our protocol and ask the components already running for theirs
+        facades
+            .handshake
+            .publish_handshake(HandshakeMessage::new(COMPONENT, None))?;
+        facades.handshake.publish_handshake_request()?;
+        facades.site.publish_sites_request()?;
+
+        spawn_timeout_checker(Arc::downgrade(&inner), timeout_check_interval);
+
+        Ok(Self {
+            _subscriptions: subscriptions,
+        })
+    }
+}
+
+struct Facades {
+    dialogue: Box<DialogueBackendFacade>,
+    hotword: Box<HotwordFacade>,
+    asr: Box<AsrFacade>,
+    nlu: Box<NluFacade>,
+    tts: Box<TtsFacade>,
+    handshake: Box<HandshakeFacade>,
+    site: Box<SiteFacade>,
+}
+
+struct State {
+    enabled: bool,
+    sites: HashMap<String, Site>,
+    intents: IntentConfig,
+}
+
+impl State {
+    fn toggle(&mut self, enabled: bool) -> HermesResult<()> {
+        self.enabled = enabled;
+        Ok(())
+    }
+}
+
+struct Inner {
+    config: DialogueConfig,
+    facades: Facades,
+    state: Mutex<State>,
+}
+
+fn handle<T, F>(inner: &Arc<Inner>, handler: F) -> Callback<T>
+where
+    F: Fn(&Inner, &mut State, &T) -> HermesResult<()> + Send + Sync + 'static,
+{
+    let inner = Arc::clone(inner);
+    Callback::new(move |message: &T| inner.with_state(|inner, state| handler(inner, state, message)))
+}
+
+fn handle0<F>(inner: &Arc<Inner>, handler: F) -> Callback0
+where
+    F: Fn(&Inner, &mut State) -> HermesResult<()> + Send + Sync + 'static,
+{
+    let inner = Arc::clone(inner);
+    Callback0::new(move || inner.with_state(|inner, state| handler(inner, state)))
+}
+
+fn spawn_timeout_checker(inner: Weak<Inner>, interval: Duration) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(interval);
+        match inner.upgrade() {
+            Some(inner) => inner.with_state(|inner, state| inner.check_timeouts(state, Instant::now())),
+            None => break,
+        }
+    });
+}
+
+/// The site whose running session matches `predicate`
+fn find_site<P>(sites: &mut HashMap<String, Site>, predicate: P) -> Option<&mut Site>
+where
+    P: Fn(&Session) -> bool,
+{
+    sites
+        .values_mut()
+        .find(|site| site.session.as_ref().map_or(false, &predicate))
+}
+
+fn running(site: &mut Site) -> HermesResult<&mut Session> {
+    site.session
+        .as_mut()
+        .ok_or_else(|| HermesError::other("no session running on this site"))
+}
+
+impl Inner {
+    fn with_state<F>(&self, f: F)
+    where
+        F: FnOnce(&Inner, &mut State) -> HermesResult<()>,
+    {
+        let result = match self.state.lock() {
+            Ok(mut state) => f(self, &mut state),
+            Err(e) => Err(HermesError::from(e)),
+        };
+        if let Err(e) = result {
+            error!("Error in dialogue manager: {}", e)
+        }
+    }
+
+    fn on_start_session(&self, state: &mut State, message: &StartSessionMessage) -> HermesResult<()> {
+        let site_id = message
+            .site_id
+            .clone()
+            .unwrap_or_else(|| self.config.default_site_id.clone());
+        let can_be_enqueued = match message.init {
+            SessionInit::Action { can_be_enqueued, .. } => can_be_enqueued,
+            SessionInit::Notification { .. } => true,
+        };
+        let session = Session::new(site_id, message.custom_data.clone(), message.init.clone());
+        self.enqueue(state, session, can_be_enqueued)
+    }
+
+    fn on_hotword_detected(&self, state: &mut State, message: &HotwordDetectedMessage) -> HermesResult<()> {
+        if !state.enabled {
+            return Ok(());
+        }
+        if let Some(Site { session: Some(_), .. }) = state.sites.get(&message.site_id) {
+            debug!("Ignoring hotword detected on busy site {}", message.site_id);
+            return Ok(());
+        }
+        let init = SessionInit::Action {
+            text: None,
+            intent_filter: None,
+            can_be_enqueued: false,
+            send_intent_not_recognized: false,
+        };
+        self.enqueue(state, Session::new(message.site_id.clone(), None, init), false)
+    }
+
+    fn on_text_captured(&self, state: &mut State, message: &TextCapturedMessage) -> HermesResult<()> {
+        let State {
+            ref mut sites,
+            ref intents,
+            ..
+        } = *state;
+        let site = match find_site(sites, |it| Some(&it.id) == message.session_id.as_ref()) {
+            Some(site) => site,
+            None => return Ok(()),
+        };
+        {
+            let session = running(site)?;
+            if session.step != Step::Listening {
+                return Ok(());
+            }
+            self.facades.asr.publish_stop_listening(session.site_message())?;
+        }
+        if message.text.trim().is_empty() {
+            return self.not_recognized(site, None, 1.);
+        }
+
+        let session = running(site)?;
+        let id = new_request_id();
+        match (&session.slot, &session.intent_filter) {
+            (Some(slot), Some(filter)) if filter.len() == 1 => {
+                self.facades.nlu.publish_partial_query(NluSlotQueryMessage {
+                    input: message.text.clone(),
+                    asr_tokens: message.tokens.clone(),
+                    intent_name: filter[0].clone(),
+                    slot_name: slot.clone(),
+                    id: Some(id.clone()),
+                    session_id: Some(session.id.clone()),
+                })?
+            }
+            _ => self.facades.nlu.publish_query(NluQueryMessage {
+                input: message.text.clone(),
+                asr_tokens: message.tokens.clone(),
+                intent_filter: session.intent_filter.as_ref().map(|filter| {
+                    filter
+                        .iter()
+                        .filter(|it| intents.is_enabled(&session.site_id, it))
+                        .cloned()
+                        .collect()
+                }),
+                id: Some(id.clone()),
+                session_id: Some(session.id.clone()),
+                intent_alternatives: self.config.intent_alternatives,
+            })?,
+        }
+        self.advance(
+            session,
+            Step::Understanding {
+                id,
+                captured: message.clone(),
+            },
+        );
+        Ok(())
+    }
+
+    fn on_intent_parsed(&self, state: &mut State, message: &NluIntentMessage) -> HermesResult<()> {
+        let State {
+            ref mut sites,
+            ref intents,
+            ..
+        } = *state;
+        let site = match find_site(sites, |it| it.is_waiting_for(message.id.as_deref())) {
+            Some(site) => site,
+            None => return Ok(()),
+        };
+        let session = running(site)?;
+        let captured = match session.step {
+            Step::Understanding { ref captured, .. } => captured.clone(),
+            _ => return Ok(()),
+        };
+        let intent_name = &message.intent.intent_name;
+        let filtered_out = session
+            .intent_filter
+            .as_ref()
+            .map_or(false, |filter| !filter.contains(intent_name));
+        if filtered_out || !intents.is_enabled(&session.site_id, intent_name) {
+            debug!("Intent {} is not enabled for session {}", intent_name, session.id);
+            return self.not_recognized(site, Some(message.input.clone()), 1.);
+        }
+        // the alternatives the session could not continue with are not forwarded
+        let alternatives = message.alternatives.as_ref().map(|alternatives| {
+            alternatives
+                .iter()
+                .filter(|alternative| {
+                    alternative.intent_name.as_ref().map_or(true, |name| {
+                        intents.is_enabled(&session.site_id, name)
+                            && session
+                                .intent_filter
+                                .as_ref()
+                                .map_or(true, |filter| filter.contains(name))
+                    })
+                })
+                .cloned()
+                .collect()
+        });
+        self.publish_intent(
+            site,
+            captured,
+            message.intent.clone(),
+            message.slots.clone(),
+            alternatives,
+        )
+    }
+
+    fn on_slot_parsed(&self, state: &mut State, message: &NluSlotMessage) -> HermesResult<()> {
+        let site = match find_site(&mut state.sites, |it| it.is_waiting_for(message.id.as_deref())) {
+            Some(site) => site,
+            None => return Ok(()),
+        };
+        let captured = match running(site)?.step {
+            Step::Understanding { ref captured, .. } => captured.clone(),
+            _ => return Ok(()),
+        };
+        let intent = NluIntentClassifierResult {
+            intent_name: message.intent_name.clone(),
+            confidence_score: 1.,
+        };
+        self.publish_intent(site, captured, intent, message.slot.clone().into_iter().collect(), None)
+    }
+
+    fn on_intent_not_recognized(&self, state: &mut State, message: &NluIntentNotRecognizedMessage) -> HermesResult<()> {
+        match find_site(&mut state.sites, |it| it.is_waiting_for(message.id.as_deref())) {
+            Some(site) => self.not_recognized(site, Some(message.input.clone()), message.confidence_score),
+            None => Ok(()),
+        }
+    }
+
+    fn on_say_finished(&self, state: &mut State, message: &SayFinishedMessage) -> HermesResult<()> {
+        let site = match find_site(&mut state.sites, |it| it.is_waiting_for(message.id.as_deref())) {
+            Some(site) => site,
+            None => return Ok(()),
+        };
+        match running(site)?.step {
+            Step::Saying { ref then, .. } => {
+                let then = then.clone();
+                self.after_say(site, then)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn on_continue_session(&self, state: &mut State, message: &ContinueSessionMessage) -> HermesResult<()> {
+        let site = match find_site(&mut state.sites, |it| it.id == message.session_id) {
+            Some(site) => site,
+            None => {
+                warn!("Received a continue session for unknown session {}", message.session_id);
+                return Ok(());
+            }
+        };
+        let session = running(site)?;
+        if session.step != Step::WaitingForAction {
+            warn!("Received a continue session for busy session {}", message.session_id);
+            return Ok(());
+        }
+        if message.custom_data.is_some() {
+            session.custom_data = message.custom_data.clone();
+        }
+        session.intent_filter = message.intent_filter.clone();
+        session.send_intent_not_recognized = message.send_intent_not_recognized;
+        session.slot = message.slot.clone();
+        self.say(site, message.text.clone(), AfterSay::Listen)
+    }
+
+    fn on_end_session(&self, state: &mut State, message: &EndSessionMessage) -> HermesResult<()> {
+        for site in state.sites.values_mut() {
+            if let Some(position) = site.queue.iter().position(|it| it.id == message.session_id) {
+                let session = site.queue.remove(position).unwrap();
+                return self.session_ended(&session, SessionTerminationType::Nominal);
+            }
+        }
+        let site = match find_site(&mut state.sites, |it| it.id == message.session_id) {
+            Some(site) => site,
+            None => {
+                warn!("Received an end session for unknown session {}", message.session_id);
+                return Ok(());
+            }
+        };
+        match message.text {
+            Some(ref text) => self.say(site, text.clone(), AfterSay::End(SessionTerminationType::Nominal)),
+            None => self.end(site, SessionTerminationType::Nominal),
+        }
+    }
+
+    fn on_configure(&self, state: &mut State, message: &DialogueConfigureMessage) -> HermesResult<()> {
+        state.intents.configure(message);
+        Ok(())
+    }
+
+    fn on_version_request(&self, _state: &mut State) -> HermesResult<()> {
+        self.facades.dialogue.publish_version(VersionMessage {
+            version: semver::Version::parse(env!("CARGO_PKG_VERSION")).map_err(HermesError::other)?,
+        })
+    }
+
+    fn on_session_state_request(&self, state: &mut State) -> HermesResult<()> {
+        let mut sites: Vec<SiteSessionState> = state
+            .sites
+            .iter()
+            .filter(|(_, site)| site.session.is_some() || !site.queue.is_empty())
+            .map(|(site_id, site)| SiteSessionState {
+                site_id: site_id.clone(),
+                active_session: site.session.as_ref().map(Session::state),
+                queued_sessions: site.queue.iter().map(Session::state).collect(),
+            })
+            .collect();
+        sites.sort_by(|a, b| a.site_id.cmp(&b.site_id));
+        self.facades
+            .dialogue
+            .publish_session_state(SessionStateMessage { sites })
+    }
+
+    fn on_handshake_request(&self, _state: &mut State) -> HermesResult<()> {
+        self.facades
+            .handshake
+            .publish_handshake(HandshakeMessage::new(COMPONENT, None))
+    }
+
+    /// Warn about the components whose messages may not be fully understood by this side, or the
+    /// other way around
+    fn on_handshake(&self, _state: &mut State, message: &HandshakeMessage) -> HermesResult<()> {
+        if let Some(warning) = CompatibilityChecker::default().warning(message) {
+            warn!("{}", warning);
+        }
+        Ok(())
+    }
+
+    /// Keep the information of the registered sites, to speak their language
+    fn on_sites(&self, state: &mut State, message: &SitesMessage) -> HermesResult<()> {
+        for site in state.sites.values_mut() {
+            site.info = None;
+        }
+        for info in &message.sites {
+            state.sites.entry(info.site_id.clone()).or_default().info = Some(info.clone());
+        }
+        Ok(())
+    }
+
+    fn check_timeouts(&self, state: &mut State, now: Instant) -> HermesResult<()> {
+        for site in state.sites.values_mut() {
+            let expired = site
+                .session
+                .as_ref()
+                .and_then(|it| it.deadline)
+                .map_or(false, |deadline| deadline <= now);
+            if expired {
+                // the timeouts of the other sites are still to be handled
+                if let Err(e) = self.end(site, SessionTerminationType::Timeout) {
+                    error!("Could not end the session that timed out: {}", e)
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Start the session right away if its site is free, queue it otherwise
+    fn enqueue(&self, state: &mut State, session: Session, can_be_enqueued: bool) -> HermesResult<()> {
+        if !state.enabled {
+            return self.session_ended(&session, SessionTerminationType::SiteUnavailable);
+        }
+        let site = state.sites.entry(session.site_id.clone()).or_default();
+        if site.session.is_none() {
+            self.start(site, session)
+        } else if can_be_enqueued {
+            self.facades.dialogue.publish_session_queued(SessionQueuedMessage {
+                session_id: session.id.clone(),
+                custom_data: session.custom_data.clone(),
+                site_id: session.site_id.clone(),
+            })?;
+            site.queue.push_back(session);
+            Ok(())
+        } else {
+            self.session_ended(&session, SessionTerminationType::SiteUnavailable)
+        }
+    }
+
+    fn start(&self, site: &mut Site, mut session: Session) -> HermesResult<()> {
+        let init = match std::mem::replace(&mut session.step, Step::WaitingForAction) {
+            Step::Queued(init) => init,
+            _ => {
+                let reason = format!("session {} was already started", session.id);
+                return Err(HermesError::other(reason));
+            }
+        };
+        self.facades.dialogue.publish_session_started(SessionStartedMessage {
+            session_id: session.id.clone(),
+            custom_data: session.custom_data.clone(),
+            site_id: session.site_id.clone(),
+            reactivated_from_session_id: None,
+        })?;
+        self.facades.hotword.publish_toggle_off(session.site_message())?;
+        session.start_time = Some(Utc::now());
+        site.session = Some(session);
+        match init {
+            SessionInit::Action {
+                text,
+                intent_filter,
+                send_intent_not_recognized,
+                ..
+            } => {
+                let session = running(site)?;
+                session.intent_filter = intent_filter;
+                session.send_intent_not_recognized = send_intent_not_recognized;
+                match text {
+                    Some(text) => self.say(site, text, AfterSay::Listen),
+                    None => self.listen(site),
+                }
+            }
+            SessionInit::Notification { text } => self.say(site, text, AfterSay::End(SessionTerminationType::Nominal)),
+        }
+    }
+
+    fn say(&self, site: &mut Site, text: String, then: AfterSay) -> HermesResult<()> {
+        if text.is_empty() {
+            return self.after_say(site, then);
+        }
+        let lang = site.info.as_ref().and_then(|it| it.language.clone());
+        let session = running(site)?;
+        let id = new_request_id();
+        self.facades.tts.publish_say(SayMessage {
+            text,
+            lang,
+            id: Some(id.clone()),
+            site_id: session.site_id.clone(),
+            session_id: Some(session.id.clone()),
+        })?;
+        self.advance(session, Step::Saying { id, then });
+        Ok(())
+    }
+
+    fn after_say(&self, site: &mut Site, then: AfterSay) -> HermesResult<()> {
+        match then {
+            AfterSay::Listen => self.listen(site),
+            AfterSay::End(termination) => self.end(site, termination),
+        }
+    }
+
+    fn listen(&self, site: &mut Site) -> HermesResult<()> {
+        let session = running(site)?;
+        self.facades.asr.publish_start_listening(AsrStartListeningMessage {
+            site_id: session.site_id.clone(),
+            session_id: Some(session.id.clone()),
+            start_signal_ms: None,
+        })?;
+        self.advance(session, Step::Listening);
+        Ok(())
+    }
+
+    fn publish_intent(
+        &self,
+        site: &mut Site,
+        captured: TextCapturedMessage,
+        intent: NluIntentClassifierResult,
+        slots: Vec<NluSlot>,
+        alternatives: Option<Vec<NluIntentAlternative>>,
+    ) -> HermesResult<()> {
+        let session = running(site)?;
+        self.facades.dialogue.publish_intent(IntentMessage {
+            session_id: session.id.clone(),
+            custom_data: session.custom_data.clone(),
+            site_id: session.site_id.clone(),
+            input: captured.text,
+            asr_tokens: captured.tokens.map(|it| vec![it]),
+            asr_confidence: Some(captured.likelihood),
+            intent,
+            slots,
+            alternatives,
+        })?;
+        self.advance(session, Step::WaitingForAction);
+        Ok(())
+    }
+
+    fn not_recognized(&self, site: &mut Site, input: Option<String>, confidence_score: f32) -> HermesResult<()> {
+        let session = running(site)?;
+        if !session.send_intent_not_recognized {
+            return self.end(site, SessionTerminationType::IntentNotRecognized);
+        }
+        self.facades
+            .dialogue
+            .publish_intent_not_recognized(IntentNotRecognizedMessage {
+                session_id: session.id.clone(),
+                custom_data: session.custom_data.clone(),
+                site_id: session.site_id.clone(),
+                input,
+                confidence_score,
+            })?;
+        self.advance(session, Step::WaitingForAction);
+        Ok(())
+    }
+
+    /// End the running session of the site and start the next queued one, if any
+    fn end(&self, site: &mut Site, termination: SessionTerminationType) -> HermesResult<()> {
+        let session = match site.session.take() {
+            Some(session) => session,
+            None => return Ok(()),
+        };
+        if session.step == Step::Listening {
+            self.facades.asr.publish_stop_listening(session.site_message())?;
+        }
+        self.session_ended(&session, termination)?;
+        self.facades.hotword.publish_toggle_on(session.site_message())?;
+        match site.queue.pop_front() {
+            Some(next) => self.start(site, next),
+            None => Ok(()),
+        }
+    }
+
+    fn session_ended(&self, session: &Session, termination: SessionTerminationType) -> HermesResult<()> {
+        self.facades.dialogue.publish_session_ended(SessionEndedMessage {
+            session_id: session.id.clone(),
+            custom_data: session.custom_data.clone(),
+            termination,
+            site_id: session.site_id.clone(),
+        })
+    }
+
+    fn advance(&self, session: &mut Session, step: Step) {
+        session.step = step;
+        session.deadline = Some(Instant::now() + self.config.session_timeout);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::mpsc;
+
+    use hermes_inprocess::InProcessHermesProtocolHandler;
+    use hermes_test_suite::mocks::{MockComponents, NluRule};
+
+    struct Receiver<T> {
+        rx: mpsc::Receiver<T>,
+        _subscription: Subscription,
+    }
+
+    impl<T> Receiver<T> {
+        fn next(&self) -> T {
+            self.rx
+                .recv_timeout(Duration::from_secs(1))
+                .expect("didn't receive message after one second")
+        }
+    }
+
+    fn receiver<T, F>(subscribe: F) -> Receiver<T>
+    where
+        T: Clone + Send + 'static,
+        F: FnOnce(Callback<T>) -> HermesResult<Subscription>,
+    {
+        let (tx, rx) = mpsc::channel();
+        let tx = Mutex::new(tx);
+        let subscription = subscribe(Callback::new(move |message: &T| {
+            let _ = tx.lock().map(|it| it.send(message.clone()));
+        }))
+        .unwrap();
+        Receiver {
+            rx,
+            _subscription: subscription,
+        }
+    }
+
+    fn create_dialogue(
+        utterances: Vec<&'static str>,
+    ) -> (InProcessHermesProtocolHandler, DialogueManager, MockComponents) {
+        create_dialogue_with_config(DialogueConfig::default(), utterances)
+    }
+
+    fn create_dialogue_with_config(
+        config: DialogueConfig,
+        utterances: Vec<&'static str>,
+    ) -> (InProcessHermesProtocolHandler, DialogueManager, MockComponents) {
+        let handler = InProcessHermesProtocolHandler::new();
+        let config = DialogueConfig {
+            session_timeout: Duration::from_millis(500),
+            timeout_check_interval: Duration::from_millis(10),
+            ..config
+        };
+        let manager = DialogueManager::new(&handler, config).unwrap();
+        let mocks = MockComponents::new(&handler).unwrap();
+        mocks
+            .nlu
+            .add_rule(NluRule::regex("lights_on", r"turn on the lights(?: in the (?P<room>\w+))?").unwrap())
+            .unwrap();
+        for utterance in utterances {
+            mocks.asr.push_utterance(utterance).unwrap();
+        }
+        (handler, manager, mocks)
+    }
+
+    fn start_action(site_id: &str, can_be_enqueued: bool, send_intent_not_recognized: bool) -> StartSessionMessage {
+        StartSessionMessage {
+            init: SessionInit::Action {
+                text: None,
+                intent_filter: None,
+                can_be_enqueued,
+                send_intent_not_recognized,
+            },
+            custom_data: Some("my data".into()),
+            site_id: Some(site_id.into()),
+        }
+    }
+
+    fn notification(site_id: &str) -> StartSessionMessage {
+        StartSessionMessage {
+            init: SessionInit::Notification { text: "hello".into() },
+            custom_data: None,
+            site_id: Some(site_id.into()),
+        }
+    }
+
+    /// Run `attempt` until it tells that the configuration of the dialogue was applied, as it is
+    /// received on other subscriptions than the sessions it applies to
+    fn until_applied<T, F>(mut attempt: F) -> T
+    where
+        F: FnMut() -> Option<T>,
+    {
+        let deadline = Instant::now() + Duration::from_secs(1);
+        loop {
+            if let Some(result) = attempt() {
+                return result;
+            }
+            assert!(Instant::now() < deadline, "the configuration was never applied");
+        }
+    }
+
+    #[test]
+    fn hotword_starts_a_session_publishing_the_intent() {
+        let (handler, _manager, mocks) = create_dialogue(vec!["turn on the lights"]);
+        let dialogue = handler.dialogue();
+        let started = receiver(|it| dialogue.subscribe_session_started(it));
+        let intents = receiver(|it| dialogue.subscribe_intents(it));
+        let ended = receiver(|it| dialogue.subscribe_session_ended(it));
+
+        mocks.hotword.detect("kitchen").unwrap();
+
+        let session = started.next();
+        assert_eq!(session.site_id, "kitchen");
+        let intent = intents.next();
+        assert_eq!(intent.session_id, session.session_id);
+        assert_eq!(intent.intent.intent_name, "lights_on");
+        assert_eq!(intent.input, "turn on the lights");
+
+        dialogue
+            .publish_end_session(EndSessionMessage {
+                session_id: session.session_id.clone(),
+                text: Some("done".into()),
+            })
+            .unwrap();
+        let end = ended.next();
+        assert_eq!(end.session_id, session.session_id);
+        assert_eq!(end.termination, SessionTerminationType::Nominal);
+    }
+
+    #[test]
+    fn notification_ends_after_being_said() {
+        let (handler, _manager, mocks) = create_dialogue(vec![]);
+        let dialogue = handler.dialogue();
+        let ended = receiver(|it| dialogue.subscribe_session_ended(it));
+
+        dialogue
+            .publish_start_session(StartSessionMessage {
+                init: SessionInit::Notification { text: "hello".into() },
+                custom_data: Some("my data".into()),
+                site_id: None,
+            })
+            .unwrap();
+
+        let end = ended.next();
+        assert_eq!(end.site_id, "default");
+        assert_eq!(end.custom_data, Some("my data".into()));
+        assert_eq!(end.termination, SessionTerminationType::Nominal);
+        assert_eq!(mocks.tts.said().unwrap()[0].text, "hello");
+    }
+
+    #[test]
+    fn sessions_are_queued_per_site() {
+        let (handler, _manager, _mocks) = create_dialogue(vec!["turn on the lights"]);
+        let dialogue = handler.dialogue();
+        let started = receiver(|it| dialogue.subscribe_session_started(it));
+        let queued = receiver(|it| dialogue.subscribe_session_queued(it));
+
+        dialogue
+            .publish_start_session(start_action("kitchen", true, false))
+            .unwrap();
+        let first = started.next();
+        dialogue
+            .publish_start_session(start_action("kitchen", true, false))
+            .unwrap();
+        let second = queued.next();
+        assert_eq!(second.site_id, "kitchen");
+
+        dialogue
+            .publish_end_session(EndSessionMessage {
+                session_id: first.session_id,
+                text: None,
+            })
+            .unwrap();
+        assert_eq!(started.next().session_id, second.session_id);
+    }
+
+    #[test]
+    fn session_state_lists_active_and_queued_sessions() {
+        let (handler, _manager, _mocks) = create_dialogue(vec![]);
+        let dialogue = handler.dialogue();
+        let started = receiver(|it| dialogue.subscribe_session_started(it));
+        let queued = receiver(|it| dialogue.subscribe_session_queued(it));
+        let states = receiver(|it| dialogue.subscribe_session_state(it));
+
+        dialogue.publish_session_state_request().unwrap();
+        assert!(states.next().sites.is_empty());
+
+        dialogue
+            .publish_start_session(start_action("kitchen", true, false))
+            .unwrap();
+        let first = started.next();
+        dialogue
+            .publish_start_session(StartSessionMessage {
+                init: SessionInit::Action {
+                    text: None,
+                    intent_filter: Some(vec!["lights_on".into()]),
+                    can_be_enqueued: true,
+                    send_intent_not_recognized: false,
+                },
+                custom_data: None,
+                site_id: Some("kitchen".into()),
+            })
+            .unwrap();
+        let second = queued.next();
+
+        dialogue.publish_session_state_request().unwrap();
+        let state = states.next();
+        assert_eq!(state.sites.len(), 1);
+        assert_eq!(state.sites[0].site_id, "kitchen");
+        let active = state.sites[0].active_session.as_ref().unwrap();
+        assert_eq!(active.session_id, first.session_id);
+        assert_eq!(active.custom_data, Some("my data".into()));
+        assert!(active.start_time.is_some());
+        let queued = &state.sites[0].queued_sessions;
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].session_id, second.session_id);
+        assert_eq!(queued[0].intent_filter, Some(vec!["lights_on".into()]));
+        assert_eq!(queued[0].start_time, None);
+    }
+
+    #[test]
+    fn session_that_cant_be_enqueued_is_refused_on_busy_site() {
+        let (handler, _manager, _mocks) = create_dialogue(vec![]);
+        let dialogue = handler.dialogue();
+        let started = receiver(|it| dialogue.subscribe_session_started(it));
+        let ended = receiver(|it| dialogue.subscribe_session_ended(it));
+
+        dialogue
+            .publish_start_session(start_action("kitchen", true, false))
+            .unwrap();
+        started.next();
+        dialogue
+            .publish_start_session(start_action("kitchen", false, false))
+            .unwrap();
+        assert_eq!(ended.next().termination, SessionTerminationType::SiteUnavailable);
+    }
+
+    #[test]
+    fn not_recognized_intent_ends_the_session() {
+        let (handler, _manager, _mocks) = create_dialogue(vec!["what time is it"]);
+        let dialogue = handler.dialogue();
+        let ended = receiver(|it| dialogue.subscribe_session_ended(it));
+
+        dialogue
+            .publish_start_session(start_action("kitchen", true, false))
+            .unwrap();
+        assert_eq!(ended.next().termination, SessionTerminationType::IntentNotRecognized);
+    }
+
+    #[test]
+    fn not_recognized_intent_is_sent_when_requested() {
+        let (handler, _manager, _mocks) = create_dialogue(vec!["what time is it"]);
+        let dialogue = handler.dialogue();
+        let not_recognized = receiver(|it| dialogue.subscribe_intent_not_recognized(it));
+
+        dialogue
+            .publish_start_session(start_action("kitchen", true, true))
+            .unwrap();
+        let message = not_recognized.next();
+        assert_eq!(message.input, Some("what time is it".into()));
+        assert_eq!(message.custom_data, Some("my data".into()));
+    }
+
+    #[test]
+    fn continue_session_fills_slot() {
+        let (handler, _manager, _mocks) = create_dialogue(vec!["turn on the lights", "kitchen"]);
+        let dialogue = handler.dialogue();
+        let intents = receiver(|it| dialogue.subscribe_intents(it));
+
+        dialogue
+            .publish_start_session(start_action("kitchen", true, false))
+            .unwrap();
+        let intent = intents.next();
+        dialogue
+            .publish_continue_session(ContinueSessionMessage {
+                session_id: intent.session_id.clone(),
+                text: "which room?".into(),
+                intent_filter: Some(vec!["lights_on".into()]),
+                custom_data: None,
+                send_intent_not_recognized: false,
+                slot: Some("room".into()),
+            })
+            .unwrap();
+
+        let intent = intents.next();
+        assert_eq!(intent.intent.intent_name, "lights_on");
+        assert_eq!(intent.custom_data, Some("my data".into()));
+        assert_eq!(intent.slots.len(), 1);
+        assert_eq!(intent.slots[0].nlu_slot.slot_name, "room");
+        assert_eq!(intent.slots[0].nlu_slot.raw_value, "kitchen");
+    }
+
+    #[test]
+    fn session_times_out_without_answer() {
+        let (handler, _manager, _mocks) = create_dialogue(vec!["turn on the lights"]);
+        let dialogue = handler.dialogue();
+        let intents = receiver(|it| dialogue.subscribe_intents(it));
+        let ended = receiver(|it| dialogue.subscribe_session_ended(it));
+
+        dialogue
+            .publish_start_session(start_action("kitchen", true, false))
+            .unwrap();
+        intents.next();
+        assert_eq!(ended.next().termination, SessionTerminationType::Timeout);
+    }
+
+    #[test]
+    fn disabled_intent_is_not_recognized_on_its_site() {
+        let (handler, _manager, _mocks) = create_dialogue(vec!["turn on the lights", "turn on the lights"]);
+        let dialogue = handler.dialogue();
+        let intents = receiver(|it| dialogue.subscribe_intents(it));
+        let ended = receiver(|it| dialogue.subscribe_session_ended(it));
+
+        dialogue
+            .publish_configure(DialogueConfigureMessage {
+                site_id: Some("kitchen".into()),
+                intents: Some(vec![DialogueConfigureIntent {
+                    intent_id: "lights_on".into(),
+                    enable: Some(false),
+                }]),
+            })
+            .unwrap();
+        dialogue
+            .publish_start_session(start_action("kitchen", true, false))
+            .unwrap();
+        assert_eq!(ended.next().termination, SessionTerminationType::IntentNotRecognized);
+
+        dialogue
+            .publish_start_session(start_action("bedroom", true, false))
+            .unwrap();
+        assert_eq!(intents.next().site_id, "bedroom");
+    }
+
+    #[test]
+    fn intent_carries_the_enabled_alternatives() {
+        let config = DialogueConfig {
+            intent_alternatives: Some(2),
+            ..DialogueConfig::default()
+        };
+        let (handler, _manager, mocks) = create_dialogue_with_config(config, vec!["turn on the lights"]);
+        for intent_name in &["lights_set", "lights_toggle", "lights_shine"] {
+            mocks
+                .nlu
+                .add_rule(NluRule::regex(intent_name, "turn on the lights").unwrap())
+                .unwrap();
+        }
+        let dialogue = handler.dialogue();
+        let intents = receiver(|it| dialogue.subscribe_intents(it));
+
+        dialogue
+            .publish_configure(DialogueConfigureMessage {
+                site_id: Some("kitchen".into()),
+                intents: Some(vec![DialogueConfigureIntent {
+                    intent_id: "lights_set".into(),
+                    enable: Some(false),
+                }]),
+            })
+            .unwrap();
+        dialogue
+            .publish_start_session(start_action("kitchen", true, false))
+            .unwrap();
+
+        let intent = intents.next();
+        assert_eq!(intent.intent.intent_name, "lights_on");
+        let alternatives = intent.alternatives.unwrap();
+        assert_eq!(alternatives.len(), 1);
+        assert_eq!(alternatives[0].intent_name, Some("lights_toggle".into()));
+    }
+
+    #[test]
+    fn disabled_dialogue_refuses_sessions() {
+        let (handler, _manager, _mocks) = create_dialogue(vec![]);
+        let dialogue = handler.dialogue();
+        let ended = receiver(|it| dialogue.subscribe_session_ended(it));
+
+        dialogue.publish_toggle_off().unwrap();
+        until_applied(|| {
+            dialogue.publish_start_session(notification("kitchen")).unwrap();
+            Some(ended.next().termination).filter(|it| *it == SessionTerminationType::SiteUnavailable)
+        });
+    }
+
+    #[test]
+    fn dialogue_answers_handshake_requests() {
+        let (handler, _manager, _mocks) = create_dialogue(vec![]);
+        let handshake = handler.handshake();
+        let handshakes = receiver(|it| handshake.subscribe_handshakes(it));
+
+        handshake.publish_handshake_request().unwrap();
+        let message = handshakes.next();
+        assert_eq!(message.component, COMPONENT);
+        assert_eq!(message.protocol_version, protocol_version());
+    }
+
+    #[test]
+    fn dialogue_speaks_the_language_of_the_site() {
+        let (handler, _manager, _mocks) = create_dialogue(vec![]);
+        let dialogue = handler.dialogue();
+        let tts = handler.tts_backend();
+        let says = receiver(|it| tts.subscribe_say(it));
+
+        handler
+            .site_backend()
+            .publish_sites(SitesMessage {
+                sites: vec![SiteInfoMessage {
+                    language: Some("fr".into()),
+                    ..SiteInfoMessage::new("kitchen")
+                }],
+            })
+            .unwrap();
+        let kitchen = until_applied(|| {
+            dialogue.publish_start_session(notification("kitchen")).unwrap();
+            Some(says.next()).filter(|it| it.lang.is_some())
+        });
+        assert_eq!(kitchen.site_id, "kitchen");
+        assert_eq!(kitchen.lang, Some("fr".into()));
+
+        dialogue.publish_start_session(notification("bedroom")).unwrap();
+        let bedroom = says.next();
+        assert_eq!(bedroom.site_id, "bedroom");
+        assert_eq!(bedroom.lang, None);
+    }
+}
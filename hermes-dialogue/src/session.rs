@@ -0,0 +1,135 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::Instant;
+
+use chrono::{DateTime, Utc};
+use hermes::*;
+
+/// What the dialogue manager must do once the text to speech has finished saying something
+#[derive(Debug, Clone, PartialEq)]
+pub enum AfterSay {
+    Listen,
+    End(SessionTerminationType),
+}
+
+/// The step a session is at, each step but `Queued` has a deadline after which the session is
+/// ended with a `Timeout` termination
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    /// The session is waiting for another one to end on its site
+    Queued(SessionInit),
+    /// The text to speech was asked to say something, `id` is the one of the `SayMessage`
+    Saying { id: String, then: AfterSay },
+    /// The ASR is listening to the user
+    Listening,
+    /// The NLU was given the text captured by the ASR, `id` is the one of the query
+    Understanding { id: String, captured: TextCapturedMessage },
+    /// An intent (or the lack of one) was published, waiting for a continue or end session
+    WaitingForAction,
+}
+
+#[derive(Debug)]
+pub struct Session {
+    pub id: String,
+    pub site_id: String,
+    pub custom_data: Option<String>,
+    pub intent_filter: Option<Vec<String>>,
+    pub send_intent_not_recognized: bool,
+    pub slot: Option<String>,
+    pub step: Step,
+    pub deadline: Option<Instant>,
+    /// When the session was started, `None` while it is queued
+    pub start_time: Option<DateTime<Utc>>,
+}
+
+impl Session {
+    pub fn new(site_id: String, custom_data: Option<String>, init: SessionInit) -> Self {
+        Self {
+            id: new_request_id(),
+            site_id,
+            custom_data,
+            intent_filter: None,
+            send_intent_not_recognized: false,
+            slot: None,
+            step: Step::Queued(init),
+            deadline: None,
+            start_time: None,
+        }
+    }
+
+    /// The state of the session, as advertised to the clients, the intent filter of a queued
+    /// session being the one it will start with
+    pub fn state(&self) -> SessionState {
+        let intent_filter = match self.step {
+            Step::Queued(SessionInit::Action { ref intent_filter, .. }) => intent_filter.clone(),
+            _ => self.intent_filter.clone(),
+        };
+        SessionState {
+            session_id: self.id.clone(),
+            custom_data: self.custom_data.clone(),
+            intent_filter,
+            start_time: self.start_time,
+        }
+    }
+
+    pub fn site_message(&self) -> SiteMessage {
+        SiteMessage {
+            site_id: self.site_id.clone(),
+            session_id: Some(self.id.clone()),
+        }
+    }
+
+    /// Whether the session is waiting for the reply to the request with the given id
+    pub fn is_waiting_for(&self, request_id: Option<&str>) -> bool {
+        match self.step {
+            Step::Saying { ref id, .. } | Step::Understanding { ref id, .. } => Some(id.as_str()) == request_id,
+            _ => false,
+        }
+    }
+}
+
+/// The sessions of a site, only one of them can be running at a time
+#[derive(Debug, Default)]
+pub struct Site {
+    pub session: Option<Session>,
+    pub queue: VecDeque<Session>,
+    /// What the site registry knows of the site, if it is registered
+    pub info: Option<SiteInfoMessage>,
+}
+
+/// The intents enabled or disabled through `DialogueConfigureMessage`s, all intents are enabled
+/// by default and a site configuration takes precedence over the global one
+#[derive(Debug, Default)]
+pub struct IntentConfig {
+    global: HashMap<String, bool>,
+    sites: HashMap<String, HashMap<String, bool>>,
+}
+
+impl IntentConfig {
+    pub fn configure(&mut self, config: &DialogueConfigureMessage) {
+        let intents = config
+            .intents
+            .iter()
+            .flatten()
+            .filter_map(|it| it.enable.map(|enable| (it.intent_id.clone(), enable)));
+        match config.site_id {
+            Some(ref site_id) => self.sites.entry(site_id.clone()).or_default().extend(intents),
+            None => {
+                for (intent, enable) in intents {
+                    for site in self.sites.values_mut() {
+                        site.remove(&intent);
+                    }
+                    self.global.insert(intent, enable);
+                }
+            }
+        }
+    }
+
+    pub fn is_enabled(&self, site_id: &str, intent: &str) -> bool {
+        self.sites
+            .get(site_id)
+            .and_then(|it| it.get(intent))
+            .or_else(|| self.global.get(intent))
+            .cloned()
+            .unwrap_or(true)
+    }
+}
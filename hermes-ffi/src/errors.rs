@@ -0,0 +1,83 @@
+//! The result codes of the C functions, telling apart the kinds of `HermesError`.
+
+use hermes::HermesError;
+
+/// The result of the C functions, it extends the one of `ffi_utils` with a code for each kind of
+/// error. The message of the error can be retrieved using the dedicated function whatever its code.
+#[repr(C)]
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SNIPS_RESULT {
+    /// The function returned successfully
+    SNIPS_RESULT_OK = 0,
+    /// The function encountered an error, you can retrieve it using the dedicated function
+    SNIPS_RESULT_KO = 1,
+    /// The handler can't reach the broker
+    SNIPS_RESULT_NOT_CONNECTED = 2,
+    /// A message could not be serialized or deserialized
+    SNIPS_RESULT_SERIALIZATION_ERROR = 3,
+    /// A thread panicked while holding a lock of the handler
+    SNIPS_RESULT_POISONED_LOCK = 4,
+    /// An argument is invalid, like a topic, an option value or a message breaking the ontology
+    SNIPS_RESULT_INVALID_ARGUMENT = 5,
+    /// No response to a request was received in time
+    SNIPS_RESULT_TIMEOUT = 6,
+}
+
+impl SNIPS_RESULT {
+    /// The code of an error, `SNIPS_RESULT_KO` when none of its causes is a `HermesError`
+    pub fn of_error(error: &failure::Error) -> Self {
+        let hermes_error = error
+            .iter_chain()
+            .filter_map(|it| it.downcast_ref::<HermesError>())
+            .next();
+        match hermes_error {
+            Some(HermesError::NotConnected(_)) => SNIPS_RESULT::SNIPS_RESULT_NOT_CONNECTED,
+            Some(HermesError::Serialization(_)) => SNIPS_RESULT::SNIPS_RESULT_SERIALIZATION_ERROR,
+            Some(HermesError::PoisonedLock) => SNIPS_RESULT::SNIPS_RESULT_POISONED_LOCK,
+            Some(HermesError::InvalidArgument(_)) | Some(HermesError::InvalidMessage(_)) => {
+                SNIPS_RESULT::SNIPS_RESULT_INVALID_ARGUMENT
+            }
+            Some(HermesError::RequestTimeout { .. }) => SNIPS_RESULT::SNIPS_RESULT_TIMEOUT,
+            Some(HermesError::Other(_)) | None => SNIPS_RESULT::SNIPS_RESULT_KO,
+        }
+    }
+}
+
+/// Like `ffi_utils::wrap!`, the error being kept to be retrieved with the last error function,
+/// but returning the code of its kind
+#[macro_export]
+macro_rules! hermes_wrap {
+    ($e:expr) => {
+        match $e {
+            Ok(_) => $crate::errors::SNIPS_RESULT::SNIPS_RESULT_OK,
+            Err(e) => {
+                let e: failure::Error = e.into();
+                let result = $crate::errors::SNIPS_RESULT::of_error(&e);
+                let _ = ffi_utils::wrap!(Err::<(), failure::Error>(e));
+                result
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use failure::ResultExt;
+
+    #[test]
+    fn errors_are_mapped_to_their_code() {
+        let error: failure::Error = HermesError::PoisonedLock.into();
+        assert_eq!(SNIPS_RESULT::of_error(&error), SNIPS_RESULT::SNIPS_RESULT_POISONED_LOCK);
+
+        let error = Err::<(), _>(HermesError::NotConnected("broker is down".into()))
+            .context("Could not publish")
+            .unwrap_err()
+            .into();
+        assert_eq!(SNIPS_RESULT::of_error(&error), SNIPS_RESULT::SNIPS_RESULT_NOT_CONNECTED);
+
+        let error = failure::format_err!("some error");
+        assert_eq!(SNIPS_RESULT::of_error(&error), SNIPS_RESULT::SNIPS_RESULT_KO);
+    }
+}
@@ -1,3 +1,4 @@
+pub mod errors;
 pub mod ontology;
 mod protocol_handler;
 
@@ -8,7 +8,7 @@ use failure::ResultExt;
 use ffi_utils::*;
 
 use crate::asr::CAsrTokenDoubleArray;
-use crate::nlu::{CNluIntentClassifierResult, CNluSlotArray};
+use crate::nlu::{CNluIntentAlternativeArray, CNluIntentClassifierResult, CNluSlotArray};
 
 #[repr(C)]
 #[derive(Debug)]
@@ -25,6 +25,8 @@ pub struct CIntentMessage {
     pub asr_tokens: *const CAsrTokenDoubleArray,
     /// Note: this value is optional. Any value not in [0,1] should be ignored.
     pub asr_confidence: libc::c_float,
+    /// Nullable, the alternative intents by decreasing confidence
+    pub alternatives: *const CNluIntentAlternativeArray,
 }
 
 unsafe impl Sync for CIntentMessage {}
@@ -58,6 +60,11 @@ impl CReprOf<hermes::IntentMessage> for CIntentMessage {
             } else {
                 -1.0
             },
+            alternatives: if let Some(alternatives) = input.alternatives {
+                CNluIntentAlternativeArray::c_repr_of(alternatives)?.into_raw_pointer()
+            } else {
+                null()
+            },
         })
     }
 }
@@ -81,6 +88,9 @@ impl Drop for CIntentMessage {
         if !self.asr_tokens.is_null() {
             let _ = unsafe { CAsrTokenDoubleArray::drop_raw_pointer(self.asr_tokens) };
         }
+        if !self.alternatives.is_null() {
+            let _ = unsafe { CNluIntentAlternativeArray::drop_raw_pointer(self.alternatives) };
+        }
     }
 }
 
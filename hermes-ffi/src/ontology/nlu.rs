@@ -21,6 +21,8 @@ pub struct CNluQueryMessage {
     pub id: *const libc::c_char,
     /// Nullable
     pub session_id: *const libc::c_char,
+    /// The number of alternative intents requested, -1 if none
+    pub intent_alternatives: libc::c_int,
 }
 
 unsafe impl Sync for CNluQueryMessage {}
@@ -43,6 +45,7 @@ impl CReprOf<hermes::NluQueryMessage> for CNluQueryMessage {
             intent_filter: convert_to_nullable_c_string_array!(input.intent_filter),
             id: convert_to_nullable_c_string!(input.id),
             session_id: convert_to_nullable_c_string!(input.session_id),
+            intent_alternatives: input.intent_alternatives.map_or(-1, |it| it as _),
         })
     }
 }
@@ -58,6 +61,11 @@ impl AsRust<hermes::NluQueryMessage> for CNluQueryMessage {
             intent_filter: create_optional_rust_vec_string_from!(self.intent_filter),
             id: create_optional_rust_string_from!(self.id),
             session_id: create_optional_rust_string_from!(self.session_id),
+            intent_alternatives: if self.intent_alternatives >= 0 {
+                Some(self.intent_alternatives as usize)
+            } else {
+                None
+            },
         })
     }
 }
@@ -328,6 +336,104 @@ impl Drop for CNluSlotArray {
     }
 }
 
+#[repr(C)]
+#[derive(Debug)]
+/// An alternative resolution of an input
+pub struct CNluIntentAlternative {
+    /// Nullable, name of the intent, null for the resolution matching no intent
+    pub intent_name: *const libc::c_char,
+    /// Between 0 and 1
+    pub confidence_score: libc::c_float,
+    /// Nullable
+    pub slots: *const CNluSlotArray,
+}
+
+impl CReprOf<hermes::NluIntentAlternative> for CNluIntentAlternative {
+    fn c_repr_of(input: hermes::NluIntentAlternative) -> Fallible<Self> {
+        Ok(Self {
+            intent_name: convert_to_nullable_c_string!(input.intent_name),
+            confidence_score: input.confidence_score,
+            slots: if !input.slots.is_empty() {
+                CNluSlotArray::c_repr_of(input.slots)?.into_raw_pointer()
+            } else {
+                null()
+            },
+        })
+    }
+}
+
+impl AsRust<hermes::NluIntentAlternative> for CNluIntentAlternative {
+    fn as_rust(&self) -> Fallible<hermes::NluIntentAlternative> {
+        Ok(hermes::NluIntentAlternative {
+            intent_name: create_optional_rust_string_from!(self.intent_name),
+            confidence_score: self.confidence_score,
+            slots: match unsafe { self.slots.as_ref() } {
+                Some(slots) => unsafe { CNluSlotArray::raw_borrow(slots)? }.as_rust()?,
+                None => vec![],
+            },
+        })
+    }
+}
+
+impl Drop for CNluIntentAlternative {
+    fn drop(&mut self) {
+        take_back_nullable_c_string!(self.intent_name);
+        if !self.slots.is_null() {
+            let _ = unsafe { CNluSlotArray::drop_raw_pointer(self.slots) };
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct CNluIntentAlternativeArray {
+    pub entries: *const *const CNluIntentAlternative,
+    pub count: libc::c_int,
+}
+
+impl CReprOf<Vec<hermes::NluIntentAlternative>> for CNluIntentAlternativeArray {
+    fn c_repr_of(input: Vec<hermes::NluIntentAlternative>) -> Fallible<Self> {
+        let array = Self {
+            count: input.len() as _,
+            entries: Box::into_raw(
+                input
+                    .into_iter()
+                    .map(|e| CNluIntentAlternative::c_repr_of(e).map(|c| c.into_raw_pointer()))
+                    .collect::<Fallible<Vec<_>>>()
+                    .context("Could not convert map to C Repr")?
+                    .into_boxed_slice(),
+            ) as *const *const _,
+        };
+        Ok(array)
+    }
+}
+
+impl AsRust<Vec<hermes::NluIntentAlternative>> for CNluIntentAlternativeArray {
+    fn as_rust(&self) -> Fallible<Vec<hermes::NluIntentAlternative>> {
+        let mut result = Vec::with_capacity(self.count as usize);
+
+        for e in unsafe { slice::from_raw_parts(self.entries, self.count as usize) } {
+            result.push(unsafe { CNluIntentAlternative::raw_borrow(*e) }?.as_rust()?);
+        }
+        Ok(result)
+    }
+}
+
+impl Drop for CNluIntentAlternativeArray {
+    fn drop(&mut self) {
+        unsafe {
+            let alternatives = Box::from_raw(std::slice::from_raw_parts_mut(
+                self.entries as *mut *mut CNluIntentAlternative,
+                self.count as usize,
+            ));
+
+            for e in alternatives.iter() {
+                let _ = CNluIntentAlternative::drop_raw_pointer(*e);
+            }
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(Debug)]
 pub struct CNluIntentMessage {
@@ -339,6 +445,8 @@ pub struct CNluIntentMessage {
     pub slots: *const CNluSlotArray,
     /// Nullable
     pub session_id: *const libc::c_char,
+    /// Nullable, the alternative intents by decreasing confidence
+    pub alternatives: *const CNluIntentAlternativeArray,
 }
 
 unsafe impl Sync for CNluIntentMessage {}
@@ -361,6 +469,11 @@ impl CReprOf<hermes::NluIntentMessage> for CNluIntentMessage {
                 null()
             },
             session_id: convert_to_nullable_c_string!(input.session_id),
+            alternatives: if let Some(alternatives) = input.alternatives {
+                CNluIntentAlternativeArray::c_repr_of(alternatives)?.into_raw_pointer()
+            } else {
+                null()
+            },
         })
     }
 }
@@ -378,6 +491,9 @@ impl Drop for CNluIntentMessage {
         let _ = unsafe { CNluIntentClassifierResult::drop_raw_pointer(self.intent) };
         let _ = unsafe { CNluSlotArray::drop_raw_pointer(self.slots) };
         take_back_nullable_c_string!(self.session_id);
+        if !self.alternatives.is_null() {
+            let _ = unsafe { CNluIntentAlternativeArray::drop_raw_pointer(self.alternatives) };
+        }
     }
 }
 
@@ -432,4 +548,19 @@ mod tests {
             confidence_score: 0.5,
         });
     }
+
+    #[test]
+    fn round_trip_intent_alternative() {
+        round_trip_test::<_, CNluIntentAlternative>(hermes::NluIntentAlternative {
+            intent_name: Some("MakeCoffee".into()),
+            confidence_score: 0.5,
+            slots: vec![],
+        });
+
+        round_trip_test::<_, CNluIntentAlternative>(hermes::NluIntentAlternative {
+            intent_name: None,
+            confidence_score: 0.2,
+            slots: vec![],
+        });
+    }
 }
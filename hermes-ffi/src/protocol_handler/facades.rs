@@ -90,7 +90,7 @@ macro_rules! generate_facade_wrapper {
         pub extern "C" fn $getter_name(
             handler: *const $crate::CProtocolHandler,
             facade: *mut *const $wrapper_name,
-        ) -> ffi_utils::SNIPS_RESULT {
+        ) -> $crate::errors::SNIPS_RESULT {
             fn fun(
                 handler: *const $crate::CProtocolHandler,
                 facade: *mut *const $wrapper_name,
@@ -105,7 +105,7 @@ macro_rules! generate_facade_wrapper {
                 Ok(())
             }
 
-            ffi_utils::wrap!(fun(handler, facade))
+            $crate::hermes_wrap!(fun(handler, facade))
         }
     };
 }
@@ -15,7 +15,7 @@ where
             let c_string = std::ffi::CString::new(json).expect("CString::new failed");
             unsafe { ptr(c_string.as_ptr(), user_data.0) }
         })),
-        None => failure::bail!("null pointer"),
+        None => Err(hermes::HermesError::InvalidArgument("null pointer".into()).into()),
     }
 }
 
@@ -23,15 +23,41 @@ pub fn json_from_slice<'a, T>(v: &'a [u8]) -> Fallible<T>
 where
     T: HermesMessage<'a>,
 {
-    Ok(serde_json::from_slice(v)?)
+    Ok(serde_json::from_slice(v).map_err(hermes::HermesError::from)?)
+}
+
+/// Wrap the JSON of some data in the envelope of the custom data of the sessions
+pub fn encode_custom_data_json(data: &str) -> Fallible<String> {
+    let data: serde_json::Value = serde_json::from_str(data).map_err(hermes::HermesError::from)?;
+    Ok(hermes::encode_custom_data(&data)?)
+}
+
+/// Unwrap the JSON of the data of a custom data of a session
+pub fn decode_custom_data_json(custom_data: &str) -> Fallible<String> {
+    let data: serde_json::Value = hermes::decode_custom_data(custom_data)?;
+    Ok(serde_json::to_string(&data).map_err(hermes::HermesError::from)?)
+}
+
+/// Apply a conversion to a C string, pointing `output` to the resulting C string
+pub fn convert_json_string<F>(input: *const libc::c_char, output: *mut *const libc::c_char, convert: F) -> Fallible<()>
+where
+    F: Fn(&str) -> Fallible<String>,
+{
+    if input.is_null() || output.is_null() {
+        return Err(hermes::HermesError::InvalidArgument("null pointer".into()).into());
+    }
+    let input = unsafe { std::ffi::CStr::from_ptr(input) }.to_str()?;
+    let converted = std::ffi::CString::new(convert(input)?)?;
+    unsafe { *output = converted.into_raw() };
+    Ok(())
 }
 
 #[macro_export]
 macro_rules! generate_facade_publish_json {
     ($c_symbol:ident = $facade:ty:$method:ident) => {
         #[no_mangle]
-        pub unsafe extern "C" fn $c_symbol(facade: *const $facade) -> ffi_utils::SNIPS_RESULT {
-            ffi_utils::wrap!(unsafe { (*facade).extract() }.$method())
+        pub unsafe extern "C" fn $c_symbol(facade: *const $facade) -> $crate::errors::SNIPS_RESULT {
+            $crate::hermes_wrap!(unsafe { (*facade).extract() }.$method())
         }
     };
 
@@ -41,7 +67,7 @@ macro_rules! generate_facade_publish_json {
             facade: *const $facade,
             $($filter_name: *const libc::c_char,)*
             message: *const libc::c_char,
-        ) -> ffi_utils::SNIPS_RESULT {
+        ) -> $crate::errors::SNIPS_RESULT {
             fn fun(
                 facade: *const $facade,
                 $($filter_name: *const libc::c_char,)*
@@ -52,12 +78,12 @@ macro_rules! generate_facade_publish_json {
                 let c_str = unsafe { CStr::from_ptr(message) };
                 let message = $crate::json_from_slice(c_str.to_bytes())?;
 
-                unsafe { (*facade).extract() }.$method(
+                Ok(unsafe { (*facade).extract() }.$method(
                     $(unsafe { CStr::from_ptr($filter_name) }.to_string_lossy().into_owned(),)*
                     message,
-                )
+                )?)
             }
-            ffi_utils::wrap!(fun(facade, $($filter_name,)* message))
+            $crate::hermes_wrap!(fun(facade, $($filter_name,)* message))
         }
     };
 }
@@ -70,7 +96,7 @@ macro_rules! generate_facade_subscribe_json {
             facade: *const $facade,
             $($filter_name: *const libc::c_char,)*
             handler: Option<unsafe extern "C" fn(*const libc::c_char, *mut libc::c_void)>,
-        ) -> ffi_utils::SNIPS_RESULT {
+        ) -> $crate::errors::SNIPS_RESULT {
 
             fn fun(facade: *const $facade,
                 $($filter_name: *const libc::c_char,)*
@@ -81,13 +107,16 @@ macro_rules! generate_facade_subscribe_json {
                 let user_data = unsafe { (*facade).user_data().duplicate() };
                 let callback = $crate::json_ptr_to_callback(handler, user_data)?;
 
+                // there is no unsubscription mechanism in the C api, keep the handler for the life
+                // of the protocol handler
                 unsafe { (*facade).extract() }.$method(
                     $(unsafe { CStr::from_ptr($filter_name) }.to_string_lossy().into_owned(),)*
                     callback,
-                )
+                )?.detach();
+                Ok(())
             }
 
-            ffi_utils::wrap!(fun(facade, $($filter_name,)* handler))
+            $crate::hermes_wrap!(fun(facade, $($filter_name,)* handler))
         }
     };
 }
@@ -113,9 +142,40 @@ macro_rules! generate_json_c_symbols {
             $crate::generate_facade_publish_json!(hermes_dialogue_publish_continue_session_json = CDialogueFacade: publish_continue_session());
             $crate::generate_facade_publish_json!(hermes_dialogue_publish_end_session_json = CDialogueFacade: publish_end_session());
             $crate::generate_facade_publish_json!(hermes_dialogue_publish_configure_json = CDialogueFacade: publish_configure());
+            $crate::generate_facade_publish_json!(hermes_dialogue_publish_session_state_request_json = CDialogueFacade: publish_session_state_request);
+            $crate::generate_facade_subscribe_json!(hermes_dialogue_subscribe_session_state_json = CDialogueFacade: subscribe_session_state());
 
             $crate::generate_facade_publish_json!(hermes_tts_publish_register_sound_json = CTtsFacade: publish_register_sound());
 
+            /// Wrap the JSON of some data in the envelope of the custom data of the sessions, the
+            /// result must be freed with `hermes_drop_custom_data_json`
+            #[no_mangle]
+            pub extern "C" fn hermes_encode_custom_data_json(
+                data: *const libc::c_char,
+                custom_data: *mut *const libc::c_char,
+            ) -> $crate::errors::SNIPS_RESULT {
+                $crate::hermes_wrap!($crate::convert_json_string(data, custom_data, $crate::encode_custom_data_json))
+            }
+
+            /// Unwrap the JSON of the data of a custom data of a session, the result must be freed
+            /// with `hermes_drop_custom_data_json`
+            #[no_mangle]
+            pub extern "C" fn hermes_decode_custom_data_json(
+                custom_data: *const libc::c_char,
+                data: *mut *const libc::c_char,
+            ) -> $crate::errors::SNIPS_RESULT {
+                $crate::hermes_wrap!($crate::convert_json_string(custom_data, data, $crate::decode_custom_data_json))
+            }
+
+            /// Free a string returned by the custom data functions
+            #[no_mangle]
+            pub unsafe extern "C" fn hermes_drop_custom_data_json(json: *mut libc::c_char) -> $crate::errors::SNIPS_RESULT {
+                if !json.is_null() {
+                    drop(std::ffi::CString::from_raw(json));
+                }
+                $crate::errors::SNIPS_RESULT::SNIPS_RESULT_OK
+            }
+
             $crate::generate_facade_publish_json!(hermes_injection_publish_injection_request_json = CInjectionFacade: publish_injection_request());
             $crate::generate_facade_publish_json!(hermes_injection_publish_injection_status_request_json = CInjectionFacade: publish_injection_status_request);
             $crate::generate_facade_subscribe_json!(hermes_injection_subscribe_injection_status_json = CInjectionFacade: subscribe_injection_status());
@@ -174,6 +234,7 @@ macro_rules! generate_json_c_symbols {
                 $crate::generate_facade_publish_json!(hermes_dialogue_backend_publish_intent_json = CDialogueBackendFacade: publish_intent());
                 $crate::generate_facade_publish_json!(hermes_dialogue_backend_publish_intent_not_recognized_json = CDialogueBackendFacade: publish_intent_not_recognized());
                 $crate::generate_facade_publish_json!(hermes_dialogue_backend_publish_session_ended_json = CDialogueBackendFacade: publish_session_ended());
+                $crate::generate_facade_publish_json!(hermes_dialogue_backend_publish_session_state_json = CDialogueBackendFacade: publish_session_state());
                 $crate::generate_facade_subscribe_json!(hermes_dialogue_backend_subscribe_start_session_json = CDialogueBackendFacade: subscribe_start_session());
                 $crate::generate_facade_subscribe_json!(hermes_dialogue_backend_subscribe_continue_session_json = CDialogueBackendFacade: subscribe_continue_session());
                 $crate::generate_facade_subscribe_json!(hermes_dialogue_backend_subscribe_end_session_json = CDialogueBackendFacade: subscribe_end_session());
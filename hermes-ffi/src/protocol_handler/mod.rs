@@ -6,7 +6,10 @@ pub mod structures;
 
 pub use facades::{CProtocolHandler, UserData};
 #[cfg(feature = "json")]
-pub use json::{json_from_slice, json_ptr_to_callback, CJsonCallback};
+pub use json::{
+    convert_json_string, decode_custom_data_json, encode_custom_data_json, json_from_slice, json_ptr_to_callback,
+    CJsonCallback,
+};
 #[cfg(feature = "structures")]
 pub use structures::structure_ptr_to_callback;
 
@@ -14,8 +17,8 @@ pub use structures::structure_ptr_to_callback;
 macro_rules! generate_hermes_c_symbols {
     () => {
         #[no_mangle]
-        pub extern "C" fn hermes_enable_debug_logs() -> ffi_utils::SNIPS_RESULT {
-            ffi_utils::wrap!($crate::init_debug_logs())
+        pub extern "C" fn hermes_enable_debug_logs() -> $crate::errors::SNIPS_RESULT {
+            $crate::hermes_wrap!($crate::init_debug_logs())
         }
 
         generate_facade_c_symbols!();
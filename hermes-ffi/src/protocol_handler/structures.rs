@@ -17,7 +17,7 @@ where
             unsafe { ptr(param, user_data.0) }
         }))
     } else {
-        Err(failure::format_err!("null pointer"))
+        Err(hermes::HermesError::InvalidArgument("null pointer".into()).into())
     }
 }
 
@@ -25,11 +25,11 @@ where
 macro_rules! generate_destroy {
     ($c_symbol:ident for $cstruct:ty) => {
         #[no_mangle]
-        pub unsafe extern "C" fn $c_symbol(cstruct: *const $cstruct) -> ffi_utils::SNIPS_RESULT {
+        pub unsafe extern "C" fn $c_symbol(cstruct: *const $cstruct) -> $crate::errors::SNIPS_RESULT {
             use ffi_utils::RawPointerConverter;
 
             let _ = <$cstruct as RawPointerConverter<$cstruct>>::from_raw_pointer(cstruct);
-            ffi_utils::SNIPS_RESULT::SNIPS_RESULT_OK
+            $crate::errors::SNIPS_RESULT::SNIPS_RESULT_OK
         }
     };
 }
@@ -38,27 +38,27 @@ macro_rules! generate_destroy {
 macro_rules! generate_facade_publish {
     ($c_symbol:ident = $facade:ty:$method:ident($( + $qualifier_name:ident : $qualifier:ty as $qualifier_raw:ty,)* $arg:ty)) => {
         #[no_mangle]
-        pub extern "C" fn $c_symbol(facade : *const $facade, $($qualifier_name : *const $qualifier_raw,)* message : *const $arg) -> ffi_utils::SNIPS_RESULT {
+        pub extern "C" fn $c_symbol(facade : *const $facade, $($qualifier_name : *const $qualifier_raw,)* message : *const $arg) -> $crate::errors::SNIPS_RESULT {
             fn fun(facade : *const $facade, $($qualifier_name : *const $qualifier_raw,)* message : *const $arg) -> failure::Fallible<()> {
                 use ffi_utils::{AsRust, RawBorrow};
 
                 let message = unsafe { (*message).as_rust() }?;
-                unsafe {(*facade).extract().$method($(<$qualifier as RawBorrow<$qualifier_raw>>::raw_borrow($qualifier_name)?.as_rust()?,)* message)}
+                Ok(unsafe {(*facade).extract().$method($(<$qualifier as RawBorrow<$qualifier_raw>>::raw_borrow($qualifier_name)?.as_rust()?,)* message)}?)
             }
 
-            ffi_utils::wrap!(fun(facade, $($qualifier_name,)* message))
+            $crate::hermes_wrap!(fun(facade, $($qualifier_name,)* message))
         }
     };
     ($c_symbol:ident = $facade:ty:$method:ident($( + $qualifier_name:ident : $qualifier:ty as $qualifier_raw:ty,)*)) => {
         #[no_mangle]
-        pub extern "C" fn $c_symbol(facade : *const $facade, $($qualifier_name : *const $qualifier_raw,)*) -> ffi_utils::SNIPS_RESULT {
+        pub extern "C" fn $c_symbol(facade : *const $facade, $($qualifier_name : *const $qualifier_raw,)*) -> $crate::errors::SNIPS_RESULT {
             fn fun(facade : *const $facade, $($qualifier_name : *const $qualifier_raw,)*) -> failure::Fallible<()> {
                 use ffi_utils::{AsRust, RawBorrow};
 
-                unsafe {(*facade).extract().$method($(<$qualifier as RawBorrow<$qualifier_raw>>::raw_borrow($qualifier_name)?.as_rust()?,)*)}
+                Ok(unsafe {(*facade).extract().$method($(<$qualifier as RawBorrow<$qualifier_raw>>::raw_borrow($qualifier_name)?.as_rust()?,)*)}?)
             }
 
-            ffi_utils::wrap!(fun(facade, $($qualifier_name,)*))
+            $crate::hermes_wrap!(fun(facade, $($qualifier_name,)*))
         }
     };
 }
@@ -67,16 +67,20 @@ macro_rules! generate_facade_publish {
 macro_rules! generate_facade_subscribe {
     ($c_symbol:ident = $facade:ty:$method:ident($( $filter_name:ident : $filter:ty as $filter_raw:ty,)* | $arg:ty|)) => {
         #[no_mangle]
-        pub extern "C" fn $c_symbol(facade: *const $facade, $($filter_name : *const $filter_raw,)* handler: Option<unsafe extern "C" fn(*const $arg, *mut libc::c_void)>) -> ffi_utils::SNIPS_RESULT {
+        pub extern "C" fn $c_symbol(facade: *const $facade, $($filter_name : *const $filter_raw,)* handler: Option<unsafe extern "C" fn(*const $arg, *mut libc::c_void)>) -> $crate::errors::SNIPS_RESULT {
             fn fun(facade: *const $facade, $($filter_name : *const $filter_raw,)* handler: Option<unsafe extern "C" fn(*const $arg, *mut libc::c_void)>) -> failure::Fallible<()> {
                 use ffi_utils::{AsRust, RawBorrow};
 
                 let user_data = unsafe { (*facade).user_data().duplicate() };
                 let callback = $crate::structure_ptr_to_callback(handler, user_data)?;
-                unsafe { (*facade).extract().$method($(<$filter as RawBorrow<$filter_raw>>::raw_borrow($filter_name)?.as_rust()?,)* callback) }
+                // there is no unsubscription mechanism in the C api, keep the handler for the life
+                // of the protocol handler
+                unsafe { (*facade).extract().$method($(<$filter as RawBorrow<$filter_raw>>::raw_borrow($filter_name)?.as_rust()?,)* callback) }?
+                    .detach();
+                Ok(())
             }
 
-            ffi_utils::wrap!(fun(facade, $($filter_name,)* handler))
+            $crate::hermes_wrap!(fun(facade, $($filter_name,)* handler))
         }
     };
 }
@@ -1,18 +1,27 @@
 use std::fmt::Debug;
 use std::sync::Mutex;
 
-use failure::Fallible;
 use hermes::*;
 use log::*;
 
 pub struct InProcessHermesProtocolHandler {
     bus: Mutex<ripb::Bus>,
+    validation: ValidationMode,
+    received: ValidationMode,
 }
 
 impl InProcessHermesProtocolHandler {
     pub fn new() -> Self {
+        Self::new_with_validation(ValidationMode::Off, ValidationMode::Off)
+    }
+
+    /// Create a handler checking the messages published by its components according to
+    /// `validation`, and the ones given to its subscribers according to `received`
+    pub fn new_with_validation(validation: ValidationMode, received: ValidationMode) -> Self {
         Self {
             bus: Mutex::new(ripb::Bus::new()),
+            validation,
+            received,
         }
     }
 
@@ -21,7 +30,8 @@ impl InProcessHermesProtocolHandler {
         Box::new(InProcessComponent {
             component,
             bus: Mutex::new(bus),
-            subscriber: Mutex::new(None),
+            validation: self.validation,
+            received: self.received,
         })
     }
 }
@@ -104,6 +114,30 @@ impl HermesProtocolHandler for InProcessHermesProtocolHandler {
     fn injection_backend(&self) -> Box<InjectionBackendFacade> {
         self.get_handler(Injection)
     }
+
+    fn connection(&self) -> Box<ConnectionFacade> {
+        Box::new(InProcessConnection)
+    }
+
+    fn handshake(&self) -> Box<HandshakeFacade> {
+        self.get_handler(Protocol)
+    }
+
+    fn registry(&self) -> Box<RegistryFacade> {
+        self.get_handler(Registry)
+    }
+
+    fn registry_backend(&self) -> Box<RegistryBackendFacade> {
+        self.get_handler(Registry)
+    }
+
+    fn site(&self) -> Box<SiteFacade> {
+        self.get_handler(Site)
+    }
+
+    fn site_backend(&self) -> Box<SiteBackendFacade> {
+        self.get_handler(Site)
+    }
 }
 
 impl std::fmt::Display for InProcessHermesProtocolHandler {
@@ -115,79 +149,117 @@ impl std::fmt::Display for InProcessHermesProtocolHandler {
 struct InProcessComponent<T: Send + Sync + Debug> {
     component: T,
     bus: Mutex<ripb::Bus>,
-    subscriber: Mutex<Option<ripb::Subscriber>>,
-}
-
-impl<T: Send + Sync + Debug> Drop for InProcessComponent<T> {
-    fn drop(&mut self) {
-        // dropping a ripb subscriber removes its subscriptions it. As we don't have unsubscription
-        // mechanic in hermes (yet) and there are quite a lot of parts in the code where we just
-        // drop the facade after subscribing, let's just forget the subscriber for now.
-        if let Ok(mut subscriber) = self.subscriber.lock() {
-            if let Some(subscriber) = subscriber.take() {
-                std::mem::forget(subscriber)
+    /// The check of the messages published by this component
+    validation: ValidationMode,
+    /// The check of the messages given to the subscribers of the bus
+    received: ValidationMode,
+}
+
+impl<T: Send + Sync + Debug + Copy + 'static> InProcessComponent<T> {
+    /// Publish a message on the bus, it is checked here once for this component and for all the
+    /// subscribers of the bus
+    fn publish<M: BusMessage>(&self, message: M) -> HermesResult<()> {
+        let checked = match self.validation {
+            ValidationMode::Off => self.received.check(&message),
+            validation => validation.check(&message),
+        };
+        if let Err(errors) = checked {
+            if self.validation == ValidationMode::Strict {
+                return Err(HermesError::InvalidMessage(errors));
+            }
+            if self.received == ValidationMode::Strict {
+                warn!("Dropping an invalid {:?}/{:?}: {}", self.component, message, errors);
+                return self.report(&message, &errors);
             }
+            warn!("Publishing an invalid {:?}/{:?}: {}", self.component, message, errors)
         }
-    }
-}
-
-impl<T: Send + Sync + Debug> InProcessComponent<T> {
-    fn publish<M: ripb::Message + Debug + 'static>(&self, message: M) -> Fallible<()> {
         debug!("Publishing {:?}/{:#?}", self.component, message);
-        let bus = self.bus.lock().map_err(PoisonLock::from)?;
+        let bus = self.bus.lock().map_err(HermesError::from)?;
         bus.publish(message);
         Ok(())
     }
 
-    fn subscribe0<M: ripb::Message + 'static>(&self, callback: Callback0) -> Fallible<()> {
-        let mut subscriber = self.subscriber.lock().map_err(PoisonLock::from)?;
-        let subscriber = subscriber.get_or_insert_with(|| self.bus.lock().unwrap().create_subscriber());
-        subscriber.on_message(move |_: &M| callback.call());
+    /// Report a message rejected by the subscribers as an error of the component, on the site of
+    /// the message for the identifiable components
+    fn report<M: BusMessage>(&self, message: &M, errors: &ValidationErrors) -> HermesResult<()> {
+        let error = ErrorMessage {
+            session_id: None,
+            error: format!("Invalid message: {}", errors),
+            context: Some(std::any::type_name::<M>().to_string()),
+        };
+        let component = self.component;
+        let bus = self.bus.lock().map_err(HermesError::from)?;
+        match message.site_id() {
+            Some(site_id) => bus.publish(IdentifiableComponentError {
+                site_id: site_id.to_string(),
+                error,
+                component,
+            }),
+            None => bus.publish(ComponentError { error, component }),
+        }
         Ok(())
     }
 
-    fn subscribe<M, P, C>(&self, callback: Callback<P>, converter: C) -> Fallible<()>
+    fn create_subscriber(&self) -> HermesResult<ripb::Subscriber> {
+        Ok(self.bus.lock().map_err(HermesError::from)?.create_subscriber())
+    }
+
+    fn subscription(subscriber: ripb::Subscriber) -> Subscription {
+        // each subscription gets its own ripb subscriber, dropping it removes the callback from
+        // the bus
+        let subscriber = Mutex::new(Some(subscriber));
+        Subscription::new(move || {
+            if let Ok(mut subscriber) = subscriber.lock() {
+                subscriber.take();
+            }
+        })
+    }
+
+    fn subscribe0<M: ripb::Message + 'static>(&self, callback: Callback0) -> HermesResult<Subscription> {
+        let mut subscriber = self.create_subscriber()?;
+        subscriber.on_message(move |_: &M| callback.call());
+        Ok(Self::subscription(subscriber))
+    }
+
+    fn subscribe<M, P, C>(&self, callback: Callback<P>, converter: C) -> HermesResult<Subscription>
     where
         M: ripb::Message + Debug + 'static,
         P: 'static,
         C: Fn(&M) -> &P + Send + 'static,
     {
-        let mut subscriber = self.subscriber.lock().map_err(PoisonLock::from)?;
-        let subscriber = subscriber.get_or_insert_with(|| self.bus.lock().unwrap().create_subscriber());
+        let mut subscriber = self.create_subscriber()?;
         subscriber.on_message(move |m: &M| callback.call(converter(m)));
-        Ok(())
+        Ok(Self::subscription(subscriber))
     }
 
-    fn subscribe0_filter<M, F>(&self, callback: Callback0, filter: F) -> Fallible<()>
+    fn subscribe0_filter<M, F>(&self, callback: Callback0, filter: F) -> HermesResult<Subscription>
     where
         M: ripb::Message + 'static,
         F: Fn(&M) -> bool + Send + 'static,
     {
-        let mut subscriber = self.subscriber.lock().map_err(PoisonLock::from)?;
-        let subscriber = subscriber.get_or_insert_with(|| self.bus.lock().unwrap().create_subscriber());
+        let mut subscriber = self.create_subscriber()?;
         subscriber.on_message(move |m: &M| {
             if filter(m) {
                 callback.call()
             }
         });
-        Ok(())
+        Ok(Self::subscription(subscriber))
     }
 
-    fn subscribe_filter<M, P, C, F>(&self, callback: Callback<P>, converter: C, filter: F) -> Fallible<()>
+    fn subscribe_filter<M, P, C, F>(&self, callback: Callback<P>, converter: C, filter: F) -> HermesResult<Subscription>
     where
         M: ripb::Message + Debug + 'static,
         P: 'static,
         C: Fn(&M) -> &P + Send + 'static,
         F: Fn(&M) -> bool + Send + 'static,
     {
-        let mut subscriber = self.subscriber.lock().map_err(PoisonLock::from)?;
-        let subscriber = subscriber.get_or_insert_with(|| self.bus.lock().unwrap().create_subscriber());
+        let mut subscriber = self.create_subscriber()?;
         subscriber.on_message(move |m: &M| {
             if filter(m) {
                 callback.call(converter(m))
             }
         });
-        Ok(())
+        Ok(Self::subscription(subscriber))
     }
 }
 
@@ -246,25 +318,25 @@ struct ComponentError<T: Debug> {
 }
 
 impl<T: Send + Sync + Debug + Copy + 'static> ComponentFacade for InProcessComponent<T> {
-    fn publish_version_request(&self) -> Fallible<()> {
+    fn publish_version_request(&self) -> HermesResult<()> {
         self.publish(ComponentVersionRequest {
             component: self.component,
         } as ComponentVersionRequest<T>)
     }
-    fn subscribe_version(&self, handler: Callback<VersionMessage>) -> Fallible<()> {
+    fn subscribe_version(&self, handler: Callback<VersionMessage>) -> HermesResult<Subscription> {
         subscribe!(self, ComponentVersion<T> { version }, handler)
     }
-    fn subscribe_error(&self, handler: Callback<ErrorMessage>) -> Fallible<()> {
+    fn subscribe_error(&self, handler: Callback<ErrorMessage>) -> HermesResult<Subscription> {
         subscribe!(self, ComponentError<T> { error }, handler)
     }
 }
 
 impl<T: Send + Sync + Debug + Copy + 'static> ComponentBackendFacade for InProcessComponent<T> {
-    fn subscribe_version_request(&self, handler: Callback0) -> Fallible<()> {
+    fn subscribe_version_request(&self, handler: Callback0) -> HermesResult<Subscription> {
         subscribe!(self, ComponentVersionRequest<T>, handler)
     }
 
-    fn publish_version(&self, version: VersionMessage) -> Fallible<()> {
+    fn publish_version(&self, version: VersionMessage) -> HermesResult<()> {
         let component_version: ComponentVersion<T> = ComponentVersion {
             version,
             component: self.component,
@@ -272,7 +344,7 @@ impl<T: Send + Sync + Debug + Copy + 'static> ComponentBackendFacade for InProce
         self.publish(component_version)
     }
 
-    fn publish_error(&self, error: ErrorMessage) -> Fallible<()> {
+    fn publish_error(&self, error: ErrorMessage) -> HermesResult<()> {
         let component_error: ComponentError<T> = ComponentError {
             error,
             component: self.component,
@@ -302,7 +374,7 @@ struct IdentifiableComponentError<T: Debug> {
 }
 
 impl<T: Send + Sync + Debug + Copy + 'static> IdentifiableComponentFacade for InProcessComponent<T> {
-    fn publish_version_request(&self, site_id: String) -> Fallible<()> {
+    fn publish_version_request(&self, site_id: String) -> HermesResult<()> {
         let version_request = IdentifiableComponentVersionRequest {
             site_id,
             component: self.component,
@@ -310,21 +382,21 @@ impl<T: Send + Sync + Debug + Copy + 'static> IdentifiableComponentFacade for In
         self.publish(version_request)
     }
 
-    fn subscribe_version(&self, site_id: String, handler: Callback<VersionMessage>) -> Fallible<()> {
+    fn subscribe_version(&self, site_id: String, handler: Callback<VersionMessage>) -> HermesResult<Subscription> {
         subscribe_filter!(self, IdentifiableComponentVersion<T> { version }, handler, site_id, |it| &it.site_id)
     }
 
-    fn subscribe_error(&self, site_id: String, handler: Callback<ErrorMessage>) -> Fallible<()> {
+    fn subscribe_error(&self, site_id: String, handler: Callback<ErrorMessage>) -> HermesResult<Subscription> {
         subscribe_filter!(self, IdentifiableComponentError<T> { error }, handler, site_id, |it| &it.site_id)
     }
 }
 
 impl<T: Send + Sync + Debug + Copy + 'static> IdentifiableComponentBackendFacade for InProcessComponent<T> {
-    fn subscribe_version_request(&self, site_id: String, handler: Callback0) -> Fallible<()> {
+    fn subscribe_version_request(&self, site_id: String, handler: Callback0) -> HermesResult<Subscription> {
         subscribe_filter!(self, IdentifiableComponentVersionRequest<T>, handler, site_id)
     }
 
-    fn publish_version(&self, site_id: String, version: VersionMessage) -> Fallible<()> {
+    fn publish_version(&self, site_id: String, version: VersionMessage) -> HermesResult<()> {
         let component_version: IdentifiableComponentVersion<T> = IdentifiableComponentVersion {
             site_id,
             version,
@@ -333,7 +405,7 @@ impl<T: Send + Sync + Debug + Copy + 'static> IdentifiableComponentBackendFacade
         self.publish(component_version)
     }
 
-    fn publish_error(&self, site_id: String, error: ErrorMessage) -> Fallible<()> {
+    fn publish_error(&self, site_id: String, error: ErrorMessage) -> HermesResult<()> {
         let component_error: IdentifiableComponentError<T> = IdentifiableComponentError {
             site_id,
             error,
@@ -356,7 +428,7 @@ struct IdentifiableToggleableToggleOff<T> {
 }
 
 impl<T: Send + Sync + Debug + Copy + 'static> IdentifiableToggleableFacade for InProcessComponent<T> {
-    fn publish_toggle_on(&self, site: SiteMessage) -> Fallible<()> {
+    fn publish_toggle_on(&self, site: SiteMessage) -> HermesResult<()> {
         let toggle_on: IdentifiableToggleableToggleOn<T> = IdentifiableToggleableToggleOn {
             site,
             component: self.component,
@@ -364,7 +436,7 @@ impl<T: Send + Sync + Debug + Copy + 'static> IdentifiableToggleableFacade for I
         self.publish(toggle_on)
     }
 
-    fn publish_toggle_off(&self, site: SiteMessage) -> Fallible<()> {
+    fn publish_toggle_off(&self, site: SiteMessage) -> HermesResult<()> {
         let toggle_off: IdentifiableToggleableToggleOff<T> = IdentifiableToggleableToggleOff {
             site,
             component: self.component,
@@ -373,12 +445,12 @@ impl<T: Send + Sync + Debug + Copy + 'static> IdentifiableToggleableFacade for I
     }
 }
 
-impl<T: Send + Sync + Debug + 'static> IdentifiableToggleableBackendFacade for InProcessComponent<T> {
-    fn subscribe_toggle_on(&self, handler: Callback<SiteMessage>) -> Fallible<()> {
+impl<T: Send + Sync + Debug + Copy + 'static> IdentifiableToggleableBackendFacade for InProcessComponent<T> {
+    fn subscribe_toggle_on(&self, handler: Callback<SiteMessage>) -> HermesResult<Subscription> {
         subscribe!(self, IdentifiableToggleableToggleOn<T> { site }, handler)
     }
 
-    fn subscribe_toggle_off(&self, handler: Callback<SiteMessage>) -> Fallible<()> {
+    fn subscribe_toggle_off(&self, handler: Callback<SiteMessage>) -> HermesResult<Subscription> {
         subscribe!(self, IdentifiableToggleableToggleOff<T> { site }, handler)
     }
 }
@@ -415,53 +487,56 @@ struct NluIntentNotRecognized {
 struct NluReload {}
 
 impl NluFacade for InProcessComponent<Nlu> {
-    fn publish_query(&self, query: NluQueryMessage) -> Fallible<()> {
+    fn publish_query(&self, query: NluQueryMessage) -> HermesResult<()> {
         self.publish(NluQuery { query })
     }
 
-    fn publish_partial_query(&self, query: NluSlotQueryMessage) -> Fallible<()> {
+    fn publish_partial_query(&self, query: NluSlotQueryMessage) -> HermesResult<()> {
         self.publish(NluPartialQuery { query })
     }
 
-    fn publish_reload(&self) -> Fallible<()> {
+    fn publish_reload(&self) -> HermesResult<()> {
         self.publish(NluReload {})
     }
 
-    fn subscribe_slot_parsed(&self, handler: Callback<NluSlotMessage>) -> Fallible<()> {
+    fn subscribe_slot_parsed(&self, handler: Callback<NluSlotMessage>) -> HermesResult<Subscription> {
         subscribe!(self, NluSlotParsed { slot }, handler)
     }
 
-    fn subscribe_intent_parsed(&self, handler: Callback<NluIntentMessage>) -> Fallible<()> {
+    fn subscribe_intent_parsed(&self, handler: Callback<NluIntentMessage>) -> HermesResult<Subscription> {
         subscribe!(self, NluIntentParsed { intent }, handler)
     }
 
-    fn subscribe_intent_not_recognized(&self, handler: Callback<NluIntentNotRecognizedMessage>) -> Fallible<()> {
+    fn subscribe_intent_not_recognized(
+        &self,
+        handler: Callback<NluIntentNotRecognizedMessage>,
+    ) -> HermesResult<Subscription> {
         subscribe!(self, NluIntentNotRecognized { status }, handler)
     }
 }
 
 impl NluBackendFacade for InProcessComponent<Nlu> {
-    fn subscribe_query(&self, handler: Callback<NluQueryMessage>) -> Fallible<()> {
+    fn subscribe_query(&self, handler: Callback<NluQueryMessage>) -> HermesResult<Subscription> {
         subscribe!(self, NluQuery { query }, handler)
     }
 
-    fn subscribe_partial_query(&self, handler: Callback<NluSlotQueryMessage>) -> Fallible<()> {
+    fn subscribe_partial_query(&self, handler: Callback<NluSlotQueryMessage>) -> HermesResult<Subscription> {
         subscribe!(self, NluPartialQuery { query }, handler)
     }
 
-    fn subscribe_reload(&self, handler: Callback0) -> Fallible<()> {
+    fn subscribe_reload(&self, handler: Callback0) -> HermesResult<Subscription> {
         subscribe!(self, NluReload, handler)
     }
 
-    fn publish_slot_parsed(&self, slot: NluSlotMessage) -> Fallible<()> {
+    fn publish_slot_parsed(&self, slot: NluSlotMessage) -> HermesResult<()> {
         self.publish(NluSlotParsed { slot })
     }
 
-    fn publish_intent_parsed(&self, intent: NluIntentMessage) -> Fallible<()> {
+    fn publish_intent_parsed(&self, intent: NluIntentMessage) -> HermesResult<()> {
         self.publish(NluIntentParsed { intent })
     }
 
-    fn publish_intent_not_recognized(&self, status: NluIntentNotRecognizedMessage) -> Fallible<()> {
+    fn publish_intent_not_recognized(&self, status: NluIntentNotRecognizedMessage) -> HermesResult<()> {
         self.publish(NluIntentNotRecognized { status })
     }
 }
@@ -477,14 +552,14 @@ struct ToggleableToggleOff<T> {
 }
 
 impl<T: Send + Sync + Debug + Copy + 'static> ToggleableFacade for InProcessComponent<T> {
-    fn publish_toggle_on(&self) -> Fallible<()> {
+    fn publish_toggle_on(&self) -> HermesResult<()> {
         let toggle_on: ToggleableToggleOn<T> = ToggleableToggleOn {
             component: self.component,
         };
         self.publish(toggle_on)
     }
 
-    fn publish_toggle_off(&self) -> Fallible<()> {
+    fn publish_toggle_off(&self) -> HermesResult<()> {
         let toggle_off: ToggleableToggleOff<T> = ToggleableToggleOff {
             component: self.component,
         };
@@ -492,12 +567,12 @@ impl<T: Send + Sync + Debug + Copy + 'static> ToggleableFacade for InProcessComp
     }
 }
 
-impl<T: Send + Sync + Debug + 'static> ToggleableBackendFacade for InProcessComponent<T> {
-    fn subscribe_toggle_on(&self, handler: Callback0) -> Fallible<()> {
+impl<T: Send + Sync + Debug + Copy + 'static> ToggleableBackendFacade for InProcessComponent<T> {
+    fn subscribe_toggle_on(&self, handler: Callback0) -> HermesResult<Subscription> {
         subscribe!(self, ToggleableToggleOn<T>, handler)
     }
 
-    fn subscribe_toggle_off(&self, handler: Callback0) -> Fallible<()> {
+    fn subscribe_toggle_off(&self, handler: Callback0) -> HermesResult<Subscription> {
         subscribe!(self, ToggleableToggleOff<T>, handler)
     }
 }
@@ -516,33 +591,33 @@ struct VoiceActivityVadDown {
 }
 
 impl VoiceActivityFacade for InProcessComponent<VoiceActivity> {
-    fn subscribe_vad_up(&self, site_id: String, handler: Callback<VadUpMessage>) -> Fallible<()> {
+    fn subscribe_vad_up(&self, site_id: String, handler: Callback<VadUpMessage>) -> HermesResult<Subscription> {
         subscribe_filter!(self, VoiceActivityVadUp { vad_up }, handler, site_id, |it| &it
             .vad_up
             .site_id)
     }
 
-    fn subscribe_vad_down(&self, site_id: String, handler: Callback<VadDownMessage>) -> Fallible<()> {
+    fn subscribe_vad_down(&self, site_id: String, handler: Callback<VadDownMessage>) -> HermesResult<Subscription> {
         subscribe_filter!(self, VoiceActivityVadDown { vad_down }, handler, site_id, |it| &it
             .vad_down
             .site_id)
     }
 
-    fn subscribe_all_vad_up(&self, handler: Callback<VadUpMessage>) -> Fallible<()> {
+    fn subscribe_all_vad_up(&self, handler: Callback<VadUpMessage>) -> HermesResult<Subscription> {
         subscribe!(self, VoiceActivityVadUp { vad_up }, handler)
     }
 
-    fn subscribe_all_vad_down(&self, handler: Callback<VadDownMessage>) -> Fallible<()> {
+    fn subscribe_all_vad_down(&self, handler: Callback<VadDownMessage>) -> HermesResult<Subscription> {
         subscribe!(self, VoiceActivityVadDown { vad_down }, handler)
     }
 }
 
 impl VoiceActivityBackendFacade for InProcessComponent<VoiceActivity> {
-    fn publish_vad_up(&self, vad_up: VadUpMessage) -> Fallible<()> {
+    fn publish_vad_up(&self, vad_up: VadUpMessage) -> HermesResult<()> {
         self.publish(VoiceActivityVadUp { vad_up })
     }
 
-    fn publish_vad_down(&self, vad_down: VadDownMessage) -> Fallible<()> {
+    fn publish_vad_down(&self, vad_down: VadDownMessage) -> HermesResult<()> {
         self.publish(VoiceActivityVadDown { vad_down })
     }
 }
@@ -557,17 +632,17 @@ struct HotwordDetected {
 }
 
 impl HotwordFacade for InProcessComponent<Hotword> {
-    fn subscribe_detected(&self, id: String, handler: Callback<HotwordDetectedMessage>) -> Fallible<()> {
+    fn subscribe_detected(&self, id: String, handler: Callback<HotwordDetectedMessage>) -> HermesResult<Subscription> {
         subscribe_filter!(self, HotwordDetected { message }, handler, id, |it| &it.id)
     }
 
-    fn subscribe_all_detected(&self, handler: Callback<HotwordDetectedMessage>) -> Fallible<()> {
+    fn subscribe_all_detected(&self, handler: Callback<HotwordDetectedMessage>) -> HermesResult<Subscription> {
         subscribe!(self, HotwordDetected { message }, handler)
     }
 }
 
 impl HotwordBackendFacade for InProcessComponent<Hotword> {
-    fn publish_detected(&self, id: String, message: HotwordDetectedMessage) -> Fallible<()> {
+    fn publish_detected(&self, id: String, message: HotwordDetectedMessage) -> HermesResult<()> {
         self.publish(HotwordDetected { id, message })
     }
 }
@@ -606,45 +681,45 @@ struct AsrPartialTextCaptured {
 }
 
 impl AsrFacade for InProcessComponent<Asr> {
-    fn publish_start_listening(&self, start: AsrStartListeningMessage) -> Fallible<()> {
+    fn publish_start_listening(&self, start: AsrStartListeningMessage) -> HermesResult<()> {
         self.publish(AsrStartListening { start })
     }
 
-    fn publish_stop_listening(&self, site: SiteMessage) -> Fallible<()> {
+    fn publish_stop_listening(&self, site: SiteMessage) -> HermesResult<()> {
         self.publish(AsrStopListening { site })
     }
 
-    fn publish_reload(&self) -> Fallible<()> {
+    fn publish_reload(&self) -> HermesResult<()> {
         self.publish(AsrReload {})
     }
 
-    fn subscribe_text_captured(&self, handler: Callback<TextCapturedMessage>) -> Fallible<()> {
+    fn subscribe_text_captured(&self, handler: Callback<TextCapturedMessage>) -> HermesResult<Subscription> {
         subscribe!(self, AsrTextCaptured { text_captured }, handler)
     }
 
-    fn subscribe_partial_text_captured(&self, handler: Callback<TextCapturedMessage>) -> Fallible<()> {
+    fn subscribe_partial_text_captured(&self, handler: Callback<TextCapturedMessage>) -> HermesResult<Subscription> {
         subscribe!(self, AsrPartialTextCaptured { text_captured }, handler)
     }
 }
 
 impl AsrBackendFacade for InProcessComponent<Asr> {
-    fn subscribe_start_listening(&self, handler: Callback<AsrStartListeningMessage>) -> Fallible<()> {
+    fn subscribe_start_listening(&self, handler: Callback<AsrStartListeningMessage>) -> HermesResult<Subscription> {
         subscribe!(self, AsrStartListening { start }, handler)
     }
 
-    fn subscribe_stop_listening(&self, handler: Callback<SiteMessage>) -> Fallible<()> {
+    fn subscribe_stop_listening(&self, handler: Callback<SiteMessage>) -> HermesResult<Subscription> {
         subscribe!(self, AsrStopListening { site }, handler)
     }
 
-    fn subscribe_reload(&self, handler: Callback0) -> Fallible<()> {
+    fn subscribe_reload(&self, handler: Callback0) -> HermesResult<Subscription> {
         subscribe!(self, AsrReload, handler)
     }
 
-    fn publish_text_captured(&self, text_captured: TextCapturedMessage) -> Fallible<()> {
+    fn publish_text_captured(&self, text_captured: TextCapturedMessage) -> HermesResult<()> {
         self.publish(AsrTextCaptured { text_captured })
     }
 
-    fn publish_partial_text_captured(&self, text_captured: TextCapturedMessage) -> Fallible<()> {
+    fn publish_partial_text_captured(&self, text_captured: TextCapturedMessage) -> HermesResult<()> {
         self.publish(AsrPartialTextCaptured { text_captured })
     }
 }
@@ -668,29 +743,29 @@ struct TtsRegisterSound {
 }
 
 impl TtsFacade for InProcessComponent<Tts> {
-    fn publish_say(&self, to_say: SayMessage) -> Fallible<()> {
+    fn publish_say(&self, to_say: SayMessage) -> HermesResult<()> {
         self.publish(TtsSay { to_say })
     }
 
-    fn subscribe_say_finished(&self, handler: Callback<SayFinishedMessage>) -> Fallible<()> {
+    fn subscribe_say_finished(&self, handler: Callback<SayFinishedMessage>) -> HermesResult<Subscription> {
         subscribe!(self, TtsSayFinished { status }, handler)
     }
 
-    fn publish_register_sound(&self, sound: RegisterSoundMessage) -> Fallible<()> {
+    fn publish_register_sound(&self, sound: RegisterSoundMessage) -> HermesResult<()> {
         self.publish(TtsRegisterSound { sound })
     }
 }
 
 impl TtsBackendFacade for InProcessComponent<Tts> {
-    fn publish_say_finished(&self, status: SayFinishedMessage) -> Fallible<()> {
+    fn publish_say_finished(&self, status: SayFinishedMessage) -> HermesResult<()> {
         self.publish(TtsSayFinished { status })
     }
 
-    fn subscribe_say(&self, handler: Callback<SayMessage>) -> Fallible<()> {
+    fn subscribe_say(&self, handler: Callback<SayMessage>) -> HermesResult<Subscription> {
         subscribe!(self, TtsSay { to_say }, handler)
     }
 
-    fn subscribe_register_sound(&self, handler: Callback<RegisterSoundMessage>) -> Fallible<()> {
+    fn subscribe_register_sound(&self, handler: Callback<RegisterSoundMessage>) -> HermesResult<Subscription> {
         subscribe!(self, TtsRegisterSound { sound }, handler)
     }
 }
@@ -724,53 +799,69 @@ struct AudioServerReplayResponse {
 }
 
 impl AudioServerFacade for InProcessComponent<AudioServer> {
-    fn publish_play_bytes(&self, bytes: PlayBytesMessage) -> Fallible<()> {
+    fn publish_play_bytes(&self, bytes: PlayBytesMessage) -> HermesResult<()> {
         self.publish(AudioServerPlayBytes { bytes })
     }
 
-    fn subscribe_play_finished(&self, site_id: String, handler: Callback<PlayFinishedMessage>) -> Fallible<()> {
+    fn subscribe_play_finished(
+        &self,
+        site_id: String,
+        handler: Callback<PlayFinishedMessage>,
+    ) -> HermesResult<Subscription> {
         subscribe_filter!(self, AudioServerPlayFinished { status }, handler, site_id)
     }
 
-    fn subscribe_all_play_finished(&self, handler: Callback<PlayFinishedMessage>) -> Fallible<()> {
+    fn subscribe_all_play_finished(&self, handler: Callback<PlayFinishedMessage>) -> HermesResult<Subscription> {
         subscribe!(self, AudioServerPlayFinished { status }, handler)
     }
 
-    fn subscribe_audio_frame(&self, site_id: String, handler: Callback<AudioFrameMessage>) -> Fallible<()> {
+    fn subscribe_audio_frame(
+        &self,
+        site_id: String,
+        handler: Callback<AudioFrameMessage>,
+    ) -> HermesResult<Subscription> {
         subscribe_filter!(self, AudioServerAudioFrame { frame }, handler, site_id)
     }
 
-    fn publish_replay_request(&self, request: ReplayRequestMessage) -> Fallible<()> {
+    fn publish_replay_request(&self, request: ReplayRequestMessage) -> HermesResult<()> {
         self.publish(AudioServerReplayRequest { request })
     }
 
-    fn subscribe_replay_response(&self, site_id: String, handler: Callback<AudioFrameMessage>) -> Fallible<()> {
+    fn subscribe_replay_response(
+        &self,
+        site_id: String,
+        handler: Callback<AudioFrameMessage>,
+    ) -> HermesResult<Subscription> {
         subscribe_filter!(self, AudioServerReplayResponse { frame }, handler, site_id)
     }
 }
 
 impl AudioServerBackendFacade for InProcessComponent<AudioServer> {
-    fn subscribe_play_bytes(&self, site_id: String, handler: Callback<PlayBytesMessage>) -> Fallible<()> {
+    fn subscribe_play_bytes(&self, site_id: String, handler: Callback<PlayBytesMessage>) -> HermesResult<Subscription> {
         subscribe_filter!(self, AudioServerPlayBytes { bytes }, handler, site_id)
     }
 
-    fn subscribe_all_play_bytes(&self, handler: Callback<PlayBytesMessage>) -> Fallible<()> {
+    fn subscribe_all_play_bytes(&self, handler: Callback<PlayBytesMessage>) -> HermesResult<Subscription> {
         subscribe!(self, AudioServerPlayBytes { bytes }, handler)
     }
 
-    fn publish_play_finished(&self, status: PlayFinishedMessage) -> Fallible<()> {
+    fn publish_play_finished(&self, status: PlayFinishedMessage) -> HermesResult<()> {
         self.publish(AudioServerPlayFinished { status })
     }
 
-    fn publish_audio_frame(&self, frame: AudioFrameMessage) -> Fallible<()> {
+    fn publish_audio_frame(&self, frame: AudioFrameMessage) -> HermesResult<()> {
         self.publish(AudioServerAudioFrame { frame })
     }
 
-    fn subscribe_replay_request(&self, site_id: String, handler: Callback<ReplayRequestMessage>) -> Fallible<()> {
+    fn subscribe_replay_request(
+        &self,
+        site_id: String,
+        handler: Callback<ReplayRequestMessage>,
+    ) -> HermesResult<Subscription> {
         subscribe_filter!(self, AudioServerReplayRequest { request }, handler, site_id)
     }
 
-    fn publish_replay_response(&self, frame: AudioFrameMessage) -> Fallible<()> {
+    fn publish_replay_response(&self, frame: AudioFrameMessage) -> HermesResult<()> {
         self.publish(AudioServerReplayResponse { frame })
     }
 }
@@ -823,87 +914,114 @@ struct DialogueConfigure {
     config: DialogueConfigureMessage,
 }
 
+#[derive(Debug)]
+struct DialogueSessionStateRequest {}
+
+#[derive(Debug)]
+struct DialogueSessionState {
+    state: SessionStateMessage,
+}
+
 impl DialogueFacade for InProcessComponent<Dialogue> {
-    fn subscribe_session_queued(&self, handler: Callback<SessionQueuedMessage>) -> Fallible<()> {
+    fn subscribe_session_queued(&self, handler: Callback<SessionQueuedMessage>) -> HermesResult<Subscription> {
         subscribe!(self, DialogueSessionQueued { status }, handler)
     }
 
-    fn subscribe_session_started(&self, handler: Callback<SessionStartedMessage>) -> Fallible<()> {
+    fn subscribe_session_started(&self, handler: Callback<SessionStartedMessage>) -> HermesResult<Subscription> {
         subscribe!(self, DialogueSessionStarted { status }, handler)
     }
 
-    fn subscribe_intent(&self, intent_name: String, handler: Callback<IntentMessage>) -> Fallible<()> {
+    fn subscribe_intent(&self, intent_name: String, handler: Callback<IntentMessage>) -> HermesResult<Subscription> {
         subscribe_filter!(self, DialogueIntent { intent }, handler, intent_name, |it| &it
             .intent
             .intent
             .intent_name)
     }
 
-    fn subscribe_intents(&self, handler: Callback<IntentMessage>) -> Fallible<()> {
+    fn subscribe_intents(&self, handler: Callback<IntentMessage>) -> HermesResult<Subscription> {
         subscribe!(self, DialogueIntent { intent }, handler)
     }
 
-    fn subscribe_intent_not_recognized(&self, handler: Callback<IntentNotRecognizedMessage>) -> Fallible<()> {
+    fn subscribe_intent_not_recognized(
+        &self,
+        handler: Callback<IntentNotRecognizedMessage>,
+    ) -> HermesResult<Subscription> {
         subscribe!(self, DialogueIntentNotRecognized { intent_not_recognized }, handler)
     }
 
-    fn subscribe_session_ended(&self, handler: Callback<SessionEndedMessage>) -> Fallible<()> {
+    fn subscribe_session_ended(&self, handler: Callback<SessionEndedMessage>) -> HermesResult<Subscription> {
         subscribe!(self, DialogueSessionEnded { status }, handler)
     }
 
-    fn publish_start_session(&self, start_session: StartSessionMessage) -> Fallible<()> {
+    fn publish_start_session(&self, start_session: StartSessionMessage) -> HermesResult<()> {
         self.publish(DialogueStartSession { start_session })
     }
 
-    fn publish_continue_session(&self, continue_session: ContinueSessionMessage) -> Fallible<()> {
+    fn publish_continue_session(&self, continue_session: ContinueSessionMessage) -> HermesResult<()> {
         self.publish(DialogueContinueSession { continue_session })
     }
 
-    fn publish_end_session(&self, end_session: EndSessionMessage) -> Fallible<()> {
+    fn publish_end_session(&self, end_session: EndSessionMessage) -> HermesResult<()> {
         self.publish(DialogueEndSession { end_session })
     }
 
-    fn publish_configure(&self, config: DialogueConfigureMessage) -> Fallible<()> {
+    fn publish_configure(&self, config: DialogueConfigureMessage) -> HermesResult<()> {
         self.publish(DialogueConfigure { config })
     }
+
+    fn publish_session_state_request(&self) -> HermesResult<()> {
+        self.publish(DialogueSessionStateRequest {})
+    }
+
+    fn subscribe_session_state(&self, handler: Callback<SessionStateMessage>) -> HermesResult<Subscription> {
+        subscribe!(self, DialogueSessionState { state }, handler)
+    }
 }
 
 impl DialogueBackendFacade for InProcessComponent<Dialogue> {
-    fn publish_session_queued(&self, status: SessionQueuedMessage) -> Fallible<()> {
+    fn publish_session_queued(&self, status: SessionQueuedMessage) -> HermesResult<()> {
         self.publish(DialogueSessionQueued { status })
     }
 
-    fn publish_session_started(&self, status: SessionStartedMessage) -> Fallible<()> {
+    fn publish_session_started(&self, status: SessionStartedMessage) -> HermesResult<()> {
         self.publish(DialogueSessionStarted { status })
     }
 
-    fn publish_intent(&self, intent: IntentMessage) -> Fallible<()> {
+    fn publish_intent(&self, intent: IntentMessage) -> HermesResult<()> {
         self.publish(DialogueIntent { intent })
     }
 
-    fn publish_intent_not_recognized(&self, intent_not_recognized: IntentNotRecognizedMessage) -> Fallible<()> {
+    fn publish_intent_not_recognized(&self, intent_not_recognized: IntentNotRecognizedMessage) -> HermesResult<()> {
         self.publish(DialogueIntentNotRecognized { intent_not_recognized })
     }
 
-    fn publish_session_ended(&self, status: SessionEndedMessage) -> Fallible<()> {
+    fn publish_session_ended(&self, status: SessionEndedMessage) -> HermesResult<()> {
         self.publish(DialogueSessionEnded { status })
     }
 
-    fn subscribe_start_session(&self, handler: Callback<StartSessionMessage>) -> Fallible<()> {
+    fn subscribe_start_session(&self, handler: Callback<StartSessionMessage>) -> HermesResult<Subscription> {
         subscribe!(self, DialogueStartSession { start_session }, handler)
     }
 
-    fn subscribe_continue_session(&self, handler: Callback<ContinueSessionMessage>) -> Fallible<()> {
+    fn subscribe_continue_session(&self, handler: Callback<ContinueSessionMessage>) -> HermesResult<Subscription> {
         subscribe!(self, DialogueContinueSession { continue_session }, handler)
     }
 
-    fn subscribe_end_session(&self, handler: Callback<EndSessionMessage>) -> Fallible<()> {
+    fn subscribe_end_session(&self, handler: Callback<EndSessionMessage>) -> HermesResult<Subscription> {
         subscribe!(self, DialogueEndSession { end_session }, handler)
     }
 
-    fn subscribe_configure(&self, handler: Callback<DialogueConfigureMessage>) -> Fallible<()> {
+    fn subscribe_configure(&self, handler: Callback<DialogueConfigureMessage>) -> HermesResult<Subscription> {
         subscribe!(self, DialogueConfigure { config }, handler)
     }
+
+    fn subscribe_session_state_request(&self, handler: Callback0) -> HermesResult<Subscription> {
+        subscribe!(self, DialogueSessionStateRequest, handler)
+    }
+
+    fn publish_session_state(&self, state: SessionStateMessage) -> HermesResult<()> {
+        self.publish(DialogueSessionState { state })
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -923,33 +1041,280 @@ struct InjectionStatus {
 struct InjectionStatusRequest {}
 
 impl InjectionFacade for InProcessComponent<Injection> {
-    fn publish_injection_request(&self, request: InjectionRequestMessage) -> Fallible<()> {
+    fn publish_injection_request(&self, request: InjectionRequestMessage) -> HermesResult<()> {
         self.publish(InjectionPerform { request })
     }
 
-    fn publish_injection_status_request(&self) -> Fallible<()> {
+    fn publish_injection_status_request(&self) -> HermesResult<()> {
         self.publish(InjectionStatusRequest {})
     }
 
-    fn subscribe_injection_status(&self, handler: Callback<InjectionStatusMessage>) -> Fallible<()> {
+    fn subscribe_injection_status(&self, handler: Callback<InjectionStatusMessage>) -> HermesResult<Subscription> {
         subscribe!(self, InjectionStatus { status }, handler)
     }
 }
 
 impl InjectionBackendFacade for InProcessComponent<Injection> {
-    fn subscribe_injection_request(&self, handler: Callback<InjectionRequestMessage>) -> Fallible<()> {
+    fn subscribe_injection_request(&self, handler: Callback<InjectionRequestMessage>) -> HermesResult<Subscription> {
         subscribe!(self, InjectionPerform { request }, handler)
     }
 
-    fn subscribe_injection_status_request(&self, handler: Callback0) -> Fallible<()> {
+    fn subscribe_injection_status_request(&self, handler: Callback0) -> HermesResult<Subscription> {
         subscribe!(self, InjectionStatusRequest, handler)
     }
 
-    fn publish_injection_status(&self, status: InjectionStatusMessage) -> Fallible<()> {
+    fn publish_injection_status(&self, status: InjectionStatusMessage) -> HermesResult<()> {
         self.publish(InjectionStatus { status })
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+struct Protocol;
+
+#[derive(Debug)]
+struct ProtocolHandshakeRequest {}
+
+#[derive(Debug)]
+struct ProtocolHandshake {
+    handshake: HandshakeMessage,
+}
+
+impl HandshakeFacade for InProcessComponent<Protocol> {
+    fn publish_handshake_request(&self) -> HermesResult<()> {
+        self.publish(ProtocolHandshakeRequest {})
+    }
+
+    fn subscribe_handshake_request(&self, handler: Callback0) -> HermesResult<Subscription> {
+        subscribe!(self, ProtocolHandshakeRequest, handler)
+    }
+
+    fn publish_handshake(&self, handshake: HandshakeMessage) -> HermesResult<()> {
+        self.publish(ProtocolHandshake { handshake })
+    }
+
+    fn subscribe_handshakes(&self, handler: Callback<HandshakeMessage>) -> HermesResult<Subscription> {
+        subscribe!(self, ProtocolHandshake { handshake }, handler)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Registry;
+
+#[derive(Debug)]
+struct RegistryComponentUp {
+    status: ComponentStatusMessage,
+}
+
+#[derive(Debug)]
+struct RegistryComponentDown {
+    status: ComponentStatusMessage,
+}
+
+#[derive(Debug)]
+struct RegistrySnapshotRequest {}
+
+#[derive(Debug)]
+struct RegistrySnapshot {
+    snapshot: RegistrySnapshotMessage,
+}
+
+impl RegistryFacade for InProcessComponent<Registry> {
+    fn publish_snapshot_request(&self) -> HermesResult<()> {
+        self.publish(RegistrySnapshotRequest {})
+    }
+
+    fn subscribe_snapshot(&self, handler: Callback<RegistrySnapshotMessage>) -> HermesResult<Subscription> {
+        subscribe!(self, RegistrySnapshot { snapshot }, handler)
+    }
+
+    fn subscribe_component_up(&self, handler: Callback<ComponentStatusMessage>) -> HermesResult<Subscription> {
+        subscribe!(self, RegistryComponentUp { status }, handler)
+    }
+
+    fn subscribe_component_down(&self, handler: Callback<ComponentStatusMessage>) -> HermesResult<Subscription> {
+        subscribe!(self, RegistryComponentDown { status }, handler)
+    }
+}
+
+impl RegistryBackendFacade for InProcessComponent<Registry> {
+    fn subscribe_snapshot_request(&self, handler: Callback0) -> HermesResult<Subscription> {
+        subscribe!(self, RegistrySnapshotRequest, handler)
+    }
+
+    fn publish_snapshot(&self, snapshot: RegistrySnapshotMessage) -> HermesResult<()> {
+        self.publish(RegistrySnapshot { snapshot })
+    }
+
+    fn publish_component_up(&self, status: ComponentStatusMessage) -> HermesResult<()> {
+        self.publish(RegistryComponentUp { status })
+    }
+
+    fn publish_component_down(&self, status: ComponentStatusMessage) -> HermesResult<()> {
+        self.publish(RegistryComponentDown { status })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Site;
+
+#[derive(Debug)]
+struct SiteRegister {
+    site: SiteInfoMessage,
+}
+
+#[derive(Debug)]
+struct SiteUnregister {
+    site: SiteMessage,
+}
+
+#[derive(Debug)]
+struct SiteSitesRequest {}
+
+#[derive(Debug)]
+struct SiteSites {
+    sites: SitesMessage,
+}
+
+impl SiteFacade for InProcessComponent<Site> {
+    fn publish_register_site(&self, site: SiteInfoMessage) -> HermesResult<()> {
+        self.publish(SiteRegister { site })
+    }
+
+    fn publish_unregister_site(&self, site: SiteMessage) -> HermesResult<()> {
+        self.publish(SiteUnregister { site })
+    }
+
+    fn publish_sites_request(&self) -> HermesResult<()> {
+        self.publish(SiteSitesRequest {})
+    }
+
+    fn subscribe_sites(&self, handler: Callback<SitesMessage>) -> HermesResult<Subscription> {
+        subscribe!(self, SiteSites { sites }, handler)
+    }
+}
+
+impl SiteBackendFacade for InProcessComponent<Site> {
+    fn subscribe_register_site(&self, handler: Callback<SiteInfoMessage>) -> HermesResult<Subscription> {
+        subscribe!(self, SiteRegister { site }, handler)
+    }
+
+    fn subscribe_unregister_site(&self, handler: Callback<SiteMessage>) -> HermesResult<Subscription> {
+        subscribe!(self, SiteUnregister { site }, handler)
+    }
+
+    fn subscribe_sites_request(&self, handler: Callback0) -> HermesResult<Subscription> {
+        subscribe!(self, SiteSitesRequest, handler)
+    }
+
+    fn publish_sites(&self, sites: SitesMessage) -> HermesResult<()> {
+        self.publish(SiteSites { sites })
+    }
+}
+
+/// A message of the bus
+trait BusMessage: ripb::Message + Debug + Validate {
+    /// The site of the identifiable component the message is about, where its errors are reported
+    fn site_id(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// The messages of the bus are checked through the ontology message they carry, if any, and the
+/// ones of the identifiable components tell their site after an `@`
+macro_rules! impl_bus_message {
+    ($($t:ident$(<$g:ident>)? $({ $field:ident })? $(@ $($site:ident).+)?),* $(,)?) => {
+        $(
+            impl$(<$g: Debug>)? Validate for $t$(<$g>)? {
+                $(
+                    fn validate(&self) -> Result<(), ValidationErrors> {
+                        self.$field.validate()
+                    }
+                )?
+            }
+
+            impl$(<$g: Send + Sync + Debug + 'static>)? BusMessage for $t$(<$g>)? {
+                $(
+                    fn site_id(&self) -> Option<&str> {
+                        Some(&self.$($site).+)
+                    }
+                )?
+            }
+        )*
+    };
+}
+
+impl_bus_message!(
+    ComponentVersionRequest<T>,
+    ComponentVersion<T> { version },
+    ComponentError<T> { error },
+    IdentifiableComponentVersionRequest<T> @ site_id,
+    IdentifiableComponentVersion<T> { version } @ site_id,
+    IdentifiableComponentError<T> { error } @ site_id,
+    IdentifiableToggleableToggleOn<T> { site } @ site.site_id,
+    IdentifiableToggleableToggleOff<T> { site } @ site.site_id,
+    ToggleableToggleOn<T>,
+    ToggleableToggleOff<T>,
+    NluQuery { query },
+    NluPartialQuery { query },
+    NluSlotParsed { slot },
+    NluIntentParsed { intent },
+    NluIntentNotRecognized { status },
+    NluReload,
+    VoiceActivityVadUp { vad_up } @ vad_up.site_id,
+    VoiceActivityVadDown { vad_down } @ vad_down.site_id,
+    HotwordDetected { message } @ id,
+    AsrStartListening { start },
+    AsrStopListening { site },
+    AsrReload,
+    AsrTextCaptured { text_captured },
+    AsrPartialTextCaptured { text_captured },
+    TtsSay { to_say },
+    TtsSayFinished { status },
+    TtsRegisterSound { sound },
+    AudioServerPlayBytes { bytes } @ bytes.site_id,
+    AudioServerPlayFinished { status } @ status.site_id,
+    AudioServerAudioFrame { frame } @ frame.site_id,
+    AudioServerReplayRequest { request } @ request.site_id,
+    AudioServerReplayResponse { frame } @ frame.site_id,
+    DialogueSessionQueued { status },
+    DialogueSessionStarted { status },
+    DialogueIntent { intent },
+    DialogueIntentNotRecognized { intent_not_recognized },
+    DialogueSessionEnded { status },
+    DialogueStartSession { start_session },
+    DialogueContinueSession { continue_session },
+    DialogueEndSession { end_session },
+    DialogueConfigure { config },
+    DialogueSessionStateRequest,
+    DialogueSessionState { state },
+    InjectionPerform { request },
+    InjectionStatus { status },
+    InjectionStatusRequest,
+    ProtocolHandshakeRequest,
+    ProtocolHandshake { handshake },
+    RegistryComponentUp { status },
+    RegistryComponentDown { status },
+    RegistrySnapshotRequest,
+    RegistrySnapshot { snapshot },
+    SiteRegister { site },
+    SiteUnregister { site },
+    SiteSitesRequest,
+    SiteSites { sites },
+);
+
+/// The in process bus can't be disconnected
+struct InProcessConnection;
+
+impl ConnectionFacade for InProcessConnection {
+    fn state(&self) -> HermesResult<ConnectionState> {
+        Ok(ConnectionState::Connected)
+    }
+
+    fn subscribe_state_changed(&self, _handler: Callback<ConnectionState>) -> HermesResult<Subscription> {
+        Ok(Subscription::new(|| {}))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -961,5 +1326,116 @@ mod tests {
         (Rc::clone(&handler), handler)
     }
 
+    #[test]
+    fn strict_handlers_reject_invalid_messages() {
+        let not_recognized = |confidence_score: f32| NluIntentNotRecognizedMessage {
+            id: None,
+            input: "hello".into(),
+            confidence_score,
+            session_id: None,
+        };
+        let strict =
+            InProcessHermesProtocolHandler::new_with_validation(ValidationMode::Strict, ValidationMode::Strict);
+        match strict.nlu_backend().publish_intent_not_recognized(not_recognized(2.0)) {
+            Err(HermesError::InvalidMessage(errors)) => {
+                assert_eq!(errors.to_string(), "confidenceScore must be between 0 and 1")
+            }
+            result => panic!("unexpected result {:?}", result.map_err(|e| e.to_string())),
+        }
+
+        // components that don't check what they publish, unlike the subscribers
+        let lenient = InProcessHermesProtocolHandler::new_with_validation(ValidationMode::Off, ValidationMode::Strict);
+        let (tx, rx) = std::sync::mpsc::channel();
+        let tx = Mutex::new(tx);
+        let _subscription = lenient
+            .nlu()
+            .subscribe_intent_not_recognized(Callback::new(move |it: &NluIntentNotRecognizedMessage| {
+                let _ = tx.lock().unwrap().send(it.clone());
+            }))
+            .unwrap();
+        let (error_tx, error_rx) = std::sync::mpsc::channel();
+        let error_tx = Mutex::new(error_tx);
+        let _error_subscription = lenient
+            .nlu()
+            .subscribe_error(Callback::new(move |it: &ErrorMessage| {
+                let _ = error_tx.lock().unwrap().send(it.clone());
+            }))
+            .unwrap();
+
+        let nlu = lenient.nlu_backend();
+        nlu.publish_intent_not_recognized(not_recognized(2.0)).unwrap();
+        nlu.publish_intent_not_recognized(not_recognized(0.8)).unwrap();
+        assert_eq!(
+            rx.recv_timeout(std::time::Duration::from_secs(1)).unwrap(),
+            not_recognized(0.8)
+        );
+        let error = error_rx.recv_timeout(std::time::Duration::from_secs(1)).unwrap();
+        assert_eq!(error.error, "Invalid message: confidenceScore must be between 0 and 1");
+        assert!(rx.recv_timeout(std::time::Duration::from_millis(100)).is_err());
+    }
+
+    #[test]
+    fn rejects_are_reported_once_per_message() {
+        let lenient = InProcessHermesProtocolHandler::new_with_validation(ValidationMode::Off, ValidationMode::Strict);
+        let _subscriptions = (0..3)
+            .map(|_| {
+                lenient
+                    .nlu()
+                    .subscribe_intent_not_recognized(Callback::new(|_: &NluIntentNotRecognizedMessage| {}))
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let tx = Mutex::new(tx);
+        let _error_subscription = lenient
+            .nlu()
+            .subscribe_error(Callback::new(move |it: &ErrorMessage| {
+                let _ = tx.lock().unwrap().send(it.clone());
+            }))
+            .unwrap();
+
+        lenient
+            .nlu_backend()
+            .publish_intent_not_recognized(NluIntentNotRecognizedMessage {
+                id: None,
+                input: "hello".into(),
+                confidence_score: 2.0,
+                session_id: None,
+            })
+            .unwrap();
+        assert!(rx.recv_timeout(std::time::Duration::from_secs(1)).is_ok());
+        assert!(rx.recv_timeout(std::time::Duration::from_millis(200)).is_err());
+    }
+
+    #[test]
+    fn rejects_of_identifiable_components_are_reported_on_their_site() {
+        let lenient = InProcessHermesProtocolHandler::new_with_validation(ValidationMode::Off, ValidationMode::Strict);
+        let _subscription = lenient
+            .audio_server()
+            .subscribe_all_play_finished(Callback::new(|_: &PlayFinishedMessage| {}))
+            .unwrap();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let tx = Mutex::new(tx);
+        let _error_subscription = lenient
+            .audio_server()
+            .subscribe_error(
+                "kitchen".into(),
+                Callback::new(move |it: &ErrorMessage| {
+                    let _ = tx.lock().unwrap().send(it.clone());
+                }),
+            )
+            .unwrap();
+
+        lenient
+            .audio_server_backend()
+            .publish_play_finished(PlayFinishedMessage {
+                id: "".into(),
+                site_id: "kitchen".into(),
+            })
+            .unwrap();
+        let error = rx.recv_timeout(std::time::Duration::from_secs(1)).unwrap();
+        assert_eq!(error.error, "Invalid message: id must not be empty");
+    }
+
     hermes_test_suite::test_suite!();
 }
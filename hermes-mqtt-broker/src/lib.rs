@@ -0,0 +1,509 @@
+//! A minimal MQTT 3.1.1 broker running in-process, used to test `hermes-mqtt` without depending
+//! on an external server.
+//!
+//! It supports the `+` and `#` wildcards, retained messages, last wills and binary payloads.
+//! Sessions are not persisted and messages are delivered with a QoS of at most 1.
+
+mod packet;
+mod topic;
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, BufReader};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+
+use log::*;
+
+use crate::packet::*;
+
+/// A broker listening on a local port, it stops and disconnects its clients when dropped
+pub struct Broker {
+    local_addr: SocketAddr,
+    state: Arc<State>,
+    acceptor: Option<thread::JoinHandle<()>>,
+}
+
+impl Broker {
+    /// Start a broker on a port chosen by the OS
+    pub fn start() -> io::Result<Self> {
+        Self::bind("127.0.0.1:0")
+    }
+
+    pub fn bind<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        let local_addr = listener.local_addr()?;
+        let state = Arc::new(State::default());
+        let acceptor_state = Arc::clone(&state);
+        let acceptor = thread::Builder::new()
+            .name("mqtt-broker".into())
+            .spawn(move || accept(&listener, &acceptor_state))?;
+        debug!("MQTT broker listening on {}", local_addr);
+        Ok(Self {
+            local_addr,
+            state,
+            acceptor: Some(acceptor),
+        })
+    }
+
+    /// The address the broker listens on, usable as the server address of `hermes-mqtt`
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+impl Drop for Broker {
+    fn drop(&mut self) {
+        self.state.stopped.store(true, Ordering::SeqCst);
+        // wake up the acceptor so that it notices the broker is stopped
+        let _ = TcpStream::connect(self.local_addr);
+        if let Some(acceptor) = self.acceptor.take() {
+            let _ = acceptor.join();
+        }
+        for (_, client) in self.state.lock().clients.drain() {
+            let _ = client.stream.shutdown(Shutdown::Both);
+        }
+        debug!("MQTT broker on {} stopped", self.local_addr);
+    }
+}
+
+impl std::fmt::Debug for Broker {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Broker").field("local_addr", &self.local_addr).finish()
+    }
+}
+
+struct Client {
+    stream: TcpStream,
+    subscriptions: Vec<(String, u8)>,
+    last_packet_id: u16,
+}
+
+impl Client {
+    fn send(&mut self, packet: &Packet) {
+        // a client that can't be written to anymore is removed when its reader notices it
+        if let Err(e) = packet.write(&mut self.stream) {
+            debug!("Could not send {:?} to client: {}", packet, e)
+        }
+    }
+
+    /// The maximum QoS of the subscriptions matching the topic, if any
+    fn granted_qos(&self, topic: &str) -> Option<u8> {
+        self.subscriptions
+            .iter()
+            .filter(|(filter, _)| topic::matches(filter, topic))
+            .map(|(_, qos)| *qos)
+            .max()
+    }
+
+    fn deliver(&mut self, publish: &Publish, granted_qos: u8, retain: bool) {
+        let qos = publish.qos.min(granted_qos).min(1);
+        let packet_id = if qos > 0 {
+            self.last_packet_id = self.last_packet_id.checked_add(1).unwrap_or(1);
+            Some(self.last_packet_id)
+        } else {
+            None
+        };
+        self.send(&Packet::Publish(Publish {
+            topic: publish.topic.clone(),
+            payload: publish.payload.clone(),
+            qos,
+            retain,
+            dup: false,
+            packet_id,
+        }))
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    last_client_id: usize,
+    clients: HashMap<usize, Client>,
+    retained: BTreeMap<String, Publish>,
+}
+
+#[derive(Default)]
+struct State {
+    stopped: AtomicBool,
+    inner: Mutex<Inner>,
+}
+
+impl State {
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        // the state stays consistent even if a client thread panicked while holding the lock
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn add_client(&self, stream: TcpStream) -> usize {
+        let mut inner = self.lock();
+        inner.last_client_id += 1;
+        let id = inner.last_client_id;
+        inner.clients.insert(
+            id,
+            Client {
+                stream,
+                subscriptions: vec![],
+                last_packet_id: 0,
+            },
+        );
+        id
+    }
+
+    fn send(&self, client_id: usize, packet: &Packet) {
+        if let Some(client) = self.lock().clients.get_mut(&client_id) {
+            client.send(packet)
+        }
+    }
+
+    fn publish(&self, publish: Publish) {
+        let mut inner = self.lock();
+        if publish.retain {
+            if publish.payload.is_empty() {
+                inner.retained.remove(&publish.topic);
+            } else {
+                inner.retained.insert(publish.topic.clone(), publish.clone());
+            }
+        }
+        for client in inner.clients.values_mut() {
+            if let Some(qos) = client.granted_qos(&publish.topic) {
+                client.deliver(&publish, qos, false)
+            }
+        }
+    }
+
+    fn subscribe(&self, client_id: usize, packet_id: u16, filters: Vec<(String, u8)>) {
+        let mut inner = self.lock();
+        let Inner {
+            ref mut clients,
+            ref retained,
+            ..
+        } = *inner;
+        let client = match clients.get_mut(&client_id) {
+            Some(client) => client,
+            None => return,
+        };
+
+        let mut codes = vec![];
+        let mut accepted = vec![];
+        for (filter, qos) in filters {
+            if topic::is_valid_filter(&filter) {
+                let qos = qos.min(1);
+                client.subscriptions.retain(|(it, _)| *it != filter);
+                client.subscriptions.push((filter.clone(), qos));
+                accepted.push((filter, qos));
+                codes.push(qos);
+            } else {
+                codes.push(0x80);
+            }
+        }
+        client.send(&Packet::Suback { packet_id, codes });
+
+        for (name, publish) in retained {
+            let granted_qos = accepted
+                .iter()
+                .filter(|(filter, _)| topic::matches(filter, name))
+                .map(|(_, qos)| *qos)
+                .max();
+            if let Some(qos) = granted_qos {
+                client.deliver(publish, qos, true)
+            }
+        }
+    }
+
+    fn unsubscribe(&self, client_id: usize, packet_id: u16, filters: &[String]) {
+        if let Some(client) = self.lock().clients.get_mut(&client_id) {
+            client.subscriptions.retain(|(it, _)| !filters.contains(it));
+            client.send(&Packet::Unsuback(packet_id))
+        }
+    }
+
+    fn handle(&self, client_id: usize, packet: Packet) -> io::Result<()> {
+        match packet {
+            Packet::Publish(publish) => {
+                if !topic::is_valid_topic(&publish.topic) {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid topic name"));
+                }
+                match (publish.qos, publish.packet_id) {
+                    (1, Some(packet_id)) => self.send(client_id, &Packet::Puback(packet_id)),
+                    (2, Some(packet_id)) => self.send(client_id, &Packet::Pubrec(packet_id)),
+                    _ => {}
+                }
+                self.publish(publish)
+            }
+            Packet::Pubrel(packet_id) => self.send(client_id, &Packet::Pubcomp(packet_id)),
+            // acknowledgements of the messages delivered with QoS 1, nothing is resent anyway
+            Packet::Puback(_) => {}
+            Packet::Subscribe { packet_id, filters } => self.subscribe(client_id, packet_id, filters),
+            Packet::Unsubscribe { packet_id, filters } => self.unsubscribe(client_id, packet_id, &filters),
+            Packet::Pingreq => self.send(client_id, &Packet::Pingresp),
+            other => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unexpected packet {:?}", other),
+                ))
+            }
+        }
+        Ok(())
+    }
+}
+
+fn accept(listener: &TcpListener, state: &Arc<State>) {
+    for stream in listener.incoming() {
+        if state.stopped.load(Ordering::SeqCst) {
+            break;
+        }
+        let (writer, reader) = match stream.and_then(|stream| Ok((stream.try_clone()?, stream))) {
+            Ok(it) => it,
+            Err(e) => {
+                warn!("Could not accept MQTT connection: {}", e);
+                continue;
+            }
+        };
+        let client_id = state.add_client(writer);
+        let state = Arc::clone(state);
+        thread::spawn(move || {
+            let mut will = None;
+            let result = serve(&state, client_id, reader, &mut will);
+            state.lock().clients.remove(&client_id);
+            match result {
+                Ok(()) => debug!("MQTT client {} disconnected", client_id),
+                Err(e) => {
+                    debug!("MQTT client {} connection lost: {}", client_id, e);
+                    if let Some(will) = will {
+                        state.publish(Publish {
+                            topic: will.topic,
+                            payload: will.payload,
+                            qos: will.qos,
+                            retain: will.retain,
+                            dup: false,
+                            packet_id: None,
+                        })
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Serve a client until it disconnects, its last will is stored in `will` once connected
+fn serve(state: &State, client_id: usize, stream: TcpStream, will: &mut Option<Will>) -> io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    match Packet::read(&mut reader)? {
+        Packet::Connect(connect) => {
+            debug!("MQTT client {} connected as {:?}", client_id, connect.client_id);
+            *will = connect.will;
+            state.send(
+                client_id,
+                &Packet::Connack {
+                    session_present: false,
+                    code: 0,
+                },
+            );
+        }
+        other => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("expected CONNECT, got {:?}", other),
+            ))
+        }
+    }
+    loop {
+        match Packet::read(&mut reader)? {
+            Packet::Disconnect => return Ok(()),
+            packet => state.handle(client_id, packet)?,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    struct TestClient {
+        stream: TcpStream,
+    }
+
+    impl TestClient {
+        fn connect(broker: &Broker, will: Option<Will>) -> Self {
+            let mut client = Self {
+                stream: TcpStream::connect(broker.local_addr()).unwrap(),
+            };
+            client.stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+            client.send(Packet::Connect(Connect {
+                client_id: "test".into(),
+                clean_session: true,
+                keep_alive: 60,
+                will,
+            }));
+            assert_eq!(
+                client.receive(),
+                Packet::Connack {
+                    session_present: false,
+                    code: 0
+                }
+            );
+            client
+        }
+
+        fn send(&mut self, packet: Packet) {
+            packet.write(&mut self.stream).unwrap()
+        }
+
+        fn receive(&mut self) -> Packet {
+            Packet::read(&mut self.stream).unwrap()
+        }
+
+        fn subscribe(&mut self, filter: &str, qos: u8) {
+            self.send(Packet::Subscribe {
+                packet_id: 1,
+                filters: vec![(filter.into(), qos)],
+            });
+            assert_eq!(
+                self.receive(),
+                Packet::Suback {
+                    packet_id: 1,
+                    codes: vec![qos.min(1)]
+                }
+            );
+        }
+
+        fn publish(&mut self, topic: &str, payload: &[u8], retain: bool) {
+            self.send(Packet::Publish(publish(topic, payload, retain)))
+        }
+
+        fn receive_publish(&mut self) -> (String, Vec<u8>, bool) {
+            match self.receive() {
+                Packet::Publish(it) => (it.topic, it.payload, it.retain),
+                other => panic!("expected a PUBLISH, got {:?}", other),
+            }
+        }
+    }
+
+    fn publish(topic: &str, payload: &[u8], retain: bool) -> Publish {
+        Publish {
+            topic: topic.into(),
+            payload: payload.to_vec(),
+            qos: 0,
+            retain,
+            dup: false,
+            packet_id: None,
+        }
+    }
+
+    #[test]
+    fn publish_reaches_matching_subscribers() {
+        let broker = Broker::start().unwrap();
+        let mut subscriber = TestClient::connect(&broker, None);
+        let mut publisher = TestClient::connect(&broker, None);
+        subscriber.subscribe("hermes/audioServer/+/playBytes/#", 0);
+
+        publisher.publish("hermes/audioServer/default/playFinished", b"{}", false);
+        publisher.publish("hermes/audioServer/default/playBytes/id", &[0, 159, 255], false);
+
+        assert_eq!(
+            subscriber.receive_publish(),
+            (
+                "hermes/audioServer/default/playBytes/id".into(),
+                vec![0, 159, 255],
+                false
+            )
+        );
+    }
+
+    #[test]
+    fn unsubscribed_clients_dont_receive() {
+        let broker = Broker::start().unwrap();
+        let mut subscriber = TestClient::connect(&broker, None);
+        let mut publisher = TestClient::connect(&broker, None);
+        subscriber.subscribe("hermes/tts/say", 0);
+        subscriber.subscribe("hermes/tts/sayFinished", 0);
+        subscriber.send(Packet::Unsubscribe {
+            packet_id: 2,
+            filters: vec!["hermes/tts/say".into()],
+        });
+        assert_eq!(subscriber.receive(), Packet::Unsuback(2));
+
+        publisher.publish("hermes/tts/say", b"say", false);
+        publisher.publish("hermes/tts/sayFinished", b"finished", false);
+
+        assert_eq!(subscriber.receive_publish().1, b"finished".to_vec());
+    }
+
+    #[test]
+    fn retained_messages_are_sent_to_new_subscribers() {
+        let broker = Broker::start().unwrap();
+        let mut publisher = TestClient::connect(&broker, None);
+        publisher.publish("hermes/site/kitchen", b"first", true);
+        publisher.publish("hermes/site/kitchen", b"second", true);
+        publisher.publish("hermes/site/bedroom", b"bedroom", true);
+        publisher.publish("hermes/site/bedroom", b"", true);
+        publisher.send(Packet::Pingreq);
+        assert_eq!(publisher.receive(), Packet::Pingresp);
+
+        let mut subscriber = TestClient::connect(&broker, None);
+        subscriber.subscribe("hermes/site/+", 0);
+        assert_eq!(
+            subscriber.receive_publish(),
+            ("hermes/site/kitchen".into(), b"second".to_vec(), true)
+        );
+
+        publisher.publish("hermes/site/kitchen", b"third", true);
+        assert_eq!(
+            subscriber.receive_publish(),
+            ("hermes/site/kitchen".into(), b"third".to_vec(), false)
+        );
+    }
+
+    #[test]
+    fn qos_1_publish_is_acknowledged_and_delivered() {
+        let broker = Broker::start().unwrap();
+        let mut client = TestClient::connect(&broker, None);
+        client.subscribe("hermes/#", 2);
+        client.send(Packet::Publish(Publish {
+            qos: 1,
+            packet_id: Some(7),
+            ..publish("hermes/intent/lights_on", b"{}", false)
+        }));
+
+        assert_eq!(client.receive(), Packet::Puback(7));
+        match client.receive() {
+            Packet::Publish(it) => {
+                assert_eq!(it.qos, 1);
+                assert!(it.packet_id.is_some());
+            }
+            other => panic!("expected a PUBLISH, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn will_is_published_when_connection_is_lost() {
+        let broker = Broker::start().unwrap();
+        let mut subscriber = TestClient::connect(&broker, None);
+        subscriber.subscribe("hermes/component/+/offline", 0);
+        let client = TestClient::connect(
+            &broker,
+            Some(Will {
+                topic: "hermes/component/asr/offline".into(),
+                payload: b"gone".to_vec(),
+                qos: 0,
+                retain: false,
+            }),
+        );
+        client.stream.shutdown(Shutdown::Both).unwrap();
+
+        assert_eq!(
+            subscriber.receive_publish(),
+            ("hermes/component/asr/offline".into(), b"gone".to_vec(), false)
+        );
+    }
+
+    #[test]
+    fn dropping_broker_disconnects_clients() {
+        let broker = Broker::start().unwrap();
+        let mut client = TestClient::connect(&broker, None);
+        drop(broker);
+        assert!(Packet::read(&mut client.stream).is_err());
+    }
+}
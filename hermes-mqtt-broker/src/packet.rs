@@ -0,0 +1,373 @@
+//! Encoding and decoding of the MQTT 3.1.1 control packets
+
+use std::io::{self, Read, Write};
+
+fn invalid<T, S: Into<String>>(reason: S) -> io::Result<T> {
+    Err(io::Error::new(io::ErrorKind::InvalidData, reason.into()))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Will {
+    pub topic: String,
+    pub payload: Vec<u8>,
+    pub qos: u8,
+    pub retain: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Connect {
+    pub client_id: String,
+    pub clean_session: bool,
+    pub keep_alive: u16,
+    pub will: Option<Will>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Publish {
+    pub topic: String,
+    pub payload: Vec<u8>,
+    pub qos: u8,
+    pub retain: bool,
+    pub dup: bool,
+    /// Only present when `qos` is greater than 0
+    pub packet_id: Option<u16>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Packet {
+    Connect(Connect),
+    Connack { session_present: bool, code: u8 },
+    Publish(Publish),
+    Puback(u16),
+    Pubrec(u16),
+    Pubrel(u16),
+    Pubcomp(u16),
+    Subscribe { packet_id: u16, filters: Vec<(String, u8)> },
+    Suback { packet_id: u16, codes: Vec<u8> },
+    Unsubscribe { packet_id: u16, filters: Vec<String> },
+    Unsuback(u16),
+    Pingreq,
+    Pingresp,
+    Disconnect,
+}
+
+/// A cursor over the body of a packet
+struct Body<'a>(&'a [u8]);
+
+impl<'a> Body<'a> {
+    fn bytes(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if self.0.len() < len {
+            return invalid("truncated packet");
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from(bytes[0]) << 8 | u16::from(bytes[1]))
+    }
+
+    fn binary(&mut self) -> io::Result<Vec<u8>> {
+        let len = self.u16()? as usize;
+        Ok(self.bytes(len)?.to_vec())
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        String::from_utf8(self.binary()?).or_else(|_| invalid("invalid utf-8 string"))
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let rest = self.0;
+        self.0 = &[];
+        rest
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+fn put_u16(buffer: &mut Vec<u8>, value: u16) {
+    buffer.push((value >> 8) as u8);
+    buffer.push(value as u8);
+}
+
+fn put_binary(buffer: &mut Vec<u8>, value: &[u8]) {
+    put_u16(buffer, value.len() as u16);
+    buffer.extend_from_slice(value);
+}
+
+fn read_remaining_length<R: Read>(reader: &mut R) -> io::Result<usize> {
+    let mut length = 0;
+    for i in 0..4 {
+        let mut byte = [0];
+        reader.read_exact(&mut byte)?;
+        length |= ((byte[0] & 0x7F) as usize) << (7 * i);
+        if byte[0] & 0x80 == 0 {
+            return Ok(length);
+        }
+    }
+    invalid("malformed remaining length")
+}
+
+fn put_remaining_length(buffer: &mut Vec<u8>, mut length: usize) {
+    loop {
+        let mut byte = (length % 128) as u8;
+        length /= 128;
+        if length > 0 {
+            byte |= 0x80;
+        }
+        buffer.push(byte);
+        if length == 0 {
+            break;
+        }
+    }
+}
+
+fn read_connect(body: &mut Body) -> io::Result<Connect> {
+    let protocol = body.string()?;
+    let level = body.u8()?;
+    if protocol != "MQTT" || level != 4 {
+        return invalid(format!("unsupported protocol {} level {}", protocol, level));
+    }
+    let flags = body.u8()?;
+    let keep_alive = body.u16()?;
+    let client_id = body.string()?;
+    let will = if flags & 0x04 != 0 {
+        Some(Will {
+            topic: body.string()?,
+            payload: body.binary()?,
+            qos: (flags >> 3) & 0x03,
+            retain: flags & 0x20 != 0,
+        })
+    } else {
+        None
+    };
+    if flags & 0x80 != 0 {
+        body.string()?;
+    }
+    if flags & 0x40 != 0 {
+        body.binary()?;
+    }
+    Ok(Connect {
+        client_id,
+        clean_session: flags & 0x02 != 0,
+        keep_alive,
+        will,
+    })
+}
+
+fn read_publish(flags: u8, body: &mut Body) -> io::Result<Publish> {
+    let qos = (flags >> 1) & 0x03;
+    if qos > 2 {
+        return invalid("invalid qos");
+    }
+    let topic = body.string()?;
+    let packet_id = if qos > 0 { Some(body.u16()?) } else { None };
+    Ok(Publish {
+        topic,
+        payload: body.rest().to_vec(),
+        qos,
+        retain: flags & 0x01 != 0,
+        dup: flags & 0x08 != 0,
+        packet_id,
+    })
+}
+
+impl Packet {
+    /// Read a whole packet, blocking until it is available
+    pub fn read<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut header = [0];
+        reader.read_exact(&mut header)?;
+        let length = read_remaining_length(reader)?;
+        let mut buffer = vec![0; length];
+        reader.read_exact(&mut buffer)?;
+        let body = &mut Body(&buffer);
+        let flags = header[0] & 0x0F;
+
+        let packet = match header[0] >> 4 {
+            1 => Packet::Connect(read_connect(body)?),
+            2 => {
+                let session_present = body.u8()? & 0x01 != 0;
+                Packet::Connack {
+                    session_present,
+                    code: body.u8()?,
+                }
+            }
+            3 => Packet::Publish(read_publish(flags, body)?),
+            4 => Packet::Puback(body.u16()?),
+            5 => Packet::Pubrec(body.u16()?),
+            6 => Packet::Pubrel(body.u16()?),
+            7 => Packet::Pubcomp(body.u16()?),
+            8 => {
+                let packet_id = body.u16()?;
+                let mut filters = vec![];
+                while !body.is_empty() {
+                    filters.push((body.string()?, body.u8()? & 0x03));
+                }
+                Packet::Subscribe { packet_id, filters }
+            }
+            9 => Packet::Suback {
+                packet_id: body.u16()?,
+                codes: body.rest().to_vec(),
+            },
+            10 => {
+                let packet_id = body.u16()?;
+                let mut filters = vec![];
+                while !body.is_empty() {
+                    filters.push(body.string()?);
+                }
+                Packet::Unsubscribe { packet_id, filters }
+            }
+            11 => Packet::Unsuback(body.u16()?),
+            12 => Packet::Pingreq,
+            13 => Packet::Pingresp,
+            14 => Packet::Disconnect,
+            other => return invalid(format!("unknown packet type {}", other)),
+        };
+        Ok(packet)
+    }
+
+    /// Write the packet at once so that packets written from several threads don't interleave
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut body = vec![];
+        let header = match *self {
+            Packet::Connect(ref connect) => {
+                put_binary(&mut body, b"MQTT");
+                body.push(4);
+                let mut flags = if connect.clean_session { 0x02 } else { 0 };
+                if let Some(ref will) = connect.will {
+                    flags |= 0x04 | will.qos << 3 | if will.retain { 0x20 } else { 0 };
+                }
+                body.push(flags);
+                put_u16(&mut body, connect.keep_alive);
+                put_binary(&mut body, connect.client_id.as_bytes());
+                if let Some(ref will) = connect.will {
+                    put_binary(&mut body, will.topic.as_bytes());
+                    put_binary(&mut body, &will.payload);
+                }
+                0x10
+            }
+            Packet::Connack { session_present, code } => {
+                body.push(session_present as u8);
+                body.push(code);
+                0x20
+            }
+            Packet::Publish(ref publish) => {
+                put_binary(&mut body, publish.topic.as_bytes());
+                if let Some(packet_id) = publish.packet_id {
+                    put_u16(&mut body, packet_id);
+                }
+                body.extend_from_slice(&publish.payload);
+                0x30 | (publish.dup as u8) << 3 | publish.qos << 1 | publish.retain as u8
+            }
+            Packet::Puback(packet_id) => {
+                put_u16(&mut body, packet_id);
+                0x40
+            }
+            Packet::Pubrec(packet_id) => {
+                put_u16(&mut body, packet_id);
+                0x50
+            }
+            Packet::Pubrel(packet_id) => {
+                put_u16(&mut body, packet_id);
+                0x62
+            }
+            Packet::Pubcomp(packet_id) => {
+                put_u16(&mut body, packet_id);
+                0x70
+            }
+            Packet::Subscribe { packet_id, ref filters } => {
+                put_u16(&mut body, packet_id);
+                for (filter, qos) in filters {
+                    put_binary(&mut body, filter.as_bytes());
+                    body.push(*qos);
+                }
+                0x82
+            }
+            Packet::Suback { packet_id, ref codes } => {
+                put_u16(&mut body, packet_id);
+                body.extend_from_slice(codes);
+                0x90
+            }
+            Packet::Unsubscribe { packet_id, ref filters } => {
+                put_u16(&mut body, packet_id);
+                for filter in filters {
+                    put_binary(&mut body, filter.as_bytes());
+                }
+                0xA2
+            }
+            Packet::Unsuback(packet_id) => {
+                put_u16(&mut body, packet_id);
+                0xB0
+            }
+            Packet::Pingreq => 0xC0,
+            Packet::Pingresp => 0xD0,
+            Packet::Disconnect => 0xE0,
+        };
+
+        let mut buffer = vec![header];
+        put_remaining_length(&mut buffer, body.len());
+        buffer.extend_from_slice(&body);
+        writer.write_all(&buffer)?;
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(packet: Packet) {
+        let mut buffer = vec![];
+        packet.write(&mut buffer).unwrap();
+        assert_eq!(Packet::read(&mut buffer.as_slice()).unwrap(), packet);
+    }
+
+    #[test]
+    fn packets_round_trip() {
+        round_trip(Packet::Connect(Connect {
+            client_id: "client".into(),
+            clean_session: true,
+            keep_alive: 30,
+            will: Some(Will {
+                topic: "hermes/component/offline".into(),
+                payload: vec![0, 1, 2],
+                qos: 1,
+                retain: true,
+            }),
+        }));
+        round_trip(Packet::Publish(Publish {
+            topic: "hermes/audioServer/default/playBytes/id".into(),
+            payload: (0..=255).collect(),
+            qos: 1,
+            retain: false,
+            dup: false,
+            packet_id: Some(42),
+        }));
+        round_trip(Packet::Subscribe {
+            packet_id: 1,
+            filters: vec![("hermes/intent/#".into(), 0), ("hermes/+/toggleOn".into(), 1)],
+        });
+        round_trip(Packet::Unsubscribe {
+            packet_id: 2,
+            filters: vec!["hermes/intent/#".into()],
+        });
+        round_trip(Packet::Pubrel(3));
+        round_trip(Packet::Pingreq);
+    }
+
+    #[test]
+    fn large_remaining_length_works() {
+        let mut buffer = vec![];
+        put_remaining_length(&mut buffer, 2_097_152);
+        assert_eq!(buffer, vec![0x80, 0x80, 0x80, 0x01]);
+        assert_eq!(read_remaining_length(&mut buffer.as_slice()).unwrap(), 2_097_152);
+    }
+}
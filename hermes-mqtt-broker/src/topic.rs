@@ -0,0 +1,86 @@
+/// Whether a topic name matches a subscription filter, `+` matching exactly one level and `#`
+/// all the remaining ones (including the parent level). Wildcards at the first level don't match
+/// topics starting with `$`.
+pub fn matches(filter: &str, topic: &str) -> bool {
+    if topic.starts_with('$') && (filter.starts_with('+') || filter.starts_with('#')) {
+        return false;
+    }
+    let mut topic_levels = topic.split('/');
+    for level in filter.split('/') {
+        match (level, topic_levels.next()) {
+            ("#", _) => return true,
+            ("+", Some(_)) => {}
+            (level, Some(topic_level)) if level == topic_level => {}
+            _ => return false,
+        }
+    }
+    topic_levels.next().is_none()
+}
+
+/// Whether a subscription filter is valid, wildcards must occupy a whole level and `#` must be the
+/// last one
+pub fn is_valid_filter(filter: &str) -> bool {
+    let levels: Vec<_> = filter.split('/').collect();
+    !filter.is_empty()
+        && levels.iter().enumerate().all(|(i, level)| match *level {
+            "#" => i == levels.len() - 1,
+            "+" => true,
+            level => !level.contains('+') && !level.contains('#'),
+        })
+}
+
+/// Whether a topic name can be published to, it must not contain any wildcard
+pub fn is_valid_topic(topic: &str) -> bool {
+    !topic.is_empty() && !topic.contains('+') && !topic.contains('#')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_filters_work() {
+        assert!(matches("hermes/tts/say", "hermes/tts/say"));
+        assert!(!matches("hermes/tts/say", "hermes/tts/sayFinished"));
+        assert!(!matches("hermes/tts", "hermes/tts/say"));
+        assert!(!matches("hermes/tts/say", "hermes/tts"));
+    }
+
+    #[test]
+    fn single_level_wildcard_works() {
+        assert!(matches("hermes/+/toggleOn", "hermes/hotword/toggleOn"));
+        assert!(matches(
+            "hermes/audioServer/+/playBytes/+",
+            "hermes/audioServer/default/playBytes/id"
+        ));
+        assert!(matches("hermes/+", "hermes/"));
+        assert!(!matches("hermes/+/toggleOn", "hermes/toggleOn"));
+        assert!(!matches("hermes/+", "hermes/hotword/toggleOn"));
+    }
+
+    #[test]
+    fn multi_level_wildcard_works() {
+        assert!(matches("#", "hermes/intent/lights_on"));
+        assert!(matches("hermes/intent/#", "hermes/intent/lights_on"));
+        assert!(matches("hermes/intent/#", "hermes/intent"));
+        assert!(matches("hermes/hotword/+/#", "hermes/hotword/default/detected"));
+        assert!(!matches("hermes/intent/#", "hermes/intentNotRecognized"));
+    }
+
+    #[test]
+    fn wildcards_dont_match_system_topics() {
+        assert!(!matches("#", "$SYS/broker/uptime"));
+        assert!(!matches("+/broker/uptime", "$SYS/broker/uptime"));
+        assert!(matches("$SYS/#", "$SYS/broker/uptime"));
+    }
+
+    #[test]
+    fn filters_are_validated() {
+        assert!(is_valid_filter("hermes/+/toggleOn"));
+        assert!(is_valid_filter("hermes/intent/#"));
+        assert!(!is_valid_filter("hermes/#/toggleOn"));
+        assert!(!is_valid_filter("hermes/intent#"));
+        assert!(!is_valid_filter(""));
+        assert!(!is_valid_topic("hermes/+/toggleOn"));
+    }
+}
@@ -9,7 +9,10 @@ use failure::format_err;
 use failure::Fallible;
 use failure::ResultExt;
 use ffi_utils::*;
+use hermes::HermesError;
+use hermes_ffi::errors::SNIPS_RESULT;
 use hermes_ffi::*;
+use hermes_mqtt::topics::{FromPath, HermesTopic};
 use log::*;
 
 generate_error_handling!(hermes_get_last_error);
@@ -36,6 +39,38 @@ pub struct CMqttOptions {
     /// Boolean indicating if the root store should be disabled if TLS is enabled. The is
     /// interpreted as a boolean, 0 meaning false, all other values meaning true
     tls_disable_root_store: libc::c_uchar,
+    /// How the messages are published on some kinds of topics, overriding the default policies.
+    /// Nullable
+    publish_policies: *mut CPublishPolicyArray,
+    /// Prefix of all the topics, like `tenant42` to use `tenant42/hermes/tts/say`, for several
+    /// assistants to share a broker. Nullable
+    topic_prefix: *mut libc::c_char,
+    /// How the messages are serialized when published: 0 for JSON, 1 for MessagePack, 2 for CBOR.
+    /// The messages received are decoded whatever their codec
+    payload_codec: libc::c_uchar,
+}
+
+impl CMqttOptions {
+    fn handler_options(&self) -> Fallible<hermes_mqtt::HandlerOptions> {
+        Ok(hermes_mqtt::HandlerOptions {
+            topic_prefix: create_optional_rust_string_from!(self.topic_prefix),
+            payload_codec: match self.payload_codec {
+                0 => hermes_mqtt::PayloadCodec::Json,
+                1 => hermes_mqtt::PayloadCodec::MessagePack,
+                2 => hermes_mqtt::PayloadCodec::Cbor,
+                codec => return Err(HermesError::InvalidArgument(format!("Invalid payload codec {}", codec)).into()),
+            },
+            ..Default::default()
+        })
+    }
+
+    fn publish_policies(&self) -> Fallible<Vec<(HermesTopic, hermes_mqtt::PublishPolicy)>> {
+        if self.publish_policies.is_null() {
+            Ok(vec![])
+        } else {
+            unsafe { CPublishPolicyArray::raw_borrow(self.publish_policies) }?.as_rust()
+        }
+    }
 }
 
 impl AsRust<hermes_mqtt::MqttOptions> for CMqttOptions {
@@ -70,6 +105,53 @@ impl AsRust<hermes_mqtt::MqttOptions> for CMqttOptions {
     }
 }
 
+/// A struct representing how the messages are published on the topics of a kind
+#[repr(C)]
+#[derive(Debug)]
+pub struct CPublishPolicy {
+    /// A hermes topic, the parts depending on the message like the site id or the intent name can
+    /// be anything as the policy applies to all the topics of its kind, e.g.
+    /// `hermes/audioServer/+/audioFrame`
+    topic: *const libc::c_char,
+    /// The MQTT quality of service: 0 for at most once, 1 for at least once, 2 for exactly once
+    qos: libc::c_uchar,
+    /// Boolean indicating if the messages are retained by the broker. It is interpreted as a
+    /// boolean, 0 meaning false, all other values meaning true
+    retain: libc::c_uchar,
+}
+
+impl AsRust<(HermesTopic, hermes_mqtt::PublishPolicy)> for CPublishPolicy {
+    fn as_rust(&self) -> Fallible<(HermesTopic, hermes_mqtt::PublishPolicy)> {
+        let path = create_rust_string_from!(self.topic);
+        let topic = HermesTopic::from_path(&path)
+            .ok_or_else(|| HermesError::InvalidArgument(format!("Unknown hermes topic {}", path)))?;
+        let qos = match self.qos {
+            0 => hermes_mqtt::QoS::AtMostOnce,
+            1 => hermes_mqtt::QoS::AtLeastOnce,
+            2 => hermes_mqtt::QoS::ExactlyOnce,
+            qos => return Err(HermesError::InvalidArgument(format!("Invalid QoS {} for topic {}", qos, path)).into()),
+        };
+        Ok((topic, hermes_mqtt::PublishPolicy::new(qos, self.retain != 0)))
+    }
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct CPublishPolicyArray {
+    entries: *const *const CPublishPolicy,
+    count: libc::c_int,
+}
+
+impl AsRust<Vec<(HermesTopic, hermes_mqtt::PublishPolicy)>> for CPublishPolicyArray {
+    fn as_rust(&self) -> Fallible<Vec<(HermesTopic, hermes_mqtt::PublishPolicy)>> {
+        let mut result = Vec::with_capacity(self.count as usize);
+        for e in unsafe { std::slice::from_raw_parts(self.entries, self.count as usize) } {
+            result.push(unsafe { CPublishPolicy::raw_borrow(*e) }?.as_rust()?);
+        }
+        Ok(result)
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn hermes_protocol_handler_new_mqtt(
     handler: *mut *const CProtocolHandler,
@@ -95,7 +177,7 @@ pub extern "C" fn hermes_protocol_handler_new_mqtt(
         }
         Ok(())
     }
-    wrap!(new_mqtt_handler(handler, broker_address, user_data))
+    hermes_wrap!(new_mqtt_handler(handler, broker_address, user_data))
 }
 
 #[no_mangle]
@@ -110,20 +192,21 @@ pub extern "C" fn hermes_protocol_handler_new_mqtt_with_options(
         user_data: *mut libc::c_void,
     ) -> Result<(), failure::Error> {
         let options = unsafe { (&*mqtt_options).as_rust() }?;
-        let cph = CProtocolHandler::new(
-            Box::new(
-                hermes_mqtt::MqttHermesProtocolHandler::new_with_options(options)
-                    .with_context(|e| format_err!("Could not create hermes MQTT handler: {:?}", e))?,
-            ),
-            user_data,
-        );
+        let handler_options = unsafe { (&*mqtt_options).handler_options() }?;
+        let publish_policies = unsafe { (&*mqtt_options).publish_policies() }?;
+        let mqtt_handler = hermes_mqtt::MqttHermesProtocolHandler::new_with_handler_options(options, handler_options)
+            .with_context(|e| format_err!("Could not create hermes MQTT handler: {:?}", e))?;
+        for (topic, policy) in publish_policies {
+            mqtt_handler.set_publish_policy(&topic, policy)?;
+        }
+        let cph = CProtocolHandler::new(Box::new(mqtt_handler), user_data);
         let ptr = CProtocolHandler::into_raw_pointer(cph);
         unsafe {
             *handler = ptr;
         }
         Ok(())
     }
-    wrap!(new_mqtt_handler(handler, mqtt_options, user_data))
+    hermes_wrap!(new_mqtt_handler(handler, mqtt_options, user_data))
 }
 
 #[no_mangle]
@@ -133,7 +216,7 @@ pub extern "C" fn hermes_destroy_mqtt_protocol_handler(handler: *mut CProtocolHa
         handler.destroy();
         Ok(())
     }
-    wrap!(destroy_mqtt_handler(handler))
+    hermes_wrap!(destroy_mqtt_handler(handler))
 }
 
 generate_hermes_c_symbols!();
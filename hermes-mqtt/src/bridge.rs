@@ -0,0 +1,560 @@
+//! Bridge the messages of two protocol handlers, for example to let components running in process
+//! talk to the ones on other devices over MQTT.
+//!
+//! The bridge subscribes to every facade of a handler and republishes what it receives through
+//! the facades of the other one. When bridging in both directions, the messages the bridge
+//! published itself are recognized when they come back and are not sent again.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use hermes::*;
+use log::*;
+
+use crate::topics::*;
+use crate::Payload;
+
+/// How long a message published by the bridge is expected to come back to its subscriptions
+const ECHO_TIMEOUT: Duration = Duration::from_secs(10);
+
+type MessageCallback = Arc<Fn(HermesTopic, Payload) + Send + Sync>;
+
+/// The handlers the messages are bridged from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    LocalToRemote,
+    RemoteToLocal,
+    Both,
+}
+
+impl Direction {
+    fn includes(self, direction: Direction) -> bool {
+        self == Direction::Both || self == direction
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BridgeConfig {
+    /// The direction of the messages not related to a site, and of the ones related to a site
+    /// without route
+    pub direction: Direction,
+    /// Only bridge the messages on the topics matching one of these MQTT filters, like
+    /// `hermes/asr/#` for all the messages of the ASR. Everything is bridged when empty.
+    pub topic_filters: Vec<String>,
+    /// The direction of the messages related to some sites, like `RemoteToLocal` to only bring the
+    /// messages of a satellite on the remote handler to the local one. The audio frames and the
+    /// versions and errors of the components identified by site are only bridged for these sites,
+    /// as the facades only allow to subscribe to them site by site.
+    pub site_routes: HashMap<String, Direction>,
+}
+
+impl Default for BridgeConfig {
+    fn default() -> Self {
+        Self {
+            direction: Direction::Both,
+            topic_filters: vec![],
+            site_routes: HashMap::new(),
+        }
+    }
+}
+
+/// Republishes the messages of a handler on another one until dropped
+pub struct Bridge {
+    _subscriptions: Vec<Subscription>,
+}
+
+impl Bridge {
+    pub fn start(
+        local: Arc<HermesProtocolHandler>,
+        remote: Arc<HermesProtocolHandler>,
+        config: BridgeConfig,
+    ) -> HermesResult<Self> {
+        let config = Arc::new(config);
+        let local_echoes = Arc::new(Echoes::default());
+        let remote_echoes = Arc::new(Echoes::default());
+        let mut subscriptions = vec![];
+        if config.bridges(Direction::LocalToRemote) {
+            subscriptions.extend(forward(
+                Direction::LocalToRemote,
+                (&local, &remote),
+                (&local_echoes, &remote_echoes),
+                &config,
+            )?);
+        }
+        if config.bridges(Direction::RemoteToLocal) {
+            subscriptions.extend(forward(
+                Direction::RemoteToLocal,
+                (&remote, &local),
+                (&remote_echoes, &local_echoes),
+                &config,
+            )?);
+        }
+        Ok(Self {
+            _subscriptions: subscriptions,
+        })
+    }
+}
+
+type HandlerPair<'a> = (&'a Arc<HermesProtocolHandler>, &'a Arc<HermesProtocolHandler>);
+
+fn forward(
+    direction: Direction,
+    (from, to): HandlerPair,
+    (from_echoes, to_echoes): (&Arc<Echoes>, &Arc<Echoes>),
+    config: &Arc<BridgeConfig>,
+) -> HermesResult<Vec<Subscription>> {
+    let to = Arc::clone(to);
+    let from_echoes = Arc::clone(from_echoes);
+    let to_echoes = Arc::clone(to_echoes);
+    let routes = Arc::clone(config);
+    let name = format!("{} -> {}", from, to);
+    let sites = config
+        .site_routes
+        .iter()
+        .filter(|(_, route)| route.includes(direction))
+        .map(|(site_id, _)| site_id.clone())
+        .collect::<Vec<_>>();
+    subscribe_all(&**from, &sites, move |topic, payload| {
+        if from_echoes.is_echo(&topic, &payload) || !routes.accepts(&topic) {
+            return;
+        }
+        let route = routes.route(&payload);
+        if !route.includes(direction) {
+            return;
+        }
+        debug!("Bridging {} on {}", topic, name);
+        // the echoes are only looked for, and thus only recorded, when bridging in both directions
+        if route == Direction::Both {
+            to_echoes.expect(&topic, &payload);
+        }
+        if let Err(e) = payload.publish(&topic, &*to) {
+            error!("Could not bridge message on {} ({}): {}", topic, name, e)
+        }
+    })
+}
+
+impl BridgeConfig {
+    /// Whether some messages are bridged in a direction
+    fn bridges(&self, direction: Direction) -> bool {
+        self.direction.includes(direction) || self.site_routes.values().any(|it| it.includes(direction))
+    }
+
+    fn accepts(&self, topic: &HermesTopic) -> bool {
+        let path = topic.as_path();
+        self.topic_filters.is_empty() || self.topic_filters.iter().any(|it| filter_matches(it, &path))
+    }
+
+    /// The direction a message is bridged in
+    fn route(&self, payload: &Payload) -> Direction {
+        site_id(payload)
+            .and_then(|site_id| self.site_routes.get(&site_id).copied())
+            .unwrap_or(self.direction)
+    }
+}
+
+/// Whether a topic matches an MQTT subscription filter
+fn filter_matches(filter: &str, topic: &str) -> bool {
+    let mut topic_levels = topic.split('/');
+    for level in filter.split('/') {
+        match (level, topic_levels.next()) {
+            ("#", _) => return true,
+            ("+", Some(_)) => {}
+            (level, Some(topic_level)) if level == topic_level => {}
+            _ => return false,
+        }
+    }
+    topic_levels.next().is_none()
+}
+
+/// The site a message is related to, if any
+fn site_id(payload: &Payload) -> Option<String> {
+    match *payload {
+        Payload::Empty | Payload::RegisterSound(_) => None,
+        Payload::AudioFrame(ref frame) => Some(frame.site_id.clone()),
+        Payload::PlayBytes(ref bytes) => Some(bytes.site_id.clone()),
+        _ => serde_json::to_value(payload)
+            .ok()
+            .and_then(|it| it.get("siteId").and_then(|it| it.as_str()).map(str::to_string)),
+    }
+}
+
+/// The messages published by the bridge on a handler, which are ignored when received back from it
+#[derive(Default)]
+struct Echoes(Mutex<Vec<(Instant, HermesTopic, Payload)>>);
+
+impl Echoes {
+    fn expect(&self, topic: &HermesTopic, payload: &Payload) {
+        if let Ok(mut echoes) = self.0.lock() {
+            Self::prune(&mut echoes);
+            echoes.push((Instant::now(), topic.clone(), payload.clone()))
+        }
+    }
+
+    fn is_echo(&self, topic: &HermesTopic, payload: &Payload) -> bool {
+        let mut echoes = match self.0.lock() {
+            Ok(echoes) => echoes,
+            Err(_) => return false,
+        };
+        Self::prune(&mut echoes);
+        match echoes.iter().position(|(_, t, p)| t == topic && p == payload) {
+            Some(index) => {
+                echoes.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Forget the messages that did not come back in time, like the ones the handler dropped
+    fn prune(echoes: &mut Vec<(Instant, HermesTopic, Payload)>) {
+        echoes.retain(|(time, _, _)| time.elapsed() < ECHO_TIMEOUT);
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.0.lock().map(|it| it.len()).unwrap_or_default()
+    }
+}
+
+/// Subscribe to all the messages exchanged through the facades of a handler, the ones only
+/// available site by site are subscribed to for the given sites
+pub fn subscribe_all<F>(
+    handler: &HermesProtocolHandler,
+    sites: &[String],
+    callback: F,
+) -> HermesResult<Vec<Subscription>>
+where
+    F: Fn(HermesTopic, Payload) + Send + Sync + 'static,
+{
+    use self::AudioServerCommand as A;
+    use self::DialogueManagerCommand as D;
+    let callback: MessageCallback = Arc::new(callback);
+    let mut subscriptions = vec![];
+
+    // `$topic` builds the topic of a message from the message itself
+    macro_rules! subscribe {
+        ($facade:ident.$method:ident($($arg:expr),*) => $variant:ident |$m:ident| $topic:expr) => {{
+            let callback = Arc::clone(&callback);
+            subscriptions.push($facade.$method($($arg,)* Callback::new(move |$m| {
+                let payload = Payload::$variant(Clone::clone($m));
+                callback($topic, payload)
+            }))?);
+        }};
+        ($facade:ident.$method:ident($($arg:expr),*) => $topic:expr) => {{
+            let callback = Arc::clone(&callback);
+            let topic = $topic;
+            subscriptions.push($facade.$method($($arg,)* Callback0::new(move || callback(topic.clone(), Payload::Empty)))?);
+        }};
+    }
+
+    macro_rules! subscribe_component {
+        ($facade:ident, $backend:ident, $component:ident, $site_id:expr; $($id:expr),*) => {{
+            let version_request = HermesTopic::Component($site_id, Component::$component, ComponentCommand::VersionRequest);
+            let version = HermesTopic::Component($site_id, Component::$component, ComponentCommand::Version);
+            let error = HermesTopic::Component($site_id, Component::$component, ComponentCommand::Error);
+            subscribe!($backend.subscribe_version_request($($id),*) => version_request);
+            subscribe!($facade.subscribe_version($($id),*) => Version |_m| version.clone());
+            subscribe!($facade.subscribe_error($($id),*) => Error |_m| error.clone());
+        }};
+    }
+
+    let feedback = handler.sound_feedback_backend();
+    let toggle_on = HermesTopic::Feedback(FeedbackCommand::Sound(SoundCommand::ToggleOn));
+    let toggle_off = HermesTopic::Feedback(FeedbackCommand::Sound(SoundCommand::ToggleOff));
+    subscribe!(feedback.subscribe_toggle_on() => Site |_m| toggle_on.clone());
+    subscribe!(feedback.subscribe_toggle_off() => Site |_m| toggle_off.clone());
+
+    let (voice_activity, voice_activity_backend) = (handler.voice_activity(), handler.voice_activity_backend());
+    subscribe!(voice_activity.subscribe_all_vad_up() => VadUp |m| HermesTopic::VoiceActivity(m.site_id.clone(), VoiceActivityCommand::VadUp));
+    subscribe!(voice_activity.subscribe_all_vad_down() => VadDown |m| HermesTopic::VoiceActivity(m.site_id.clone(), VoiceActivityCommand::VadDown));
+
+    let (hotword, hotword_backend) = (handler.hotword(), handler.hotword_backend());
+    subscribe!(hotword_backend.subscribe_toggle_on() => Site |_m| HermesTopic::Hotword(None, HotwordCommand::ToggleOn));
+    subscribe!(hotword_backend.subscribe_toggle_off() => Site |_m| HermesTopic::Hotword(None, HotwordCommand::ToggleOff));
+    subscribe!(hotword.subscribe_all_detected() => HotwordDetected |m| HermesTopic::Hotword(Some(m.site_id.clone()), HotwordCommand::Detected));
+
+    let (asr, asr_backend) = (handler.asr(), handler.asr_backend());
+    subscribe!(asr_backend.subscribe_toggle_on() => HermesTopic::Asr(AsrCommand::ToggleOn));
+    subscribe!(asr_backend.subscribe_toggle_off() => HermesTopic::Asr(AsrCommand::ToggleOff));
+    subscribe!(asr_backend.subscribe_start_listening() => AsrStartListening |_m| HermesTopic::Asr(AsrCommand::StartListening));
+    subscribe!(asr_backend.subscribe_stop_listening() => Site |_m| HermesTopic::Asr(AsrCommand::StopListening));
+    subscribe!(asr_backend.subscribe_reload() => HermesTopic::Asr(AsrCommand::Reload));
+    subscribe!(asr.subscribe_text_captured() => TextCaptured |_m| HermesTopic::Asr(AsrCommand::TextCaptured));
+    subscribe!(asr.subscribe_partial_text_captured() => TextCaptured |_m| HermesTopic::Asr(AsrCommand::PartialTextCaptured));
+    subscribe_component!(asr, asr_backend, Asr, None;);
+
+    let (tts, tts_backend) = (handler.tts(), handler.tts_backend());
+    subscribe!(tts_backend.subscribe_say() => Say |_m| HermesTopic::Tts(TtsCommand::Say));
+    subscribe!(tts_backend.subscribe_register_sound() => RegisterSound |m| HermesTopic::Tts(TtsCommand::RegisterSound(m.sound_id.clone())));
+    subscribe!(tts.subscribe_say_finished() => SayFinished |_m| HermesTopic::Tts(TtsCommand::SayFinished));
+    subscribe_component!(tts, tts_backend, Tts, None;);
+
+    let (nlu, nlu_backend) = (handler.nlu(), handler.nlu_backend());
+    subscribe!(nlu_backend.subscribe_query() => NluQuery |_m| HermesTopic::Nlu(NluCommand::Query));
+    subscribe!(nlu_backend.subscribe_partial_query() => NluSlotQuery |_m| HermesTopic::Nlu(NluCommand::PartialQuery));
+    subscribe!(nlu_backend.subscribe_reload() => HermesTopic::Nlu(NluCommand::Reload));
+    subscribe!(nlu.subscribe_slot_parsed() => NluSlot |_m| HermesTopic::Nlu(NluCommand::SlotParsed));
+    subscribe!(nlu.subscribe_intent_parsed() => NluIntent |_m| HermesTopic::Nlu(NluCommand::IntentParsed));
+    subscribe!(nlu.subscribe_intent_not_recognized() => NluIntentNotRecognized |_m| HermesTopic::Nlu(NluCommand::IntentNotRecognized));
+    subscribe_component!(nlu, nlu_backend, Nlu, None;);
+
+    let (audio_server, audio_server_backend) = (handler.audio_server(), handler.audio_server_backend());
+    subscribe!(audio_server_backend.subscribe_toggle_on() => Site |_m| HermesTopic::AudioServer(None, A::ToggleOn));
+    subscribe!(audio_server_backend.subscribe_toggle_off() => Site |_m| HermesTopic::AudioServer(None, A::ToggleOff));
+    subscribe!(audio_server_backend.subscribe_all_play_bytes() => PlayBytes |m| HermesTopic::AudioServer(Some(m.site_id.clone()), A::PlayBytes(m.id.clone())));
+    subscribe!(audio_server.subscribe_all_play_finished() => PlayFinished |m| HermesTopic::AudioServer(Some(m.site_id.clone()), A::PlayFinished));
+
+    let (dialogue, dialogue_backend) = (handler.dialogue(), handler.dialogue_backend());
+    subscribe!(dialogue_backend.subscribe_toggle_on() => HermesTopic::DialogueManager(D::ToggleOn));
+    subscribe!(dialogue_backend.subscribe_toggle_off() => HermesTopic::DialogueManager(D::ToggleOff));
+    subscribe!(dialogue_backend.subscribe_start_session() => StartSession |_m| HermesTopic::DialogueManager(D::StartSession));
+    subscribe!(dialogue_backend.subscribe_continue_session() => ContinueSession |_m| HermesTopic::DialogueManager(D::ContinueSession));
+    subscribe!(dialogue_backend.subscribe_end_session() => EndSession |_m| HermesTopic::DialogueManager(D::EndSession));
+    subscribe!(dialogue_backend.subscribe_configure() => DialogueConfigure |_m| HermesTopic::DialogueManager(D::Configure));
+    subscribe!(dialogue_backend.subscribe_session_state_request() => HermesTopic::DialogueManager(D::SessionStateRequest));
+    subscribe!(dialogue.subscribe_session_state() => SessionState |_m| HermesTopic::DialogueManager(D::SessionState));
+    subscribe!(dialogue.subscribe_session_queued() => SessionQueued |_m| HermesTopic::DialogueManager(D::SessionQueued));
+    subscribe!(dialogue.subscribe_session_started() => SessionStarted |_m| HermesTopic::DialogueManager(D::SessionStarted));
+    subscribe!(dialogue.subscribe_session_ended() => SessionEnded |_m| HermesTopic::DialogueManager(D::SessionEnded));
+    subscribe!(dialogue.subscribe_intents() => Intent |m| HermesTopic::Intent(m.intent.intent_name.clone()));
+    subscribe!(dialogue.subscribe_intent_not_recognized() => IntentNotRecognized |_m| HermesTopic::DialogueManager(D::IntentNotRecognized));
+    subscribe_component!(dialogue, dialogue_backend, DialogueManager, None;);
+
+    let (injection, injection_backend) = (handler.injection(), handler.injection_backend());
+    subscribe!(injection_backend.subscribe_injection_request() => InjectionRequest |_m| HermesTopic::Injection(InjectionCommand::Perform));
+    subscribe!(injection_backend.subscribe_injection_status_request() => HermesTopic::Injection(InjectionCommand::StatusRequest));
+    subscribe!(injection.subscribe_injection_status() => InjectionStatus |_m| HermesTopic::Injection(InjectionCommand::Status));
+    subscribe_component!(injection, injection_backend, Injection, None;);
+
+    let handshake = handler.handshake();
+    subscribe!(handshake.subscribe_handshake_request() => HermesTopic::Protocol(ProtocolCommand::HandshakeRequest));
+    subscribe!(handshake.subscribe_handshakes() => Handshake |_m| HermesTopic::Protocol(ProtocolCommand::Handshake));
+
+    let (registry, registry_backend) = (handler.registry(), handler.registry_backend());
+    subscribe!(registry_backend.subscribe_snapshot_request() => HermesTopic::Registry(RegistryCommand::SnapshotRequest));
+    subscribe!(registry.subscribe_snapshot() => RegistrySnapshot |_m| HermesTopic::Registry(RegistryCommand::Snapshot));
+    subscribe!(registry.subscribe_component_up() => ComponentStatus |_m| HermesTopic::Registry(RegistryCommand::ComponentUp));
+    subscribe!(registry.subscribe_component_down() => ComponentStatus |_m| HermesTopic::Registry(RegistryCommand::ComponentDown));
+
+    let (site, site_backend) = (handler.site(), handler.site_backend());
+    subscribe!(site_backend.subscribe_register_site() => SiteInfo |_m| HermesTopic::Site(SiteCommand::Register));
+    subscribe!(site_backend.subscribe_unregister_site() => Site |_m| HermesTopic::Site(SiteCommand::Unregister));
+    subscribe!(site_backend.subscribe_sites_request() => HermesTopic::Site(SiteCommand::SitesRequest));
+    subscribe!(site.subscribe_sites() => Sites |_m| HermesTopic::Site(SiteCommand::Sites));
+
+    for site_id in sites {
+        let frame = HermesTopic::AudioServer(Some(site_id.clone()), A::AudioFrame);
+        let replay_response = HermesTopic::AudioServer(Some(site_id.clone()), A::ReplayResponse);
+        let replay_request = HermesTopic::AudioServer(Some(site_id.clone()), A::ReplayRequest);
+        subscribe!(audio_server.subscribe_audio_frame(site_id.clone()) => AudioFrame |_m| frame.clone());
+        subscribe!(audio_server.subscribe_replay_response(site_id.clone()) => AudioFrame |_m| replay_response.clone());
+        subscribe!(audio_server_backend.subscribe_replay_request(site_id.clone()) => ReplayRequest |_m| replay_request.clone());
+        subscribe_component!(voice_activity, voice_activity_backend, VoiceActivity, Some(site_id.clone()); site_id.clone());
+        subscribe_component!(hotword, hotword_backend, Hotword, Some(site_id.clone()); site_id.clone());
+        subscribe_component!(audio_server, audio_server_backend, AudioServer, Some(site_id.clone()); site_id.clone());
+    }
+
+    Ok(subscriptions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hermes_inprocess::InProcessHermesProtocolHandler;
+    use std::sync::mpsc;
+
+    const WAIT: Duration = Duration::from_millis(200);
+
+    fn handlers() -> (Arc<HermesProtocolHandler>, Arc<HermesProtocolHandler>) {
+        (
+            Arc::new(InProcessHermesProtocolHandler::new()),
+            Arc::new(InProcessHermesProtocolHandler::new()),
+        )
+    }
+
+    fn say(site_id: &str) -> SayMessage {
+        SayMessage {
+            text: "hello".into(),
+            lang: None,
+            id: None,
+            site_id: site_id.into(),
+            session_id: None,
+        }
+    }
+
+    fn receive_says(handler: &HermesProtocolHandler) -> (Subscription, mpsc::Receiver<SayMessage>) {
+        let (tx, rx) = mpsc::channel();
+        let tx = Mutex::new(tx);
+        let subscription = handler
+            .tts_backend()
+            .subscribe_say(Callback::new(move |it: &SayMessage| {
+                let _ = tx.lock().unwrap().send(it.clone());
+            }))
+            .unwrap();
+        (subscription, rx)
+    }
+
+    #[test]
+    fn messages_are_bridged_in_both_directions_once() {
+        let (local, remote) = handlers();
+        let _bridge = Bridge::start(Arc::clone(&local), Arc::clone(&remote), BridgeConfig::default()).unwrap();
+        let (_local_subscription, local_says) = receive_says(&*local);
+        let (_remote_subscription, remote_says) = receive_says(&*remote);
+
+        local.tts().publish_say(say("kitchen")).unwrap();
+        assert_eq!(remote_says.recv_timeout(WAIT * 10).unwrap(), say("kitchen"));
+        assert_eq!(local_says.recv_timeout(WAIT * 10).unwrap(), say("kitchen"));
+
+        remote.tts().publish_say(say("bedroom")).unwrap();
+        assert_eq!(local_says.recv_timeout(WAIT * 10).unwrap(), say("bedroom"));
+        assert_eq!(remote_says.recv_timeout(WAIT * 10).unwrap(), say("bedroom"));
+
+        // nothing bounces back
+        assert!(local_says.recv_timeout(WAIT).is_err());
+        assert!(remote_says.recv_timeout(WAIT).is_err());
+    }
+
+    #[test]
+    fn one_way_bridge_records_no_echo() {
+        let (local, remote) = handlers();
+        let config = Arc::new(BridgeConfig {
+            direction: Direction::LocalToRemote,
+            ..BridgeConfig::default()
+        });
+        let (local_echoes, remote_echoes) = (Arc::new(Echoes::default()), Arc::new(Echoes::default()));
+        let _subscriptions = forward(
+            Direction::LocalToRemote,
+            (&local, &remote),
+            (&local_echoes, &remote_echoes),
+            &config,
+        )
+        .unwrap();
+        let (_remote_subscription, remote_says) = receive_says(&*remote);
+
+        for _ in 0..100 {
+            local.tts().publish_say(say("kitchen")).unwrap();
+        }
+        for _ in 0..100 {
+            assert_eq!(remote_says.recv_timeout(WAIT * 10).unwrap(), say("kitchen"));
+        }
+        assert_eq!(remote_echoes.len(), 0);
+    }
+
+    #[test]
+    fn direction_is_respected() {
+        let (local, remote) = handlers();
+        let config = BridgeConfig {
+            direction: Direction::LocalToRemote,
+            ..BridgeConfig::default()
+        };
+        let _bridge = Bridge::start(Arc::clone(&local), Arc::clone(&remote), config).unwrap();
+        let (_local_subscription, local_says) = receive_says(&*local);
+        let (_remote_subscription, remote_says) = receive_says(&*remote);
+
+        remote.tts().publish_say(say("bedroom")).unwrap();
+        assert_eq!(remote_says.recv_timeout(WAIT * 10).unwrap(), say("bedroom"));
+        assert!(local_says.recv_timeout(WAIT).is_err());
+    }
+
+    #[test]
+    fn filters_are_applied() {
+        let (local, remote) = handlers();
+        let config = BridgeConfig {
+            direction: Direction::RemoteToLocal,
+            topic_filters: vec!["hermes/tts/+".into()],
+            site_routes: vec![("kitchen".into(), Direction::Both)].into_iter().collect(),
+        };
+        let _bridge = Bridge::start(Arc::clone(&local), Arc::clone(&remote), config).unwrap();
+        let (_subscription, remote_says) = receive_says(&*remote);
+        let (tx, rx) = mpsc::channel();
+        let tx = Mutex::new(tx);
+        let _text_subscription = remote
+            .asr()
+            .subscribe_text_captured(Callback::new(move |it: &TextCapturedMessage| {
+                let _ = tx.lock().unwrap().send(it.clone());
+            }))
+            .unwrap();
+
+        local.tts().publish_say(say("bedroom")).unwrap();
+        local.tts().publish_say(say("kitchen")).unwrap();
+        local
+            .asr_backend()
+            .publish_text_captured(TextCapturedMessage {
+                text: "hello".into(),
+                likelihood: 0.5,
+                tokens: None,
+                seconds: 1.0,
+                site_id: "kitchen".into(),
+                session_id: None,
+            })
+            .unwrap();
+
+        assert_eq!(remote_says.recv_timeout(WAIT * 10).unwrap(), say("kitchen"));
+        assert!(remote_says.recv_timeout(WAIT).is_err());
+        assert!(rx.recv_timeout(WAIT).is_err());
+    }
+
+    #[test]
+    fn sites_are_routed_in_their_own_direction() {
+        let (local, remote) = handlers();
+        let config = BridgeConfig {
+            direction: Direction::LocalToRemote,
+            site_routes: vec![("kitchen".into(), Direction::RemoteToLocal)].into_iter().collect(),
+            ..BridgeConfig::default()
+        };
+        let _bridge = Bridge::start(Arc::clone(&local), Arc::clone(&remote), config).unwrap();
+        let (_local_subscription, local_says) = receive_says(&*local);
+        let (_remote_subscription, remote_says) = receive_says(&*remote);
+
+        remote.tts().publish_say(say("kitchen")).unwrap();
+        remote.tts().publish_say(say("bedroom")).unwrap();
+        assert_eq!(local_says.recv_timeout(WAIT * 10).unwrap(), say("kitchen"));
+        assert_eq!(remote_says.recv_timeout(WAIT * 10).unwrap(), say("kitchen"));
+        assert_eq!(remote_says.recv_timeout(WAIT * 10).unwrap(), say("bedroom"));
+
+        local.tts().publish_say(say("kitchen")).unwrap();
+        local.tts().publish_say(say("bedroom")).unwrap();
+        assert_eq!(local_says.recv_timeout(WAIT * 10).unwrap(), say("kitchen"));
+        assert_eq!(local_says.recv_timeout(WAIT * 10).unwrap(), say("bedroom"));
+        assert_eq!(remote_says.recv_timeout(WAIT * 10).unwrap(), say("bedroom"));
+
+        assert!(local_says.recv_timeout(WAIT).is_err());
+        assert!(remote_says.recv_timeout(WAIT).is_err());
+    }
+
+    #[test]
+    fn per_site_messages_are_bridged_for_the_routed_sites() {
+        let (local, remote) = handlers();
+        let config = BridgeConfig {
+            site_routes: vec![("kitchen".into(), Direction::LocalToRemote)].into_iter().collect(),
+            ..BridgeConfig::default()
+        };
+        let _bridge = Bridge::start(Arc::clone(&local), Arc::clone(&remote), config).unwrap();
+        let (tx, rx) = mpsc::channel();
+        let tx = Mutex::new(tx);
+        let _subscription = remote
+            .audio_server()
+            .subscribe_audio_frame(
+                "kitchen".into(),
+                Callback::new(move |it: &AudioFrameMessage| {
+                    let _ = tx.lock().unwrap().send(it.clone());
+                }),
+            )
+            .unwrap();
+        let frame = AudioFrameMessage {
+            wav_frame: vec![0, 159, 255],
+            site_id: "kitchen".into(),
+        };
+        local.audio_server_backend().publish_audio_frame(frame.clone()).unwrap();
+        assert_eq!(rx.recv_timeout(WAIT * 10).unwrap(), frame);
+    }
+
+    #[test]
+    fn topic_filters_match_like_mqtt() {
+        assert!(filter_matches("hermes/asr/#", "hermes/asr/textCaptured"));
+        assert!(filter_matches("hermes/+/toggleOn", "hermes/hotword/toggleOn"));
+        assert!(!filter_matches("hermes/+/toggleOn", "hermes/feedback/sound/toggleOn"));
+        assert!(!filter_matches("hermes/asr", "hermes/asr/textCaptured"));
+    }
+}
@@ -0,0 +1,169 @@
+//! The serialization formats of the payloads published on MQTT.
+//!
+//! JSON payloads are published as is, so that the handlers stay compatible with the clients that
+//! only know about JSON. The binary formats are prefixed with a marker, a zero byte (that can't
+//! start a JSON document) followed by the id of the format, so that a handler can decode the
+//! payloads whatever the codec of the handler that published them.
+
+use std::fmt;
+use std::str::FromStr;
+
+use hermes::{HermesError, HermesResult};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+const MARKER: u8 = 0x00;
+
+/// How the messages of the ontology are serialized in the payloads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PayloadCodec {
+    #[default]
+    Json,
+    MessagePack,
+    Cbor,
+}
+
+impl PayloadCodec {
+    /// The byte following the marker in the payloads of this codec
+    fn id(self) -> Option<u8> {
+        match self {
+            PayloadCodec::Json => None,
+            PayloadCodec::MessagePack => Some(0x01),
+            PayloadCodec::Cbor => Some(0x02),
+        }
+    }
+
+    /// The codec a payload was encoded with, along with the payload without its marker
+    pub fn detect(bytes: &[u8]) -> HermesResult<(Self, &[u8])> {
+        if bytes.len() < 2 || bytes[0] != MARKER {
+            return Ok((PayloadCodec::Json, bytes));
+        }
+        [PayloadCodec::MessagePack, PayloadCodec::Cbor]
+            .iter()
+            .find(|it| it.id() == Some(bytes[1]))
+            .map(|it| (*it, &bytes[2..]))
+            .ok_or_else(|| HermesError::InvalidArgument(format!("Unknown payload codec id {}", bytes[1])))
+    }
+
+    /// Serialize a message, prefixed with the marker of the codec
+    pub fn encode<T: Serialize>(self, message: &T) -> HermesResult<Vec<u8>> {
+        let mut bytes = match self.id() {
+            Some(id) => vec![MARKER, id],
+            None => vec![],
+        };
+        match self {
+            PayloadCodec::Json => serde_json::to_writer(&mut bytes, message)?,
+            PayloadCodec::MessagePack => {
+                rmp_serde::encode::write_named(&mut bytes, message).map_err(HermesError::serialization)?
+            }
+            PayloadCodec::Cbor => serde_cbor::to_writer(&mut bytes, message).map_err(HermesError::serialization)?,
+        }
+        Ok(bytes)
+    }
+
+    /// Deserialize a message encoded with any of the codecs
+    pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> HermesResult<T> {
+        let (codec, payload) = Self::detect(bytes)?;
+        let message = match codec {
+            PayloadCodec::Json => serde_json::from_slice(payload)?,
+            PayloadCodec::MessagePack => rmp_serde::from_slice(payload).map_err(HermesError::serialization)?,
+            PayloadCodec::Cbor => serde_cbor::from_slice(payload).map_err(HermesError::serialization)?,
+        };
+        Ok(message)
+    }
+
+    /// A printable version of a payload, for the logs
+    pub(crate) fn describe(bytes: &[u8]) -> String {
+        match Self::detect(bytes) {
+            Ok((PayloadCodec::Json, _)) if bytes.len() < 2048 => String::from_utf8_lossy(bytes).to_string(),
+            Ok((PayloadCodec::Json, _)) => format!(
+                "size = {}, start = {}",
+                bytes.len(),
+                String::from_utf8_lossy(&bytes[0..128])
+            ),
+            Ok((codec, payload)) => format!("{} payload of size {}", codec, payload.len()),
+            Err(_) => format!("unknown payload of size {}", bytes.len()),
+        }
+    }
+}
+
+impl fmt::Display for PayloadCodec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            PayloadCodec::Json => "json",
+            PayloadCodec::MessagePack => "msgpack",
+            PayloadCodec::Cbor => "cbor",
+        })
+    }
+}
+
+impl FromStr for PayloadCodec {
+    type Err = HermesError;
+
+    fn from_str(s: &str) -> HermesResult<Self> {
+        match s {
+            "json" => Ok(PayloadCodec::Json),
+            "msgpack" => Ok(PayloadCodec::MessagePack),
+            "cbor" => Ok(PayloadCodec::Cbor),
+            _ => Err(HermesError::InvalidArgument(format!(
+                "Unknown payload codec '{}', expected json, msgpack or cbor",
+                s
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hermes::*;
+
+    fn round_trip<T: Serialize + DeserializeOwned + PartialEq + fmt::Debug>(message: T) {
+        for codec in &[PayloadCodec::Json, PayloadCodec::MessagePack, PayloadCodec::Cbor] {
+            let bytes = codec.encode(&message).unwrap();
+            assert_eq!(PayloadCodec::detect(&bytes).unwrap().0, *codec);
+            assert_eq!(PayloadCodec::decode::<T>(&bytes).unwrap(), message, "with {}", codec);
+        }
+    }
+
+    #[test]
+    fn messages_round_trip_through_all_codecs() {
+        round_trip(SayMessage {
+            text: "hello".into(),
+            lang: None,
+            id: Some("abc".into()),
+            site_id: "kitchen".into(),
+            session_id: None,
+        });
+        round_trip(AudioFrameMessage {
+            wav_frame: vec![0, 159, 255],
+            site_id: "kitchen".into(),
+        });
+        round_trip(StartSessionMessage {
+            init: SessionInit::Notification { text: "hello".into() },
+            custom_data: Some("data".into()),
+            site_id: Some("kitchen".into()),
+        });
+    }
+
+    #[test]
+    fn json_payloads_have_no_marker() {
+        let bytes = PayloadCodec::Json
+            .encode(&SiteMessage {
+                site_id: "kitchen".into(),
+                session_id: None,
+            })
+            .unwrap();
+        assert_eq!(bytes[0], b'{');
+        assert_eq!(PayloadCodec::MessagePack.encode(&()).unwrap()[..2], [0x00, 0x01]);
+        assert!(PayloadCodec::decode::<SiteMessage>(&[0x00, 0x42]).is_err());
+    }
+
+    #[test]
+    fn codec_names() {
+        for codec in &[PayloadCodec::Json, PayloadCodec::MessagePack, PayloadCodec::Cbor] {
+            assert_eq!(codec.to_string().parse::<PayloadCodec>().unwrap(), *codec);
+        }
+        assert!("xml".parse::<PayloadCodec>().is_err());
+    }
+}
@@ -0,0 +1,348 @@
+//! Supervision of the connection to the broker.
+//!
+//! When enabled, the handler publishes heartbeats on a topic of its own and listens to them: when
+//! they stop coming back the connection is considered lost, and new clients are started with an
+//! exponential backoff until they come back again. All the subscriptions registered on the handler are replayed
+//! on the new client, and the messages published meanwhile can be kept to be sent once reconnected.
+
+use std::collections::VecDeque;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use hermes::{Callback, ConnectionFacade, ConnectionState, HermesError, HermesResult, Subscription};
+use log::*;
+
+use crate::{not_connected, MqttHandler, PublishPolicy};
+
+/// How the connection to the broker is watched and restored
+#[derive(Debug, Clone)]
+pub struct ReconnectOptions {
+    /// The heartbeats the connection is watched with, none by default. They are published on
+    /// `hermes-mqtt/heartbeat/<uuid>`, which the broker must allow. Without them the connection is
+    /// never considered lost, so it is not restored either.
+    pub heartbeat: Option<HeartbeatOptions>,
+    /// The delay between the first two attempts to reconnect, it is doubled after each attempt
+    pub initial_backoff: Duration,
+    /// The maximum delay between two attempts to reconnect
+    pub max_backoff: Duration,
+    /// How many of the messages published while offline are kept to be sent once reconnected, the
+    /// oldest ones being dropped first. When 0 they are handed to the client as usual.
+    pub offline_buffer_size: usize,
+}
+
+impl Default for ReconnectOptions {
+    fn default() -> Self {
+        Self {
+            heartbeat: None,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            offline_buffer_size: 0,
+        }
+    }
+}
+
+/// How often the heartbeats are published, and how late they can come back
+#[derive(Debug, Clone)]
+pub struct HeartbeatOptions {
+    /// The delay between two heartbeats
+    pub interval: Duration,
+    /// The connection is considered lost when no heartbeat came back for this long
+    pub timeout: Duration,
+}
+
+impl Default for HeartbeatOptions {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(2),
+            timeout: Duration::from_secs(6),
+        }
+    }
+}
+
+/// The state of the connection of a handler
+pub(crate) struct Connection {
+    mqtt_options: rumqtt::MqttOptions,
+    options: ReconnectOptions,
+    /// The topic of the heartbeats, without the prefix of the handler
+    heartbeat_topic: String,
+    last_heartbeat: Mutex<Option<Instant>>,
+    status: Mutex<Status>,
+}
+
+struct Status {
+    state: ConnectionState,
+    listeners: Vec<(usize, Arc<Callback<ConnectionState>>)>,
+    offline_messages: VecDeque<(String, Vec<u8>, PublishPolicy)>,
+}
+
+impl Connection {
+    pub(crate) fn new(mqtt_options: rumqtt::MqttOptions, options: ReconnectOptions) -> Self {
+        // until the first heartbeat comes back, if they are watched
+        let state = if options.heartbeat.is_some() {
+            ConnectionState::Disconnected
+        } else {
+            ConnectionState::Connected
+        };
+        Self {
+            mqtt_options,
+            options,
+            heartbeat_topic: format!("hermes-mqtt/heartbeat/{}", uuid::Uuid::new_v4()),
+            last_heartbeat: Mutex::new(None),
+            status: Mutex::new(Status {
+                state,
+                listeners: vec![],
+                offline_messages: VecDeque::new(),
+            }),
+        }
+    }
+}
+
+/// The progress of the supervisor of a connection
+struct Supervisor {
+    /// When the current client was started
+    started: Instant,
+    /// When to start a new client if the connection is still down
+    next_attempt: Instant,
+    backoff: Duration,
+}
+
+impl MqttHandler {
+    /// Watch the connection until the handler is dropped, if heartbeats are enabled
+    pub(crate) fn supervise(handler: &Arc<Self>) -> HermesResult<()> {
+        let heartbeat_options = match &handler.connection.options.heartbeat {
+            Some(heartbeat_options) => heartbeat_options.clone(),
+            None => return Ok(()),
+        };
+        let weak_handler = Arc::downgrade(handler);
+        let heartbeat_topic = handler.mqtt_topic(&handler.connection.heartbeat_topic);
+        let heartbeat = handler.inner_subscribe(heartbeat_topic, move |_| {
+            if let Some(handler) = weak_handler.upgrade() {
+                if let Ok(mut last_heartbeat) = handler.connection.last_heartbeat.lock() {
+                    *last_heartbeat = Some(Instant::now())
+                }
+            }
+        })?;
+
+        let now = Instant::now();
+        let mut supervisor = Supervisor {
+            started: now,
+            next_attempt: now + heartbeat_options.timeout,
+            backoff: handler.connection.options.initial_backoff,
+        };
+        let weak_handler = Arc::downgrade(handler);
+        std::thread::Builder::new()
+            .name("hermes-mqtt-supervisor".into())
+            .spawn(move || {
+                let _heartbeat = heartbeat;
+                while let Some(handler) = weak_handler.upgrade() {
+                    if let Err(e) = handler.check_connection(&heartbeat_options, &mut supervisor) {
+                        error!("Error while checking the connection to the MQTT broker: {}", e)
+                    }
+                    drop(handler);
+                    std::thread::sleep(heartbeat_options.interval);
+                }
+            })?;
+        Ok(())
+    }
+
+    fn check_connection(&self, heartbeat_options: &HeartbeatOptions, supervisor: &mut Supervisor) -> HermesResult<()> {
+        let options = &self.connection.options;
+        let now = Instant::now();
+        let last_heartbeat = *self.connection.last_heartbeat.lock().map_err(HermesError::from)?;
+        let state = self.connection.status.lock().map_err(HermesError::from)?.state;
+        let alive = match last_heartbeat {
+            Some(last_heartbeat) if state == ConnectionState::Connected => {
+                now.duration_since(last_heartbeat) <= heartbeat_options.timeout
+            }
+            Some(last_heartbeat) => last_heartbeat >= supervisor.started,
+            None => false,
+        };
+
+        if alive && state != ConnectionState::Connected {
+            supervisor.backoff = options.initial_backoff;
+            self.set_state(ConnectionState::Connected)?;
+        } else if !alive {
+            if state == ConnectionState::Connected {
+                supervisor.next_attempt = now;
+                self.set_state(ConnectionState::Disconnected)?;
+            }
+            if now >= supervisor.next_attempt {
+                self.set_state(ConnectionState::Reconnecting)?;
+                supervisor.started = now;
+                supervisor.next_attempt = now + supervisor.backoff;
+                supervisor.backoff = std::cmp::min(supervisor.backoff * 2, options.max_backoff);
+                if let Err(e) = self.reconnect() {
+                    warn!("Could not reconnect to the MQTT broker: {}", e)
+                }
+            }
+        }
+
+        let heartbeat_topic = self.mqtt_topic(&self.connection.heartbeat_topic);
+        if let Err(e) =
+            self.with_client(|it| Self::publish_client(it, &heartbeat_topic, vec![], PublishPolicy::default()))
+        {
+            debug!("Could not publish heartbeat: {}", e)
+        }
+        Ok(())
+    }
+
+    /// Replace the client by a new one subscribed to the same topics. The previous client is
+    /// stopped first, so that the broker doesn't see two clients with the same id and that the
+    /// messages are not dispatched twice.
+    fn reconnect(&self) -> HermesResult<()> {
+        info!(
+            "Reconnecting to the MQTT broker on {}",
+            self.connection.mqtt_options.broker_addr
+        );
+        let previous = self.mqtt_client.write().map_err(HermesError::from)?.take();
+        // the previous client may be dispatching to a handler that (un)subscribes
+        drop(previous);
+
+        let mqtt_client = rumqtt::MqttClient::start(self.connection.mqtt_options.clone()).map_err(not_connected)?;
+        let subscriptions = self.subscriptions.lock().map_err(HermesError::from)?;
+        for (topic, handlers) in subscriptions.iter() {
+            Self::subscribe_client(&mqtt_client, topic, handlers).map_err(|e| {
+                HermesError::NotConnected(format!("Could not subscribe again to MQTT topic '{}': {}", topic, e))
+            })?;
+        }
+        *self.mqtt_client.write().map_err(HermesError::from)? = Some(mqtt_client);
+        Ok(())
+    }
+
+    /// Run `f` with the current client, there is none while reconnecting
+    pub(crate) fn with_client<T, F>(&self, f: F) -> HermesResult<T>
+    where
+        F: FnOnce(&rumqtt::MqttClient) -> HermesResult<T>,
+    {
+        match &*self.mqtt_client.read().map_err(HermesError::from)? {
+            Some(mqtt_client) => f(mqtt_client),
+            None => Err(HermesError::NotConnected("Reconnecting to the MQTT broker".into())),
+        }
+    }
+
+    fn set_state(&self, state: ConnectionState) -> HermesResult<()> {
+        let (listeners, offline_messages) = {
+            let mut status = self.connection.status.lock().map_err(HermesError::from)?;
+            if status.state == state {
+                return Ok(());
+            }
+            status.state = state;
+            let offline_messages: Vec<_> = if state == ConnectionState::Connected {
+                status.offline_messages.drain(..).collect()
+            } else {
+                vec![]
+            };
+            let listeners: Vec<_> = status.listeners.iter().map(|(_, it)| Arc::clone(it)).collect();
+            (listeners, offline_messages)
+        };
+
+        match state {
+            ConnectionState::Connected => info!("Connected to the MQTT broker"),
+            ConnectionState::Disconnected => warn!("Lost the connection to the MQTT broker"),
+            ConnectionState::Reconnecting => {}
+        }
+        if !offline_messages.is_empty() {
+            debug!("Sending {} messages published while offline", offline_messages.len());
+            for (topic, payload, policy) in offline_messages {
+                if let Err(e) = self.with_client(|it| Self::publish_client(it, &topic, payload, policy)) {
+                    warn!("Could not send message published while offline on '{}': {}", topic, e)
+                }
+            }
+        }
+        for listener in listeners {
+            listener.call(&state)
+        }
+        Ok(())
+    }
+
+    /// Publish on the broker, or keep the message until the connection is restored if configured so
+    pub(crate) fn send(&self, topic: &str, payload: Vec<u8>, policy: PublishPolicy) -> HermesResult<()> {
+        let buffer_size = self.connection.options.offline_buffer_size;
+        if buffer_size > 0 {
+            let mut status = self.connection.status.lock().map_err(HermesError::from)?;
+            if status.state != ConnectionState::Connected {
+                if status.offline_messages.len() >= buffer_size {
+                    warn!("Too many messages published while offline, dropping the oldest one");
+                    status.offline_messages.pop_front();
+                }
+                debug!("Keeping message on MQTT topic '{}' until reconnected", topic);
+                status.offline_messages.push_back((topic.into(), payload, policy));
+                return Ok(());
+            }
+        }
+        self.with_client(|it| Self::publish_client(it, topic, payload, policy))
+    }
+
+    fn publish_client(
+        mqtt_client: &rumqtt::MqttClient,
+        topic: &str,
+        payload: Vec<u8>,
+        policy: PublishPolicy,
+    ) -> HermesResult<()> {
+        mqtt_client
+            .publish(topic)
+            .map(|m| m.payload(payload).qos(policy.qos).retain(policy.retain))
+            .and_then(|p| p.send())
+            .map_err(not_connected)?;
+        Ok(())
+    }
+}
+
+pub(crate) struct MqttConnectionFacade {
+    pub(crate) mqtt_handler: Arc<MqttHandler>,
+}
+
+impl ConnectionFacade for MqttConnectionFacade {
+    fn state(&self) -> HermesResult<ConnectionState> {
+        let status = self.mqtt_handler.connection.status.lock().map_err(HermesError::from)?;
+        Ok(status.state)
+    }
+
+    fn subscribe_state_changed(&self, handler: Callback<ConnectionState>) -> HermesResult<Subscription> {
+        let id = self.mqtt_handler.subscription_counter.fetch_add(1, Ordering::Relaxed);
+        self.mqtt_handler
+            .connection
+            .status
+            .lock()
+            .map_err(HermesError::from)?
+            .listeners
+            .push((id, Arc::new(handler)));
+
+        let mqtt_handler = Arc::downgrade(&self.mqtt_handler);
+        Ok(Subscription::new(move || {
+            if let Some(mqtt_handler) = mqtt_handler.upgrade() {
+                if let Ok(mut status) = mqtt_handler.connection.status.lock() {
+                    status.listeners.retain(|(listener_id, _)| *listener_id != id)
+                }
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MqttHermesProtocolHandler;
+    use hermes_mqtt_broker::Broker;
+
+    #[test]
+    fn messages_are_dispatched_once_after_a_reconnection() {
+        let broker = Broker::start().expect("could not start the broker");
+        let handler = MqttHermesProtocolHandler::new(&broker.local_addr().to_string()).unwrap();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let tx = Mutex::new(tx);
+        let _subscription = handler
+            .subscribe_raw("hermes/tts/say", move |topic, _| {
+                let _ = tx.lock().unwrap().send(topic.to_string());
+            })
+            .unwrap();
+
+        handler.mqtt_handler.reconnect().unwrap();
+        std::thread::sleep(Duration::from_millis(200));
+        handler.publish_raw("hermes/tts/say", vec![]).unwrap();
+
+        assert!(rx.recv_timeout(Duration::from_secs(5)).is_ok());
+        assert!(rx.recv_timeout(Duration::from_millis(500)).is_err());
+    }
+}
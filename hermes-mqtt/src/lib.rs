@@ -1,17 +1,29 @@
-mod topics;
-
+pub mod bridge;
+mod codec;
+mod connection;
+mod payload;
+mod policy;
+pub mod record;
+pub mod topics;
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::ops::Deref;
 use std::string::ToString;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, RwLock};
 
-use failure::{format_err, Fallible, ResultExt, SyncFailure};
 use hermes::*;
 use lazy_static::lazy_static;
 use log::*;
 
 use crate::topics::*;
 
-pub use rumqtt::{MqttOptions, TlsOptions};
+pub use crate::codec::PayloadCodec;
+pub use crate::connection::{HeartbeatOptions, ReconnectOptions};
+pub use crate::payload::Payload;
+pub use crate::policy::PublishPolicy;
+pub use rumqtt::{MqttOptions, QoS, TlsOptions};
 
 lazy_static! {
     static ref MQTT_ID_COUNTER: AtomicUsize = AtomicUsize::from(0);
@@ -30,106 +42,183 @@ pub fn get_mqtt_id() -> String {
     )
 }
 
+type MqttCallback = Arc<Fn(&Received) -> () + Send + Sync>;
+
+/// The handlers registered on a topic, they all share a single subscription on the broker
+type TopicHandlers = Arc<Mutex<Vec<(usize, MqttCallback)>>>;
+
+/// A message received from the broker, checked once for all the handlers of its topic
+struct Received<'a> {
+    message: &'a rumqtt::Publish,
+    accepted: Cell<Option<bool>>,
+}
+
+impl<'a> Received<'a> {
+    fn new(message: &'a rumqtt::Publish) -> Self {
+        Self {
+            message,
+            accepted: Cell::new(None),
+        }
+    }
+
+    /// Whether the message is to be given to the handlers, `check` being only run for the first one
+    fn accepted<F: FnOnce() -> bool>(&self, check: F) -> bool {
+        let accepted = self.accepted.get().unwrap_or_else(check);
+        self.accepted.set(Some(accepted));
+        accepted
+    }
+}
+
+impl<'a> Deref for Received<'a> {
+    type Target = rumqtt::Publish;
+
+    fn deref(&self) -> &rumqtt::Publish {
+        self.message
+    }
+}
+
 struct MqttHandler {
-    mqtt_client: rumqtt::MqttClient,
+    /// The client is replaced by a new one when the connection to the broker is restored, there is
+    /// none in between
+    mqtt_client: Arc<RwLock<Option<rumqtt::MqttClient>>>,
+    subscriptions: Arc<Mutex<HashMap<String, TopicHandlers>>>,
+    subscription_counter: AtomicUsize,
+    connection: connection::Connection,
+    publish_policies: RwLock<policy::PublishPolicies>,
+    topic_prefix: Option<String>,
+    payload_codec: PayloadCodec,
+    validation: ValidationMode,
+}
+
+/// The errors of the MQTT client, which only fails when it can't reach the broker
+fn not_connected<E: std::fmt::Display>(error: E) -> HermesError {
+    HermesError::NotConnected(error.to_string())
+}
+
+/// The topic a message was received on, without the prefix of the handler
+fn unprefixed<'a>(topic_prefix: &Option<String>, topic_name: &'a str) -> &'a str {
+    match topic_prefix {
+        Some(prefix) if topic_name.starts_with(prefix.as_str()) && topic_name[prefix.len()..].starts_with('/') => {
+            &topic_name[prefix.len() + 1..]
+        }
+        _ => topic_name,
+    }
 }
 
 impl MqttHandler {
-    pub fn publish(&self, topic: &HermesTopic) -> Fallible<()> {
-        let topic = &*topic.as_path();
+    pub fn publish(&self, topic: &HermesTopic) -> HermesResult<()> {
+        let policy = self.publish_policy(topic)?;
+        let topic = &*self.mqtt_topic(&topic.as_path());
         debug!("Publishing on MQTT topic '{}'", topic);
-        self.mqtt_client
-            .publish(topic)
-            .and_then(|p| p.send())
-            .map_err(SyncFailure::new)?;
-        Ok(())
+        self.send(topic, vec![], policy)
     }
 
-    pub fn publish_payload<P: serde::Serialize>(&self, topic: &HermesTopic, payload: P) -> Fallible<()> {
-        serde_json::to_vec(&payload).map(|p| {
-            let topic = &*topic.as_path();
+    pub fn publish_payload<P: serde::Serialize + Validate>(&self, topic: &HermesTopic, payload: P) -> HermesResult<()> {
+        self.check_published(&payload)?;
+        let policy = self.publish_policy(topic)?;
+        self.payload_codec.encode(&payload).map(|p| {
+            let topic = &*self.mqtt_topic(&topic.as_path());
             debug!(
                 "Publishing on MQTT topic '{}', payload: {}",
                 topic,
-                if p.len() < 2048 {
-                    String::from_utf8_lossy(&p).to_string()
-                } else {
-                    format!("size = {}, start = {}", p.len(), String::from_utf8_lossy(&p[0..128]))
-                }
+                PayloadCodec::describe(&p)
             );
             trace!("Payload: {}", String::from_utf8_lossy(&p));
-            self.mqtt_client
-                .publish(topic)
-                .map(|m| m.payload(p))
-                .and_then(|p| p.send())
-                .map_err(SyncFailure::new)
-        })??;
-        Ok(())
+            self.send(topic, p, policy)
+        })?
+    }
+
+    pub fn publish_binary_payload(&self, topic: &HermesTopic, payload: Vec<u8>) -> HermesResult<()> {
+        self.send_binary(&self.mqtt_topic(&topic.as_path()), payload, self.publish_policy(topic)?)
     }
 
-    pub fn publish_binary_payload(&self, topic: &HermesTopic, payload: Vec<u8>) -> Fallible<()> {
-        let topic = &*topic.as_path();
+    pub fn publish_raw(&self, topic: &str, payload: Vec<u8>) -> HermesResult<()> {
+        let policy = match HermesTopic::from_path(topic) {
+            Some(topic) => self.publish_policy(&topic)?,
+            None => PublishPolicy::default(),
+        };
+        self.send_binary(&self.mqtt_topic(topic), payload, policy)
+    }
+
+    fn send_binary(&self, topic: &str, payload: Vec<u8>, policy: PublishPolicy) -> HermesResult<()> {
         debug!(
             "Publishing as binary on MQTT topic '{}', with size {}",
             topic,
             payload.len()
         );
-        self.mqtt_client
-            .publish(topic)
-            .map(|m| m.payload(payload))
-            .and_then(|p| p.send())
-            .map_err(SyncFailure::new)?;
+        self.send(topic, payload, policy)
+    }
 
-        Ok(())
+    /// The topic of the broker a topic of the handler is mapped to
+    fn mqtt_topic(&self, topic: &str) -> String {
+        match self.topic_prefix {
+            Some(ref prefix) => format!("{}/{}", prefix, topic),
+            None => topic.to_string(),
+        }
+    }
+
+    fn publish_policy(&self, topic: &HermesTopic) -> HermesResult<PublishPolicy> {
+        Ok(self.publish_policies.read().map_err(HermesError::from)?.get(topic))
     }
 
-    pub fn subscribe<F>(&self, topic: &HermesTopic, handler: F) -> Fallible<()>
+    pub fn subscribe<F>(&self, topic: &HermesTopic, handler: F) -> HermesResult<Subscription>
     where
         F: Fn() -> () + Send + Sync + 'static,
     {
         let log_level = Self::log_level(topic);
-        self.inner_subscribe(topic, move |m| {
+        self.inner_subscribe(self.mqtt_topic(&topic.to_string()), move |m| {
             log!(log_level, "Received a message on MQTT topic '{:?}'", m.topic_name);
             handler()
         })
     }
 
-    pub fn subscribe_payload<F, P>(&self, topic: &HermesTopic, handler: F) -> Fallible<()>
+    pub fn subscribe_payload<F, P>(self: &Arc<Self>, topic: &HermesTopic, handler: F) -> HermesResult<Subscription>
     where
         F: Fn(&P) -> () + Send + Sync + 'static,
-        P: serde::de::DeserializeOwned,
+        P: serde::de::DeserializeOwned + Validate,
     {
         let log_level = Self::log_level(topic);
-        self.inner_subscribe(topic, move |m| {
+        let weak_handler = Arc::downgrade(self);
+        self.inner_subscribe(self.mqtt_topic(&topic.to_string()), move |m| {
             log!(
                 log_level,
                 "Received a message on MQTT topic '{:?}', payload: {}",
                 m.topic_name,
-                if m.payload.len() < 2048 {
-                    String::from_utf8_lossy(&m.payload).to_string()
-                } else {
-                    format!(
-                        "size = {}, start = {}",
-                        m.payload.len(),
-                        String::from_utf8_lossy(&m.payload[0..128])
-                    )
-                }
+                PayloadCodec::describe(&m.payload)
             );
             trace!("Payload: {}", String::from_utf8_lossy(&m.payload));
-            let r = serde_json::from_slice(m.payload.as_slice());
+            let r = PayloadCodec::decode(m.payload.as_slice());
             match r {
-                Ok(p) => handler(&p),
+                Ok(p) => {
+                    let accepted = m.accepted(|| match weak_handler.upgrade() {
+                        Some(mqtt_handler) => mqtt_handler.accept_received(&m.topic_name, &p),
+                        None => true,
+                    });
+                    if accepted {
+                        handler(&p)
+                    }
+                }
                 Err(e) => warn!("Error while decoding object on topic {:?}: {}", m.topic_name, e),
             }
         })
     }
 
-    pub fn subscribe_binary_payload<F>(&self, topic: &HermesTopic, handler: F) -> Fallible<()>
+    /// Subscribe to messages whose fields are in the topic and the payload, `decoder` building them
+    pub fn subscribe_binary_payload<D, F, P>(
+        self: &Arc<Self>,
+        topic: &HermesTopic,
+        decoder: D,
+        handler: F,
+    ) -> HermesResult<Subscription>
     where
-        F: Fn(&HermesTopic, &[u8]) -> () + Send + Sync + 'static,
+        D: Fn(&HermesTopic, &[u8]) -> P + Send + Sync + 'static,
+        F: Fn(&P) -> () + Send + Sync + 'static,
+        P: Validate,
     {
         let log_level = Self::log_level(topic);
-        self.inner_subscribe(topic, move |m| {
+        let topic_prefix = self.topic_prefix.clone();
+        let weak_handler = Arc::downgrade(self);
+        self.inner_subscribe(self.mqtt_topic(&topic.to_string()), move |m| {
             log!(
                 log_level,
                 "Received a message on MQTT topic '{:?}', payload: {}",
@@ -145,27 +234,159 @@ impl MqttHandler {
                 }
             );
             trace!("Payload: {}", String::from_utf8_lossy(&m.payload));
-            let topic = HermesTopic::from_path(&m.topic_name);
+            let topic = HermesTopic::from_path(unprefixed(&topic_prefix, &m.topic_name));
             if let Some(topic) = topic {
-                handler(&topic, &m.payload)
+                let message = decoder(&topic, &m.payload);
+                let accepted = m.accepted(|| match weak_handler.upgrade() {
+                    Some(mqtt_handler) => mqtt_handler.accept_received(&m.topic_name, &message),
+                    None => true,
+                });
+                if accepted {
+                    handler(&message)
+                }
             } else {
                 error!("could not parse topic: {:?}", m.topic_name)
             }
         })
     }
 
-    fn inner_subscribe<F>(&self, topic: &HermesTopic, callback: F) -> Fallible<()>
+    pub fn subscribe_raw<F>(&self, topic_filter: &str, handler: F) -> HermesResult<Subscription>
     where
-        F: Fn(&::rumqtt::Publish) -> () + Send + Sync + 'static,
+        F: Fn(&str, &[u8]) -> () + Send + Sync + 'static,
     {
-        self.mqtt_client
-            .subscribe(topic.to_string(), Box::new(callback))
-            .map_err(SyncFailure::new)?
+        let topic_prefix = self.topic_prefix.clone();
+        self.inner_subscribe(self.mqtt_topic(topic_filter), move |m| {
+            trace!("Received a message on MQTT topic '{:?}'", m.topic_name);
+            handler(unprefixed(&topic_prefix, &m.topic_name), &m.payload)
+        })
+    }
+
+    fn inner_subscribe<F>(&self, topic: String, callback: F) -> HermesResult<Subscription>
+    where
+        F: Fn(&Received) -> () + Send + Sync + 'static,
+    {
+        let id = self.subscription_counter.fetch_add(1, Ordering::Relaxed);
+        let callback: MqttCallback = Arc::new(callback);
+        {
+            let mut subscriptions = self.subscriptions.lock().map_err(HermesError::from)?;
+            if let Some(handlers) = subscriptions.get(&topic) {
+                handlers.lock().map_err(HermesError::from)?.push((id, callback));
+            } else {
+                let handlers: TopicHandlers = Arc::new(Mutex::new(vec![(id, callback)]));
+                // otherwise subscribed once reconnected
+                if let Some(mqtt_client) = &*self.mqtt_client.read().map_err(HermesError::from)? {
+                    Self::subscribe_client(mqtt_client, &topic, &handlers)?;
+                }
+                subscriptions.insert(topic.clone(), handlers);
+            }
+        }
+
+        let mqtt_client = Arc::downgrade(&self.mqtt_client);
+        let subscriptions = Arc::downgrade(&self.subscriptions);
+        Ok(Subscription::new(move || {
+            // nothing to do if the handler is already gone
+            if let (Some(mqtt_client), Some(subscriptions)) = (mqtt_client.upgrade(), subscriptions.upgrade()) {
+                if let Err(e) = Self::remove_handler(&mqtt_client, &subscriptions, &topic, id) {
+                    warn!("Error while unsubscribing from MQTT topic '{}': {}", topic, e)
+                }
+            }
+        }))
+    }
+
+    /// Subscribe the client to a topic, the messages received being dispatched to its handlers
+    fn subscribe_client(mqtt_client: &rumqtt::MqttClient, topic: &str, handlers: &TopicHandlers) -> HermesResult<()> {
+        let dispatch_handlers = Arc::clone(handlers);
+        mqtt_client
+            .subscribe(
+                topic,
+                Box::new(move |m: &rumqtt::Publish| {
+                    // take a snapshot so that handlers can (un)subscribe from their callback
+                    let handlers: Vec<MqttCallback> = match dispatch_handlers.lock() {
+                        Ok(handlers) => handlers.iter().map(|(_, it)| Arc::clone(it)).collect(),
+                        Err(_) => {
+                            error!("Could not dispatch message received on {:?}", m.topic_name);
+                            return;
+                        }
+                    };
+                    let received = Received::new(m);
+                    for handler in handlers {
+                        handler(&received)
+                    }
+                }),
+            )
+            .map_err(not_connected)?
             .send()
-            .map_err(SyncFailure::new)?;
+            .map_err(not_connected)?;
         Ok(())
     }
 
+    fn remove_handler(
+        mqtt_client: &RwLock<Option<rumqtt::MqttClient>>,
+        subscriptions: &Mutex<HashMap<String, TopicHandlers>>,
+        topic: &str,
+        id: usize,
+    ) -> HermesResult<()> {
+        let mut subscriptions = subscriptions.lock().map_err(HermesError::from)?;
+        let last_handler = match subscriptions.get(topic) {
+            Some(handlers) => {
+                let mut handlers = handlers.lock().map_err(HermesError::from)?;
+                handlers.retain(|(handler_id, _)| *handler_id != id);
+                handlers.is_empty()
+            }
+            None => false,
+        };
+        if last_handler {
+            subscriptions.remove(topic);
+            debug!("Unsubscribing from MQTT topic '{}'", topic);
+            if let Some(mqtt_client) = &*mqtt_client.read().map_err(HermesError::from)? {
+                mqtt_client.unsubscribe(topic).map_err(not_connected)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Check a message before publishing it, according to the validation mode
+    fn check_published<V: Validate>(&self, message: &V) -> HermesResult<()> {
+        match self.validation.check(message) {
+            Ok(()) => Ok(()),
+            Err(errors) if self.validation == ValidationMode::Strict => Err(HermesError::InvalidMessage(errors)),
+            Err(errors) => {
+                warn!("Publishing an invalid {}: {}", std::any::type_name::<V>(), errors);
+                Ok(())
+            }
+        }
+    }
+
+    /// Check a message received according to the validation mode, returning whether it should be
+    /// given to the handlers of its topic, which share the verdict. The ones rejected are reported
+    /// on the error topic of their component.
+    fn accept_received<V: Validate>(&self, topic_name: &str, message: &V) -> bool {
+        let errors = match self.validation.check(message) {
+            Ok(()) => return true,
+            Err(errors) => errors,
+        };
+        if self.validation != ValidationMode::Strict {
+            warn!("Received an invalid message on MQTT topic '{}': {}", topic_name, errors);
+            return true;
+        }
+        warn!(
+            "Dropping an invalid message received on MQTT topic '{}': {}",
+            topic_name, errors
+        );
+        let topic = unprefixed(&self.topic_prefix, topic_name);
+        if let Some(error_topic) = HermesTopic::from_path(topic).and_then(|it| it.error_topic()) {
+            let error = ErrorMessage {
+                session_id: None,
+                error: format!("Invalid message: {}", errors),
+                context: Some(topic.to_string()),
+            };
+            if let Err(e) = self.publish_payload(&error_topic, error) {
+                warn!("Could not report the invalid message on {}: {}", error_topic, e)
+            }
+        }
+        false
+    }
+
     fn log_level(topic: &HermesTopic) -> log::Level {
         match *topic {
             HermesTopic::AudioServer(_, AudioServerCommand::AudioFrame) => log::Level::Trace,
@@ -179,42 +400,123 @@ pub struct MqttHermesProtocolHandler {
     mqtt_handler: Arc<MqttHandler>,
 }
 
+/// The configuration of the handler beyond the one of its MQTT client
+#[derive(Debug, Clone, Default)]
+pub struct HandlerOptions {
+    /// The prefix of all the topics of the handler, for instance `tenant42` to use
+    /// `tenant42/hermes/tts/say`, so that several assistants can share a broker. The messages
+    /// received through the raw subscriptions have their topics stripped from it.
+    pub topic_prefix: Option<String>,
+    pub reconnect: ReconnectOptions,
+    /// How the messages are serialized when published, the ones received are decoded whatever
+    /// their codec
+    pub payload_codec: PayloadCodec,
+    /// How the messages published and received are checked against the invariants of the ontology
+    pub validation: ValidationMode,
+}
+
 impl MqttHermesProtocolHandler {
     #[allow(clippy::new_ret_no_self)]
-    pub fn new(broker_address: &str) -> Fallible<MqttHermesProtocolHandler> {
+    pub fn new(broker_address: &str) -> HermesResult<MqttHermesProtocolHandler> {
         let id = get_mqtt_id();
         let client_options = rumqtt::MqttOptions::new(id, broker_address);
         Self::new_with_options(client_options)
     }
 
-    pub fn new_with_options(mut options: rumqtt::MqttOptions) -> Fallible<MqttHermesProtocolHandler> {
+    pub fn new_with_options(options: rumqtt::MqttOptions) -> HermesResult<MqttHermesProtocolHandler> {
+        Self::new_with_handler_options(options, HandlerOptions::default())
+    }
+
+    pub fn new_with_handler_options(
+        mut options: rumqtt::MqttOptions,
+        handler_options: HandlerOptions,
+    ) -> HermesResult<MqttHermesProtocolHandler> {
+        let topic_prefix = match handler_options.topic_prefix {
+            Some(prefix) => {
+                let prefix = prefix.trim_matches('/');
+                if prefix.is_empty() || prefix.contains(['+', '#']) {
+                    return Err(HermesError::InvalidArgument(format!(
+                        "Invalid topic prefix '{}'",
+                        prefix
+                    )));
+                }
+                Some(prefix.to_string())
+            }
+            None => None,
+        };
         let name = options.broker_addr.clone();
         options.max_packet_size = 10_000_000;
-        let mqtt_client = rumqtt::MqttClient::start(options)
-            .map_err(SyncFailure::new)
-            .with_context(|_| format_err!("Could not start MQTT client on {}", name))?;
-
-        let mqtt_handler = Arc::new(MqttHandler { mqtt_client });
+        let mqtt_client = rumqtt::MqttClient::start(options.clone())
+            .map_err(|e| HermesError::NotConnected(format!("Could not start MQTT client on {}: {}", name, e)))?;
+
+        let mqtt_handler = Arc::new(MqttHandler {
+            mqtt_client: Arc::new(RwLock::new(Some(mqtt_client))),
+            subscriptions: Arc::new(Mutex::new(HashMap::new())),
+            subscription_counter: AtomicUsize::new(0),
+            connection: connection::Connection::new(options, handler_options.reconnect),
+            publish_policies: RwLock::new(policy::PublishPolicies::default()),
+            topic_prefix,
+            payload_codec: handler_options.payload_codec,
+            validation: handler_options.validation,
+        });
+        MqttHandler::supervise(&mqtt_handler)?;
 
         Ok(MqttHermesProtocolHandler { name, mqtt_handler })
     }
+
+    /// Set how the messages are published on all the topics of the kind of `topic`, that is
+    /// regardless of the site id, intent name... it contains
+    pub fn set_publish_policy(&self, topic: &HermesTopic, policy: PublishPolicy) -> HermesResult<()> {
+        let mut policies = self.mqtt_handler.publish_policies.write().map_err(HermesError::from)?;
+        policies.set(topic, policy);
+        Ok(())
+    }
+
+    /// How the messages are published on a topic
+    pub fn publish_policy(&self, topic: &HermesTopic) -> HermesResult<PublishPolicy> {
+        self.mqtt_handler.publish_policy(topic)
+    }
+
+    /// How the messages are serialized when published
+    pub fn payload_codec(&self) -> PayloadCodec {
+        self.mqtt_handler.payload_codec
+    }
+
+    /// How the messages published and received are checked
+    pub fn validation(&self) -> ValidationMode {
+        self.mqtt_handler.validation
+    }
+
+    /// Subscribe to the undecoded messages published on the topics matching the filter, which may
+    /// contain the `+` and `#` wildcards. `Payload::decode` turns them into ontology messages.
+    pub fn subscribe_raw<F>(&self, topic_filter: &str, handler: F) -> HermesResult<Subscription>
+    where
+        F: Fn(&str, &[u8]) -> () + Send + Sync + 'static,
+    {
+        self.mqtt_handler.subscribe_raw(topic_filter, handler)
+    }
+
+    /// Publish some bytes as is on a topic, prefixed by the topic prefix of the handler if any
+    pub fn publish_raw(&self, topic: &str, payload: Vec<u8>) -> HermesResult<()> {
+        self.mqtt_handler.publish_raw(topic, payload)
+    }
 }
 
 macro_rules! s {
-    ($n:ident<$t:ty> $topic:expr; ) => {
-        fn $n(&self, handler: Callback<$t>) -> Fallible<()> {
+    ($n:ident<$t:ty>($($a:ident: $ta:ty),*) $topic:block) => {
+        fn $n(&self, $($a: $ta),*, handler: Callback<$t>) -> HermesResult<Subscription> {
             self.mqtt_handler.subscribe_payload($topic, move |p| handler.call(p))
         }
     };
 
-    ($n:ident<$t:ty>($($a:ident: $ta:ty),*) $topic:block) => {
-        fn $n(&self, $($a: $ta),*, handler: Callback<$t>) -> Fallible<()> {
+    ($n:ident<$t:ty> $topic:expr; ) => {
+        fn $n(&self, handler: Callback<$t>) -> HermesResult<Subscription> {
             self.mqtt_handler.subscribe_payload($topic, move |p| handler.call(p))
         }
     };
 
     ($n:ident $topic:expr; ) => {
-        fn $n(&self, handler: Callback0) -> Fallible<()> {
+        fn $n(&self, handler: Callback0) -> HermesResult<Subscription> {
             self.mqtt_handler.subscribe($topic, move || handler.call())
         }
     };
@@ -222,39 +524,39 @@ macro_rules! s {
 
 macro_rules! s_bin {
     ($n:ident<$t:ty> $topic:block |$rt:ident, $p:ident| $decoder:block) => {
-        fn $n(&self, handler: Callback<$t>) -> Fallible<()> {
-            self.mqtt_handler.subscribe_binary_payload($topic, move |$rt, $p| handler.call(&$decoder))
+        fn $n(&self, handler: Callback<$t>) -> HermesResult<Subscription> {
+            self.mqtt_handler.subscribe_binary_payload($topic, move |$rt, $p| $decoder, move |p| handler.call(p))
         }
     };
 
     ($n:ident<$t:ty>($($a:ident: $ta:ty),*) $topic:block |$rt:ident, $p:ident| $decoder:block) => {
-        fn $n(&self, $($a: $ta),*, handler: Callback<$t>) -> Fallible<()> {
-            self.mqtt_handler.subscribe_binary_payload($topic, move |$rt, $p| handler.call(&$decoder))
+        fn $n(&self, $($a: $ta),*, handler: Callback<$t>) -> HermesResult<Subscription> {
+            self.mqtt_handler.subscribe_binary_payload($topic, move |$rt, $p| $decoder, move |p| handler.call(p))
         }
     };
 }
 
 macro_rules! p {
-    ($n:ident<$t:ty> $topic:expr; ) => {
-        fn $n(&self, payload: $t) -> Fallible<()> {
+    ($n:ident<$t:ty>($param1:ident: $t1:ty) $topic:block ) => {
+        fn $n(&self, $param1: $t1, payload: $t) -> HermesResult<()> {
             self.mqtt_handler.publish_payload($topic, payload)
         }
     };
 
-    ($n:ident<$t:ty>($param1:ident: $t1:ty) $topic:block ) => {
-        fn $n(&self, $param1: $t1, payload: $t) -> Fallible<()> {
+    ($n:ident<$t:ty> $topic:expr; ) => {
+        fn $n(&self, payload: $t) -> HermesResult<()> {
             self.mqtt_handler.publish_payload($topic, payload)
         }
     };
 
     ($n:ident($payload:ident: $t:ty) $topic:block ) => {
-        fn $n(&self, $payload: $t) -> Fallible<()> {
+        fn $n(&self, $payload: $t) -> HermesResult<()> {
             self.mqtt_handler.publish_payload($topic, $payload)
         }
     };
 
     ($n:ident $topic:expr; ) => {
-        fn $n(&self) -> Fallible<()> {
+        fn $n(&self) -> HermesResult<()> {
             self.mqtt_handler.publish($topic)
         }
     };
@@ -262,7 +564,8 @@ macro_rules! p {
 
 macro_rules! p_bin {
     ($n:ident($payload:ident: $t:ty) $topic:block $bytes:block ) => {
-        fn $n(&self, $payload: $t) -> Fallible<()> {
+        fn $n(&self, $payload: $t) -> HermesResult<()> {
+            self.mqtt_handler.check_published(&$payload)?;
             self.mqtt_handler.publish_binary_payload($topic, $bytes)
         }
     };
@@ -273,7 +576,7 @@ macro_rules! impl_component_facades_for {
     // to get the component... I'm sad...
     ($t:ty) => {
         impl ComponentFacade for $t {
-            fn publish_version_request(&self) -> Fallible<()> {
+            fn publish_version_request(&self) -> HermesResult<()> {
                 self.mqtt_handler.publish(&HermesTopic::Component(
                     None,
                     self.component,
@@ -281,14 +584,14 @@ macro_rules! impl_component_facades_for {
                 ))
             }
 
-            fn subscribe_version(&self, handler: Callback<VersionMessage>) -> Fallible<()> {
+            fn subscribe_version(&self, handler: Callback<VersionMessage>) -> HermesResult<Subscription> {
                 self.mqtt_handler.subscribe_payload(
                     &HermesTopic::Component(None, self.component, ComponentCommand::Version),
                     move |p| handler.call(p),
                 )
             }
 
-            fn subscribe_error(&self, handler: Callback<ErrorMessage>) -> Fallible<()> {
+            fn subscribe_error(&self, handler: Callback<ErrorMessage>) -> HermesResult<Subscription> {
                 self.mqtt_handler.subscribe_payload(
                     &HermesTopic::Component(None, self.component, ComponentCommand::Error),
                     move |p| handler.call(p),
@@ -297,21 +600,21 @@ macro_rules! impl_component_facades_for {
         }
 
         impl ComponentBackendFacade for $t {
-            fn subscribe_version_request(&self, handler: Callback0) -> Fallible<()> {
+            fn subscribe_version_request(&self, handler: Callback0) -> HermesResult<Subscription> {
                 self.mqtt_handler.subscribe(
                     &HermesTopic::Component(None, self.component, ComponentCommand::VersionRequest),
                     move || handler.call(),
                 )
             }
 
-            fn publish_version(&self, version: VersionMessage) -> Fallible<()> {
+            fn publish_version(&self, version: VersionMessage) -> HermesResult<()> {
                 self.mqtt_handler.publish_payload(
                     &HermesTopic::Component(None, self.component, ComponentCommand::Version),
                     version,
                 )
             }
 
-            fn publish_error(&self, error: ErrorMessage) -> Fallible<()> {
+            fn publish_error(&self, error: ErrorMessage) -> HermesResult<()> {
                 self.mqtt_handler.publish_payload(
                     &HermesTopic::Component(None, self.component, ComponentCommand::Error),
                     error,
@@ -326,22 +629,22 @@ macro_rules! impl_toggleable_facades_for {
     // to get the toggle on/off topics... I'm sad...
     ($t:ty) => {
         impl ToggleableFacade for $t {
-            fn publish_toggle_on(&self) -> Fallible<()> {
+            fn publish_toggle_on(&self) -> HermesResult<()> {
                 self.mqtt_handler.publish(&self.toggle_on_topic)
             }
 
-            fn publish_toggle_off(&self) -> Fallible<()> {
+            fn publish_toggle_off(&self) -> HermesResult<()> {
                 self.mqtt_handler.publish(&self.toggle_off_topic)
             }
         }
 
         impl ToggleableBackendFacade for $t {
-            fn subscribe_toggle_on(&self, handler: Callback0) -> Fallible<()> {
+            fn subscribe_toggle_on(&self, handler: Callback0) -> HermesResult<Subscription> {
                 self.mqtt_handler
                     .subscribe(&self.toggle_on_topic, move || handler.call())
             }
 
-            fn subscribe_toggle_off(&self, handler: Callback0) -> Fallible<()> {
+            fn subscribe_toggle_off(&self, handler: Callback0) -> HermesResult<Subscription> {
                 self.mqtt_handler
                     .subscribe(&self.toggle_off_topic, move || handler.call())
             }
@@ -352,22 +655,22 @@ macro_rules! impl_toggleable_facades_for {
 macro_rules! impl_identifiable_toggleable_facades_for {
     ($t:ty) => {
         impl IdentifiableToggleableFacade for $t {
-            fn publish_toggle_on(&self, site: SiteMessage) -> Fallible<()> {
+            fn publish_toggle_on(&self, site: SiteMessage) -> HermesResult<()> {
                 self.mqtt_handler.publish_payload(&self.toggle_on_topic, site)
             }
 
-            fn publish_toggle_off(&self, site: SiteMessage) -> Fallible<()> {
+            fn publish_toggle_off(&self, site: SiteMessage) -> HermesResult<()> {
                 self.mqtt_handler.publish_payload(&self.toggle_off_topic, site)
             }
         }
 
         impl IdentifiableToggleableBackendFacade for $t {
-            fn subscribe_toggle_on(&self, handler: Callback<SiteMessage>) -> Fallible<()> {
+            fn subscribe_toggle_on(&self, handler: Callback<SiteMessage>) -> HermesResult<Subscription> {
                 self.mqtt_handler
                     .subscribe_payload(&self.toggle_on_topic, move |p| handler.call(p))
             }
 
-            fn subscribe_toggle_off(&self, handler: Callback<SiteMessage>) -> Fallible<()> {
+            fn subscribe_toggle_off(&self, handler: Callback<SiteMessage>) -> HermesResult<Subscription> {
                 self.mqtt_handler
                     .subscribe_payload(&self.toggle_off_topic, move |p| handler.call(p))
             }
@@ -378,7 +681,7 @@ macro_rules! impl_identifiable_toggleable_facades_for {
 macro_rules! impl_identifiable_component_facades_for {
     ($t:ty) => {
         impl IdentifiableComponentFacade for $t {
-            fn publish_version_request(&self, site_id: String) -> Fallible<()> {
+            fn publish_version_request(&self, site_id: String) -> HermesResult<()> {
                 self.mqtt_handler.publish(&HermesTopic::Component(
                     Some(site_id),
                     self.component,
@@ -386,14 +689,18 @@ macro_rules! impl_identifiable_component_facades_for {
                 ))
             }
 
-            fn subscribe_version(&self, site_id: String, handler: Callback<VersionMessage>) -> Fallible<()> {
+            fn subscribe_version(
+                &self,
+                site_id: String,
+                handler: Callback<VersionMessage>,
+            ) -> HermesResult<Subscription> {
                 self.mqtt_handler.subscribe_payload(
                     &HermesTopic::Component(Some(site_id), self.component, ComponentCommand::Version),
                     move |p| handler.call(p),
                 )
             }
 
-            fn subscribe_error(&self, site_id: String, handler: Callback<ErrorMessage>) -> Fallible<()> {
+            fn subscribe_error(&self, site_id: String, handler: Callback<ErrorMessage>) -> HermesResult<Subscription> {
                 self.mqtt_handler.subscribe_payload(
                     &HermesTopic::Component(Some(site_id), self.component, ComponentCommand::Error),
                     move |p| handler.call(p),
@@ -402,21 +709,21 @@ macro_rules! impl_identifiable_component_facades_for {
         }
 
         impl IdentifiableComponentBackendFacade for $t {
-            fn subscribe_version_request(&self, site_id: String, handler: Callback0) -> Fallible<()> {
+            fn subscribe_version_request(&self, site_id: String, handler: Callback0) -> HermesResult<Subscription> {
                 self.mqtt_handler.subscribe(
                     &HermesTopic::Component(Some(site_id), self.component, ComponentCommand::VersionRequest),
                     move || handler.call(),
                 )
             }
 
-            fn publish_version(&self, site_id: String, version: VersionMessage) -> Fallible<()> {
+            fn publish_version(&self, site_id: String, version: VersionMessage) -> HermesResult<()> {
                 self.mqtt_handler.publish_payload(
                     &HermesTopic::Component(Some(site_id), self.component, ComponentCommand::Version),
                     version,
                 )
             }
 
-            fn publish_error(&self, site_id: String, error: ErrorMessage) -> Fallible<()> {
+            fn publish_error(&self, site_id: String, error: ErrorMessage) -> HermesResult<()> {
                 self.mqtt_handler.publish_payload(
                     &HermesTopic::Component(Some(site_id), self.component, ComponentCommand::Error),
                     error,
@@ -599,6 +906,8 @@ impl DialogueFacade for MqttToggleableComponentFacade {
     p!(publish_continue_session<ContinueSessionMessage> &HermesTopic::DialogueManager(DialogueManagerCommand::ContinueSession););
     p!(publish_end_session<EndSessionMessage> &HermesTopic::DialogueManager(DialogueManagerCommand::EndSession););
     p!(publish_configure<DialogueConfigureMessage> &HermesTopic::DialogueManager(DialogueManagerCommand::Configure););
+    p!(publish_session_state_request &HermesTopic::DialogueManager(DialogueManagerCommand::SessionStateRequest););
+    s!(subscribe_session_state<SessionStateMessage> &HermesTopic::DialogueManager(DialogueManagerCommand::SessionState););
 }
 
 impl DialogueBackendFacade for MqttToggleableComponentFacade {
@@ -611,6 +920,8 @@ impl DialogueBackendFacade for MqttToggleableComponentFacade {
     s!(subscribe_continue_session<ContinueSessionMessage> &HermesTopic::DialogueManager(DialogueManagerCommand::ContinueSession););
     s!(subscribe_end_session<EndSessionMessage> &HermesTopic::DialogueManager(DialogueManagerCommand::EndSession););
     s!(subscribe_configure<DialogueConfigureMessage> &HermesTopic::DialogueManager(DialogueManagerCommand::Configure););
+    s!(subscribe_session_state_request &HermesTopic::DialogueManager(DialogueManagerCommand::SessionStateRequest););
+    p!(publish_session_state<SessionStateMessage> &HermesTopic::DialogueManager(DialogueManagerCommand::SessionState););
 }
 
 impl InjectionFacade for MqttComponentFacade {
@@ -625,6 +936,53 @@ impl InjectionBackendFacade for MqttComponentFacade {
     p!(publish_injection_status<InjectionStatusMessage> &HermesTopic::Injection(InjectionCommand::Status););
 }
 
+struct MqttHandshakeFacade {
+    mqtt_handler: Arc<MqttHandler>,
+}
+
+impl HandshakeFacade for MqttHandshakeFacade {
+    p!(publish_handshake_request &HermesTopic::Protocol(ProtocolCommand::HandshakeRequest););
+    s!(subscribe_handshake_request &HermesTopic::Protocol(ProtocolCommand::HandshakeRequest););
+    p!(publish_handshake<HandshakeMessage> &HermesTopic::Protocol(ProtocolCommand::Handshake););
+    s!(subscribe_handshakes<HandshakeMessage> &HermesTopic::Protocol(ProtocolCommand::Handshake););
+}
+
+struct MqttRegistryFacade {
+    mqtt_handler: Arc<MqttHandler>,
+}
+
+impl RegistryFacade for MqttRegistryFacade {
+    p!(publish_snapshot_request &HermesTopic::Registry(RegistryCommand::SnapshotRequest););
+    s!(subscribe_snapshot<RegistrySnapshotMessage> &HermesTopic::Registry(RegistryCommand::Snapshot););
+    s!(subscribe_component_up<ComponentStatusMessage> &HermesTopic::Registry(RegistryCommand::ComponentUp););
+    s!(subscribe_component_down<ComponentStatusMessage> &HermesTopic::Registry(RegistryCommand::ComponentDown););
+}
+
+impl RegistryBackendFacade for MqttRegistryFacade {
+    s!(subscribe_snapshot_request &HermesTopic::Registry(RegistryCommand::SnapshotRequest););
+    p!(publish_snapshot<RegistrySnapshotMessage> &HermesTopic::Registry(RegistryCommand::Snapshot););
+    p!(publish_component_up<ComponentStatusMessage> &HermesTopic::Registry(RegistryCommand::ComponentUp););
+    p!(publish_component_down<ComponentStatusMessage> &HermesTopic::Registry(RegistryCommand::ComponentDown););
+}
+
+struct MqttSiteFacade {
+    mqtt_handler: Arc<MqttHandler>,
+}
+
+impl SiteFacade for MqttSiteFacade {
+    p!(publish_register_site<SiteInfoMessage> &HermesTopic::Site(SiteCommand::Register););
+    p!(publish_unregister_site<SiteMessage> &HermesTopic::Site(SiteCommand::Unregister););
+    p!(publish_sites_request &HermesTopic::Site(SiteCommand::SitesRequest););
+    s!(subscribe_sites<SitesMessage> &HermesTopic::Site(SiteCommand::Sites););
+}
+
+impl SiteBackendFacade for MqttSiteFacade {
+    s!(subscribe_register_site<SiteInfoMessage> &HermesTopic::Site(SiteCommand::Register););
+    s!(subscribe_unregister_site<SiteMessage> &HermesTopic::Site(SiteCommand::Unregister););
+    s!(subscribe_sites_request &HermesTopic::Site(SiteCommand::SitesRequest););
+    p!(publish_sites<SitesMessage> &HermesTopic::Site(SiteCommand::Sites););
+}
+
 impl MqttHermesProtocolHandler {
     fn hotword_component(&self) -> Box<MqttToggleableComponentFacade> {
         Box::new(MqttToggleableComponentFacade {
@@ -670,6 +1028,18 @@ impl MqttHermesProtocolHandler {
         })
     }
 
+    fn registry_component(&self) -> Box<MqttRegistryFacade> {
+        Box::new(MqttRegistryFacade {
+            mqtt_handler: Arc::clone(&self.mqtt_handler),
+        })
+    }
+
+    fn site_component(&self) -> Box<MqttSiteFacade> {
+        Box::new(MqttSiteFacade {
+            mqtt_handler: Arc::clone(&self.mqtt_handler),
+        })
+    }
+
     fn component(&self, component: Component) -> Box<MqttComponentFacade> {
         Box::new(MqttComponentFacade {
             mqtt_handler: Arc::clone(&self.mqtt_handler),
@@ -750,6 +1120,34 @@ impl HermesProtocolHandler for MqttHermesProtocolHandler {
     fn injection_backend(&self) -> Box<InjectionBackendFacade> {
         self.component(Component::Injection)
     }
+
+    fn connection(&self) -> Box<ConnectionFacade> {
+        Box::new(connection::MqttConnectionFacade {
+            mqtt_handler: Arc::clone(&self.mqtt_handler),
+        })
+    }
+
+    fn handshake(&self) -> Box<HandshakeFacade> {
+        Box::new(MqttHandshakeFacade {
+            mqtt_handler: Arc::clone(&self.mqtt_handler),
+        })
+    }
+
+    fn registry(&self) -> Box<RegistryFacade> {
+        self.registry_component()
+    }
+
+    fn registry_backend(&self) -> Box<RegistryBackendFacade> {
+        self.registry_component()
+    }
+
+    fn site(&self) -> Box<SiteFacade> {
+        self.site_component()
+    }
+
+    fn site_backend(&self) -> Box<SiteBackendFacade> {
+        self.site_component()
+    }
 }
 
 impl std::fmt::Display for MqttHermesProtocolHandler {
@@ -761,22 +1159,14 @@ impl std::fmt::Display for MqttHermesProtocolHandler {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::net::{TcpListener, TcpStream};
-    use std::process::Command;
+    use hermes_mqtt_broker::Broker;
     use std::rc::Rc;
-    use std::thread::sleep;
-    use std::time::Duration;
-
-    struct ServerHolder {
-        server: std::process::Child,
-    }
 
     struct HandlerHolder {
         handler: MqttHermesProtocolHandler,
-        // this code is not dead, we need this as there is a drop on server holder that will kill
-        // the child process
+        // this code is not dead, we need this as the broker is stopped when dropped
         #[allow(dead_code)]
-        server: Rc<ServerHolder>,
+        broker: Rc<Broker>,
     }
 
     impl std::ops::Deref for HandlerHolder {
@@ -786,64 +1176,474 @@ mod tests {
         }
     }
 
-    impl Drop for ServerHolder {
-        fn drop(&mut self) {
-            self.server.kill().unwrap();
-        }
+    fn create_handlers() -> (HandlerHolder, HandlerHolder) {
+        let broker = Rc::new(Broker::start().expect("could not start the broker"));
+        let server_address = broker.local_addr().to_string();
+
+        let handler1 = HandlerHolder {
+            handler: MqttHermesProtocolHandler::new(&server_address).expect("could not create first client"),
+            broker: Rc::clone(&broker),
+        };
+
+        let handler2 = HandlerHolder {
+            handler: MqttHermesProtocolHandler::new(&server_address).expect("could not create second client"),
+            broker,
+        };
+
+        (handler1, handler2)
     }
 
-    fn create_handlers() -> (HandlerHolder, HandlerHolder) {
-        // get a random free port form the OS
-        let port = { TcpListener::bind("localhost:0").unwrap().local_addr().unwrap().port() };
-
-        // /usr/sbin is not in path on non login session on raspbian and it is where mosquitto is
-        // same goes for /usr/local/sbin on macos/homebrew
-        std::env::set_var(
-            "PATH",
-            format!("{}:/usr/sbin:/usr/local/sbin", std::env::var("PATH").unwrap()),
-        );
+    #[test]
+    fn raw_messages_can_be_decoded() {
+        let (handler_source, handler_receiver) = create_handlers();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let tx = Mutex::new(tx);
+        let _subscription = handler_receiver
+            .subscribe_raw("hermes/tts/#", move |topic, bytes| {
+                let topic = HermesTopic::from_path(topic).unwrap();
+                let _ = tx.lock().unwrap().send(Payload::decode(&topic, bytes).unwrap());
+            })
+            .unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(200));
+
+        let say = SayMessage {
+            text: "hello".into(),
+            lang: None,
+            id: None,
+            site_id: "kitchen".into(),
+            session_id: None,
+        };
+        handler_source.tts().publish_say(say.clone()).unwrap();
+        let sound = RegisterSoundMessage {
+            wav_sound: vec![0, 159, 255],
+            sound_id: "ding".into(),
+        };
+        handler_source.tts().publish_register_sound(sound.clone()).unwrap();
 
-        let server = Rc::new(ServerHolder {
-            server: Command::new("mosquitto")
-                .arg("-p")
-                .arg(format!("{}", port))
-                .arg("-v")
-                .spawn()
-                .expect("could not start mosquitto"),
-        });
+        let timeout = std::time::Duration::from_secs(5);
+        assert_eq!(rx.recv_timeout(timeout).unwrap(), Payload::Say(say));
+        assert_eq!(rx.recv_timeout(timeout).unwrap(), Payload::RegisterSound(sound));
+    }
 
-        let server_address = format!("localhost:{}", port);
+    #[test]
+    fn recorded_messages_can_be_replayed() {
+        let (handler_source, handler_receiver) = create_handlers();
+        let path = std::env::temp_dir().join(format!("hermes-recording-{}.jsonl", std::process::id()));
+        let recorder = record::Recorder::start(&handler_receiver, std::fs::File::create(&path).unwrap()).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(200));
+
+        let say = SayMessage {
+            text: "hello".into(),
+            lang: None,
+            id: None,
+            site_id: "kitchen".into(),
+            session_id: None,
+        };
+        handler_source.tts().publish_say(say.clone()).unwrap();
+        handler_source
+            .hotword()
+            .publish_toggle_on(SiteMessage::default())
+            .unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        drop(recorder);
+
+        let messages = record::read_recording(std::io::BufReader::new(std::fs::File::open(&path).unwrap())).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].topic, HermesTopic::Tts(TtsCommand::Say));
+        assert_eq!(messages[0].payload, Payload::Say(say.clone()));
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let tx = Mutex::new(tx);
+        let _subscription = handler_receiver
+            .tts_backend()
+            .subscribe_say(Callback::new(move |it: &SayMessage| {
+                let _ = tx.lock().unwrap().send(it.clone());
+            }))
+            .unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        record::replay(&*handler_source, &messages, record::Timing::Immediate).unwrap();
+        assert_eq!(rx.recv_timeout(std::time::Duration::from_secs(5)).unwrap(), say);
+    }
 
-        // wait 'till mosquitto is accessible.
-        let server_is_live = || {
-            for _ in 0..100 {
-                if TcpStream::connect(&server_address).is_ok() {
-                    return true;
-                } else {
-                    sleep(Duration::from_millis(50));
-                }
-            }
-            false
+    #[test]
+    fn in_process_bus_can_be_bridged() {
+        let (handler_mqtt, _) = create_handlers();
+        let local: Arc<HermesProtocolHandler> = Arc::new(hermes_inprocess::InProcessHermesProtocolHandler::new());
+        let remote: Arc<HermesProtocolHandler> = Arc::new(handler_mqtt.handler);
+        let _bridge = bridge::Bridge::start(Arc::clone(&local), Arc::clone(&remote), Default::default()).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(200));
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let tx = Mutex::new(tx);
+        let _subscription = local
+            .tts_backend()
+            .subscribe_say(Callback::new(move |it: &SayMessage| {
+                let _ = tx.lock().unwrap().send(it.clone());
+            }))
+            .unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(200));
+
+        let say = SayMessage {
+            text: "hello".into(),
+            lang: None,
+            id: None,
+            site_id: "kitchen".into(),
+            session_id: None,
+        };
+        remote.tts().publish_say(say.clone()).unwrap();
+        assert_eq!(rx.recv_timeout(std::time::Duration::from_secs(5)).unwrap(), say);
+        // the message published by the bridge on MQTT is not bridged back
+        assert!(rx.recv_timeout(std::time::Duration::from_millis(500)).is_err());
+    }
+
+    #[test]
+    fn connection_is_restored_after_broker_restart() {
+        let broker = Broker::start().expect("could not start the broker");
+        let address = broker.local_addr();
+        let reconnect_options = ReconnectOptions {
+            heartbeat: Some(HeartbeatOptions {
+                interval: std::time::Duration::from_millis(100),
+                timeout: std::time::Duration::from_millis(300),
+            }),
+            initial_backoff: std::time::Duration::from_millis(100),
+            max_backoff: std::time::Duration::from_millis(400),
+            offline_buffer_size: 10,
+        };
+        let handler = MqttHermesProtocolHandler::new_with_handler_options(
+            rumqtt::MqttOptions::new(get_mqtt_id(), address.to_string()),
+            HandlerOptions {
+                reconnect: reconnect_options,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let timeout = std::time::Duration::from_secs(5);
+
+        let (state_tx, state_rx) = std::sync::mpsc::channel();
+        let state_tx = Mutex::new(state_tx);
+        let _state_subscription = handler
+            .connection()
+            .subscribe_state_changed(Callback::new(move |it: &ConnectionState| {
+                let _ = state_tx.lock().unwrap().send(*it);
+            }))
+            .unwrap();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let tx = Mutex::new(tx);
+        let _subscription = handler
+            .tts_backend()
+            .subscribe_say(Callback::new(move |it: &SayMessage| {
+                let _ = tx.lock().unwrap().send(it.clone());
+            }))
+            .unwrap();
+        assert_eq!(state_rx.recv_timeout(timeout).unwrap(), ConnectionState::Connected);
+
+        drop(broker);
+        assert_eq!(state_rx.recv_timeout(timeout).unwrap(), ConnectionState::Disconnected);
+        assert_eq!(state_rx.recv_timeout(timeout).unwrap(), ConnectionState::Reconnecting);
+        let say = SayMessage {
+            text: "hello".into(),
+            lang: None,
+            id: None,
+            site_id: "kitchen".into(),
+            session_id: None,
+        };
+        // kept until the connection is restored
+        handler.tts().publish_say(say.clone()).unwrap();
+        assert_eq!(handler.connection().state().unwrap(), ConnectionState::Reconnecting);
+
+        let _broker = Broker::bind(address).expect("could not restart the broker");
+        assert_eq!(state_rx.recv_timeout(timeout).unwrap(), ConnectionState::Connected);
+        // received through the subscription made before the restart
+        assert_eq!(rx.recv_timeout(timeout).unwrap(), say);
+    }
+
+    #[test]
+    fn retained_messages_are_received_by_late_subscribers() {
+        let (handler_source, handler_receiver) = create_handlers();
+        let say_topic = HermesTopic::Tts(TtsCommand::Say);
+        assert!(!handler_source.publish_policy(&say_topic).unwrap().retain);
+        handler_source
+            .set_publish_policy(&say_topic, PublishPolicy::new(QoS::AtLeastOnce, true))
+            .unwrap();
+
+        let status = InjectionStatusMessage {
+            last_injection_date: None,
+        };
+        handler_source
+            .injection_backend()
+            .publish_injection_status(status.clone())
+            .unwrap();
+        let say = SayMessage {
+            text: "hello".into(),
+            lang: None,
+            id: None,
+            site_id: "kitchen".into(),
+            session_id: None,
+        };
+        handler_source.tts().publish_say(say.clone()).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(200));
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let tx = Mutex::new(tx);
+        let _status_subscription = handler_receiver
+            .injection()
+            .subscribe_injection_status(Callback::new(move |it: &InjectionStatusMessage| {
+                let _ = tx.lock().unwrap().send(it.clone());
+            }))
+            .unwrap();
+        let (say_tx, say_rx) = std::sync::mpsc::channel();
+        let say_tx = Mutex::new(say_tx);
+        let _say_subscription = handler_receiver
+            .tts_backend()
+            .subscribe_say(Callback::new(move |it: &SayMessage| {
+                let _ = say_tx.lock().unwrap().send(it.clone());
+            }))
+            .unwrap();
+
+        let timeout = std::time::Duration::from_secs(5);
+        assert_eq!(rx.recv_timeout(timeout).unwrap(), status);
+        assert_eq!(say_rx.recv_timeout(timeout).unwrap(), say);
+    }
+
+    #[test]
+    fn topic_prefix_isolates_assistants_sharing_a_broker() {
+        let broker = Broker::start().expect("could not start the broker");
+        let address = broker.local_addr().to_string();
+        let handler = |topic_prefix: Option<&str>| {
+            MqttHermesProtocolHandler::new_with_handler_options(
+                rumqtt::MqttOptions::new(get_mqtt_id(), address.clone()),
+                HandlerOptions {
+                    topic_prefix: topic_prefix.map(Into::into),
+                    ..Default::default()
+                },
+            )
         };
-        assert!(
-            server_is_live(),
-            format!("can't connect to mosquitto server {}", &server_address)
+        assert!(handler(Some("tenant42/+")).is_err());
+        let tenant = handler(Some("tenant42")).unwrap();
+        let other_tenant = handler(Some("/tenant43/")).unwrap();
+        let unprefixed = handler(None).unwrap();
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let tx = Mutex::new(tx);
+        let _subscription = tenant
+            .subscribe_raw("hermes/#", move |topic, bytes| {
+                let payload = Payload::decode(&HermesTopic::from_path(topic).unwrap(), bytes).unwrap();
+                let _ = tx.lock().unwrap().send((topic.to_string(), payload));
+            })
+            .unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(200));
+
+        let say = |text: &str| SayMessage {
+            text: text.into(),
+            lang: None,
+            id: None,
+            site_id: "kitchen".into(),
+            session_id: None,
+        };
+        other_tenant.tts().publish_say(say("other tenant")).unwrap();
+        unprefixed.tts().publish_say(say("no tenant")).unwrap();
+        tenant.tts().publish_say(say("tenant")).unwrap();
+
+        assert_eq!(
+            rx.recv_timeout(std::time::Duration::from_secs(5)).unwrap(),
+            ("hermes/tts/say".to_string(), Payload::Say(say("tenant")))
         );
+        assert!(rx.recv_timeout(std::time::Duration::from_millis(500)).is_err());
+    }
 
-        let handler1 = HandlerHolder {
-            handler: MqttHermesProtocolHandler::new(&server_address).expect("could not create first client"),
-            server: Rc::clone(&server),
+    /// Wait for the heartbeats of the handler to come back
+    fn wait_until_connected(handler: &MqttHermesProtocolHandler) {
+        let connection = handler.connection();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while connection.state().unwrap() != ConnectionState::Connected {
+            assert!(std::time::Instant::now() < deadline, "the heartbeats never came back");
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+    }
+
+    #[test]
+    fn heartbeats_are_prefixed() {
+        let broker = Broker::start().expect("could not start the broker");
+        let address = broker.local_addr().to_string();
+        let unprefixed = MqttHermesProtocolHandler::new(&address).unwrap();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let tx = Mutex::new(tx);
+        let _subscription = unprefixed
+            .subscribe_raw("tenant42/hermes-mqtt/heartbeat/+", move |topic, _| {
+                let _ = tx.lock().unwrap().send(topic.to_string());
+            })
+            .unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(200));
+
+        let tenant = MqttHermesProtocolHandler::new_with_handler_options(
+            rumqtt::MqttOptions::new(get_mqtt_id(), address.clone()),
+            HandlerOptions {
+                topic_prefix: Some("tenant42".into()),
+                reconnect: ReconnectOptions {
+                    heartbeat: Some(HeartbeatOptions {
+                        interval: std::time::Duration::from_millis(100),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(rx.recv_timeout(std::time::Duration::from_secs(5)).is_ok());
+        wait_until_connected(&tenant);
+    }
+
+    #[test]
+    fn heartbeats_are_opt_in() {
+        let broker = Broker::start().expect("could not start the broker");
+        let address = broker.local_addr().to_string();
+        let watcher = MqttHermesProtocolHandler::new(&address).unwrap();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let tx = Mutex::new(tx);
+        let _subscription = watcher
+            .subscribe_raw("#", move |topic, _| {
+                let _ = tx.lock().unwrap().send(topic.to_string());
+            })
+            .unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(200));
+
+        let handler = MqttHermesProtocolHandler::new(&address).unwrap();
+        assert_eq!(handler.connection().state().unwrap(), ConnectionState::Connected);
+        assert!(rx.recv_timeout(std::time::Duration::from_secs(3)).is_err());
+    }
+
+    #[test]
+    fn handlers_with_different_codecs_interoperate() {
+        let broker = Broker::start().expect("could not start the broker");
+        let address = broker.local_addr().to_string();
+        let handler = |payload_codec: PayloadCodec| {
+            MqttHermesProtocolHandler::new_with_handler_options(
+                rumqtt::MqttOptions::new(get_mqtt_id(), address.clone()),
+                HandlerOptions {
+                    payload_codec,
+                    reconnect: ReconnectOptions {
+                        heartbeat: Some(HeartbeatOptions {
+                            interval: std::time::Duration::from_millis(50),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            )
+            .unwrap()
         };
+        let receiver = handler(PayloadCodec::Json);
+        let codecs = [PayloadCodec::Json, PayloadCodec::MessagePack, PayloadCodec::Cbor];
+        let publishers: Vec<_> = codecs.iter().map(|codec| handler(*codec)).collect();
+        for publisher in &publishers {
+            wait_until_connected(publisher);
+        }
 
-        let handler2 = HandlerHolder {
-            handler: MqttHermesProtocolHandler::new(&server_address).expect("could not create second client"),
-            server,
+        let (tx, rx) = std::sync::mpsc::channel();
+        let tx = Mutex::new(tx);
+        let _subscription = receiver
+            .dialogue_backend()
+            .subscribe_start_session(Callback::new(move |it: &StartSessionMessage| {
+                let _ = tx.lock().unwrap().send(it.clone());
+            }))
+            .unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(200));
+
+        for (codec, publisher) in codecs.iter().zip(&publishers) {
+            let start_session = StartSessionMessage {
+                init: SessionInit::Action {
+                    text: Some(format!("published as {}", codec)),
+                    intent_filter: Some(vec!["lightsOn".into()]),
+                    can_be_enqueued: false,
+                    send_intent_not_recognized: true,
+                },
+                custom_data: None,
+                site_id: Some("kitchen".into()),
+            };
+            publisher
+                .dialogue()
+                .publish_start_session(start_session.clone())
+                .unwrap();
+            assert_eq!(
+                rx.recv_timeout(std::time::Duration::from_secs(5)).unwrap(),
+                start_session
+            );
+        }
+    }
+
+    #[test]
+    fn strict_handlers_reject_invalid_messages() {
+        let broker = Broker::start().expect("could not start the broker");
+        let address = broker.local_addr().to_string();
+        let handler = |validation: ValidationMode| {
+            MqttHermesProtocolHandler::new_with_handler_options(
+                rumqtt::MqttOptions::new(get_mqtt_id(), address.clone()),
+                HandlerOptions {
+                    validation,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
         };
+        let strict = handler(ValidationMode::Strict);
+        let lenient = handler(ValidationMode::Off);
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let tx = Mutex::new(tx);
+        let _subscription = strict
+            .tts_backend()
+            .subscribe_say(Callback::new(move |it: &SayMessage| {
+                let _ = tx.lock().unwrap().send(it.clone());
+            }))
+            .unwrap();
+        let _other_subscription = strict
+            .tts_backend()
+            .subscribe_say(Callback::new(|_: &SayMessage| {}))
+            .unwrap();
+        let (error_tx, error_rx) = std::sync::mpsc::channel();
+        let error_tx = Mutex::new(error_tx);
+        let _error_subscription = lenient
+            .tts()
+            .subscribe_error(Callback::new(move |it: &ErrorMessage| {
+                let _ = error_tx.lock().unwrap().send(it.clone());
+            }))
+            .unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(200));
+
+        let say = |site_id: &str| SayMessage {
+            text: "hello".into(),
+            lang: None,
+            id: None,
+            site_id: site_id.into(),
+            session_id: None,
+        };
+        match strict.tts().publish_say(say("")) {
+            Err(HermesError::InvalidMessage(errors)) => assert_eq!(errors.to_string(), "siteId must not be empty"),
+            result => panic!("unexpected result {:?}", result.map_err(|e| e.to_string())),
+        }
+        lenient.tts().publish_say(say("")).unwrap();
+        lenient.tts().publish_say(say("kitchen")).unwrap();
 
-        (handler1, handler2)
+        assert_eq!(
+            rx.recv_timeout(std::time::Duration::from_secs(5)).unwrap(),
+            say("kitchen")
+        );
+        assert_eq!(
+            error_rx.recv_timeout(std::time::Duration::from_secs(5)).unwrap(),
+            ErrorMessage {
+                session_id: None,
+                error: "Invalid message: siteId must not be empty".into(),
+                context: Some("hermes/tts/say".into()),
+            }
+        );
+        // the message is reported once, not once per handler
+        assert!(error_rx.recv_timeout(std::time::Duration::from_millis(500)).is_err());
     }
 
-    // sleep 50ms between registering the callback and sending the message to be "sure" the event
-    // arrive in the right order to the mosquitto server
+    // sleep 200ms between registering the callback and sending the message to be "sure" the event
+    // arrive in the right order to the broker
     hermes_test_suite::test_suite!(WAIT_DURATION = 200);
 }
@@ -0,0 +1,509 @@
+use hermes::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::codec::PayloadCodec;
+use crate::topics::*;
+
+/// The payload of a message published on a hermes topic, decoded into its ontology type
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Payload {
+    /// Topics carrying no payload, like version requests or reloads
+    Empty,
+    Site(SiteMessage),
+    Version(VersionMessage),
+    Error(ErrorMessage),
+    VadUp(VadUpMessage),
+    VadDown(VadDownMessage),
+    HotwordDetected(HotwordDetectedMessage),
+    AsrStartListening(AsrStartListeningMessage),
+    TextCaptured(TextCapturedMessage),
+    Say(SayMessage),
+    SayFinished(SayFinishedMessage),
+    RegisterSound(RegisterSoundMessage),
+    NluQuery(NluQueryMessage),
+    NluSlotQuery(NluSlotQueryMessage),
+    NluSlot(NluSlotMessage),
+    NluIntent(NluIntentMessage),
+    NluIntentNotRecognized(NluIntentNotRecognizedMessage),
+    AudioFrame(AudioFrameMessage),
+    PlayBytes(PlayBytesMessage),
+    ReplayRequest(ReplayRequestMessage),
+    PlayFinished(PlayFinishedMessage),
+    SessionQueued(SessionQueuedMessage),
+    SessionStarted(SessionStartedMessage),
+    Intent(IntentMessage),
+    IntentNotRecognized(IntentNotRecognizedMessage),
+    SessionEnded(SessionEndedMessage),
+    StartSession(StartSessionMessage),
+    ContinueSession(ContinueSessionMessage),
+    EndSession(EndSessionMessage),
+    DialogueConfigure(DialogueConfigureMessage),
+    SessionState(SessionStateMessage),
+    InjectionRequest(InjectionRequestMessage),
+    InjectionStatus(InjectionStatusMessage),
+    Handshake(HandshakeMessage),
+    ComponentStatus(ComponentStatusMessage),
+    RegistrySnapshot(RegistrySnapshotMessage),
+    SiteInfo(SiteInfoMessage),
+    Sites(SitesMessage),
+}
+
+fn json<T: DeserializeOwned>(bytes: &[u8]) -> HermesResult<T> {
+    PayloadCodec::decode(bytes)
+}
+
+impl Payload {
+    /// Decode a payload as published on MQTT, the binary ones (audio frames, wav to play and
+    /// sounds to register) are taken as is and completed with the ids found in the topic
+    pub fn decode(topic: &HermesTopic, bytes: &[u8]) -> HermesResult<Self> {
+        use self::AudioServerCommand::*;
+        let payload = match *topic {
+            HermesTopic::AudioServer(Some(ref site_id), AudioFrame)
+            | HermesTopic::AudioServer(Some(ref site_id), ReplayResponse) => Payload::AudioFrame(AudioFrameMessage {
+                wav_frame: bytes.into(),
+                site_id: site_id.clone(),
+            }),
+            HermesTopic::AudioServer(Some(ref site_id), PlayBytes(ref id)) => Payload::PlayBytes(PlayBytesMessage {
+                id: id.clone(),
+                wav_bytes: bytes.into(),
+                site_id: site_id.clone(),
+            }),
+            HermesTopic::Tts(TtsCommand::RegisterSound(ref sound_id)) => Payload::RegisterSound(RegisterSoundMessage {
+                wav_sound: bytes.into(),
+                sound_id: sound_id.clone(),
+            }),
+            _ => Self::from_json(topic, bytes)?,
+        };
+        Ok(payload)
+    }
+
+    /// Decode the JSON representation of a payload, binary payloads are expected base64 encoded
+    /// like in their ontology serialization. The payloads of the other codecs are accepted too.
+    pub fn from_json(topic: &HermesTopic, bytes: &[u8]) -> HermesResult<Self> {
+        let payload = match *topic {
+            // toggles may carry the site to toggle
+            HermesTopic::Hotword(None, HotwordCommand::ToggleOn)
+            | HermesTopic::Hotword(None, HotwordCommand::ToggleOff)
+            | HermesTopic::Feedback(_)
+            | HermesTopic::Asr(AsrCommand::ToggleOn)
+            | HermesTopic::Asr(AsrCommand::ToggleOff)
+            | HermesTopic::DialogueManager(DialogueManagerCommand::ToggleOn)
+            | HermesTopic::DialogueManager(DialogueManagerCommand::ToggleOff)
+            | HermesTopic::AudioServer(None, AudioServerCommand::ToggleOn)
+            | HermesTopic::AudioServer(None, AudioServerCommand::ToggleOff) => {
+                if bytes.is_empty() {
+                    Payload::Empty
+                } else {
+                    Payload::Site(json(bytes)?)
+                }
+            }
+            HermesTopic::Component(_, _, ComponentCommand::VersionRequest)
+            | HermesTopic::Asr(AsrCommand::Reload)
+            | HermesTopic::Nlu(NluCommand::Reload)
+            | HermesTopic::Injection(InjectionCommand::StatusRequest)
+            | HermesTopic::DialogueManager(DialogueManagerCommand::SessionStateRequest)
+            | HermesTopic::Protocol(ProtocolCommand::HandshakeRequest)
+            | HermesTopic::Registry(RegistryCommand::SnapshotRequest)
+            | HermesTopic::Site(SiteCommand::SitesRequest) => Payload::Empty,
+            HermesTopic::Component(_, _, ComponentCommand::Version) => Payload::Version(json(bytes)?),
+            HermesTopic::Component(_, _, ComponentCommand::Error) => Payload::Error(json(bytes)?),
+            HermesTopic::VoiceActivity(_, VoiceActivityCommand::VadUp) => Payload::VadUp(json(bytes)?),
+            HermesTopic::VoiceActivity(_, VoiceActivityCommand::VadDown) => Payload::VadDown(json(bytes)?),
+            HermesTopic::Hotword(_, HotwordCommand::Detected) => Payload::HotwordDetected(json(bytes)?),
+            HermesTopic::Asr(AsrCommand::StartListening) => Payload::AsrStartListening(json(bytes)?),
+            HermesTopic::Asr(AsrCommand::StopListening) => Payload::Site(json(bytes)?),
+            HermesTopic::Asr(AsrCommand::TextCaptured) | HermesTopic::Asr(AsrCommand::PartialTextCaptured) => {
+                Payload::TextCaptured(json(bytes)?)
+            }
+            HermesTopic::Tts(TtsCommand::Say) => Payload::Say(json(bytes)?),
+            HermesTopic::Tts(TtsCommand::SayFinished) => Payload::SayFinished(json(bytes)?),
+            HermesTopic::Tts(TtsCommand::RegisterSound(_)) => Payload::RegisterSound(json(bytes)?),
+            HermesTopic::Nlu(NluCommand::Query) => Payload::NluQuery(json(bytes)?),
+            HermesTopic::Nlu(NluCommand::PartialQuery) => Payload::NluSlotQuery(json(bytes)?),
+            HermesTopic::Nlu(NluCommand::SlotParsed) => Payload::NluSlot(json(bytes)?),
+            HermesTopic::Nlu(NluCommand::IntentParsed) => Payload::NluIntent(json(bytes)?),
+            HermesTopic::Nlu(NluCommand::IntentNotRecognized) => Payload::NluIntentNotRecognized(json(bytes)?),
+            HermesTopic::AudioServer(Some(_), AudioServerCommand::AudioFrame)
+            | HermesTopic::AudioServer(Some(_), AudioServerCommand::ReplayResponse) => {
+                Payload::AudioFrame(json(bytes)?)
+            }
+            HermesTopic::AudioServer(Some(_), AudioServerCommand::PlayBytes(_)) => Payload::PlayBytes(json(bytes)?),
+            HermesTopic::AudioServer(Some(_), AudioServerCommand::ReplayRequest) => {
+                Payload::ReplayRequest(json(bytes)?)
+            }
+            HermesTopic::AudioServer(Some(_), AudioServerCommand::PlayFinished) => Payload::PlayFinished(json(bytes)?),
+            HermesTopic::DialogueManager(DialogueManagerCommand::SessionQueued) => Payload::SessionQueued(json(bytes)?),
+            HermesTopic::DialogueManager(DialogueManagerCommand::SessionStarted) => {
+                Payload::SessionStarted(json(bytes)?)
+            }
+            HermesTopic::DialogueManager(DialogueManagerCommand::SessionEnded) => Payload::SessionEnded(json(bytes)?),
+            HermesTopic::DialogueManager(DialogueManagerCommand::IntentNotRecognized) => {
+                Payload::IntentNotRecognized(json(bytes)?)
+            }
+            HermesTopic::DialogueManager(DialogueManagerCommand::StartSession) => Payload::StartSession(json(bytes)?),
+            HermesTopic::DialogueManager(DialogueManagerCommand::ContinueSession) => {
+                Payload::ContinueSession(json(bytes)?)
+            }
+            HermesTopic::DialogueManager(DialogueManagerCommand::EndSession) => Payload::EndSession(json(bytes)?),
+            HermesTopic::DialogueManager(DialogueManagerCommand::Configure) => Payload::DialogueConfigure(json(bytes)?),
+            HermesTopic::DialogueManager(DialogueManagerCommand::SessionState) => Payload::SessionState(json(bytes)?),
+            HermesTopic::Intent(_) => Payload::Intent(json(bytes)?),
+            HermesTopic::Injection(InjectionCommand::Perform) => Payload::InjectionRequest(json(bytes)?),
+            HermesTopic::Injection(InjectionCommand::Status) => Payload::InjectionStatus(json(bytes)?),
+            HermesTopic::Protocol(ProtocolCommand::Handshake) => Payload::Handshake(json(bytes)?),
+            HermesTopic::Registry(RegistryCommand::ComponentUp)
+            | HermesTopic::Registry(RegistryCommand::ComponentDown) => Payload::ComponentStatus(json(bytes)?),
+            HermesTopic::Registry(RegistryCommand::Snapshot) => Payload::RegistrySnapshot(json(bytes)?),
+            HermesTopic::Site(SiteCommand::Register) => Payload::SiteInfo(json(bytes)?),
+            HermesTopic::Site(SiteCommand::Unregister) => Payload::Site(json(bytes)?),
+            HermesTopic::Site(SiteCommand::Sites) => Payload::Sites(json(bytes)?),
+            HermesTopic::Hotword(Some(_), _) | HermesTopic::AudioServer(_, _) => {
+                return Err(HermesError::InvalidArgument(format!("Unexpected topic {}", topic)))
+            }
+        };
+        Ok(payload)
+    }
+
+    /// Encode the payload the way it is published on MQTT
+    pub fn to_bytes(&self) -> HermesResult<Vec<u8>> {
+        self.encode(PayloadCodec::Json)
+    }
+
+    /// Encode the payload the way it is published on MQTT by a handler using `codec`
+    pub fn encode(&self, codec: PayloadCodec) -> HermesResult<Vec<u8>> {
+        let bytes = match *self {
+            Payload::Empty => vec![],
+            Payload::AudioFrame(ref frame) => frame.wav_frame.clone(),
+            Payload::PlayBytes(ref bytes) => bytes.wav_bytes.clone(),
+            Payload::RegisterSound(ref sound) => sound.wav_sound.clone(),
+            _ => codec.encode(self)?,
+        };
+        Ok(bytes)
+    }
+
+    /// Publish the payload on a topic through the facades of a handler, so that messages captured
+    /// on MQTT can be sent on any transport. Toggles without payload of the components toggled per
+    /// site apply to the default site.
+    pub fn publish(self, topic: &HermesTopic, handler: &HermesProtocolHandler) -> HermesResult<()> {
+        let site = |payload| match payload {
+            Payload::Site(site) => Ok(site),
+            Payload::Empty => Ok(SiteMessage::default()),
+            _ => Err(HermesError::InvalidArgument(format!(
+                "Unexpected payload for topic {}",
+                topic
+            ))),
+        };
+        match (topic, self) {
+            (HermesTopic::Component(site_id, component, command), payload) => {
+                publish_component(handler, topic, site_id.clone(), *component, *command, payload)
+            }
+            (HermesTopic::Feedback(FeedbackCommand::Sound(SoundCommand::ToggleOn)), payload) => {
+                handler.sound_feedback().publish_toggle_on(site(payload)?)
+            }
+            (HermesTopic::Feedback(FeedbackCommand::Sound(SoundCommand::ToggleOff)), payload) => {
+                handler.sound_feedback().publish_toggle_off(site(payload)?)
+            }
+            (HermesTopic::VoiceActivity(_, VoiceActivityCommand::VadUp), Payload::VadUp(it)) => {
+                handler.voice_activity_backend().publish_vad_up(it)
+            }
+            (HermesTopic::VoiceActivity(_, VoiceActivityCommand::VadDown), Payload::VadDown(it)) => {
+                handler.voice_activity_backend().publish_vad_down(it)
+            }
+            (HermesTopic::Hotword(None, HotwordCommand::ToggleOn), payload) => {
+                handler.hotword().publish_toggle_on(site(payload)?)
+            }
+            (HermesTopic::Hotword(None, HotwordCommand::ToggleOff), payload) => {
+                handler.hotword().publish_toggle_off(site(payload)?)
+            }
+            (HermesTopic::Hotword(Some(id), HotwordCommand::Detected), Payload::HotwordDetected(it)) => {
+                handler.hotword_backend().publish_detected(id.clone(), it)
+            }
+            (HermesTopic::Asr(AsrCommand::ToggleOn), Payload::Empty)
+            | (HermesTopic::Asr(AsrCommand::ToggleOn), Payload::Site(_)) => handler.asr().publish_toggle_on(),
+            (HermesTopic::Asr(AsrCommand::ToggleOff), Payload::Empty)
+            | (HermesTopic::Asr(AsrCommand::ToggleOff), Payload::Site(_)) => handler.asr().publish_toggle_off(),
+            (HermesTopic::Asr(AsrCommand::StartListening), Payload::AsrStartListening(it)) => {
+                handler.asr().publish_start_listening(it)
+            }
+            (HermesTopic::Asr(AsrCommand::StopListening), Payload::Site(it)) => {
+                handler.asr().publish_stop_listening(it)
+            }
+            (HermesTopic::Asr(AsrCommand::Reload), Payload::Empty) => handler.asr().publish_reload(),
+            (HermesTopic::Asr(AsrCommand::TextCaptured), Payload::TextCaptured(it)) => {
+                handler.asr_backend().publish_text_captured(it)
+            }
+            (HermesTopic::Asr(AsrCommand::PartialTextCaptured), Payload::TextCaptured(it)) => {
+                handler.asr_backend().publish_partial_text_captured(it)
+            }
+            (HermesTopic::Tts(TtsCommand::Say), Payload::Say(it)) => handler.tts().publish_say(it),
+            (HermesTopic::Tts(TtsCommand::SayFinished), Payload::SayFinished(it)) => {
+                handler.tts_backend().publish_say_finished(it)
+            }
+            (HermesTopic::Tts(TtsCommand::RegisterSound(_)), Payload::RegisterSound(it)) => {
+                handler.tts().publish_register_sound(it)
+            }
+            (HermesTopic::Nlu(NluCommand::Query), Payload::NluQuery(it)) => handler.nlu().publish_query(it),
+            (HermesTopic::Nlu(NluCommand::PartialQuery), Payload::NluSlotQuery(it)) => {
+                handler.nlu().publish_partial_query(it)
+            }
+            (HermesTopic::Nlu(NluCommand::Reload), Payload::Empty) => handler.nlu().publish_reload(),
+            (HermesTopic::Nlu(NluCommand::SlotParsed), Payload::NluSlot(it)) => {
+                handler.nlu_backend().publish_slot_parsed(it)
+            }
+            (HermesTopic::Nlu(NluCommand::IntentParsed), Payload::NluIntent(it)) => {
+                handler.nlu_backend().publish_intent_parsed(it)
+            }
+            (HermesTopic::Nlu(NluCommand::IntentNotRecognized), Payload::NluIntentNotRecognized(it)) => {
+                handler.nlu_backend().publish_intent_not_recognized(it)
+            }
+            (HermesTopic::AudioServer(None, AudioServerCommand::ToggleOn), payload) => {
+                handler.audio_server().publish_toggle_on(site(payload)?)
+            }
+            (HermesTopic::AudioServer(None, AudioServerCommand::ToggleOff), payload) => {
+                handler.audio_server().publish_toggle_off(site(payload)?)
+            }
+            (HermesTopic::AudioServer(Some(_), AudioServerCommand::AudioFrame), Payload::AudioFrame(it)) => {
+                handler.audio_server_backend().publish_audio_frame(it)
+            }
+            (HermesTopic::AudioServer(Some(_), AudioServerCommand::ReplayResponse), Payload::AudioFrame(it)) => {
+                handler.audio_server_backend().publish_replay_response(it)
+            }
+            (HermesTopic::AudioServer(Some(_), AudioServerCommand::ReplayRequest), Payload::ReplayRequest(it)) => {
+                handler.audio_server().publish_replay_request(it)
+            }
+            (HermesTopic::AudioServer(Some(_), AudioServerCommand::PlayBytes(_)), Payload::PlayBytes(it)) => {
+                handler.audio_server().publish_play_bytes(it)
+            }
+            (HermesTopic::AudioServer(Some(_), AudioServerCommand::PlayFinished), Payload::PlayFinished(it)) => {
+                handler.audio_server_backend().publish_play_finished(it)
+            }
+            (HermesTopic::DialogueManager(DialogueManagerCommand::ToggleOn), Payload::Empty)
+            | (HermesTopic::DialogueManager(DialogueManagerCommand::ToggleOn), Payload::Site(_)) => {
+                handler.dialogue().publish_toggle_on()
+            }
+            (HermesTopic::DialogueManager(DialogueManagerCommand::ToggleOff), Payload::Empty)
+            | (HermesTopic::DialogueManager(DialogueManagerCommand::ToggleOff), Payload::Site(_)) => {
+                handler.dialogue().publish_toggle_off()
+            }
+            (HermesTopic::DialogueManager(DialogueManagerCommand::StartSession), Payload::StartSession(it)) => {
+                handler.dialogue().publish_start_session(it)
+            }
+            (HermesTopic::DialogueManager(DialogueManagerCommand::ContinueSession), Payload::ContinueSession(it)) => {
+                handler.dialogue().publish_continue_session(it)
+            }
+            (HermesTopic::DialogueManager(DialogueManagerCommand::EndSession), Payload::EndSession(it)) => {
+                handler.dialogue().publish_end_session(it)
+            }
+            (HermesTopic::DialogueManager(DialogueManagerCommand::Configure), Payload::DialogueConfigure(it)) => {
+                handler.dialogue().publish_configure(it)
+            }
+            (HermesTopic::DialogueManager(DialogueManagerCommand::SessionStateRequest), Payload::Empty) => {
+                handler.dialogue().publish_session_state_request()
+            }
+            (HermesTopic::DialogueManager(DialogueManagerCommand::SessionState), Payload::SessionState(it)) => {
+                handler.dialogue_backend().publish_session_state(it)
+            }
+            (HermesTopic::DialogueManager(DialogueManagerCommand::SessionQueued), Payload::SessionQueued(it)) => {
+                handler.dialogue_backend().publish_session_queued(it)
+            }
+            (HermesTopic::DialogueManager(DialogueManagerCommand::SessionStarted), Payload::SessionStarted(it)) => {
+                handler.dialogue_backend().publish_session_started(it)
+            }
+            (HermesTopic::DialogueManager(DialogueManagerCommand::SessionEnded), Payload::SessionEnded(it)) => {
+                handler.dialogue_backend().publish_session_ended(it)
+            }
+            (
+                HermesTopic::DialogueManager(DialogueManagerCommand::IntentNotRecognized),
+                Payload::IntentNotRecognized(it),
+            ) => handler.dialogue_backend().publish_intent_not_recognized(it),
+            (HermesTopic::Intent(_), Payload::Intent(it)) => handler.dialogue_backend().publish_intent(it),
+            (HermesTopic::Injection(InjectionCommand::Perform), Payload::InjectionRequest(it)) => {
+                handler.injection().publish_injection_request(it)
+            }
+            (HermesTopic::Injection(InjectionCommand::StatusRequest), Payload::Empty) => {
+                handler.injection().publish_injection_status_request()
+            }
+            (HermesTopic::Injection(InjectionCommand::Status), Payload::InjectionStatus(it)) => {
+                handler.injection_backend().publish_injection_status(it)
+            }
+            (HermesTopic::Protocol(ProtocolCommand::HandshakeRequest), Payload::Empty) => {
+                handler.handshake().publish_handshake_request()
+            }
+            (HermesTopic::Protocol(ProtocolCommand::Handshake), Payload::Handshake(it)) => {
+                handler.handshake().publish_handshake(it)
+            }
+            (HermesTopic::Registry(RegistryCommand::ComponentUp), Payload::ComponentStatus(it)) => {
+                handler.registry_backend().publish_component_up(it)
+            }
+            (HermesTopic::Registry(RegistryCommand::ComponentDown), Payload::ComponentStatus(it)) => {
+                handler.registry_backend().publish_component_down(it)
+            }
+            (HermesTopic::Registry(RegistryCommand::SnapshotRequest), Payload::Empty) => {
+                handler.registry().publish_snapshot_request()
+            }
+            (HermesTopic::Registry(RegistryCommand::Snapshot), Payload::RegistrySnapshot(it)) => {
+                handler.registry_backend().publish_snapshot(it)
+            }
+            (HermesTopic::Site(SiteCommand::Register), Payload::SiteInfo(it)) => {
+                handler.site().publish_register_site(it)
+            }
+            (HermesTopic::Site(SiteCommand::Unregister), Payload::Site(it)) => {
+                handler.site().publish_unregister_site(it)
+            }
+            (HermesTopic::Site(SiteCommand::SitesRequest), Payload::Empty) => handler.site().publish_sites_request(),
+            (HermesTopic::Site(SiteCommand::Sites), Payload::Sites(it)) => handler.site_backend().publish_sites(it),
+            _ => Err(HermesError::InvalidArgument(format!(
+                "Unexpected payload for topic {}",
+                topic
+            ))),
+        }
+    }
+}
+
+/// Publish the version requests, versions and errors of the components, the ones identified by
+/// site need the site in the topic
+macro_rules! publish_component {
+    ($topic:expr, $facade:expr, $backend:expr, $command:expr, $payload:expr) => {
+        match ($command, $payload) {
+            (ComponentCommand::VersionRequest, Payload::Empty) => $facade.publish_version_request(),
+            (ComponentCommand::Version, Payload::Version(it)) => $backend.publish_version(it),
+            (ComponentCommand::Error, Payload::Error(it)) => $backend.publish_error(it),
+            _ => Err(HermesError::InvalidArgument(format!(
+                "Unexpected payload for topic {}",
+                $topic
+            ))),
+        }
+    };
+    ($topic:expr, $facade:expr, $backend:expr, $site_id:expr, $command:expr, $payload:expr) => {
+        match ($site_id, $command, $payload) {
+            (Some(id), ComponentCommand::VersionRequest, Payload::Empty) => $facade.publish_version_request(id),
+            (Some(id), ComponentCommand::Version, Payload::Version(it)) => $backend.publish_version(id, it),
+            (Some(id), ComponentCommand::Error, Payload::Error(it)) => $backend.publish_error(id, it),
+            _ => Err(HermesError::InvalidArgument(format!(
+                "Unexpected payload for topic {}",
+                $topic
+            ))),
+        }
+    };
+}
+
+fn publish_component(
+    handler: &HermesProtocolHandler,
+    topic: &HermesTopic,
+    site_id: Option<String>,
+    component: Component,
+    command: ComponentCommand,
+    payload: Payload,
+) -> HermesResult<()> {
+    match component {
+        Component::VoiceActivity => publish_component!(
+            topic,
+            handler.voice_activity(),
+            handler.voice_activity_backend(),
+            site_id,
+            command,
+            payload
+        ),
+        Component::Hotword => {
+            publish_component!(
+                topic,
+                handler.hotword(),
+                handler.hotword_backend(),
+                site_id,
+                command,
+                payload
+            )
+        }
+        Component::AudioServer => publish_component!(
+            topic,
+            handler.audio_server(),
+            handler.audio_server_backend(),
+            site_id,
+            command,
+            payload
+        ),
+        Component::Asr => publish_component!(topic, handler.asr(), handler.asr_backend(), command, payload),
+        Component::Tts => publish_component!(topic, handler.tts(), handler.tts_backend(), command, payload),
+        Component::Nlu => publish_component!(topic, handler.nlu(), handler.nlu_backend(), command, payload),
+        Component::DialogueManager => {
+            publish_component!(topic, handler.dialogue(), handler.dialogue_backend(), command, payload)
+        }
+        Component::Injection => publish_component!(
+            topic,
+            handler.injection(),
+            handler.injection_backend(),
+            command,
+            payload
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_payload_round_trips() {
+        let topic = HermesTopic::Tts(TtsCommand::Say);
+        let say = Payload::Say(SayMessage {
+            text: "hello".into(),
+            lang: None,
+            id: Some("abc".into()),
+            site_id: "kitchen".into(),
+            session_id: None,
+        });
+        let bytes = say.to_bytes().unwrap();
+        assert_eq!(Payload::decode(&topic, &bytes).unwrap(), say);
+    }
+
+    #[test]
+    fn binary_codec_payload_is_decoded() {
+        let topic = HermesTopic::Tts(TtsCommand::Say);
+        let say = Payload::Say(SayMessage {
+            text: "hello".into(),
+            lang: Some("en".into()),
+            id: None,
+            site_id: "kitchen".into(),
+            session_id: None,
+        });
+        for codec in &[PayloadCodec::MessagePack, PayloadCodec::Cbor] {
+            let bytes = say.encode(*codec).unwrap();
+            assert_eq!(Payload::decode(&topic, &bytes).unwrap(), say);
+        }
+    }
+
+    #[test]
+    fn binary_payload_takes_ids_from_topic() {
+        let topic = HermesTopic::AudioServer(Some("kitchen".into()), AudioServerCommand::PlayBytes("abc".into()));
+        let payload = Payload::decode(&topic, &[0, 159, 255]).unwrap();
+        assert_eq!(
+            payload,
+            Payload::PlayBytes(PlayBytesMessage {
+                id: "abc".into(),
+                wav_bytes: vec![0, 159, 255],
+                site_id: "kitchen".into(),
+            })
+        );
+        assert_eq!(payload.to_bytes().unwrap(), vec![0, 159, 255]);
+    }
+
+    #[test]
+    fn toggle_payload_is_optional() {
+        let topic = HermesTopic::Hotword(None, HotwordCommand::ToggleOn);
+        assert_eq!(Payload::decode(&topic, b"").unwrap(), Payload::Empty);
+        assert_eq!(
+            Payload::decode(&topic, br#"{"siteId": "kitchen"}"#).unwrap(),
+            Payload::Site(SiteMessage {
+                site_id: "kitchen".into(),
+                session_id: None,
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_payload_is_rejected() {
+        assert!(Payload::decode(&HermesTopic::Tts(TtsCommand::Say), br#"{"siteId": "kitchen"}"#).is_err());
+    }
+}
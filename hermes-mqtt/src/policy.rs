@@ -0,0 +1,159 @@
+//! The quality of service and retain flag used to publish on each kind of topic.
+
+use rumqtt::QoS;
+
+use crate::topics::*;
+
+/// How the messages published on a topic are sent to the broker
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PublishPolicy {
+    pub qos: QoS,
+    /// Whether the broker keeps the last message to send it to the clients subscribing later
+    pub retain: bool,
+}
+
+impl PublishPolicy {
+    pub fn new(qos: QoS, retain: bool) -> Self {
+        Self { qos, retain }
+    }
+}
+
+impl Default for PublishPolicy {
+    fn default() -> Self {
+        Self::new(QoS::AtMostOnce, false)
+    }
+}
+
+/// The topic standing for all the topics of its kind, that is regardless of the site ids, intent
+/// names, sound ids and request ids they contain
+fn kind(topic: &HermesTopic) -> HermesTopic {
+    use self::HermesTopic::*;
+    match topic {
+        VoiceActivity(_, command) => VoiceActivity(String::new(), *command),
+        Hotword(_, command) => Hotword(None, *command),
+        Tts(TtsCommand::RegisterSound(_)) => Tts(TtsCommand::RegisterSound(String::new())),
+        Intent(_) => Intent(String::new()),
+        AudioServer(_, AudioServerCommand::PlayBytes(_)) => {
+            AudioServer(None, AudioServerCommand::PlayBytes(String::new()))
+        }
+        AudioServer(_, command) => AudioServer(None, command.clone()),
+        Component(_, component, command) => Component(None, *component, *command),
+        topic => topic.clone(),
+    }
+}
+
+/// The publish policy of each kind of topic, the ones without a policy use the default one
+#[derive(Debug, Clone)]
+pub(crate) struct PublishPolicies {
+    policies: Vec<(HermesTopic, PublishPolicy)>,
+}
+
+impl PublishPolicies {
+    pub fn set(&mut self, topic: &HermesTopic, policy: PublishPolicy) {
+        let kind = kind(topic);
+        self.policies.retain(|(it, _)| *it != kind);
+        if policy != PublishPolicy::default() {
+            self.policies.push((kind, policy))
+        }
+    }
+
+    pub fn get(&self, topic: &HermesTopic) -> PublishPolicy {
+        let kind = kind(topic);
+        self.policies
+            .iter()
+            .find(|(it, _)| *it == kind)
+            .map_or_else(PublishPolicy::default, |(_, policy)| *policy)
+    }
+}
+
+impl Default for PublishPolicies {
+    /// The dialogue control messages, the presence changes of the components and the registrations
+    /// of the sites are sent at least once and the injection status and sites are retained, all the
+    /// other messages (among which the audio frames) are sent at most once. The versions are not
+    /// retained, as the ones of components long gone would be taken for answers of live ones.
+    fn default() -> Self {
+        use self::DialogueManagerCommand::*;
+        let mut policies = Self { policies: vec![] };
+        let at_least_once = PublishPolicy::new(QoS::AtLeastOnce, false);
+        for command in &[
+            ToggleOn,
+            ToggleOff,
+            StartSession,
+            ContinueSession,
+            EndSession,
+            SessionQueued,
+            SessionStarted,
+            SessionEnded,
+            IntentNotRecognized,
+            Configure,
+            SessionStateRequest,
+            SessionState,
+        ] {
+            policies.set(&HermesTopic::DialogueManager(*command), at_least_once);
+        }
+        for command in &[RegistryCommand::ComponentUp, RegistryCommand::ComponentDown] {
+            policies.set(&HermesTopic::Registry(*command), at_least_once);
+        }
+        for command in &[SiteCommand::Register, SiteCommand::Unregister] {
+            policies.set(&HermesTopic::Site(*command), at_least_once);
+        }
+
+        let retained = PublishPolicy::new(QoS::AtMostOnce, true);
+        policies.set(&HermesTopic::Injection(InjectionCommand::Status), retained);
+        policies.set(&HermesTopic::Site(SiteCommand::Sites), retained);
+        policies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policies_apply_to_all_the_topics_of_a_kind() {
+        let mut policies = PublishPolicies::default();
+        let frame = |site_id: &str| HermesTopic::AudioServer(Some(site_id.into()), AudioServerCommand::AudioFrame);
+        assert_eq!(policies.get(&frame("kitchen")), PublishPolicy::default());
+
+        policies.set(&frame("+"), PublishPolicy::new(QoS::AtLeastOnce, false));
+        assert_eq!(policies.get(&frame("kitchen")).qos, QoS::AtLeastOnce);
+        assert_eq!(
+            policies.get(&HermesTopic::AudioServer(
+                Some("kitchen".into()),
+                AudioServerCommand::PlayFinished
+            )),
+            PublishPolicy::default()
+        );
+
+        policies.set(&frame("bedroom"), PublishPolicy::default());
+        assert_eq!(policies.get(&frame("kitchen")), PublishPolicy::default());
+    }
+
+    #[test]
+    fn default_policies() {
+        let policies = PublishPolicies::default();
+        assert_eq!(
+            policies.get(&HermesTopic::DialogueManager(DialogueManagerCommand::SessionEnded)),
+            PublishPolicy::new(QoS::AtLeastOnce, false)
+        );
+        assert!(
+            !policies
+                .get(&HermesTopic::Component(
+                    Some("kitchen".into()),
+                    Component::Hotword,
+                    ComponentCommand::Version
+                ))
+                .retain
+        );
+        assert!(policies.get(&HermesTopic::Injection(InjectionCommand::Status)).retain);
+        assert!(policies.get(&HermesTopic::Site(SiteCommand::Sites)).retain);
+        assert_eq!(
+            policies.get(&HermesTopic::Registry(RegistryCommand::ComponentDown)).qos,
+            QoS::AtLeastOnce
+        );
+        assert_eq!(
+            policies.get(&HermesTopic::Intent("lightsOn".into())),
+            PublishPolicy::default()
+        );
+    }
+}
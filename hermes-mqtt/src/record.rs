@@ -0,0 +1,291 @@
+//! Record the traffic of a hermes bus and replay it later, on MQTT or on any other transport.
+//!
+//! A recording holds one JSON object per line with the time elapsed since the start of the
+//! recording, the topic and the payload in its ontology serialization (binary payloads like audio
+//! frames being base64 encoded), which keeps the files readable and editable by hand.
+
+use std::io::{BufRead, Write};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use hermes::*;
+use log::*;
+use serde::{Deserialize, Serialize};
+
+use crate::topics::*;
+use crate::{MqttHermesProtocolHandler, Payload};
+
+/// A message captured on the bus
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedMessage {
+    /// The time elapsed between the start of the recording and the reception of the message
+    pub elapsed: Duration,
+    pub topic: HermesTopic,
+    pub payload: Payload,
+}
+
+/// The serialized form of a recorded message
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Line {
+    elapsed_ms: u64,
+    topic: String,
+    payload: serde_json::Value,
+}
+
+impl RecordedMessage {
+    pub fn to_json_line(&self) -> HermesResult<String> {
+        Ok(serde_json::to_string(&Line {
+            elapsed_ms: self.elapsed.as_millis() as u64,
+            topic: self.topic.as_path(),
+            payload: serde_json::to_value(&self.payload)?,
+        })?)
+    }
+
+    pub fn from_json_line(line: &str) -> HermesResult<Self> {
+        let line: Line = serde_json::from_str(line)?;
+        let topic = HermesTopic::from_path(&line.topic)
+            .ok_or_else(|| HermesError::other(format!("Unknown topic {}", line.topic)))?;
+        let payload = match line.payload {
+            serde_json::Value::Null => Payload::from_json(&topic, b"")?,
+            payload => Payload::from_json(&topic, &serde_json::to_vec(&payload)?)?,
+        };
+        Ok(Self {
+            elapsed: Duration::from_millis(line.elapsed_ms),
+            topic,
+            payload,
+        })
+    }
+}
+
+/// Read the messages of a recording, blank lines are ignored
+pub fn read_recording<R: BufRead>(reader: R) -> HermesResult<Vec<RecordedMessage>> {
+    let mut messages = vec![];
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        messages.push(
+            RecordedMessage::from_json_line(&line)
+                .map_err(|e| HermesError::other(format!("Invalid message on line {}: {}", index + 1, e)))?,
+        );
+    }
+    Ok(messages)
+}
+
+/// Records all the messages published on the hermes topics of an MQTT bus, until dropped
+pub struct Recorder {
+    _subscription: Subscription,
+}
+
+impl Recorder {
+    /// Start recording, each message is written and flushed as soon as it is received. The ones
+    /// that can't be decoded are skipped with a warning.
+    pub fn start<W: Write + Send + 'static>(handler: &MqttHermesProtocolHandler, writer: W) -> HermesResult<Self> {
+        let start = Instant::now();
+        let writer = Mutex::new(writer);
+        let subscription = handler.subscribe_raw("hermes/#", move |topic, bytes| {
+            let elapsed = start.elapsed();
+            let line = HermesTopic::from_path(topic)
+                .ok_or_else(|| HermesError::other("Unknown topic"))
+                .and_then(|topic| {
+                    RecordedMessage {
+                        elapsed,
+                        payload: Payload::decode(&topic, bytes)?,
+                        topic,
+                    }
+                    .to_json_line()
+                });
+            match line {
+                Ok(line) => {
+                    if let Ok(mut writer) = writer.lock() {
+                        if let Err(e) = writeln!(writer, "{}", line).and_then(|_| writer.flush()) {
+                            error!("Could not write recorded message: {}", e)
+                        }
+                    }
+                }
+                Err(e) => warn!("Skipping message on {} from the recording: {}", topic, e),
+            }
+        })?;
+        Ok(Self {
+            _subscription: subscription,
+        })
+    }
+}
+
+/// The pace at which a recording is replayed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Timing {
+    /// Keep the delays between the messages as they were recorded
+    Original,
+    /// Divide the delays between the messages by a factor
+    Accelerated(f64),
+    /// Publish all the messages without waiting
+    Immediate,
+}
+
+/// Replay recorded messages through the facades of a handler, the first one being published right
+/// away. This blocks until all the messages have been published.
+pub fn replay(handler: &HermesProtocolHandler, messages: &[RecordedMessage], timing: Timing) -> HermesResult<()> {
+    if let Timing::Accelerated(factor) = timing {
+        if !(factor.is_finite() && factor > 0.0) {
+            return Err(HermesError::InvalidArgument(format!(
+                "Invalid acceleration factor {}",
+                factor
+            )));
+        }
+    }
+    let start = Instant::now();
+    let first = messages.first().map_or_else(Duration::default, |it| it.elapsed);
+    for message in messages {
+        if let Some(wait) = due(first, message, timing).and_then(|it| it.checked_sub(start.elapsed())) {
+            std::thread::sleep(wait)
+        }
+        message
+            .payload
+            .clone()
+            .publish(&message.topic, handler)
+            .map_err(|e| HermesError::other(format!("Could not replay message on {}: {}", message.topic, e)))?;
+    }
+    Ok(())
+}
+
+/// When a message is to be replayed, relative to the start of the replay, none if right away
+fn due(first: Duration, message: &RecordedMessage, timing: Timing) -> Option<Duration> {
+    let offset = message.elapsed.checked_sub(first).unwrap_or_default();
+    match timing {
+        Timing::Original => Some(offset),
+        Timing::Accelerated(factor) => Some(offset.div_f64(factor)),
+        Timing::Immediate => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    fn intent(intent_name: &str) -> IntentMessage {
+        IntentMessage {
+            session_id: "abc".into(),
+            custom_data: None,
+            site_id: "kitchen".into(),
+            input: "turn on the lights".into(),
+            asr_tokens: None,
+            asr_confidence: None,
+            intent: NluIntentClassifierResult {
+                intent_name: intent_name.into(),
+                confidence_score: 0.9,
+            },
+            slots: vec![],
+            alternatives: None,
+        }
+    }
+
+    fn message(elapsed_ms: u64, topic: HermesTopic, payload: Payload) -> RecordedMessage {
+        RecordedMessage {
+            elapsed: Duration::from_millis(elapsed_ms),
+            topic,
+            payload,
+        }
+    }
+
+    #[test]
+    fn messages_round_trip_through_json_lines() {
+        let messages = vec![
+            message(
+                0,
+                HermesTopic::AudioServer(Some("kitchen".into()), AudioServerCommand::AudioFrame),
+                Payload::AudioFrame(AudioFrameMessage {
+                    wav_frame: vec![0, 159, 255],
+                    site_id: "kitchen".into(),
+                }),
+            ),
+            message(12, HermesTopic::Hotword(None, HotwordCommand::ToggleOn), Payload::Empty),
+            message(
+                250,
+                HermesTopic::Intent("lightsOn".into()),
+                Payload::Intent(intent("lightsOn")),
+            ),
+        ];
+        let recording = messages
+            .iter()
+            .map(|it| it.to_json_line().unwrap())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        assert_eq!(read_recording(recording.as_bytes()).unwrap(), messages);
+    }
+
+    #[test]
+    fn invalid_lines_are_reported() {
+        let recording = r#"{"elapsedMs": 0, "topic": "hermes/tts/say", "payload": {"siteId": "kitchen"}}"#;
+        let error = read_recording(recording.as_bytes()).unwrap_err();
+        assert!(error.to_string().starts_with("Invalid message on line 1: "));
+    }
+
+    #[test]
+    fn replay_works_on_any_transport() {
+        let handler = hermes_inprocess::InProcessHermesProtocolHandler::new();
+        let (tx, rx) = mpsc::channel();
+        let tx = Mutex::new(tx);
+        let _subscription = handler
+            .dialogue()
+            .subscribe_intents(Callback::new(move |it: &IntentMessage| {
+                let _ = tx.lock().unwrap().send(it.intent.intent_name.clone());
+            }))
+            .unwrap();
+
+        let messages = vec![
+            message(
+                1000,
+                HermesTopic::Intent("lightsOn".into()),
+                Payload::Intent(intent("lightsOn")),
+            ),
+            message(
+                1400,
+                HermesTopic::Intent("lightsOff".into()),
+                Payload::Intent(intent("lightsOff")),
+            ),
+        ];
+        let start = Instant::now();
+        replay(&handler, &messages, Timing::Accelerated(4.0)).unwrap();
+        // the replay sleeps until the second message is due, the upper bound depends on the load
+        assert!(start.elapsed() >= Duration::from_millis(100));
+
+        let timeout = Duration::from_secs(5);
+        let mut received = vec![rx.recv_timeout(timeout).unwrap(), rx.recv_timeout(timeout).unwrap()];
+        received.sort();
+        assert_eq!(received, vec!["lightsOff".to_string(), "lightsOn".to_string()]);
+    }
+
+    #[test]
+    fn replay_scales_the_delays_between_messages() {
+        let first = Duration::from_millis(1000);
+        let toggle_on = |elapsed_ms| {
+            message(
+                elapsed_ms,
+                HermesTopic::Hotword(None, HotwordCommand::ToggleOn),
+                Payload::Empty,
+            )
+        };
+        let schedule = |timing| {
+            [toggle_on(1000), toggle_on(1400), toggle_on(2000)]
+                .iter()
+                .map(|it| due(first, it, timing))
+                .collect::<Vec<_>>()
+        };
+        let millis = |it: &[u64]| it.iter().map(|it| Some(Duration::from_millis(*it))).collect::<Vec<_>>();
+
+        assert_eq!(schedule(Timing::Original), millis(&[0, 400, 1000]));
+        assert_eq!(schedule(Timing::Accelerated(4.0)), millis(&[0, 100, 250]));
+        assert_eq!(schedule(Timing::Immediate), vec![None, None, None]);
+        assert_eq!(due(first, &toggle_on(500), Timing::Original), Some(Duration::default()));
+    }
+
+    #[test]
+    fn invalid_acceleration_is_rejected() {
+        let handler = hermes_inprocess::InProcessHermesProtocolHandler::new();
+        assert!(replay(&handler, &[], Timing::Accelerated(0.0)).is_err());
+    }
+}
@@ -31,17 +31,41 @@ pub enum HermesTopic {
     AudioServer(Option<String>, AudioServerCommand),
     Injection(InjectionCommand),
     Component(Option<String>, Component, ComponentCommand),
+    Protocol(ProtocolCommand),
+    Registry(RegistryCommand),
+    Site(SiteCommand),
 }
 
 impl ToPath for HermesTopic {}
 
 impl HermesTopic {
+    /// The topic on which the component this topic relates to publishes its errors, if any
+    pub fn error_topic(&self) -> Option<HermesTopic> {
+        let (site_id, component) = match self {
+            HermesTopic::Feedback(_) | HermesTopic::Protocol(_) | HermesTopic::Registry(_) | HermesTopic::Site(_) => {
+                return None
+            }
+            HermesTopic::DialogueManager(_) | HermesTopic::Intent(_) => (None, Component::DialogueManager),
+            HermesTopic::VoiceActivity(site_id, _) => (Some(site_id.clone()), Component::VoiceActivity),
+            HermesTopic::Hotword(site_id, _) => (site_id.clone(), Component::Hotword),
+            HermesTopic::Asr(_) => (None, Component::Asr),
+            HermesTopic::Tts(_) => (None, Component::Tts),
+            HermesTopic::Nlu(_) => (None, Component::Nlu),
+            HermesTopic::AudioServer(site_id, _) => (site_id.clone(), Component::AudioServer),
+            HermesTopic::Injection(_) => (None, Component::Injection),
+            HermesTopic::Component(site_id, component, _) => (site_id.clone(), *component),
+        };
+        Some(HermesTopic::Component(site_id, component, ComponentCommand::Error))
+    }
+
     fn parse_asr<'a, It: Iterator<Item = &'a str>>(mut comps: It) -> Option<HermesTopic> {
         use self::AsrCommand::*;
         use self::HermesTopic::Asr;
         match comps.next() {
             Some("toggleOn") => Some(Asr(ToggleOn)),
             Some("toggleOff") => Some(Asr(ToggleOff)),
+            Some("startListening") => Some(Asr(StartListening)),
+            Some("stopListening") => Some(Asr(StopListening)),
             Some("textCaptured") => Some(Asr(TextCaptured)),
             Some("partialTextCaptured") => Some(Asr(PartialTextCaptured)),
             Some("reload") => Some(Asr(Reload)),
@@ -103,6 +127,8 @@ impl HermesTopic {
             Some("sessionEnded") => Some(DialogueManager(SessionEnded)),
             Some("intentNotRecognized") => Some(DialogueManager(IntentNotRecognized)),
             Some("configure") => Some(DialogueManager(Configure)),
+            Some("sessionStateRequest") => Some(DialogueManager(SessionStateRequest)),
+            Some("sessionState") => Some(DialogueManager(SessionState)),
             Some("versionRequest") => Some(HermesTopic::Component(
                 None,
                 Component::DialogueManager,
@@ -141,6 +167,21 @@ impl HermesTopic {
         match (one, two) {
             (Some(site_id), Some("vadUp")) => Some(VoiceActivity(site_id.to_string(), VadUp)),
             (Some(site_id), Some("vadDown")) => Some(VoiceActivity(site_id.to_string(), VadDown)),
+            (Some(site_id), Some("versionRequest")) => Some(HermesTopic::Component(
+                Some(site_id.to_string()),
+                Component::VoiceActivity,
+                ComponentCommand::VersionRequest,
+            )),
+            (Some(site_id), Some("version")) => Some(HermesTopic::Component(
+                Some(site_id.to_string()),
+                Component::VoiceActivity,
+                ComponentCommand::Version,
+            )),
+            (Some(site_id), Some("error")) => Some(HermesTopic::Component(
+                Some(site_id.to_string()),
+                Component::VoiceActivity,
+                ComponentCommand::Error,
+            )),
             _ => None,
         }
     }
@@ -224,6 +265,40 @@ impl HermesTopic {
         }
     }
 
+    fn parse_protocol<'a, It: Iterator<Item = &'a str>>(mut comps: It) -> Option<HermesTopic> {
+        use self::HermesTopic::Protocol;
+        use self::ProtocolCommand::*;
+        match comps.next() {
+            Some("handshakeRequest") => Some(Protocol(HandshakeRequest)),
+            Some("handshake") => Some(Protocol(Handshake)),
+            _ => None,
+        }
+    }
+
+    fn parse_registry<'a, It: Iterator<Item = &'a str>>(mut comps: It) -> Option<HermesTopic> {
+        use self::HermesTopic::Registry;
+        use self::RegistryCommand::*;
+        match comps.next() {
+            Some("componentUp") => Some(Registry(ComponentUp)),
+            Some("componentDown") => Some(Registry(ComponentDown)),
+            Some("snapshotRequest") => Some(Registry(SnapshotRequest)),
+            Some("snapshot") => Some(Registry(Snapshot)),
+            _ => None,
+        }
+    }
+
+    fn parse_site<'a, It: Iterator<Item = &'a str>>(mut comps: It) -> Option<HermesTopic> {
+        use self::HermesTopic::Site;
+        use self::SiteCommand::*;
+        match comps.next() {
+            Some("register") => Some(Site(Register)),
+            Some("unregister") => Some(Site(Unregister)),
+            Some("sitesRequest") => Some(Site(SitesRequest)),
+            Some("sites") => Some(Site(Sites)),
+            _ => None,
+        }
+    }
+
     fn parse_injection<'a, It: Iterator<Item = &'a str>>(mut comps: It) -> Option<HermesTopic> {
         use self::HermesTopic::Injection;
         use self::InjectionCommand::*;
@@ -272,6 +347,9 @@ impl FromPath<Self> for HermesTopic {
             Some("nlu") => HermesTopic::parse_nlu(comps),
             Some("tts") => HermesTopic::parse_tts(comps),
             Some("injection") => HermesTopic::parse_injection(comps),
+            Some("protocol") => HermesTopic::parse_protocol(comps),
+            Some("registry") => HermesTopic::parse_registry(comps),
+            Some("site") => HermesTopic::parse_site(comps),
             _ => None,
         }
     }
@@ -311,6 +389,9 @@ impl fmt::Display for HermesTopic {
                 }
             }
             HermesTopic::Injection(ref cmd) => format!("{}/{}", Component::Injection.as_path(), cmd.as_path()),
+            HermesTopic::Protocol(ref cmd) => format!("protocol/{}", cmd.as_path()),
+            HermesTopic::Registry(ref cmd) => format!("registry/{}", cmd.as_path()),
+            HermesTopic::Site(ref cmd) => format!("site/{}", cmd.as_path()),
         };
         write!(f, "hermes/{}", subpath)
     }
@@ -373,6 +454,8 @@ pub enum DialogueManagerCommand {
     SessionEnded,
     IntentNotRecognized,
     Configure,
+    SessionStateRequest,
+    SessionState,
 }
 
 impl ToPath for DialogueManagerCommand {}
@@ -477,6 +560,34 @@ pub enum ComponentCommand {
 
 impl ToPath for ComponentCommand {}
 
+#[derive(Debug, Clone, Copy, PartialEq, ToString)]
+pub enum ProtocolCommand {
+    HandshakeRequest,
+    Handshake,
+}
+
+impl ToPath for ProtocolCommand {}
+
+#[derive(Debug, Clone, Copy, PartialEq, ToString)]
+pub enum RegistryCommand {
+    ComponentUp,
+    ComponentDown,
+    SnapshotRequest,
+    Snapshot,
+}
+
+impl ToPath for RegistryCommand {}
+
+#[derive(Debug, Clone, Copy, PartialEq, ToString)]
+pub enum SiteCommand {
+    Register,
+    Unregister,
+    SitesRequest,
+    Sites,
+}
+
+impl ToPath for SiteCommand {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -523,6 +634,14 @@ mod tests {
                 HermesTopic::DialogueManager(DialogueManagerCommand::Configure),
                 "hermes/dialogueManager/configure",
             ),
+            (
+                HermesTopic::DialogueManager(DialogueManagerCommand::SessionStateRequest),
+                "hermes/dialogueManager/sessionStateRequest",
+            ),
+            (
+                HermesTopic::DialogueManager(DialogueManagerCommand::SessionState),
+                "hermes/dialogueManager/sessionState",
+            ),
             (
                 HermesTopic::Component(None, Component::DialogueManager, ComponentCommand::VersionRequest),
                 "hermes/dialogueManager/versionRequest",
@@ -551,6 +670,26 @@ mod tests {
                 HermesTopic::VoiceActivity("mysite".into(), VoiceActivityCommand::VadDown),
                 "hermes/voiceActivity/mysite/vadDown",
             ),
+            (
+                HermesTopic::Component(
+                    Some("mysite".into()),
+                    Component::VoiceActivity,
+                    ComponentCommand::VersionRequest,
+                ),
+                "hermes/voiceActivity/mysite/versionRequest",
+            ),
+            (
+                HermesTopic::Component(
+                    Some("mysite".into()),
+                    Component::VoiceActivity,
+                    ComponentCommand::Version,
+                ),
+                "hermes/voiceActivity/mysite/version",
+            ),
+            (
+                HermesTopic::Component(Some("mysite".into()), Component::VoiceActivity, ComponentCommand::Error),
+                "hermes/voiceActivity/mysite/error",
+            ),
             (
                 HermesTopic::Hotword(None, HotwordCommand::ToggleOn),
                 "hermes/hotword/toggleOn",
@@ -581,6 +720,11 @@ mod tests {
             ),
             (HermesTopic::Asr(AsrCommand::ToggleOn), "hermes/asr/toggleOn"),
             (HermesTopic::Asr(AsrCommand::ToggleOff), "hermes/asr/toggleOff"),
+            (
+                HermesTopic::Asr(AsrCommand::StartListening),
+                "hermes/asr/startListening",
+            ),
+            (HermesTopic::Asr(AsrCommand::StopListening), "hermes/asr/stopListening"),
             (HermesTopic::Asr(AsrCommand::TextCaptured), "hermes/asr/textCaptured"),
             (
                 HermesTopic::Asr(AsrCommand::PartialTextCaptured),
@@ -702,6 +846,34 @@ mod tests {
                 HermesTopic::Injection(InjectionCommand::StatusRequest),
                 "hermes/injection/statusRequest",
             ),
+            (
+                HermesTopic::Protocol(ProtocolCommand::HandshakeRequest),
+                "hermes/protocol/handshakeRequest",
+            ),
+            (
+                HermesTopic::Protocol(ProtocolCommand::Handshake),
+                "hermes/protocol/handshake",
+            ),
+            (
+                HermesTopic::Registry(RegistryCommand::ComponentUp),
+                "hermes/registry/componentUp",
+            ),
+            (
+                HermesTopic::Registry(RegistryCommand::ComponentDown),
+                "hermes/registry/componentDown",
+            ),
+            (
+                HermesTopic::Registry(RegistryCommand::SnapshotRequest),
+                "hermes/registry/snapshotRequest",
+            ),
+            (
+                HermesTopic::Registry(RegistryCommand::Snapshot),
+                "hermes/registry/snapshot",
+            ),
+            (HermesTopic::Site(SiteCommand::Register), "hermes/site/register"),
+            (HermesTopic::Site(SiteCommand::Unregister), "hermes/site/unregister"),
+            (HermesTopic::Site(SiteCommand::SitesRequest), "hermes/site/sitesRequest"),
+            (HermesTopic::Site(SiteCommand::Sites), "hermes/site/sites"),
         ]
     }
 
@@ -723,4 +895,24 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn errors_are_published_by_the_component_of_the_topic() {
+        let error_topic = |path: &str| {
+            HermesTopic::from_path(path)
+                .unwrap()
+                .error_topic()
+                .map(|it| it.as_path())
+        };
+        assert_eq!(
+            error_topic("hermes/intent/lights_on"),
+            Some("hermes/dialogueManager/error".into())
+        );
+        assert_eq!(error_topic("hermes/nlu/query"), Some("hermes/nlu/error".into()));
+        assert_eq!(
+            error_topic("hermes/audioServer/kitchen/playFinished"),
+            Some("hermes/audioServer/kitchen/error".into())
+        );
+        assert_eq!(error_topic("hermes/feedback/sound/toggleOn"), None);
+    }
 }
@@ -0,0 +1,408 @@
+//! A registry of the components alive on the bus, implemented on top of the hermes facades.
+//!
+//! It regularly asks every component for its version, on each of the configured sites for the
+//! components running per site, and considers the ones that didn't answer for a while as gone. The
+//! components appearing and disappearing are advertised through the `RegistryBackendFacade`, and a
+//! snapshot of the ones alive is published when requested.
+//!
+//! The `SiteRegistry` keeps the sites registered by the satellites through the `SiteFacade`, with
+//! their room, language and capabilities, and publishes their list.
+
+mod sites;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use hermes::*;
+use log::*;
+
+pub use crate::sites::SiteRegistry;
+
+/// The configuration of a `Registry`
+#[derive(Debug, Clone)]
+pub struct RegistryConfig {
+    /// The sites on which the components running per site are looked for
+    pub sites: Vec<String>,
+    /// How often the components are asked for their version
+    pub poll_interval: Duration,
+    /// How long a component can stay silent before being considered gone
+    pub absence_timeout: Duration,
+}
+
+impl Default for RegistryConfig {
+    fn default() -> Self {
+        Self {
+            sites: vec!["default".into()],
+            poll_interval: Duration::from_secs(10),
+            absence_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// A component, along with the site it runs on for the ones running per site
+type ComponentId = (HermesComponent, Option<String>);
+
+/// The registry, it tracks the components until it is dropped
+pub struct Registry {
+    inner: Arc<Inner>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl Registry {
+    pub fn new(handler: &HermesProtocolHandler, config: RegistryConfig) -> HermesResult<Self> {
+        let poll_interval = config.poll_interval;
+        let inner = Arc::new(Inner::new(handler, config));
+
+        let mut subscriptions = vec![inner
+            .facades
+            .registry
+            .subscribe_snapshot_request(handle0(&inner, Inner::on_snapshot_request))?];
+        for (component, site_id) in &inner.targets {
+            let id = (*component, site_id.clone());
+            let callback = handle(&inner, move |inner, message| inner.on_version(&id, message));
+            subscriptions.push(inner.facades.subscribe_version(*component, site_id.clone(), callback)?);
+        }
+
+        spawn_poller(Arc::downgrade(&inner), poll_interval);
+
+        Ok(Self {
+            inner,
+            _subscriptions: subscriptions,
+        })
+    }
+
+    /// The components currently alive
+    pub fn snapshot(&self) -> HermesResult<RegistrySnapshotMessage> {
+        self.inner.snapshot()
+    }
+}
+
+/// The components to poll, the ones running per site being polled on each site
+fn targets(sites: &[String]) -> Vec<ComponentId> {
+    let mut targets = vec![];
+    for component in &HermesComponent::ALL {
+        if component.is_identifiable() {
+            targets.extend(sites.iter().map(|site_id| (*component, Some(site_id.clone()))));
+        } else {
+            targets.push((*component, None));
+        }
+    }
+    targets
+}
+
+fn handle<T, F>(inner: &Arc<Inner>, handler: F) -> Callback<T>
+where
+    F: Fn(&Inner, &T) -> HermesResult<()> + Send + Sync + 'static,
+{
+    let inner = Arc::clone(inner);
+    Callback::new(move |message: &T| inner.log_error(handler(&inner, message)))
+}
+
+fn handle0<F>(inner: &Arc<Inner>, handler: F) -> Callback0
+where
+    F: Fn(&Inner) -> HermesResult<()> + Send + Sync + 'static,
+{
+    let inner = Arc::clone(inner);
+    Callback0::new(move || inner.log_error(handler(&inner)))
+}
+
+fn spawn_poller(inner: Weak<Inner>, interval: Duration) {
+    std::thread::spawn(move || loop {
+        match inner.upgrade() {
+            Some(inner) => inner.log_error(inner.poll(Instant::now())),
+            None => break,
+        }
+        std::thread::sleep(interval);
+    });
+}
+
+struct Facades {
+    voice_activity: Box<VoiceActivityFacade>,
+    hotword: Box<HotwordFacade>,
+    asr: Box<AsrFacade>,
+    tts: Box<TtsFacade>,
+    nlu: Box<NluFacade>,
+    dialogue: Box<DialogueFacade>,
+    audio_server: Box<AudioServerFacade>,
+    injection: Box<InjectionFacade>,
+    registry: Box<RegistryBackendFacade>,
+}
+
+impl Facades {
+    fn publish_version_request(&self, component: HermesComponent, site_id: Option<String>) -> HermesResult<()> {
+        use self::HermesComponent::*;
+        match (component, site_id) {
+            (VoiceActivity, Some(site_id)) => self.voice_activity.publish_version_request(site_id),
+            (Hotword, Some(site_id)) => self.hotword.publish_version_request(site_id),
+            (AudioServer, Some(site_id)) => self.audio_server.publish_version_request(site_id),
+            (Asr, _) => self.asr.publish_version_request(),
+            (Tts, _) => self.tts.publish_version_request(),
+            (Nlu, _) => self.nlu.publish_version_request(),
+            (DialogueManager, _) => self.dialogue.publish_version_request(),
+            (Injection, _) => self.injection.publish_version_request(),
+            (component, None) => Err(HermesError::InvalidArgument(format!(
+                "{:?} runs on each site",
+                component
+            ))),
+        }
+    }
+
+    fn subscribe_version(
+        &self,
+        component: HermesComponent,
+        site_id: Option<String>,
+        handler: Callback<VersionMessage>,
+    ) -> HermesResult<Subscription> {
+        use self::HermesComponent::*;
+        match (component, site_id) {
+            (VoiceActivity, Some(site_id)) => self.voice_activity.subscribe_version(site_id, handler),
+            (Hotword, Some(site_id)) => self.hotword.subscribe_version(site_id, handler),
+            (AudioServer, Some(site_id)) => self.audio_server.subscribe_version(site_id, handler),
+            (Asr, _) => self.asr.subscribe_version(handler),
+            (Tts, _) => self.tts.subscribe_version(handler),
+            (Nlu, _) => self.nlu.subscribe_version(handler),
+            (DialogueManager, _) => self.dialogue.subscribe_version(handler),
+            (Injection, _) => self.injection.subscribe_version(handler),
+            (component, None) => Err(HermesError::InvalidArgument(format!(
+                "{:?} runs on each site",
+                component
+            ))),
+        }
+    }
+}
+
+/// A component alive, `seen` being when it last answered
+struct Presence {
+    status: ComponentStatusMessage,
+    seen: Instant,
+}
+
+struct Inner {
+    config: RegistryConfig,
+    facades: Facades,
+    targets: Vec<ComponentId>,
+    components: Mutex<HashMap<ComponentId, Presence>>,
+}
+
+impl Inner {
+    fn new(handler: &HermesProtocolHandler, config: RegistryConfig) -> Self {
+        Self {
+            targets: targets(&config.sites),
+            config,
+            facades: Facades {
+                voice_activity: handler.voice_activity(),
+                hotword: handler.hotword(),
+                asr: handler.asr(),
+                tts: handler.tts(),
+                nlu: handler.nlu(),
+                dialogue: handler.dialogue(),
+                audio_server: handler.audio_server(),
+                injection: handler.injection(),
+                registry: handler.registry_backend(),
+            },
+            components: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn log_error(&self, result: HermesResult<()>) {
+        if let Err(e) = result {
+            error!("Error in registry: {}", e)
+        }
+    }
+
+    fn on_version(&self, id: &ComponentId, message: &VersionMessage) -> HermesResult<()> {
+        let status = ComponentStatusMessage {
+            component: id.0,
+            site_id: id.1.clone(),
+            version: message.version.clone(),
+            last_seen: Utc::now(),
+        };
+        let previous = self.components.lock().map_err(HermesError::from)?.insert(
+            id.clone(),
+            Presence {
+                status: status.clone(),
+                seen: Instant::now(),
+            },
+        );
+        // a new version is advertised as the component coming up again
+        match previous {
+            Some(ref previous) if previous.status.version == status.version => Ok(()),
+            _ => {
+                info!("{:?} {} is up", status.component, status.version);
+                self.facades.registry.publish_component_up(status)
+            }
+        }
+    }
+
+    fn on_snapshot_request(&self) -> HermesResult<()> {
+        self.facades.registry.publish_snapshot(self.snapshot()?)
+    }
+
+    fn snapshot(&self) -> HermesResult<RegistrySnapshotMessage> {
+        let mut components: Vec<ComponentStatusMessage> = self
+            .components
+            .lock()
+            .map_err(HermesError::from)?
+            .values()
+            .map(|it| it.status.clone())
+            .collect();
+        components.sort_by(|a, b| (a.component, &a.site_id).cmp(&(b.component, &b.site_id)));
+        Ok(RegistrySnapshotMessage { components })
+    }
+
+    /// Ask all the components for their version, and advertise the ones that didn't answer in time
+    /// as gone
+    fn poll(&self, now: Instant) -> HermesResult<()> {
+        for (component, site_id) in &self.targets {
+            if let Err(e) = self.facades.publish_version_request(*component, site_id.clone()) {
+                warn!("Could not request the version of {:?}: {}", component, e)
+            }
+        }
+
+        let absent: Vec<ComponentStatusMessage> = {
+            let mut components = self.components.lock().map_err(HermesError::from)?;
+            let absent = components
+                .iter()
+                .filter(|(_, it)| now.saturating_duration_since(it.seen) > self.config.absence_timeout)
+                .map(|(id, _)| id.clone())
+                .collect::<Vec<_>>();
+            absent
+                .into_iter()
+                .filter_map(|id| components.remove(&id))
+                .map(|it| it.status)
+                .collect()
+        };
+        for status in absent {
+            warn!("{:?} {} is down", status.component, status.version);
+            let component = status.component;
+            if let Err(e) = self.facades.registry.publish_component_down(status) {
+                error!("Could not advertise {:?} as down: {}", component, e)
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::mpsc;
+
+    use hermes_inprocess::InProcessHermesProtocolHandler;
+    use hermes_mqtt::MqttHermesProtocolHandler;
+    use hermes_mqtt_broker::Broker;
+
+    fn version(version: &str) -> VersionMessage {
+        VersionMessage {
+            version: semver::Version::parse(version).unwrap(),
+        }
+    }
+
+    /// Answer the version requests of the NLU and of the hotword of the kitchen while returned
+    /// subscriptions are alive
+    fn answer_version_requests(handler: &HermesProtocolHandler) -> Vec<Subscription> {
+        let nlu = handler.nlu_backend();
+        let hotword = handler.hotword_backend();
+        vec![
+            handler
+                .nlu_backend()
+                .subscribe_version_request(Callback0::new(move || nlu.publish_version(version("1.2.0")).unwrap()))
+                .unwrap(),
+            handler
+                .hotword_backend()
+                .subscribe_version_request(
+                    "kitchen".into(),
+                    Callback0::new(move || hotword.publish_version("kitchen".into(), version("0.9.1")).unwrap()),
+                )
+                .unwrap(),
+        ]
+    }
+
+    fn receiver<T: Clone + Send + 'static>() -> (Callback<T>, mpsc::Receiver<T>) {
+        let (tx, rx) = mpsc::channel();
+        let tx = Mutex::new(tx);
+        let callback = Callback::new(move |message: &T| {
+            let _ = tx.lock().map(|it| it.send(message.clone()));
+        });
+        (callback, rx)
+    }
+
+    fn config() -> RegistryConfig {
+        RegistryConfig {
+            sites: vec!["kitchen".into(), "bedroom".into()],
+            poll_interval: Duration::from_millis(20),
+            absence_timeout: Duration::from_millis(500),
+        }
+    }
+
+    #[test]
+    fn components_answering_version_requests_are_up() {
+        let handler = InProcessHermesProtocolHandler::new();
+        let _components = answer_version_requests(&handler);
+        let registry = handler.registry();
+        let (callback, up) = receiver();
+        let _up = registry.subscribe_component_up(callback).unwrap();
+
+        let _registry = Registry::new(&handler, config()).unwrap();
+
+        let timeout = Duration::from_secs(1);
+        let mut statuses = [up.recv_timeout(timeout).unwrap(), up.recv_timeout(timeout).unwrap()];
+        statuses.sort_by_key(|it| it.component);
+        assert_eq!(statuses[0].component, HermesComponent::Hotword);
+        assert_eq!(statuses[0].site_id, Some("kitchen".into()));
+        assert_eq!(statuses[0].version, semver::Version::new(0, 9, 1));
+        assert_eq!(statuses[1].component, HermesComponent::Nlu);
+        assert_eq!(statuses[1].site_id, None);
+        // the components are only advertised once while they are up
+        assert!(up.recv_timeout(Duration::from_millis(200)).is_err());
+
+        let (callback, snapshots) = receiver();
+        let _snapshots = registry.subscribe_snapshot(callback).unwrap();
+        registry.publish_snapshot_request().unwrap();
+        let snapshot: RegistrySnapshotMessage = snapshots.recv_timeout(timeout).unwrap();
+        let components: Vec<_> = snapshot.components.iter().map(|it| it.component).collect();
+        assert_eq!(components, vec![HermesComponent::Hotword, HermesComponent::Nlu]);
+    }
+
+    #[test]
+    fn silent_components_are_down() {
+        let handler = InProcessHermesProtocolHandler::new();
+        let components = answer_version_requests(&handler);
+        let registry_facade = handler.registry();
+        let (callback, up) = receiver::<ComponentStatusMessage>();
+        let _up = registry_facade.subscribe_component_up(callback).unwrap();
+        let (callback, down) = receiver();
+        let _down = registry_facade.subscribe_component_down(callback).unwrap();
+
+        let registry = Registry::new(&handler, config()).unwrap();
+        let timeout = Duration::from_secs(2);
+        up.recv_timeout(timeout).unwrap();
+        up.recv_timeout(timeout).unwrap();
+        assert_eq!(registry.snapshot().unwrap().components.len(), 2);
+
+        drop(components);
+        let status: ComponentStatusMessage = down.recv_timeout(timeout).unwrap();
+        assert!(status.last_seen <= Utc::now());
+        down.recv_timeout(timeout).unwrap();
+        assert!(registry.snapshot().unwrap().components.is_empty());
+    }
+
+    #[test]
+    fn components_gone_before_the_registry_started_are_not_up() {
+        let broker = Broker::start().expect("could not start the broker");
+        let address = broker.local_addr().to_string();
+        let gone = MqttHermesProtocolHandler::new(&address).unwrap();
+        gone.nlu_backend().publish_version(version("1.2.0")).unwrap();
+        std::thread::sleep(Duration::from_millis(200));
+        drop(gone);
+
+        let handler = MqttHermesProtocolHandler::new(&address).unwrap();
+        let (callback, up) = receiver();
+        let _up = handler.registry().subscribe_component_up(callback).unwrap();
+        let _registry = Registry::new(&handler, config()).unwrap();
+        assert!(up.recv_timeout(Duration::from_millis(500)).is_err());
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use hermes::*;
+use log::*;
+
+/// The registry of the sites, it keeps the sites registered by the satellites until it is dropped
+/// and publishes their list each time it changes and when requested
+pub struct SiteRegistry {
+    inner: Arc<SiteInner>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl SiteRegistry {
+    pub fn new(handler: &HermesProtocolHandler) -> HermesResult<Self> {
+        let inner = Arc::new(SiteInner {
+            facade: handler.site_backend(),
+            sites: Mutex::new(HashMap::new()),
+        });
+
+        let subscriptions = vec![
+            inner
+                .facade
+                .subscribe_register_site(handle(&inner, SiteInner::on_register))?,
+            inner
+                .facade
+                .subscribe_unregister_site(handle(&inner, SiteInner::on_unregister))?,
+            inner.facade.subscribe_sites_request({
+                let inner = Arc::clone(&inner);
+                Callback0::new(move || inner.log_error(inner.publish_sites()))
+            })?,
+        ];
+
+        Ok(Self {
+            inner,
+            _subscriptions: subscriptions,
+        })
+    }
+
+    /// The sites currently registered
+    pub fn sites(&self) -> HermesResult<SitesMessage> {
+        self.inner.sites()
+    }
+
+    /// The information registered for a site, if any
+    pub fn site(&self, site_id: &str) -> HermesResult<Option<SiteInfoMessage>> {
+        Ok(self
+            .inner
+            .sites
+            .lock()
+            .map_err(HermesError::from)?
+            .get(site_id)
+            .cloned())
+    }
+}
+
+fn handle<T, F>(inner: &Arc<SiteInner>, handler: F) -> Callback<T>
+where
+    F: Fn(&SiteInner, &T) -> HermesResult<()> + Send + Sync + 'static,
+{
+    let inner = Arc::clone(inner);
+    Callback::new(move |message: &T| inner.log_error(handler(&inner, message)))
+}
+
+struct SiteInner {
+    facade: Box<SiteBackendFacade>,
+    sites: Mutex<HashMap<String, SiteInfoMessage>>,
+}
+
+impl SiteInner {
+    fn log_error(&self, result: HermesResult<()>) {
+        if let Err(e) = result {
+            error!("Error in site registry: {}", e)
+        }
+    }
+
+    /// Register a new site or update a registered one
+    fn on_register(&self, site: &SiteInfoMessage) -> HermesResult<()> {
+        let previous = self
+            .sites
+            .lock()
+            .map_err(HermesError::from)?
+            .insert(site.site_id.clone(), site.clone());
+        match previous {
+            Some(ref previous) if previous == site => Ok(()),
+            Some(_) => {
+                info!("Site {} updated", site.site_id);
+                self.publish_sites()
+            }
+            None => {
+                info!("Site {} registered", site.site_id);
+                self.publish_sites()
+            }
+        }
+    }
+
+    fn on_unregister(&self, site: &SiteMessage) -> HermesResult<()> {
+        let removed = self.sites.lock().map_err(HermesError::from)?.remove(&site.site_id);
+        if removed.is_some() {
+            info!("Site {} unregistered", site.site_id);
+            self.publish_sites()?;
+        }
+        Ok(())
+    }
+
+    fn publish_sites(&self) -> HermesResult<()> {
+        self.facade.publish_sites(self.sites()?)
+    }
+
+    fn sites(&self) -> HermesResult<SitesMessage> {
+        let mut sites: Vec<SiteInfoMessage> = self
+            .sites
+            .lock()
+            .map_err(HermesError::from)?
+            .values()
+            .cloned()
+            .collect();
+        sites.sort_by(|a, b| a.site_id.cmp(&b.site_id));
+        Ok(SitesMessage { sites })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    use hermes_inprocess::InProcessHermesProtocolHandler;
+
+    fn receive_sites(handler: &HermesProtocolHandler) -> (Subscription, mpsc::Receiver<SitesMessage>) {
+        let (tx, rx) = mpsc::channel();
+        let tx = Mutex::new(tx);
+        let subscription = handler
+            .site()
+            .subscribe_sites(Callback::new(move |message: &SitesMessage| {
+                let _ = tx.lock().map(|it| it.send(message.clone()));
+            }))
+            .unwrap();
+        (subscription, rx)
+    }
+
+    fn site_ids(sites: &SitesMessage) -> Vec<&str> {
+        sites.sites.iter().map(|it| it.site_id.as_str()).collect()
+    }
+
+    #[test]
+    fn sites_can_be_registered_updated_and_unregistered() {
+        let handler = InProcessHermesProtocolHandler::new();
+        let registry = SiteRegistry::new(&handler).unwrap();
+        let (_subscription, sites) = receive_sites(&handler);
+        let site = handler.site();
+        let timeout = Duration::from_secs(1);
+
+        site.publish_register_site(SiteInfoMessage::new("kitchen")).unwrap();
+        site.publish_register_site(SiteInfoMessage::new("bedroom")).unwrap();
+        sites.recv_timeout(timeout).unwrap();
+        assert_eq!(
+            site_ids(&sites.recv_timeout(timeout).unwrap()),
+            vec!["bedroom", "kitchen"]
+        );
+
+        // registering the same site again changes nothing
+        site.publish_register_site(SiteInfoMessage::new("kitchen")).unwrap();
+        assert!(sites.recv_timeout(Duration::from_millis(100)).is_err());
+
+        site.publish_register_site(SiteInfoMessage {
+            language: Some("fr".into()),
+            ..SiteInfoMessage::new("kitchen")
+        })
+        .unwrap();
+        sites.recv_timeout(timeout).unwrap();
+        let kitchen = registry.site("kitchen").unwrap().unwrap();
+        assert_eq!(kitchen.language, Some("fr".into()));
+
+        site.publish_unregister_site(SiteMessage {
+            site_id: "bedroom".into(),
+            session_id: None,
+        })
+        .unwrap();
+        assert_eq!(site_ids(&sites.recv_timeout(timeout).unwrap()), vec!["kitchen"]);
+        assert_eq!(registry.sites().unwrap().sites, vec![kitchen]);
+    }
+
+    #[test]
+    fn sites_are_published_when_requested() {
+        let handler = InProcessHermesProtocolHandler::new();
+        let _registry = SiteRegistry::new(&handler).unwrap();
+        handler
+            .site()
+            .publish_register_site(SiteInfoMessage::new("kitchen"))
+            .unwrap();
+        let (_subscription, sites) = receive_sites(&handler);
+
+        handler.site().publish_sites_request().unwrap();
+        let message = sites.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(site_ids(&message), vec!["kitchen"]);
+    }
+}
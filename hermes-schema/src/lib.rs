@@ -0,0 +1,316 @@
+//! JSON schemas of the hermes messages and AsyncAPI description of the topics they are published
+//! on, for the clients that can't use the rust crate or the guest language bindings.
+
+use hermes::*;
+use hermes_mqtt::topics::*;
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::schema::{RootSchema, Schema};
+use serde_json::{json, Map, Value};
+
+/// The version of the AsyncAPI specification the description follows
+pub const ASYNCAPI_VERSION: &str = "2.0.0";
+
+/// The JSON schema of each hermes message, along with the name of its type
+pub fn message_schemas() -> Vec<(&'static str, RootSchema)> {
+    macro_rules! schemas {
+        ($($t:ty),*) => {
+            vec![$((stringify!($t), schemars::schema_for!($t))),*]
+        };
+    }
+    schemas!(
+        SiteMessage,
+        VersionMessage,
+        ErrorMessage,
+        VadUpMessage,
+        VadDownMessage,
+        HotwordDetectedMessage,
+        AsrStartListeningMessage,
+        TextCapturedMessage,
+        SayMessage,
+        SayFinishedMessage,
+        RegisterSoundMessage,
+        NluQueryMessage,
+        NluSlotQueryMessage,
+        NluSlotMessage,
+        NluIntentMessage,
+        NluIntentNotRecognizedMessage,
+        PlayBytesMessage,
+        AudioFrameMessage,
+        ReplayRequestMessage,
+        PlayFinishedMessage,
+        StartSessionMessage,
+        ContinueSessionMessage,
+        EndSessionMessage,
+        DialogueConfigureMessage,
+        SessionStateMessage,
+        SessionQueuedMessage,
+        SessionStartedMessage,
+        SessionEndedMessage,
+        IntentMessage,
+        IntentNotRecognizedMessage,
+        InjectionRequestMessage,
+        InjectionStatusMessage,
+        HandshakeMessage,
+        ComponentStatusMessage,
+        RegistrySnapshotMessage,
+        SiteInfoMessage,
+        SitesMessage
+    )
+}
+
+/// What is published on a topic
+enum ChannelPayload {
+    /// A JSON message of the ontology
+    Message(&'static str, fn(&mut SchemaGenerator) -> Schema),
+    /// The raw bytes of a wav file
+    Wav,
+    /// Nothing
+    Empty,
+}
+
+macro_rules! message {
+    ($t:ty) => {
+        ChannelPayload::Message(stringify!($t), |gen| gen.subschema_for::<$t>())
+    };
+}
+
+/// All the topics along with their payload, the parts of the topics depending on the message are
+/// replaced by placeholders like `{siteId}`
+fn channels() -> Vec<(HermesTopic, ChannelPayload)> {
+    use self::AudioServerCommand as A;
+    use self::Component as C;
+    use self::DialogueManagerCommand as D;
+    use self::HermesTopic::*;
+    use self::SoundCommand as S;
+    let site = || Some("{siteId}".to_string());
+
+    let mut channels = vec![
+        (Feedback(FeedbackCommand::Sound(S::ToggleOn)), message!(SiteMessage)),
+        (Feedback(FeedbackCommand::Sound(S::ToggleOff)), message!(SiteMessage)),
+        (
+            VoiceActivity("{siteId}".into(), VoiceActivityCommand::VadUp),
+            message!(VadUpMessage),
+        ),
+        (
+            VoiceActivity("{siteId}".into(), VoiceActivityCommand::VadDown),
+            message!(VadDownMessage),
+        ),
+        (Hotword(None, HotwordCommand::ToggleOn), message!(SiteMessage)),
+        (Hotword(None, HotwordCommand::ToggleOff), message!(SiteMessage)),
+        (
+            Hotword(site(), HotwordCommand::Detected),
+            message!(HotwordDetectedMessage),
+        ),
+        (Asr(AsrCommand::ToggleOn), ChannelPayload::Empty),
+        (Asr(AsrCommand::ToggleOff), ChannelPayload::Empty),
+        (Asr(AsrCommand::StartListening), message!(AsrStartListeningMessage)),
+        (Asr(AsrCommand::StopListening), message!(SiteMessage)),
+        (Asr(AsrCommand::TextCaptured), message!(TextCapturedMessage)),
+        (Asr(AsrCommand::PartialTextCaptured), message!(TextCapturedMessage)),
+        (Asr(AsrCommand::Reload), ChannelPayload::Empty),
+        (Tts(TtsCommand::Say), message!(SayMessage)),
+        (Tts(TtsCommand::SayFinished), message!(SayFinishedMessage)),
+        (Tts(TtsCommand::RegisterSound("{soundId}".into())), ChannelPayload::Wav),
+        (Nlu(NluCommand::Query), message!(NluQueryMessage)),
+        (Nlu(NluCommand::PartialQuery), message!(NluSlotQueryMessage)),
+        (Nlu(NluCommand::SlotParsed), message!(NluSlotMessage)),
+        (Nlu(NluCommand::IntentParsed), message!(NluIntentMessage)),
+        (
+            Nlu(NluCommand::IntentNotRecognized),
+            message!(NluIntentNotRecognizedMessage),
+        ),
+        (Nlu(NluCommand::Reload), ChannelPayload::Empty),
+        (AudioServer(None, A::ToggleOn), message!(SiteMessage)),
+        (AudioServer(None, A::ToggleOff), message!(SiteMessage)),
+        (AudioServer(site(), A::AudioFrame), ChannelPayload::Wav),
+        (AudioServer(site(), A::ReplayRequest), message!(ReplayRequestMessage)),
+        (AudioServer(site(), A::ReplayResponse), ChannelPayload::Wav),
+        (
+            AudioServer(site(), A::PlayBytes("{requestId}".into())),
+            ChannelPayload::Wav,
+        ),
+        (AudioServer(site(), A::PlayFinished), message!(PlayFinishedMessage)),
+        (DialogueManager(D::ToggleOn), ChannelPayload::Empty),
+        (DialogueManager(D::ToggleOff), ChannelPayload::Empty),
+        (DialogueManager(D::StartSession), message!(StartSessionMessage)),
+        (DialogueManager(D::ContinueSession), message!(ContinueSessionMessage)),
+        (DialogueManager(D::EndSession), message!(EndSessionMessage)),
+        (DialogueManager(D::Configure), message!(DialogueConfigureMessage)),
+        (DialogueManager(D::SessionStateRequest), ChannelPayload::Empty),
+        (DialogueManager(D::SessionState), message!(SessionStateMessage)),
+        (DialogueManager(D::SessionQueued), message!(SessionQueuedMessage)),
+        (DialogueManager(D::SessionStarted), message!(SessionStartedMessage)),
+        (DialogueManager(D::SessionEnded), message!(SessionEndedMessage)),
+        (
+            DialogueManager(D::IntentNotRecognized),
+            message!(IntentNotRecognizedMessage),
+        ),
+        (Intent("{intentName}".into()), message!(IntentMessage)),
+        (Injection(InjectionCommand::Perform), message!(InjectionRequestMessage)),
+        (Injection(InjectionCommand::Status), message!(InjectionStatusMessage)),
+        (Injection(InjectionCommand::StatusRequest), ChannelPayload::Empty),
+        (Protocol(ProtocolCommand::HandshakeRequest), ChannelPayload::Empty),
+        (Protocol(ProtocolCommand::Handshake), message!(HandshakeMessage)),
+        (Registry(RegistryCommand::ComponentUp), message!(ComponentStatusMessage)),
+        (
+            Registry(RegistryCommand::ComponentDown),
+            message!(ComponentStatusMessage),
+        ),
+        (Registry(RegistryCommand::SnapshotRequest), ChannelPayload::Empty),
+        (Registry(RegistryCommand::Snapshot), message!(RegistrySnapshotMessage)),
+        (Site(SiteCommand::Register), message!(SiteInfoMessage)),
+        (Site(SiteCommand::Unregister), message!(SiteMessage)),
+        (Site(SiteCommand::SitesRequest), ChannelPayload::Empty),
+        (Site(SiteCommand::Sites), message!(SitesMessage)),
+    ];
+
+    let components = vec![
+        (C::VoiceActivity, site()),
+        (C::Hotword, site()),
+        (C::Asr, None),
+        (C::Tts, None),
+        (C::Nlu, None),
+        (C::DialogueManager, None),
+        (C::AudioServer, site()),
+        (C::Injection, None),
+    ];
+    for (component, site_id) in components {
+        channels.extend(vec![
+            (
+                Component(site_id.clone(), component, ComponentCommand::VersionRequest),
+                ChannelPayload::Empty,
+            ),
+            (
+                Component(site_id.clone(), component, ComponentCommand::Version),
+                message!(VersionMessage),
+            ),
+            (
+                Component(site_id, component, ComponentCommand::Error),
+                message!(ErrorMessage),
+            ),
+        ]);
+    }
+    channels
+}
+
+/// The placeholders of a topic, like `siteId` in `hermes/hotword/{siteId}/detected`
+fn parameters(path: &str) -> Vec<&str> {
+    path.split('/')
+        .filter(|it| it.starts_with('{') && it.ends_with('}'))
+        .map(|it| &it[1..it.len() - 1])
+        .collect()
+}
+
+/// The AsyncAPI document describing the hermes protocol over MQTT, the payloads being described
+/// by the JSON schemas of the messages
+pub fn asyncapi() -> Value {
+    let mut generator = SchemaSettings::draft07()
+        .with(|it| it.definitions_path = "#/components/schemas/".into())
+        .into_generator();
+    let mut messages = Map::new();
+    let mut channels = Map::new();
+
+    for (topic, payload) in self::channels() {
+        let (name, message) = match payload {
+            ChannelPayload::Message(name, schema) => (name, json!({ "name": name, "payload": schema(&mut generator) })),
+            ChannelPayload::Wav => (
+                "Wav",
+                json!({
+                    "name": "Wav",
+                    "summary": "The raw bytes of a wav file, they are not JSON encoded",
+                    "contentType": "audio/wav",
+                    "payload": { "type": "string", "format": "binary" },
+                }),
+            ),
+            ChannelPayload::Empty => (
+                "Empty",
+                json!({ "name": "Empty", "summary": "Nothing is published on the topic but the topic itself" }),
+            ),
+        };
+        messages.insert(name.into(), message);
+
+        let path = topic.as_path();
+        let reference = json!({ "message": { "$ref": format!("#/components/messages/{}", name) } });
+        let mut channel = json!({ "publish": reference, "subscribe": reference });
+        let parameters: Map<String, Value> = parameters(&path)
+            .into_iter()
+            .map(|it| (it.to_string(), json!({ "schema": { "type": "string" } })))
+            .collect();
+        if !parameters.is_empty() {
+            channel["parameters"] = parameters.into();
+        }
+        channels.insert(path, channel);
+    }
+
+    json!({
+        "asyncapi": ASYNCAPI_VERSION,
+        "info": {
+            "title": "Hermes",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "The protocol used by the components of the Snips platform to communicate over MQTT",
+        },
+        "defaultContentType": "application/json",
+        "channels": channels,
+        "components": {
+            "messages": messages,
+            "schemas": generator.take_definitions(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema(name: &str) -> Value {
+        let (_, schema) = message_schemas().into_iter().find(|(it, _)| *it == name).unwrap();
+        serde_json::to_value(schema).unwrap()
+    }
+
+    #[test]
+    fn channels_are_hermes_topics() {
+        for (topic, _) in channels() {
+            assert_eq!(HermesTopic::from_path(topic.as_path()), Some(topic));
+        }
+    }
+
+    #[test]
+    fn schemas_follow_serde_attributes() {
+        let say = schema("SayMessage");
+        assert!(say["properties"]["siteId"].is_object());
+        assert_eq!(say["required"], json!(["siteId", "text"]));
+
+        let start_session = schema("StartSessionMessage");
+        let variants = start_session["definitions"]["SessionInit"]["oneOf"].as_array().unwrap();
+        let types: Vec<_> = variants.iter().map(|it| &it["properties"]["type"]["enum"][0]).collect();
+        assert_eq!(types, vec!["action", "notification"]);
+
+        let injection = schema("InjectionRequestMessage");
+        let entity_value = &injection["definitions"]["EntityValue"]["anyOf"];
+        assert_eq!(entity_value[0]["type"], "array");
+        assert_eq!(entity_value[1]["type"], "string");
+
+        let frame = schema("AudioFrameMessage");
+        assert_eq!(frame["properties"]["wavFrame"]["contentEncoding"], "base64");
+    }
+
+    #[test]
+    fn asyncapi_references_the_schemas() {
+        let asyncapi = asyncapi();
+        let intent = &asyncapi["channels"]["hermes/intent/{intentName}"];
+        assert_eq!(
+            intent["subscribe"]["message"]["$ref"],
+            "#/components/messages/IntentMessage"
+        );
+        assert!(intent["parameters"]["intentName"].is_object());
+        assert_eq!(
+            asyncapi["components"]["messages"]["IntentMessage"]["payload"]["$ref"],
+            "#/components/schemas/IntentMessage"
+        );
+        assert!(asyncapi["components"]["schemas"]["SlotValue"].is_object());
+        assert_eq!(
+            asyncapi["channels"]["hermes/audioServer/{siteId}/audioFrame"]["publish"]["message"]["$ref"],
+            "#/components/messages/Wav"
+        );
+    }
+}
@@ -0,0 +1,49 @@
+//! Print the JSON schemas of the hermes messages or the AsyncAPI description of the protocol.
+
+use std::path::PathBuf;
+
+use hermes::{HermesError, HermesResult};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "hermes-schema", about = "Export the description of the hermes protocol")]
+enum Command {
+    /// Print the AsyncAPI document describing the MQTT topics and their payloads
+    Asyncapi,
+    /// Print the JSON schema of each message, keyed by the name of its type
+    JsonSchema {
+        /// Write each schema in a `<type>.json` file of this directory instead
+        #[structopt(long, parse(from_os_str))]
+        out_dir: Option<PathBuf>,
+    },
+}
+
+fn run(command: Command) -> HermesResult<()> {
+    match command {
+        Command::Asyncapi => println!("{}", serde_json::to_string_pretty(&hermes_schema::asyncapi())?),
+        Command::JsonSchema { out_dir: Some(dir) } => {
+            std::fs::create_dir_all(&dir)
+                .map_err(|e| HermesError::other(format!("Could not create {:?}: {}", dir, e)))?;
+            for (name, schema) in hermes_schema::message_schemas() {
+                let path = dir.join(format!("{}.json", name));
+                std::fs::write(&path, serde_json::to_string_pretty(&schema)?)
+                    .map_err(|e| HermesError::other(format!("Could not write {:?}: {}", path, e)))?;
+            }
+        }
+        Command::JsonSchema { out_dir: None } => {
+            let schemas: serde_json::Map<_, _> = hermes_schema::message_schemas()
+                .into_iter()
+                .map(|(name, schema)| Ok((name.to_string(), serde_json::to_value(schema)?)))
+                .collect::<HermesResult<_>>()?;
+            println!("{}", serde_json::to_string_pretty(&schemas)?)
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = run(Command::from_args()) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
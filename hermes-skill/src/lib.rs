@@ -0,0 +1,403 @@
+//! A higher level API to write skills on top of the hermes facades.
+//!
+//! A `SkillApp` dispatches the intents recognized by the dialogue manager to the handlers
+//! registered for them, each handler receiving the intent along with a handle to continue or end
+//! the session it was recognized in, without having to deal with the session ids. A
+//! `SessionHandle<T>` also takes care of the encoding of the custom data of the session, while an
+//! `UntypedSessionHandle` leaves it as is.
+//!
+//! The handlers are registered for a `Route`, matching the intents by name, by pattern or by
+//! confidence, with fallbacks for the intents matching no route and for the user answers matching
+//! no intent. Middlewares can be added around the handlers, for logging, authorization or metrics.
+
+mod router;
+mod session;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+
+use hermes::*;
+use log::*;
+
+use crate::router::{IntentHandler, Router};
+pub use crate::router::{Middleware, Next, Route};
+pub use crate::session::{FromSession, SessionHandle, UntypedSessionHandle};
+
+/// A skill, it handles the intents it registered handlers for until it is dropped.
+///
+/// The intents are dispatched to the handler of the first route matching them, in the order the
+/// routes were registered, through the middlewares.
+pub struct SkillApp {
+    dialogue: Arc<DialogueFacade>,
+    router: Arc<RwLock<Router>>,
+    intent_not_recognized: Arc<AtomicBool>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl SkillApp {
+    pub fn new(handler: &HermesProtocolHandler) -> HermesResult<Self> {
+        let dialogue: Arc<DialogueFacade> = Arc::from(handler.dialogue());
+        let router = Arc::new(RwLock::new(Router::new(Arc::clone(&dialogue))));
+
+        let subscriptions = vec![
+            dialogue.subscribe_intents({
+                let router = Arc::clone(&router);
+                Callback::new(move |intent: &IntentMessage| {
+                    let result = router
+                        .read()
+                        .map_err(HermesError::from)
+                        .and_then(|it| it.dispatch_intent(intent));
+                    if let Err(e) = result {
+                        error!("Error while handling intent {}: {}", intent.intent.intent_name, e)
+                    }
+                })
+            })?,
+            dialogue.subscribe_intent_not_recognized({
+                let router = Arc::clone(&router);
+                Callback::new(move |intent_not_recognized: &IntentNotRecognizedMessage| {
+                    let result = router
+                        .read()
+                        .map_err(HermesError::from)
+                        .and_then(|it| it.dispatch_intent_not_recognized(intent_not_recognized));
+                    if let Err(e) = result {
+                        error!("Error while handling a not recognized intent: {}", e)
+                    }
+                })
+            })?,
+        ];
+
+        Ok(Self {
+            dialogue,
+            router,
+            intent_not_recognized: Arc::new(AtomicBool::new(false)),
+            _subscriptions: subscriptions,
+        })
+    }
+
+    /// Handle the intents matching `route`, their sessions being handled through an `S`
+    pub fn route<S, F>(&self, route: Route, handler: F) -> HermesResult<()>
+    where
+        S: FromSession + 'static,
+        F: Fn(&IntentMessage, S) -> HermesResult<()> + Send + Sync + 'static,
+    {
+        let handler = self.intent_handler(handler);
+        self.router
+            .write()
+            .map_err(HermesError::from)?
+            .add_route(route, handler);
+        Ok(())
+    }
+
+    /// Handle the intents named `intent_name`, their sessions being handled through an `S`
+    pub fn on_intent<S, F>(&self, intent_name: &str, handler: F) -> HermesResult<()>
+    where
+        S: FromSession + 'static,
+        F: Fn(&IntentMessage, S) -> HermesResult<()> + Send + Sync + 'static,
+    {
+        self.route(Route::intent(intent_name), handler)
+    }
+
+    /// Handle the intents matching none of the routes, they are left to the other skills otherwise
+    pub fn on_unmatched_intent<S, F>(&self, handler: F) -> HermesResult<()>
+    where
+        S: FromSession + 'static,
+        F: Fn(&IntentMessage, S) -> HermesResult<()> + Send + Sync + 'static,
+    {
+        let handler = self.intent_handler(handler);
+        self.router.write().map_err(HermesError::from)?.set_unmatched(handler);
+        Ok(())
+    }
+
+    /// Handle the user answers matching no intent, the sessions continued by the handlers then ask
+    /// the dialogue manager to send them instead of handling them itself
+    pub fn on_intent_not_recognized<S, F>(&self, handler: F) -> HermesResult<()>
+    where
+        S: FromSession + 'static,
+        F: Fn(&IntentNotRecognizedMessage, S) -> HermesResult<()> + Send + Sync + 'static,
+    {
+        let dialogue = Arc::clone(&self.dialogue);
+        let handler = Box::new(move |intent_not_recognized: &IntentNotRecognizedMessage| {
+            let session = UntypedSessionHandle::for_intent_not_recognized(Arc::clone(&dialogue), intent_not_recognized);
+            handler(
+                intent_not_recognized,
+                S::from_session(session.send_intent_not_recognized(true))?,
+            )
+        });
+        self.router
+            .write()
+            .map_err(HermesError::from)?
+            .set_intent_not_recognized(handler);
+        self.intent_not_recognized.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Run `middleware` around the handlers of the intents, after the middlewares already added
+    pub fn middleware<M: Middleware + 'static>(&self, middleware: M) -> HermesResult<()> {
+        self.router
+            .write()
+            .map_err(HermesError::from)?
+            .add_middleware(Box::new(middleware));
+        Ok(())
+    }
+
+    fn intent_handler<S, F>(&self, handler: F) -> IntentHandler
+    where
+        S: FromSession + 'static,
+        F: Fn(&IntentMessage, S) -> HermesResult<()> + Send + Sync + 'static,
+    {
+        let dialogue = Arc::clone(&self.dialogue);
+        let intent_not_recognized = Arc::clone(&self.intent_not_recognized);
+        Box::new(move |intent: &IntentMessage| {
+            let session = UntypedSessionHandle::for_intent(Arc::clone(&dialogue), intent)
+                .send_intent_not_recognized(intent_not_recognized.load(Ordering::SeqCst));
+            handler(intent, S::from_session(session)?)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::mpsc;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use hermes_inprocess::InProcessHermesProtocolHandler;
+    use serde_derive::{Deserialize, Serialize};
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Order {
+        pizzas: u32,
+    }
+
+    fn receiver<T, F>(subscribe: F) -> (Subscription, mpsc::Receiver<T>)
+    where
+        T: Clone + Send + 'static,
+        F: FnOnce(Callback<T>) -> HermesResult<Subscription>,
+    {
+        let (tx, rx) = mpsc::channel();
+        let tx = Mutex::new(tx);
+        let subscription = subscribe(Callback::new(move |message: &T| {
+            let _ = tx.lock().map(|it| it.send(message.clone()));
+        }))
+        .unwrap();
+        (subscription, rx)
+    }
+
+    fn intent(intent_name: &str, custom_data: Option<&str>) -> IntentMessage {
+        IntentMessage {
+            session_id: "some session".into(),
+            custom_data: custom_data.map(str::to_string),
+            site_id: "kitchen".into(),
+            input: "some input".into(),
+            asr_tokens: None,
+            asr_confidence: None,
+            intent: NluIntentClassifierResult {
+                intent_name: intent_name.into(),
+                confidence_score: 0.9,
+            },
+            slots: vec![],
+            alternatives: None,
+        }
+    }
+
+    #[test]
+    fn handler_continues_the_session_with_its_data() {
+        let handler = InProcessHermesProtocolHandler::new();
+        let backend = handler.dialogue_backend();
+        let (_continues, continues) = receiver(|it| backend.subscribe_continue_session(it));
+        let app = SkillApp::new(&handler).unwrap();
+        app.on_intent("order_pizza", |_intent, mut session: SessionHandle<Order>| {
+            let pizzas = session.data().map_or(0, |it| it.pizzas);
+            session.set_data(Order { pizzas: pizzas + 1 });
+            session.ask_slot("which one?", "pizza_kind")
+        })
+        .unwrap();
+
+        backend
+            .publish_intent(intent("order_pizza", Some(r#"{"version":1,"data":{"pizzas":1}}"#)))
+            .unwrap();
+        let message = continues.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(message.session_id, "some session");
+        assert_eq!(message.intent_filter, Some(vec!["order_pizza".to_string()]));
+        assert_eq!(message.slot, Some("pizza_kind".into()));
+        assert_eq!(message.custom_data, Some(r#"{"version":1,"data":{"pizzas":2}}"#.into()));
+    }
+
+    #[test]
+    fn custom_data_of_other_formats_is_kept_by_untyped_handlers() {
+        let handler = InProcessHermesProtocolHandler::new();
+        let backend = handler.dialogue_backend();
+        let (_continues, continues) = receiver(|it| backend.subscribe_continue_session(it));
+        let app = SkillApp::new(&handler).unwrap();
+        app.on_intent("order_pizza", |_intent, session: UntypedSessionHandle| {
+            session.say_and_continue("which one?", vec![])
+        })
+        .unwrap();
+
+        backend
+            .publish_intent(intent("order_pizza", Some("started by another skill")))
+            .unwrap();
+        let message = continues.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(message.text, "which one?");
+        assert_eq!(message.custom_data, Some("started by another skill".into()));
+    }
+
+    #[test]
+    fn sessions_whose_data_cant_be_decoded_are_ended() {
+        let handler = InProcessHermesProtocolHandler::new();
+        let backend = handler.dialogue_backend();
+        let (_ends, ends) = receiver(|it| backend.subscribe_end_session(it));
+        let app = SkillApp::new(&handler).unwrap();
+        app.on_intent("order_pizza", |_intent, session: SessionHandle<Order>| {
+            session.end(Some("never reached".into()))
+        })
+        .unwrap();
+
+        backend
+            .publish_intent(intent("order_pizza", Some("started by another skill")))
+            .unwrap();
+        let message = ends.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(message.text, None);
+    }
+
+    #[test]
+    fn unanswered_sessions_are_ended() {
+        let handler = InProcessHermesProtocolHandler::new();
+        let backend = handler.dialogue_backend();
+        let (_ends, ends) = receiver(|it| backend.subscribe_end_session(it));
+        let app = SkillApp::new(&handler).unwrap();
+        app.on_intent("failing", |_intent, _session: UntypedSessionHandle| {
+            Err(HermesError::other("something went wrong"))
+        })
+        .unwrap();
+        app.on_intent("ending", |_intent, session: UntypedSessionHandle| {
+            session.end(Some("bye".into()))
+        })
+        .unwrap();
+
+        backend.publish_intent(intent("failing", None)).unwrap();
+        let message = ends.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(message.text, None);
+
+        backend.publish_intent(intent("ending", None)).unwrap();
+        let message = ends.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(message.text, Some("bye".into()));
+
+        // the intents without handler are left to the other skills
+        backend.publish_intent(intent("other", None)).unwrap();
+        assert!(ends.recv_timeout(Duration::from_millis(100)).is_err());
+    }
+
+    #[test]
+    fn intents_are_dispatched_to_the_first_matching_route() {
+        let handler = InProcessHermesProtocolHandler::new();
+        let backend = handler.dialogue_backend();
+        let (_ends, ends) = receiver(|it| backend.subscribe_end_session(it));
+        let app = SkillApp::new(&handler).unwrap();
+        let reply = |text: &'static str| {
+            move |_intent: &IntentMessage, session: UntypedSessionHandle| session.end(Some(text.into()))
+        };
+        app.route(Route::intent("pizza:order").min_confidence(0.95), reply("sure"))
+            .unwrap();
+        app.route(Route::glob("pizza:*"), reply("pizza")).unwrap();
+        app.on_intent("pizza:cancel", reply("never reached")).unwrap();
+        app.on_unmatched_intent(reply("what?")).unwrap();
+
+        let mut order = intent("pizza:order", None);
+        order.intent.confidence_score = 0.99;
+        for (intent, text) in &[
+            (order, "sure"),
+            (intent("pizza:order", None), "pizza"),
+            (intent("pizza:cancel", None), "pizza"),
+            (intent("pasta:order", None), "what?"),
+        ] {
+            backend.publish_intent(intent.clone()).unwrap();
+            let message = ends.recv_timeout(Duration::from_secs(1)).unwrap();
+            assert_eq!(message.text, Some(text.to_string()));
+        }
+    }
+
+    #[test]
+    fn middlewares_run_around_the_handlers() {
+        let handler = InProcessHermesProtocolHandler::new();
+        let backend = handler.dialogue_backend();
+        let (_ends, ends) = receiver(|it| backend.subscribe_end_session(it));
+        let app = SkillApp::new(&handler).unwrap();
+        let calls = Arc::new(Mutex::new(vec![]));
+        {
+            let calls = Arc::clone(&calls);
+            app.middleware(move |intent: &IntentMessage, next: Next| {
+                calls.lock().unwrap().push(format!("log {}", intent.intent.intent_name));
+                next(intent)
+            })
+            .unwrap();
+        }
+        app.middleware(|intent: &IntentMessage, next: Next| {
+            if intent.site_id == "kitchen" {
+                next(intent)
+            } else {
+                Ok(())
+            }
+        })
+        .unwrap();
+        {
+            let calls = Arc::clone(&calls);
+            app.on_intent("order", move |_intent, session: UntypedSessionHandle| {
+                calls.lock().unwrap().push("handler".into());
+                session.end(Some("done".into()))
+            })
+            .unwrap();
+        }
+
+        backend.publish_intent(intent("order", None)).unwrap();
+        let message = ends.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(message.text, Some("done".into()));
+        assert_eq!(*calls.lock().unwrap(), vec!["log order", "handler"]);
+
+        // the sessions of the intents stopped by a middleware are ended
+        backend
+            .publish_intent(IntentMessage {
+                site_id: "garage".into(),
+                ..intent("order", None)
+            })
+            .unwrap();
+        let message = ends.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(message.text, None);
+        assert_eq!(*calls.lock().unwrap(), vec!["log order", "handler", "log order"]);
+    }
+
+    #[test]
+    fn not_recognized_intents_can_be_handled() {
+        let handler = InProcessHermesProtocolHandler::new();
+        let backend = handler.dialogue_backend();
+        let (_continues, continues) = receiver(|it| backend.subscribe_continue_session(it));
+        let app = SkillApp::new(&handler).unwrap();
+        app.on_intent("order", |_intent, session: UntypedSessionHandle| {
+            session.say_and_continue("what pizza?", vec![])
+        })
+        .unwrap();
+        app.on_intent_not_recognized(|_intent_not_recognized, session: UntypedSessionHandle| {
+            session.say_and_continue("sorry, what pizza?", vec![])
+        })
+        .unwrap();
+
+        backend.publish_intent(intent("order", None)).unwrap();
+        let message = continues.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert!(message.send_intent_not_recognized);
+
+        backend
+            .publish_intent_not_recognized(IntentNotRecognizedMessage {
+                session_id: "some session".into(),
+                custom_data: None,
+                site_id: "kitchen".into(),
+                input: Some("some input".into()),
+                confidence_score: 0.8,
+            })
+            .unwrap();
+        let message = continues.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(message.text, "sorry, what pizza?");
+        assert!(message.send_intent_not_recognized);
+    }
+}
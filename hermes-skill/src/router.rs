@@ -0,0 +1,242 @@
+use std::cell::Cell;
+use std::sync::Arc;
+
+use hermes::*;
+use log::*;
+
+pub(crate) type IntentHandler = Box<Fn(&IntentMessage) -> HermesResult<()> + Send + Sync>;
+pub(crate) type IntentNotRecognizedHandler = Box<Fn(&IntentNotRecognizedMessage) -> HermesResult<()> + Send + Sync>;
+
+/// The rest of the handling of an intent, given to the middlewares
+pub type Next<'a> = &'a Fn(&IntentMessage) -> HermesResult<()>;
+
+/// Code run around the handlers of the intents, like logging, authorization or metrics.
+///
+/// A middleware calls `next` to run the following middlewares and then the handler, a middleware
+/// not calling it, like one denying an intent, leaves the session to be ended by the skill.
+pub trait Middleware: Send + Sync {
+    fn handle(&self, intent: &IntentMessage, next: Next) -> HermesResult<()>;
+}
+
+impl<F> Middleware for F
+where
+    F: Fn(&IntentMessage, Next) -> HermesResult<()> + Send + Sync,
+{
+    fn handle(&self, intent: &IntentMessage, next: Next) -> HermesResult<()> {
+        self(intent, next)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Matcher {
+    Any,
+    Name(String),
+    Glob(String),
+}
+
+/// The intents a handler is registered for
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    matcher: Matcher,
+    min_confidence: f32,
+}
+
+impl Route {
+    /// The intents named `intent_name`
+    pub fn intent<S: Into<String>>(intent_name: S) -> Self {
+        Self::new(Matcher::Name(intent_name.into()))
+    }
+
+    /// The intents whose name matches `pattern`, where `*` matches any sequence of characters and
+    /// `?` any single character, like `pizza:*`
+    pub fn glob<S: Into<String>>(pattern: S) -> Self {
+        Self::new(Matcher::Glob(pattern.into()))
+    }
+
+    /// All the intents
+    pub fn any() -> Self {
+        Self::new(Matcher::Any)
+    }
+
+    /// Only match the intents recognized with a confidence of at least `min_confidence`
+    pub fn min_confidence(self, min_confidence: f32) -> Self {
+        Self { min_confidence, ..self }
+    }
+
+    fn new(matcher: Matcher) -> Self {
+        Self {
+            matcher,
+            min_confidence: 0.,
+        }
+    }
+
+    fn matches(&self, intent: &IntentMessage) -> bool {
+        let name = &intent.intent.intent_name;
+        let name_matches = match &self.matcher {
+            Matcher::Any => true,
+            Matcher::Name(intent_name) => intent_name == name,
+            Matcher::Glob(pattern) => glob_matches(pattern, name),
+        };
+        name_matches && intent.intent.confidence_score >= self.min_confidence
+    }
+}
+
+/// Whether `name` matches `pattern`, in which `*` stands for any sequence of characters and `?` for
+/// any single character
+fn glob_matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // the position of the last star of the pattern, and the end of the part of the name it matches
+    let mut star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            // let the last star match one more character
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    star = Some((star_p, star_n + 1));
+                    p = star_p + 1;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Dispatches the intents to the handler of the first route matching them, through the middlewares
+pub(crate) struct Router {
+    dialogue: Arc<DialogueFacade>,
+    routes: Vec<(Route, IntentHandler)>,
+    unmatched: Option<IntentHandler>,
+    intent_not_recognized: Option<IntentNotRecognizedHandler>,
+    middlewares: Vec<Box<Middleware>>,
+}
+
+impl Router {
+    pub(crate) fn new(dialogue: Arc<DialogueFacade>) -> Self {
+        Self {
+            dialogue,
+            routes: vec![],
+            unmatched: None,
+            intent_not_recognized: None,
+            middlewares: vec![],
+        }
+    }
+
+    pub(crate) fn add_route(&mut self, route: Route, handler: IntentHandler) {
+        self.routes.push((route, handler))
+    }
+
+    pub(crate) fn set_unmatched(&mut self, handler: IntentHandler) {
+        self.unmatched = Some(handler)
+    }
+
+    pub(crate) fn set_intent_not_recognized(&mut self, handler: IntentNotRecognizedHandler) {
+        self.intent_not_recognized = Some(handler)
+    }
+
+    pub(crate) fn add_middleware(&mut self, middleware: Box<Middleware>) {
+        self.middlewares.push(middleware)
+    }
+
+    pub(crate) fn dispatch_intent(&self, intent: &IntentMessage) -> HermesResult<()> {
+        let handler = self
+            .routes
+            .iter()
+            .find(|(route, _)| route.matches(intent))
+            .map(|(_, handler)| handler)
+            .or(self.unmatched.as_ref());
+        let handler = match handler {
+            Some(handler) => handler,
+            // the intents without handler are left to the other skills
+            None => return Ok(()),
+        };
+
+        let handled = Cell::new(false);
+        let result = self.run(0, intent, &|intent| {
+            handled.set(true);
+            handler(intent)
+        });
+        if !handled.get() {
+            debug!("Intent {} stopped by a middleware", intent.intent.intent_name);
+            self.dialogue.publish_end_session(EndSessionMessage {
+                session_id: intent.session_id.clone(),
+                text: None,
+            })?;
+        }
+        result
+    }
+
+    pub(crate) fn dispatch_intent_not_recognized(
+        &self,
+        intent_not_recognized: &IntentNotRecognizedMessage,
+    ) -> HermesResult<()> {
+        match &self.intent_not_recognized {
+            Some(handler) => handler(intent_not_recognized),
+            None => Ok(()),
+        }
+    }
+
+    fn run(&self, index: usize, intent: &IntentMessage, handler: Next) -> HermesResult<()> {
+        match self.middlewares.get(index) {
+            Some(middleware) => middleware.handle(intent, &|intent| self.run(index + 1, intent, handler)),
+            None => handler(intent),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn intent(intent_name: &str, confidence_score: f32) -> IntentMessage {
+        IntentMessage {
+            session_id: "some session".into(),
+            custom_data: None,
+            site_id: "kitchen".into(),
+            input: "some input".into(),
+            asr_tokens: None,
+            asr_confidence: None,
+            intent: NluIntentClassifierResult {
+                intent_name: intent_name.into(),
+                confidence_score,
+            },
+            slots: vec![],
+            alternatives: None,
+        }
+    }
+
+    #[test]
+    fn routes_match_by_name_pattern_and_confidence() {
+        assert!(Route::intent("pizza:order").matches(&intent("pizza:order", 0.5)));
+        assert!(!Route::intent("pizza:order").matches(&intent("pizza:orders", 0.5)));
+
+        assert!(Route::glob("pizza:*").matches(&intent("pizza:order", 0.5)));
+        assert!(Route::glob("*:order").matches(&intent("pizza:order", 0.5)));
+        assert!(Route::glob("pizza:?rder").matches(&intent("pizza:order", 0.5)));
+        assert!(!Route::glob("pizza:*").matches(&intent("pasta:order", 0.5)));
+        assert!(!Route::glob("pizza:?").matches(&intent("pizza:order", 0.5)));
+        assert!(Route::glob("*:*r*").matches(&intent("pizza:order", 0.5)));
+        assert!(!Route::glob("*:*z*").matches(&intent("pizza:order", 0.5)));
+        assert!(Route::glob("café:?clair").matches(&intent("café:éclair", 0.5)));
+        assert!(Route::glob("caf?:*").matches(&intent("café:éclair", 0.5)));
+        assert!(!Route::glob("caf??:*").matches(&intent("café:éclair", 0.5)));
+        assert!(!Route::glob("*a*a*a*a*a*a*a*a*a*b").matches(&intent(&"a".repeat(200), 0.5)));
+
+        assert!(Route::any().matches(&intent("pizza:order", 0.)));
+        assert!(Route::any().min_confidence(0.5).matches(&intent("pizza:order", 0.5)));
+        assert!(!Route::glob("pizza:*")
+            .min_confidence(0.6)
+            .matches(&intent("pizza:order", 0.5)));
+    }
+}
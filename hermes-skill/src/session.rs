@@ -0,0 +1,222 @@
+use std::sync::Arc;
+
+use hermes::*;
+use log::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// The session an intent was recognized in, it must be answered once by continuing or ending it.
+///
+/// The custom data of the session is left as is, it is sent back when continuing the session, so
+/// that the sessions whose custom data has another format can still be handled. A handle dropped
+/// without being answered, like when the handler of the intent failed, ends the session.
+pub struct UntypedSessionHandle {
+    dialogue: Arc<DialogueFacade>,
+    session_id: String,
+    site_id: String,
+    intent_name: Option<String>,
+    custom_data: Option<String>,
+    send_intent_not_recognized: bool,
+    answered: bool,
+}
+
+impl UntypedSessionHandle {
+    pub(crate) fn for_intent(dialogue: Arc<DialogueFacade>, intent: &IntentMessage) -> Self {
+        Self {
+            dialogue,
+            session_id: intent.session_id.clone(),
+            site_id: intent.site_id.clone(),
+            intent_name: Some(intent.intent.intent_name.clone()),
+            custom_data: intent.custom_data.clone(),
+            send_intent_not_recognized: false,
+            answered: false,
+        }
+    }
+
+    pub(crate) fn for_intent_not_recognized(
+        dialogue: Arc<DialogueFacade>,
+        intent_not_recognized: &IntentNotRecognizedMessage,
+    ) -> Self {
+        Self {
+            dialogue,
+            session_id: intent_not_recognized.session_id.clone(),
+            site_id: intent_not_recognized.site_id.clone(),
+            intent_name: None,
+            custom_data: intent_not_recognized.custom_data.clone(),
+            send_intent_not_recognized: false,
+            answered: false,
+        }
+    }
+
+    /// Ask the dialogue manager to send the next user answer as an `IntentNotRecognizedMessage`
+    /// when it matches no intent, instead of handling it itself
+    pub(crate) fn send_intent_not_recognized(mut self, send_intent_not_recognized: bool) -> Self {
+        self.send_intent_not_recognized = send_intent_not_recognized;
+        self
+    }
+
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    pub fn site_id(&self) -> &str {
+        &self.site_id
+    }
+
+    /// The custom data of the session as received, if any
+    pub fn custom_data(&self) -> Option<&str> {
+        self.custom_data.as_deref()
+    }
+
+    /// Replace the custom data of the session, it is sent as is when continuing the session
+    pub fn set_custom_data(&mut self, custom_data: String) {
+        self.custom_data = Some(custom_data)
+    }
+
+    /// Say something and wait for the user to answer with one of the intents of `intent_filter`, or
+    /// with any intent if it is empty
+    pub fn say_and_continue<S: Into<String>>(self, text: S, intent_filter: Vec<String>) -> HermesResult<()> {
+        let intent_filter = if intent_filter.is_empty() {
+            None
+        } else {
+            Some(intent_filter)
+        };
+        self.continue_session(text.into(), intent_filter, None)
+    }
+
+    /// Ask the user for the value of a slot of the current intent, the answer comes back as this
+    /// intent with the slot filled. This fails when no intent was recognized, the session being
+    /// ended then
+    pub fn ask_slot<S: Into<String>>(self, text: S, slot: &str) -> HermesResult<()> {
+        let intent_name = self
+            .intent_name
+            .clone()
+            .ok_or_else(|| HermesError::InvalidArgument("no intent to fill the slot of".into()))?;
+        self.continue_session(text.into(), Some(vec![intent_name]), Some(slot.to_string()))
+    }
+
+    /// End the session, saying something first if `text` is set
+    pub fn end(mut self, text: Option<String>) -> HermesResult<()> {
+        self.answered = true;
+        self.dialogue.publish_end_session(EndSessionMessage {
+            session_id: self.session_id.clone(),
+            text,
+        })
+    }
+
+    fn continue_session(
+        mut self,
+        text: String,
+        intent_filter: Option<Vec<String>>,
+        slot: Option<String>,
+    ) -> HermesResult<()> {
+        self.answered = true;
+        self.dialogue.publish_continue_session(ContinueSessionMessage {
+            session_id: self.session_id.clone(),
+            text,
+            intent_filter,
+            custom_data: self.custom_data.take(),
+            send_intent_not_recognized: self.send_intent_not_recognized,
+            slot,
+        })
+    }
+}
+
+impl Drop for UntypedSessionHandle {
+    fn drop(&mut self) {
+        if self.answered {
+            return;
+        }
+        warn!("Session {} was not answered, ending it", self.session_id);
+        let end = EndSessionMessage {
+            session_id: self.session_id.clone(),
+            text: None,
+        };
+        if let Err(e) = self.dialogue.publish_end_session(end) {
+            error!("Could not end session {}: {}", self.session_id, e)
+        }
+    }
+}
+
+/// The session an intent was recognized in, its custom data being a `T`.
+///
+/// The custom data is decoded from the envelope of `encode_custom_data`, and encoded back in the
+/// messages continuing the session. The session is ended without calling the handler when its
+/// custom data can't be decoded, the `UntypedSessionHandle` handles any custom data.
+pub struct SessionHandle<T> {
+    session: UntypedSessionHandle,
+    data: Option<T>,
+}
+
+impl<T> SessionHandle<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    pub fn session_id(&self) -> &str {
+        self.session.session_id()
+    }
+
+    pub fn site_id(&self) -> &str {
+        self.session.site_id()
+    }
+
+    /// The custom data of the session, if any
+    pub fn data(&self) -> Option<&T> {
+        self.data.as_ref()
+    }
+
+    /// Replace the custom data of the session, it is sent when continuing the session
+    pub fn set_data(&mut self, data: T) {
+        self.data = Some(data)
+    }
+
+    /// Say something and wait for the user to answer with one of the intents of `intent_filter`, or
+    /// with any intent if it is empty
+    pub fn say_and_continue<S: Into<String>>(self, text: S, intent_filter: Vec<String>) -> HermesResult<()> {
+        self.into_untyped()?.say_and_continue(text, intent_filter)
+    }
+
+    /// Ask the user for the value of a slot of the current intent, the answer comes back as this
+    /// intent with the slot filled. This fails when no intent was recognized, the session being
+    /// ended then
+    pub fn ask_slot<S: Into<String>>(self, text: S, slot: &str) -> HermesResult<()> {
+        self.into_untyped()?.ask_slot(text, slot)
+    }
+
+    /// End the session, saying something first if `text` is set
+    pub fn end(self, text: Option<String>) -> HermesResult<()> {
+        self.session.end(text)
+    }
+
+    /// The untyped handle of the session, with the data encoded as its custom data
+    fn into_untyped(self) -> HermesResult<UntypedSessionHandle> {
+        let mut session = self.session;
+        if let Some(data) = &self.data {
+            session.set_custom_data(encode_custom_data(data)?);
+        }
+        Ok(session)
+    }
+}
+
+/// The handles of the sessions given to the handlers of a `SkillApp`, built from the untyped
+/// handle of the session
+pub trait FromSession: Sized {
+    /// Build the handle, the session is ended if it fails
+    fn from_session(session: UntypedSessionHandle) -> HermesResult<Self>;
+}
+
+impl FromSession for UntypedSessionHandle {
+    fn from_session(session: UntypedSessionHandle) -> HermesResult<Self> {
+        Ok(session)
+    }
+}
+
+impl<T> FromSession for SessionHandle<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    fn from_session(session: UntypedSessionHandle) -> HermesResult<Self> {
+        let data = session.custom_data().map(decode_custom_data).transpose()?;
+        Ok(Self { session, data })
+    }
+}
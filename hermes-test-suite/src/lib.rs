@@ -1,9 +1,26 @@
+#[cfg(feature = "mocks")]
+pub mod mocks;
+
 use chrono::prelude::*;
+use futures::stream::{Stream, StreamExt};
+
+pub use futures::executor::block_on;
 
 pub fn now() -> DateTime<Utc> {
     Utc::now()
 }
 
+/// Wait at most `timeout` for the next item of `stream`, driving it on a dedicated executor
+pub fn next_item<S>(mut stream: S, timeout: std::time::Duration) -> Option<S::Item>
+where
+    S: Stream + Unpin + Send + 'static,
+    S::Item: Send + 'static,
+{
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || tx.send(block_on(stream.next())));
+    rx.recv_timeout(timeout).ok().and_then(|it| it)
+}
+
 #[macro_export]
 macro_rules! t {
     (
@@ -22,7 +39,7 @@ macro_rules! t {
             let receiver = handler_receiver.$s_facade();
             let (tx, rx) = std::sync::mpsc::channel();
             let tx = std::sync::Mutex::new(tx);
-            receiver
+            let _subscription = receiver
                 .$s(hermes::Callback::new(move |o: &$t| {
                     tx.lock().map(|it| it.send(o.clone())).unwrap().unwrap()
                 }))
@@ -43,7 +60,7 @@ macro_rules! t {
             let receiver = handler_receiver.$s_facade();
             let (tx, rx) = std::sync::mpsc::channel();
             let tx = std::sync::Mutex::new(tx);
-            receiver
+            let _subscription = receiver
                 .$s(hermes::Callback0::new(move || {
                     tx.lock().map(|it| it.send(())).unwrap().unwrap()
                 }))
@@ -62,7 +79,7 @@ macro_rules! t {
             let receiver = handler_receiver.$s_facade();
             let (tx, rx) = std::sync::mpsc::channel();
             let tx = std::sync::Mutex::new(tx);
-            receiver
+            let _subscription = receiver
                 .$s(
                     $a,
                     hermes::Callback0::new(move || tx.lock().map(|it| it.send(())).unwrap().unwrap()),
@@ -91,7 +108,7 @@ macro_rules! t {
             let receiver = handler_receiver.$s_facade();
             let (tx, rx) = std::sync::mpsc::channel();
             let tx = std::sync::Mutex::new(tx);
-            receiver
+            let _subscription = receiver
                 .$s(
                     $a,
                     hermes::Callback::new(move |o: &$t| tx.lock().map(|it| it.send(o.clone())).unwrap().unwrap()),
@@ -113,7 +130,7 @@ macro_rules! t {
             let receiver = handler_receiver.$s_facade();
             let (tx, rx) = std::sync::mpsc::channel();
             let tx = std::sync::Mutex::new(tx);
-            receiver
+            let _subscription = receiver
                 .$s(
                     $a,
                     hermes::Callback0::new(move || tx.lock().map(|it| it.send(())).unwrap().unwrap()),
@@ -142,7 +159,7 @@ macro_rules! t {
             let receiver = handler_receiver.$s_facade();
             let (tx, rx) = std::sync::mpsc::channel();
             let tx = std::sync::Mutex::new(tx);
-            receiver
+            let _subscription = receiver
                 .$s(
                     $a,
                     hermes::Callback::new(move |o: &$t| tx.lock().map(|it| it.send(o.clone())).unwrap().unwrap()),
@@ -164,7 +181,7 @@ macro_rules! t {
             let receiver = handler_receiver.$s_facade();
             let (tx, rx) = std::sync::mpsc::channel();
             let tx = std::sync::Mutex::new(tx);
-            receiver
+            let _subscription = receiver
                 .$s(hermes::Callback0::new(move || {
                     tx.lock().map(|it| it.send(())).unwrap().unwrap()
                 }))
@@ -192,7 +209,7 @@ macro_rules! t {
             let receiver = handler_receiver.$s_facade();
             let (tx, rx) = std::sync::mpsc::channel();
             let tx = std::sync::Mutex::new(tx);
-            receiver
+            let _subscription = receiver
                 .$s(hermes::Callback::new(move |o: &$t| {
                     tx.lock().map(|it| it.send(o.clone())).unwrap().unwrap()
                 }))
@@ -222,7 +239,7 @@ macro_rules! t {
             let receiver = handler_receiver.$s_facade();
             let (tx, rx) = std::sync::mpsc::channel();
             let tx = std::sync::Mutex::new(tx);
-            receiver
+            let _subscription = receiver
                 .$s(hermes::Callback::new(move |o: &$t| {
                     tx.lock().map(|it| it.send(o.clone())).unwrap().unwrap()
                 }))
@@ -299,6 +316,332 @@ macro_rules! t_identifiable_component {
         };
     }
 
+#[macro_export]
+macro_rules! t_unsubscribe {
+    ($name:ident) => {
+        mod $name {
+            use super::*;
+
+            fn say_finished_callback(
+                tx: std::sync::mpsc::Sender<SayFinishedMessage>,
+            ) -> hermes::Callback<SayFinishedMessage> {
+                let tx = std::sync::Mutex::new(tx);
+                hermes::Callback::new(move |o: &SayFinishedMessage| {
+                    tx.lock().map(|it| it.send(o.clone())).unwrap().unwrap()
+                })
+            }
+
+            #[test]
+            fn unsubscribe_works() {
+                let (handler_source, handler_receiver) = create_handlers();
+                let source = handler_source.tts_backend();
+                let receiver = handler_receiver.tts();
+                let (tx, rx) = std::sync::mpsc::channel();
+                let subscription = receiver
+                    .subscribe_say_finished(say_finished_callback(tx))
+                    .unwrap();
+                let message = SayFinishedMessage {
+                    id: Some("my id".into()),
+                    session_id: None,
+                };
+                std::thread::sleep(WAIT_DURATION);
+                source.publish_say_finished(message.clone()).unwrap();
+                let result = rx.recv_timeout(std::time::Duration::from_secs(1));
+                assert!(result.is_ok(), "didn't receive message after one second");
+                subscription.unsubscribe();
+                std::thread::sleep(WAIT_DURATION);
+                source.publish_say_finished(message).unwrap();
+                let result = rx.recv_timeout(std::time::Duration::from_millis(500));
+                assert!(result.is_err(), "received a message after unsubscribing");
+            }
+
+            #[test]
+            fn unsubscribe_keeps_other_handlers_on_same_topic() {
+                let (handler_source, handler_receiver) = create_handlers();
+                let source = handler_source.tts_backend();
+                let receiver = handler_receiver.tts();
+                let (tx1, rx1) = std::sync::mpsc::channel();
+                let (tx2, rx2) = std::sync::mpsc::channel();
+                let subscription1 = receiver
+                    .subscribe_say_finished(say_finished_callback(tx1))
+                    .unwrap();
+                let _subscription2 = receiver
+                    .subscribe_say_finished(say_finished_callback(tx2))
+                    .unwrap();
+                drop(subscription1);
+                let message = SayFinishedMessage {
+                    id: Some("my id".into()),
+                    session_id: None,
+                };
+                std::thread::sleep(WAIT_DURATION);
+                source.publish_say_finished(message.clone()).unwrap();
+                let result = rx2.recv_timeout(std::time::Duration::from_secs(1));
+                assert!(result.is_ok(), "didn't receive message after one second");
+                assert_eq!(result.unwrap(), message);
+                assert!(rx1.try_recv().is_err(), "received a message after unsubscribing");
+            }
+
+            #[test]
+            fn detached_subscription_survives_facade() {
+                let (handler_source, handler_receiver) = create_handlers();
+                let source = handler_source.tts_backend();
+                let (tx, rx) = std::sync::mpsc::channel();
+                handler_receiver
+                    .tts()
+                    .subscribe_say_finished(say_finished_callback(tx))
+                    .unwrap()
+                    .detach();
+                let message = SayFinishedMessage {
+                    id: Some("my id".into()),
+                    session_id: None,
+                };
+                std::thread::sleep(WAIT_DURATION);
+                source.publish_say_finished(message.clone()).unwrap();
+                let result = rx.recv_timeout(std::time::Duration::from_secs(1));
+                assert!(result.is_ok(), "didn't receive message after one second");
+                assert_eq!(result.unwrap(), message)
+            }
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! t_async {
+    ($name:ident) => {
+        mod $name {
+            use super::*;
+
+            #[test]
+            fn stream_works() {
+                let (handler_source, handler_receiver) = create_handlers();
+                let source = handler_source.tts_backend();
+                let receiver = handler_receiver.tts();
+                let stream = receiver.say_finished_stream().unwrap();
+                let message = SayFinishedMessage {
+                    id: Some("my id".into()),
+                    session_id: None,
+                };
+                std::thread::sleep(WAIT_DURATION);
+                $crate::block_on(source.publish_say_finished_async(message.clone())).unwrap();
+                let result = $crate::next_item(stream, std::time::Duration::from_secs(1));
+                assert_eq!(result, Some(message), "didn't receive message after one second");
+            }
+
+            #[test]
+            fn unit_stream_works() {
+                let (handler_source, handler_receiver) = create_handlers();
+                let source = handler_source.tts();
+                let receiver = handler_receiver.tts_backend();
+                let stream = receiver.version_request_stream().unwrap();
+                std::thread::sleep(WAIT_DURATION);
+                $crate::block_on(source.publish_version_request_async()).unwrap();
+                let result = $crate::next_item(stream, std::time::Duration::from_secs(1));
+                assert_eq!(result, Some(()), "didn't receive message after one second");
+            }
+
+            #[test]
+            fn publish_is_lazy() {
+                let (handler_source, handler_receiver) = create_handlers();
+                let source = handler_source.tts_backend();
+                let receiver = handler_receiver.tts();
+                let (tx, rx) = std::sync::mpsc::channel();
+                let tx = std::sync::Mutex::new(tx);
+                let _subscription = receiver
+                    .subscribe_say_finished(hermes::Callback::new(move |o: &SayFinishedMessage| {
+                        tx.lock().map(|it| it.send(o.clone())).unwrap().unwrap()
+                    }))
+                    .unwrap();
+                let message = SayFinishedMessage {
+                    id: Some("my id".into()),
+                    session_id: None,
+                };
+                std::thread::sleep(WAIT_DURATION);
+                let publication = source.publish_say_finished_async(message.clone());
+                assert!(
+                    rx.recv_timeout(std::time::Duration::from_millis(500)).is_err(),
+                    "message was published before the future was polled"
+                );
+                $crate::block_on(publication).unwrap();
+                let result = rx.recv_timeout(std::time::Duration::from_secs(1));
+                assert_eq!(
+                    result.ok(),
+                    Some(message),
+                    "didn't receive message after one second"
+                );
+            }
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! t_request {
+    ($name:ident) => {
+        mod $name {
+            use super::*;
+
+            const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+
+            #[test]
+            fn nlu_query_works() {
+                let (handler_source, handler_receiver) = create_handlers();
+                let backend = handler_source.nlu_backend();
+                let nlu = handler_receiver.nlu();
+                let _responder = handler_source
+                    .nlu_backend()
+                    .subscribe_query(hermes::Callback::new(move |query: &NluQueryMessage| {
+                        let reply = |id: Option<String>| NluIntentMessage {
+                            id,
+                            input: query.input.clone(),
+                            intent: NluIntentClassifierResult {
+                                intent_name: "my intent".into(),
+                                confidence_score: 0.73,
+                            },
+                            slots: vec![],
+                            session_id: None,
+                            alternatives: None,
+                        };
+                        backend
+                            .publish_intent_parsed(reply(Some("other id".into())))
+                            .unwrap();
+                        backend.publish_intent_parsed(reply(query.id.clone())).unwrap();
+                    }))
+                    .unwrap();
+                let query = NluQueryMessage {
+                    input: "hello world".into(),
+                    asr_tokens: None,
+                    intent_filter: None,
+                    id: None,
+                    session_id: None,
+                    intent_alternatives: None,
+                };
+                std::thread::sleep(WAIT_DURATION);
+                match nlu.query(query, TIMEOUT).unwrap() {
+                    NluResult::IntentParsed(intent) => {
+                        assert_ne!(intent.id, Some("other id".into()));
+                        assert_eq!(intent.input, "hello world");
+                    }
+                    other => panic!("unexpected result {:?}", other),
+                }
+            }
+
+            #[test]
+            fn nlu_query_not_recognized_works() {
+                let (handler_source, handler_receiver) = create_handlers();
+                let backend = handler_source.nlu_backend();
+                let nlu = handler_receiver.nlu();
+                let _responder = handler_source
+                    .nlu_backend()
+                    .subscribe_query(hermes::Callback::new(move |query: &NluQueryMessage| {
+                        backend
+                            .publish_intent_not_recognized(NluIntentNotRecognizedMessage {
+                                id: query.id.clone(),
+                                input: query.input.clone(),
+                                confidence_score: 0.5,
+                                session_id: None,
+                            })
+                            .unwrap()
+                    }))
+                    .unwrap();
+                let query = NluQueryMessage {
+                    input: "hello world".into(),
+                    asr_tokens: None,
+                    intent_filter: None,
+                    id: Some("my id".into()),
+                    session_id: None,
+                    intent_alternatives: None,
+                };
+                std::thread::sleep(WAIT_DURATION);
+                let result = nlu.query(query, TIMEOUT).unwrap();
+                assert_eq!(
+                    result,
+                    NluResult::IntentNotRecognized(NluIntentNotRecognizedMessage {
+                        id: Some("my id".into()),
+                        input: "hello world".into(),
+                        confidence_score: 0.5,
+                        session_id: None,
+                    })
+                );
+            }
+
+            #[test]
+            fn say_and_wait_works() {
+                let (handler_source, handler_receiver) = create_handlers();
+                let backend = handler_source.tts_backend();
+                let tts = handler_receiver.tts();
+                let _responder = handler_source
+                    .tts_backend()
+                    .subscribe_say(hermes::Callback::new(move |say: &SayMessage| {
+                        backend
+                            .publish_say_finished(SayFinishedMessage {
+                                id: say.id.clone(),
+                                session_id: say.session_id.clone(),
+                            })
+                            .unwrap()
+                    }))
+                    .unwrap();
+                let say = SayMessage {
+                    text: "hello world".into(),
+                    lang: None,
+                    id: None,
+                    site_id: "some site".into(),
+                    session_id: Some("abc".into()),
+                };
+                std::thread::sleep(WAIT_DURATION);
+                let finished = tts.say_and_wait(say, TIMEOUT).unwrap();
+                assert!(finished.id.is_some());
+                assert_eq!(finished.session_id, Some("abc".into()));
+            }
+
+            #[test]
+            fn play_and_wait_works() {
+                let (handler_source, handler_receiver) = create_handlers();
+                let backend = handler_source.audio_server_backend();
+                let audio_server = handler_receiver.audio_server();
+                let _responder = handler_source
+                    .audio_server_backend()
+                    .subscribe_all_play_bytes(hermes::Callback::new(move |bytes: &PlayBytesMessage| {
+                        backend
+                            .publish_play_finished(PlayFinishedMessage {
+                                id: bytes.id.clone(),
+                                site_id: bytes.site_id.clone(),
+                            })
+                            .unwrap()
+                    }))
+                    .unwrap();
+                let bytes = PlayBytesMessage {
+                    wav_bytes: vec![42; 1000],
+                    id: "".into(),
+                    site_id: "some site".into(),
+                };
+                std::thread::sleep(WAIT_DURATION);
+                let finished = audio_server.play_and_wait(bytes, TIMEOUT).unwrap();
+                assert!(!finished.id.is_empty());
+                assert_eq!(finished.site_id, "some site");
+            }
+
+            #[test]
+            fn request_times_out() {
+                let (_, handler_receiver) = create_handlers();
+                let say = SayMessage {
+                    text: "hello world".into(),
+                    lang: None,
+                    id: Some("my id".into()),
+                    site_id: "some site".into(),
+                    session_id: None,
+                };
+                let error = handler_receiver
+                    .tts()
+                    .say_and_wait(say, std::time::Duration::from_millis(100))
+                    .unwrap_err();
+                match error {
+                    hermes::HermesError::RequestTimeout { request_id, .. } => assert_eq!(request_id, "my id"),
+                    error => panic!("unexpected error {}", error),
+                }
+            }
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! test_suite {
     () => {
@@ -306,7 +649,7 @@ macro_rules! test_suite {
     };
 
     (WAIT_DURATION = $wait_duration:expr) => {
-        use $crate::{t, t_identifiable_component, t_identifiable_toggleable, t_component, t_toggleable};
+        use $crate::{t, t_identifiable_component, t_identifiable_toggleable, t_component, t_toggleable, t_unsubscribe, t_async, t_request};
         use snips_nlu_ontology::Slot;
 
         const WAIT_DURATION: std::time::Duration = std::time::Duration::from_millis($wait_duration);
@@ -375,7 +718,7 @@ macro_rules! test_suite {
         t_component!(nlu_component: nlu_backend | nlu);
         t!(nlu_query_works:
                     nlu_backend.subscribe_query <= NluQueryMessage | nlu.publish_query
-                    with NluQueryMessage { input: "hello world".into(), asr_tokens: Some(vec![AsrToken { value: "hello".into(), confidence: 1., range_start: 0, range_end: 4, time: AsrDecodingDuration { start: 0.0, end: 2.0 }}]), intent_filter: None, id: None, session_id: Some("abc".into()) };
+                    with NluQueryMessage { input: "hello world".into(), asr_tokens: Some(vec![AsrToken { value: "hello".into(), confidence: 1., range_start: 0, range_end: 4, time: AsrDecodingDuration { start: 0.0, end: 2.0 }}]), intent_filter: None, id: None, session_id: Some("abc".into()), intent_alternatives: Some(2) };
             );
         t!(nlu_partial_query_works:
                     nlu_backend.subscribe_partial_query <= NluSlotQueryMessage | nlu.publish_partial_query
@@ -387,7 +730,10 @@ macro_rules! test_suite {
             );
         t!(nlu_intent_parsed_works:
                     nlu.subscribe_intent_parsed <= NluIntentMessage | nlu_backend.publish_intent_parsed
-                    with NluIntentMessage { id: None, input: "hello world".into(), intent: NluIntentClassifierResult { intent_name: "my intent".into(), confidence_score: 0.73 }, slots: vec![], session_id: Some("abc".into()) };);
+                    with NluIntentMessage { id: None, input: "hello world".into(), intent: NluIntentClassifierResult { intent_name: "my intent".into(), confidence_score: 0.73 }, slots: vec![], session_id: Some("abc".into()), alternatives: None };);
+        t!(nlu_intent_parsed_with_alternatives_works:
+                    nlu.subscribe_intent_parsed <= NluIntentMessage | nlu_backend.publish_intent_parsed
+                    with NluIntentMessage { id: None, input: "hello world".into(), intent: NluIntentClassifierResult { intent_name: "my intent".into(), confidence_score: 0.73 }, slots: vec![], session_id: Some("abc".into()), alternatives: Some(vec![NluIntentAlternative { intent_name: Some("other intent".into()), confidence_score: 0.2, slots: vec![] }, NluIntentAlternative { intent_name: None, confidence_score: 0.07, slots: vec![] }]) };);
         t!(nlu_intent_not_recognized_works:
                     nlu.subscribe_intent_not_recognized <= NluIntentNotRecognizedMessage | nlu_backend.publish_intent_not_recognized
                     with NluIntentNotRecognizedMessage { id: None, input: "hello world".into(), session_id: Some("abc".into()), confidence_score: 0.5 };);
@@ -440,11 +786,11 @@ macro_rules! test_suite {
                     with SessionQueuedMessage { session_id: "some id".into(), custom_data: None, site_id: "some site".into() };);
         t!(dialogue_intents_works:
                     dialogue.subscribe_intents <= IntentMessage | dialogue_backend.publish_intent
-                    with IntentMessage { site_id: "some site".into(), session_id: "some id".into(), custom_data: None, input: "hello world".into(), asr_tokens: None, asr_confidence: None, intent: NluIntentClassifierResult { intent_name: "my intent".into(), confidence_score: 0.73 }, slots: vec![] };);
+                    with IntentMessage { site_id: "some site".into(), session_id: "some id".into(), custom_data: None, input: "hello world".into(), asr_tokens: None, asr_confidence: None, intent: NluIntentClassifierResult { intent_name: "my intent".into(), confidence_score: 0.73 }, slots: vec![], alternatives: None };);
         t!(dialogue_intent_works:
                     OneToMany
                     dialogue.subscribe_intent { "my intent".into() } <= IntentMessage | dialogue_backend.publish_intent
-                    with IntentMessage { site_id: "some site".into(), session_id: "some id".into(), custom_data: None, input: "hello world".into(), asr_tokens: Some(vec![vec![AsrToken { value: "hello".into(), confidence: 1., range_start: 0, range_end: 4, time: AsrDecodingDuration { start: 0.0, end: 2.0 } }, AsrToken { value: "world".into(), confidence: 1., range_start: 5, range_end: 9, time: AsrDecodingDuration { start: 2.0, end: 4.0 } },]]), asr_confidence: Some(0.5),intent: NluIntentClassifierResult { intent_name: "my intent".into(), confidence_score: 0.73 }, slots: vec![] };);
+                    with IntentMessage { site_id: "some site".into(), session_id: "some id".into(), custom_data: None, input: "hello world".into(), asr_tokens: Some(vec![vec![AsrToken { value: "hello".into(), confidence: 1., range_start: 0, range_end: 4, time: AsrDecodingDuration { start: 0.0, end: 2.0 } }, AsrToken { value: "world".into(), confidence: 1., range_start: 5, range_end: 9, time: AsrDecodingDuration { start: 2.0, end: 4.0 } },]]), asr_confidence: Some(0.5),intent: NluIntentClassifierResult { intent_name: "my intent".into(), confidence_score: 0.73 }, slots: vec![], alternatives: Some(vec![NluIntentAlternative { intent_name: Some("other intent".into()), confidence_score: 0.2, slots: vec![] }]) };);
         t!(dialogue_intent_not_recognized_works:
                     dialogue.subscribe_intent_not_recognized <= IntentNotRecognizedMessage | dialogue_backend.publish_intent_not_recognized
                     with IntentNotRecognizedMessage { site_id: "some site".into(), session_id: "some id".into(), custom_data: None, input: Some("hello world".into()), confidence_score: 0.5 };);
@@ -463,6 +809,11 @@ macro_rules! test_suite {
         t!(dialogue_configure_works:
                     dialogue_backend.subscribe_configure <= DialogueConfigureMessage | dialogue.publish_configure
                     with DialogueConfigureMessage { site_id: Some("some site".into()), intents: Some(vec![DialogueConfigureIntent { intent_id: "some intent".into(), enable: Some(true)}] )};);
+        t!(dialogue_session_state_request_works:
+                    dialogue_backend.subscribe_session_state_request <= dialogue.publish_session_state_request);
+        t!(dialogue_session_state_works:
+                    dialogue.subscribe_session_state <= SessionStateMessage | dialogue_backend.publish_session_state
+                    with SessionStateMessage { sites: vec![SiteSessionState { site_id: "some site".into(), active_session: Some(SessionState { session_id: "some id".into(), custom_data: Some("custom data".into()), intent_filter: Some(vec!["some intent".into()]), start_time: Some($crate::now()) }), queued_sessions: vec![SessionState { session_id: "other id".into(), custom_data: None, intent_filter: None, start_time: None }] }] };);
 
         t_component!(injection_component: injection_backend | injection);
         t!(injection_request:
@@ -473,5 +824,39 @@ macro_rules! test_suite {
         t!(injection_status:
                     injection.subscribe_injection_status <= InjectionStatusMessage | injection_backend.publish_injection_status
                     with InjectionStatusMessage { last_injection_date: Some($crate::now()) };);
+
+        t!(handshake_request_works:
+                    handshake.subscribe_handshake_request <= handshake.publish_handshake_request);
+        t!(handshake_works:
+                    handshake.subscribe_handshakes <= HandshakeMessage | handshake.publish_handshake
+                    with HandshakeMessage::new("snips-nlu", Some("some site".into())););
+
+        t!(registry_snapshot_request_works:
+                    registry_backend.subscribe_snapshot_request <= registry.publish_snapshot_request);
+        t!(registry_snapshot_works:
+                    registry.subscribe_snapshot <= RegistrySnapshotMessage | registry_backend.publish_snapshot
+                    with RegistrySnapshotMessage { components: vec![ComponentStatusMessage { component: HermesComponent::Hotword, site_id: Some("some site".into()), version: semver::Version::new(1, 2, 3), last_seen: $crate::now() }] };);
+        t!(registry_component_up_works:
+                    registry.subscribe_component_up <= ComponentStatusMessage | registry_backend.publish_component_up
+                    with ComponentStatusMessage { component: HermesComponent::Nlu, site_id: None, version: semver::Version::new(1, 2, 3), last_seen: $crate::now() };);
+        t!(registry_component_down_works:
+                    registry.subscribe_component_down <= ComponentStatusMessage | registry_backend.publish_component_down
+                    with ComponentStatusMessage { component: HermesComponent::AudioServer, site_id: Some("some site".into()), version: semver::Version::new(1, 2, 3), last_seen: $crate::now() };);
+
+        t!(site_register_works:
+                    site_backend.subscribe_register_site <= SiteInfoMessage | site.publish_register_site
+                    with SiteInfoMessage { site_id: "some site".into(), room_name: Some("kitchen".into()), language: Some("en".into()), capabilities: SiteCapabilities { has_speaker: true, has_microphone: true, has_screen: true }, audio_format: Some(AudioFormat { sample_rate: 44100, channels: 2, bits_per_sample: 16 }) };);
+        t!(site_unregister_works:
+                    site_backend.subscribe_unregister_site <= SiteMessage | site.publish_unregister_site
+                    with SiteMessage { site_id: "some site".into(), session_id: None };);
+        t!(site_sites_request_works:
+                    site_backend.subscribe_sites_request <= site.publish_sites_request);
+        t!(site_sites_works:
+                    site.subscribe_sites <= SitesMessage | site_backend.publish_sites
+                    with SitesMessage { sites: vec![SiteInfoMessage::new("some site")] };);
+
+        t_unsubscribe!(unsubscription);
+        t_async!(asynchronous);
+        t_request!(request);
     };
 }
@@ -0,0 +1,369 @@
+//! Scriptable fakes of the platform components, they plug into any `HermesProtocolHandler` and
+//! answer the requests made to them like the real components would, which allows to run whole
+//! conversations in unit tests.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use hermes::*;
+use regex::Regex;
+use snips_nlu_ontology::{Slot, SlotValue};
+
+fn lock<T>(mutex: &Mutex<T>) -> HermesResult<std::sync::MutexGuard<'_, T>> {
+    Ok(mutex.lock()?)
+}
+
+/// A fake ASR capturing scripted utterances. Each `AsrStartListeningMessage` is answered with the
+/// next queued utterance, nothing is captured when the queue is empty.
+pub struct MockAsr {
+    utterances: Arc<Mutex<VecDeque<String>>>,
+    _subscription: Subscription,
+}
+
+impl MockAsr {
+    pub fn new(handler: &HermesProtocolHandler) -> HermesResult<Self> {
+        let utterances = Arc::new(Mutex::new(VecDeque::<String>::new()));
+        let backend = handler.asr_backend();
+        let queue = Arc::clone(&utterances);
+        let subscription = handler.asr_backend().subscribe_start_listening(Callback::new(
+            move |message: &AsrStartListeningMessage| {
+                let text = match queue.lock().ok().and_then(|mut it| it.pop_front()) {
+                    Some(text) => text,
+                    None => return,
+                };
+                let _ = backend.publish_text_captured(TextCapturedMessage {
+                    text,
+                    likelihood: 1.,
+                    tokens: None,
+                    seconds: 0.,
+                    site_id: message.site_id.clone(),
+                    session_id: message.session_id.clone(),
+                });
+            },
+        ))?;
+        Ok(Self {
+            utterances,
+            _subscription: subscription,
+        })
+    }
+
+    /// Queue the text captured the next time the ASR is asked to listen
+    pub fn push_utterance<S: Into<String>>(&self, text: S) -> HermesResult<()> {
+        lock(&self.utterances)?.push_back(text.into());
+        Ok(())
+    }
+}
+
+/// A rule of the fake NLU, named groups of the pattern are returned as custom slots
+#[derive(Debug, Clone)]
+pub struct NluRule {
+    pub intent_name: String,
+    pub pattern: Regex,
+}
+
+impl NluRule {
+    pub fn regex(intent_name: &str, pattern: &str) -> HermesResult<Self> {
+        Ok(Self {
+            intent_name: intent_name.into(),
+            pattern: Regex::new(&format!("(?i){}", pattern))
+                .map_err(|e| HermesError::InvalidArgument(e.to_string()))?,
+        })
+    }
+
+    /// A rule matching inputs containing any of the given keywords
+    pub fn keywords(intent_name: &str, keywords: &[&str]) -> HermesResult<Self> {
+        let keywords: Vec<_> = keywords.iter().map(|it| regex::escape(it)).collect();
+        Self::regex(intent_name, &format!(r"\b({})\b", keywords.join("|")))
+    }
+
+    fn slots(&self, input: &str) -> Option<Vec<NluSlot>> {
+        let captures = self.pattern.captures(input)?;
+        Some(
+            self.pattern
+                .capture_names()
+                .flatten()
+                .filter_map(|name| captures.name(name).map(|it| (name, it)))
+                .map(|(name, it)| custom_slot(name, it.as_str(), it.start()))
+                .collect(),
+        )
+    }
+}
+
+fn custom_slot(slot_name: &str, raw_value: &str, start: usize) -> NluSlot {
+    NluSlot {
+        nlu_slot: Slot {
+            raw_value: raw_value.into(),
+            value: SlotValue::Custom(raw_value.into()),
+            range: start..start + raw_value.len(),
+            entity: slot_name.into(),
+            slot_name: slot_name.into(),
+            confidence_score: Some(1.),
+        },
+    }
+}
+
+/// The rules matching the input among the ones allowed by the filter, in order
+fn parse<'a>(
+    rules: &'a [NluRule],
+    input: &'a str,
+    intent_filter: Option<&'a [String]>,
+) -> impl Iterator<Item = (String, Vec<NluSlot>)> + 'a {
+    rules
+        .iter()
+        .filter(move |rule| intent_filter.map_or(true, |filter| filter.contains(&rule.intent_name)))
+        .filter_map(move |rule| rule.slots(input).map(|slots| (rule.intent_name.clone(), slots)))
+}
+
+/// Find a slot in the input, if no rule of the intent captures it the whole input is used
+fn parse_slot(rules: &[NluRule], input: &str, intent_name: &str, slot_name: &str) -> NluSlot {
+    rules
+        .iter()
+        .filter(|rule| rule.intent_name == intent_name)
+        .filter_map(|rule| rule.slots(input))
+        .flatten()
+        .find(|it| it.nlu_slot.slot_name == slot_name)
+        .unwrap_or_else(|| custom_slot(slot_name, input.trim(), input.find(input.trim()).unwrap_or(0)))
+}
+
+/// A rule based fake NLU, the rules are tried in order and the first matching one gives the intent
+pub struct MockNlu {
+    rules: Arc<Mutex<Vec<NluRule>>>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl MockNlu {
+    pub fn new(handler: &HermesProtocolHandler) -> HermesResult<Self> {
+        let rules = Arc::new(Mutex::new(Vec::<NluRule>::new()));
+
+        let backend = handler.nlu_backend();
+        let query_rules = Arc::clone(&rules);
+        let query = handler
+            .nlu_backend()
+            .subscribe_query(Callback::new(move |query: &NluQueryMessage| {
+                let rules = match query_rules.lock() {
+                    Ok(rules) => rules,
+                    Err(_) => return,
+                };
+                // the next matching rules are the alternatives
+                let mut results = parse(&rules, &query.input, query.intent_filter.as_deref());
+                let _ = match results.next() {
+                    Some((intent_name, slots)) => backend.publish_intent_parsed(NluIntentMessage {
+                        id: query.id.clone(),
+                        input: query.input.clone(),
+                        intent: NluIntentClassifierResult {
+                            intent_name,
+                            confidence_score: 1.,
+                        },
+                        slots,
+                        session_id: query.session_id.clone(),
+                        alternatives: query.intent_alternatives.map(|count| {
+                            results
+                                .take(count)
+                                .map(|(intent_name, slots)| NluIntentAlternative {
+                                    intent_name: Some(intent_name),
+                                    confidence_score: 1.,
+                                    slots,
+                                })
+                                .collect()
+                        }),
+                    }),
+                    None => backend.publish_intent_not_recognized(NluIntentNotRecognizedMessage {
+                        id: query.id.clone(),
+                        input: query.input.clone(),
+                        confidence_score: 1.,
+                        session_id: query.session_id.clone(),
+                    }),
+                };
+            }))?;
+
+        let backend = handler.nlu_backend();
+        let slot_rules = Arc::clone(&rules);
+        let partial_query =
+            handler
+                .nlu_backend()
+                .subscribe_partial_query(Callback::new(move |query: &NluSlotQueryMessage| {
+                    let slot = match slot_rules.lock() {
+                        Ok(rules) => parse_slot(&rules, &query.input, &query.intent_name, &query.slot_name),
+                        Err(_) => return,
+                    };
+                    let _ = backend.publish_slot_parsed(NluSlotMessage {
+                        id: query.id.clone(),
+                        input: query.input.clone(),
+                        intent_name: query.intent_name.clone(),
+                        slot: Some(slot),
+                        session_id: query.session_id.clone(),
+                    });
+                }))?;
+
+        Ok(Self {
+            rules,
+            _subscriptions: vec![query, partial_query],
+        })
+    }
+
+    pub fn add_rule(&self, rule: NluRule) -> HermesResult<()> {
+        lock(&self.rules)?.push(rule);
+        Ok(())
+    }
+}
+
+/// A fake TTS saying everything instantly, it keeps the messages it was asked to say
+pub struct MockTts {
+    said: Arc<Mutex<Vec<SayMessage>>>,
+    _subscription: Subscription,
+}
+
+impl MockTts {
+    pub fn new(handler: &HermesProtocolHandler) -> HermesResult<Self> {
+        let said = Arc::new(Mutex::new(vec![]));
+        let backend = handler.tts_backend();
+        let history = Arc::clone(&said);
+        let subscription = handler
+            .tts_backend()
+            .subscribe_say(Callback::new(move |message: &SayMessage| {
+                if let Ok(mut it) = history.lock() {
+                    it.push(message.clone())
+                }
+                let _ = backend.publish_say_finished(SayFinishedMessage {
+                    id: message.id.clone(),
+                    session_id: message.session_id.clone(),
+                });
+            }))?;
+        Ok(Self {
+            said,
+            _subscription: subscription,
+        })
+    }
+
+    /// The messages said so far
+    pub fn said(&self) -> HermesResult<Vec<SayMessage>> {
+        Ok(lock(&self.said)?.clone())
+    }
+}
+
+/// A fake audio server playing everything instantly, it keeps the messages it was asked to play
+pub struct MockAudioServer {
+    played: Arc<Mutex<Vec<PlayBytesMessage>>>,
+    _subscription: Subscription,
+}
+
+impl MockAudioServer {
+    pub fn new(handler: &HermesProtocolHandler) -> HermesResult<Self> {
+        let played = Arc::new(Mutex::new(vec![]));
+        let backend = handler.audio_server_backend();
+        let history = Arc::clone(&played);
+        let subscription = handler.audio_server_backend().subscribe_all_play_bytes(Callback::new(
+            move |message: &PlayBytesMessage| {
+                if let Ok(mut it) = history.lock() {
+                    it.push(message.clone())
+                }
+                let _ = backend.publish_play_finished(PlayFinishedMessage {
+                    id: message.id.clone(),
+                    site_id: message.site_id.clone(),
+                });
+            },
+        ))?;
+        Ok(Self {
+            played,
+            _subscription: subscription,
+        })
+    }
+
+    /// The messages played so far
+    pub fn played(&self) -> HermesResult<Vec<PlayBytesMessage>> {
+        Ok(lock(&self.played)?.clone())
+    }
+}
+
+/// A fake hotword detector, detections are triggered manually
+pub struct MockHotword {
+    backend: Box<HotwordBackendFacade>,
+}
+
+impl MockHotword {
+    pub fn new(handler: &HermesProtocolHandler) -> HermesResult<Self> {
+        Ok(Self {
+            backend: handler.hotword_backend(),
+        })
+    }
+
+    /// Publish a hotword detection on the given site
+    pub fn detect(&self, site_id: &str) -> HermesResult<()> {
+        self.backend.publish_detected(
+            site_id.into(),
+            HotwordDetectedMessage {
+                site_id: site_id.into(),
+                model_id: "default".into(),
+                model_version: None,
+                model_type: Some(HotwordModelType::Universal),
+                current_sensitivity: None,
+                detection_signal_ms: None,
+                end_signal_ms: None,
+            },
+        )
+    }
+}
+
+/// All the fake components at once
+pub struct MockComponents {
+    pub asr: MockAsr,
+    pub nlu: MockNlu,
+    pub tts: MockTts,
+    pub audio_server: MockAudioServer,
+    pub hotword: MockHotword,
+}
+
+impl MockComponents {
+    pub fn new(handler: &HermesProtocolHandler) -> HermesResult<Self> {
+        Ok(Self {
+            asr: MockAsr::new(handler)?,
+            nlu: MockNlu::new(handler)?,
+            tts: MockTts::new(handler)?,
+            audio_server: MockAudioServer::new(handler)?,
+            hotword: MockHotword::new(handler)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> Vec<NluRule> {
+        vec![
+            NluRule::regex("lights_on", r"turn on the lights(?: in the (?P<room>\w+))?").unwrap(),
+            NluRule::keywords("weather", &["weather", "rain"]).unwrap(),
+        ]
+    }
+
+    #[test]
+    fn regex_rule_captures_slots() {
+        let (intent, slots) = parse(&rules(), "Turn on the lights in the kitchen", None)
+            .next()
+            .unwrap();
+        assert_eq!(intent, "lights_on");
+        assert_eq!(slots.len(), 1);
+        assert_eq!(slots[0].nlu_slot.slot_name, "room");
+        assert_eq!(slots[0].nlu_slot.raw_value, "kitchen");
+        assert_eq!(slots[0].nlu_slot.range, 26..33);
+    }
+
+    #[test]
+    fn keywords_rule_matches_whole_words() {
+        assert_eq!(parse(&rules(), "will it rain today", None).next().unwrap().0, "weather");
+        assert!(parse(&rules(), "the weatherman", None).next().is_none());
+    }
+
+    #[test]
+    fn intent_filter_is_honored() {
+        let filter = vec!["weather".to_string()];
+        assert!(parse(&rules(), "turn on the lights", Some(&filter)).next().is_none());
+    }
+
+    #[test]
+    fn slot_falls_back_to_whole_input() {
+        let slot = parse_slot(&rules(), " bedroom", "lights_on", "room");
+        assert_eq!(slot.nlu_slot.raw_value, "bedroom");
+        assert_eq!(slot.nlu_slot.range, 1..8);
+    }
+}
@@ -0,0 +1,552 @@
+//! Async counterparts of the facades, usable from any futures 0.3 executor.
+//!
+//! Every facade gets an `Async*` extension trait that is implemented for all its implementors, so
+//! both the MQTT and the in-process protocol handlers get them for free. Subscriptions are exposed
+//! as `Stream`s and publications as `Future`s.
+
+use std::pin::Pin;
+
+use futures::channel::mpsc;
+use futures::future::{self, BoxFuture};
+use futures::stream::Stream;
+use futures::task::{Context, Poll};
+
+use crate::*;
+
+/// A future resolving once a message has been handed to the underlying transport
+pub type PublishFuture<'a> = BoxFuture<'a, HermesResult<()>>;
+
+/// A stream of the messages received on a subscription. The subscription is cancelled when the
+/// stream is dropped.
+pub struct SubscriptionStream<T> {
+    receiver: mpsc::UnboundedReceiver<T>,
+    _subscription: Subscription,
+}
+
+impl<T> Stream for SubscriptionStream<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<T>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+impl<T> std::fmt::Debug for SubscriptionStream<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("SubscriptionStream")
+            .field("subscription", &self._subscription)
+            .finish()
+    }
+}
+
+/// Turn a callback based subscription into a stream, `subscribe` is called once with the
+/// callback feeding the stream
+pub fn subscription_stream<T, F>(subscribe: F) -> HermesResult<SubscriptionStream<T>>
+where
+    T: Clone + Send + 'static,
+    F: FnOnce(Callback<T>) -> HermesResult<Subscription>,
+{
+    let (sender, receiver) = mpsc::unbounded();
+    // the send only fails when the stream has been dropped, in which case we are unsubscribing
+    let subscription = subscribe(Callback::new(move |message: &T| {
+        let _ = sender.unbounded_send(message.clone());
+    }))?;
+    Ok(SubscriptionStream {
+        receiver,
+        _subscription: subscription,
+    })
+}
+
+/// Same as `subscription_stream` for subscriptions whose messages carry no payload
+pub fn subscription_stream0<F>(subscribe: F) -> HermesResult<SubscriptionStream<()>>
+where
+    F: FnOnce(Callback0) -> HermesResult<Subscription>,
+{
+    let (sender, receiver) = mpsc::unbounded();
+    let subscription = subscribe(Callback0::new(move || {
+        let _ = sender.unbounded_send(());
+    }))?;
+    Ok(SubscriptionStream {
+        receiver,
+        _subscription: subscription,
+    })
+}
+
+macro_rules! async_facade {
+    (
+        $(#[$meta:meta])*
+        $async_facade:ident: $facade:ident {
+            streams { $($stream:ident<$t:ty>($($sa:ident: $sta:ty),*) = $subscribe:ident;)* }
+            unit_streams { $($stream0:ident($($s0a:ident: $s0ta:ty),*) = $subscribe0:ident;)* }
+            publishers { $($publish_async:ident($($pa:ident: $pta:ty),*) = $publish:ident;)* }
+        }
+    ) => {
+        $(#[$meta])*
+        pub trait $async_facade: $facade {
+            $(
+                fn $stream(&self, $($sa: $sta),*) -> HermesResult<SubscriptionStream<$t>> {
+                    subscription_stream(|handler| $facade::$subscribe(self, $($sa,)* handler))
+                }
+            )*
+            $(
+                fn $stream0(&self, $($s0a: $s0ta),*) -> HermesResult<SubscriptionStream<()>> {
+                    subscription_stream0(|handler| $facade::$subscribe0(self, $($s0a,)* handler))
+                }
+            )*
+            $(
+                /// The message is handed to the transport when the future is first polled
+                fn $publish_async(&self, $($pa: $pta),*) -> PublishFuture<'_> {
+                    Box::pin(future::lazy(move |_| $facade::$publish(self, $($pa),*)))
+                }
+            )*
+        }
+
+        impl<T: $facade + ?Sized> $async_facade for T {}
+    };
+}
+
+async_facade!(
+    /// Async counterpart of `ComponentFacade`
+    AsyncComponentFacade: ComponentFacade {
+        streams {
+            version_stream<VersionMessage>() = subscribe_version;
+            error_stream<ErrorMessage>() = subscribe_error;
+        }
+        unit_streams {}
+        publishers {
+            publish_version_request_async() = publish_version_request;
+        }
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `IdentifiableComponentFacade`
+    AsyncIdentifiableComponentFacade: IdentifiableComponentFacade {
+        streams {
+            version_stream<VersionMessage>(id: String) = subscribe_version;
+            error_stream<ErrorMessage>(id: String) = subscribe_error;
+        }
+        unit_streams {}
+        publishers {
+            publish_version_request_async(id: String) = publish_version_request;
+        }
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `ComponentBackendFacade`
+    AsyncComponentBackendFacade: ComponentBackendFacade {
+        streams {}
+        unit_streams {
+            version_request_stream() = subscribe_version_request;
+        }
+        publishers {
+            publish_version_async(version: VersionMessage) = publish_version;
+            publish_error_async(error: ErrorMessage) = publish_error;
+        }
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `IdentifiableComponentBackendFacade`
+    AsyncIdentifiableComponentBackendFacade: IdentifiableComponentBackendFacade {
+        streams {}
+        unit_streams {
+            version_request_stream(id: String) = subscribe_version_request;
+        }
+        publishers {
+            publish_version_async(id: String, version: VersionMessage) = publish_version;
+            publish_error_async(id: String, error: ErrorMessage) = publish_error;
+        }
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `ToggleableFacade`
+    AsyncToggleableFacade: ToggleableFacade {
+        streams {}
+        unit_streams {}
+        publishers {
+            publish_toggle_on_async() = publish_toggle_on;
+            publish_toggle_off_async() = publish_toggle_off;
+        }
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `ToggleableBackendFacade`
+    AsyncToggleableBackendFacade: ToggleableBackendFacade {
+        streams {}
+        unit_streams {
+            toggle_on_stream() = subscribe_toggle_on;
+            toggle_off_stream() = subscribe_toggle_off;
+        }
+        publishers {}
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `IdentifiableToggleableFacade`
+    AsyncIdentifiableToggleableFacade: IdentifiableToggleableFacade {
+        streams {}
+        unit_streams {}
+        publishers {
+            publish_toggle_on_async(site: SiteMessage) = publish_toggle_on;
+            publish_toggle_off_async(site: SiteMessage) = publish_toggle_off;
+        }
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `IdentifiableToggleableBackendFacade`
+    AsyncIdentifiableToggleableBackendFacade: IdentifiableToggleableBackendFacade {
+        streams {
+            toggle_on_stream<SiteMessage>() = subscribe_toggle_on;
+            toggle_off_stream<SiteMessage>() = subscribe_toggle_off;
+        }
+        unit_streams {}
+        publishers {}
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `VoiceActivityFacade`
+    AsyncVoiceActivityFacade: VoiceActivityFacade {
+        streams {
+            vad_up_stream<VadUpMessage>(site_id: String) = subscribe_vad_up;
+            vad_down_stream<VadDownMessage>(site_id: String) = subscribe_vad_down;
+            all_vad_up_stream<VadUpMessage>() = subscribe_all_vad_up;
+            all_vad_down_stream<VadDownMessage>() = subscribe_all_vad_down;
+        }
+        unit_streams {}
+        publishers {}
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `VoiceActivityBackendFacade`
+    AsyncVoiceActivityBackendFacade: VoiceActivityBackendFacade {
+        streams {}
+        unit_streams {}
+        publishers {
+            publish_vad_up_async(vad_up: VadUpMessage) = publish_vad_up;
+            publish_vad_down_async(vad_down: VadDownMessage) = publish_vad_down;
+        }
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `HotwordFacade`
+    AsyncHotwordFacade: HotwordFacade {
+        streams {
+            detected_stream<HotwordDetectedMessage>(site_id: String) = subscribe_detected;
+            all_detected_stream<HotwordDetectedMessage>() = subscribe_all_detected;
+        }
+        unit_streams {}
+        publishers {}
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `HotwordBackendFacade`
+    AsyncHotwordBackendFacade: HotwordBackendFacade {
+        streams {}
+        unit_streams {}
+        publishers {
+            publish_detected_async(site_id: String, message: HotwordDetectedMessage) = publish_detected;
+        }
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `AsrFacade`
+    AsyncAsrFacade: AsrFacade {
+        streams {
+            text_captured_stream<TextCapturedMessage>() = subscribe_text_captured;
+            partial_text_captured_stream<TextCapturedMessage>() = subscribe_partial_text_captured;
+        }
+        unit_streams {}
+        publishers {
+            publish_start_listening_async(start: AsrStartListeningMessage) = publish_start_listening;
+            publish_stop_listening_async(site: SiteMessage) = publish_stop_listening;
+            publish_reload_async() = publish_reload;
+        }
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `AsrBackendFacade`
+    AsyncAsrBackendFacade: AsrBackendFacade {
+        streams {
+            start_listening_stream<AsrStartListeningMessage>() = subscribe_start_listening;
+            stop_listening_stream<SiteMessage>() = subscribe_stop_listening;
+        }
+        unit_streams {
+            reload_stream() = subscribe_reload;
+        }
+        publishers {
+            publish_text_captured_async(text_captured: TextCapturedMessage) = publish_text_captured;
+            publish_partial_text_captured_async(text_captured: TextCapturedMessage) = publish_partial_text_captured;
+        }
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `TtsFacade`
+    AsyncTtsFacade: TtsFacade {
+        streams {
+            say_finished_stream<SayFinishedMessage>() = subscribe_say_finished;
+        }
+        unit_streams {}
+        publishers {
+            publish_say_async(to_say: SayMessage) = publish_say;
+            publish_register_sound_async(sound: RegisterSoundMessage) = publish_register_sound;
+        }
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `TtsBackendFacade`
+    AsyncTtsBackendFacade: TtsBackendFacade {
+        streams {
+            say_stream<SayMessage>() = subscribe_say;
+            register_sound_stream<RegisterSoundMessage>() = subscribe_register_sound;
+        }
+        unit_streams {}
+        publishers {
+            publish_say_finished_async(status: SayFinishedMessage) = publish_say_finished;
+        }
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `NluFacade`
+    AsyncNluFacade: NluFacade {
+        streams {
+            slot_parsed_stream<NluSlotMessage>() = subscribe_slot_parsed;
+            intent_parsed_stream<NluIntentMessage>() = subscribe_intent_parsed;
+            intent_not_recognized_stream<NluIntentNotRecognizedMessage>() = subscribe_intent_not_recognized;
+        }
+        unit_streams {}
+        publishers {
+            publish_query_async(query: NluQueryMessage) = publish_query;
+            publish_partial_query_async(query: NluSlotQueryMessage) = publish_partial_query;
+            publish_reload_async() = publish_reload;
+        }
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `NluBackendFacade`
+    AsyncNluBackendFacade: NluBackendFacade {
+        streams {
+            query_stream<NluQueryMessage>() = subscribe_query;
+            partial_query_stream<NluSlotQueryMessage>() = subscribe_partial_query;
+        }
+        unit_streams {
+            reload_stream() = subscribe_reload;
+        }
+        publishers {
+            publish_slot_parsed_async(slot: NluSlotMessage) = publish_slot_parsed;
+            publish_intent_parsed_async(intent: NluIntentMessage) = publish_intent_parsed;
+            publish_intent_not_recognized_async(status: NluIntentNotRecognizedMessage) = publish_intent_not_recognized;
+        }
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `AudioServerFacade`
+    AsyncAudioServerFacade: AudioServerFacade {
+        streams {
+            play_finished_stream<PlayFinishedMessage>(site_id: String) = subscribe_play_finished;
+            all_play_finished_stream<PlayFinishedMessage>() = subscribe_all_play_finished;
+            audio_frame_stream<AudioFrameMessage>(site_id: String) = subscribe_audio_frame;
+            replay_response_stream<AudioFrameMessage>(site_id: String) = subscribe_replay_response;
+        }
+        unit_streams {}
+        publishers {
+            publish_play_bytes_async(bytes: PlayBytesMessage) = publish_play_bytes;
+            publish_replay_request_async(request: ReplayRequestMessage) = publish_replay_request;
+        }
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `AudioServerBackendFacade`
+    AsyncAudioServerBackendFacade: AudioServerBackendFacade {
+        streams {
+            play_bytes_stream<PlayBytesMessage>(site_id: String) = subscribe_play_bytes;
+            all_play_bytes_stream<PlayBytesMessage>() = subscribe_all_play_bytes;
+            replay_request_stream<ReplayRequestMessage>(site_id: String) = subscribe_replay_request;
+        }
+        unit_streams {}
+        publishers {
+            publish_play_finished_async(status: PlayFinishedMessage) = publish_play_finished;
+            publish_audio_frame_async(frame: AudioFrameMessage) = publish_audio_frame;
+            publish_replay_response_async(frame: AudioFrameMessage) = publish_replay_response;
+        }
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `DialogueFacade`
+    AsyncDialogueFacade: DialogueFacade {
+        streams {
+            session_queued_stream<SessionQueuedMessage>() = subscribe_session_queued;
+            session_started_stream<SessionStartedMessage>() = subscribe_session_started;
+            intent_stream<IntentMessage>(intent_name: String) = subscribe_intent;
+            intents_stream<IntentMessage>() = subscribe_intents;
+            intent_not_recognized_stream<IntentNotRecognizedMessage>() = subscribe_intent_not_recognized;
+            session_ended_stream<SessionEndedMessage>() = subscribe_session_ended;
+            session_state_stream<SessionStateMessage>() = subscribe_session_state;
+        }
+        unit_streams {}
+        publishers {
+            publish_start_session_async(start_session: StartSessionMessage) = publish_start_session;
+            publish_continue_session_async(continue_session: ContinueSessionMessage) = publish_continue_session;
+            publish_end_session_async(end_session: EndSessionMessage) = publish_end_session;
+            publish_configure_async(config: DialogueConfigureMessage) = publish_configure;
+            publish_session_state_request_async() = publish_session_state_request;
+        }
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `DialogueBackendFacade`
+    AsyncDialogueBackendFacade: DialogueBackendFacade {
+        streams {
+            start_session_stream<StartSessionMessage>() = subscribe_start_session;
+            continue_session_stream<ContinueSessionMessage>() = subscribe_continue_session;
+            end_session_stream<EndSessionMessage>() = subscribe_end_session;
+            configure_stream<DialogueConfigureMessage>() = subscribe_configure;
+        }
+        unit_streams {
+            session_state_request_stream() = subscribe_session_state_request;
+        }
+        publishers {
+            publish_session_queued_async(status: SessionQueuedMessage) = publish_session_queued;
+            publish_session_started_async(status: SessionStartedMessage) = publish_session_started;
+            publish_intent_async(intent: IntentMessage) = publish_intent;
+            publish_intent_not_recognized_async(status: IntentNotRecognizedMessage) = publish_intent_not_recognized;
+            publish_session_ended_async(status: SessionEndedMessage) = publish_session_ended;
+            publish_session_state_async(state: SessionStateMessage) = publish_session_state;
+        }
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `InjectionFacade`
+    AsyncInjectionFacade: InjectionFacade {
+        streams {
+            injection_status_stream<InjectionStatusMessage>() = subscribe_injection_status;
+        }
+        unit_streams {}
+        publishers {
+            publish_injection_request_async(request: InjectionRequestMessage) = publish_injection_request;
+            publish_injection_status_request_async() = publish_injection_status_request;
+        }
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `InjectionBackendFacade`
+    AsyncInjectionBackendFacade: InjectionBackendFacade {
+        streams {
+            injection_request_stream<InjectionRequestMessage>() = subscribe_injection_request;
+        }
+        unit_streams {
+            injection_status_request_stream() = subscribe_injection_status_request;
+        }
+        publishers {
+            publish_injection_status_async(status: InjectionStatusMessage) = publish_injection_status;
+        }
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `ConnectionFacade`
+    AsyncConnectionFacade: ConnectionFacade {
+        streams {
+            state_changed_stream<ConnectionState>() = subscribe_state_changed;
+        }
+        unit_streams {}
+        publishers {}
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `HandshakeFacade`
+    AsyncHandshakeFacade: HandshakeFacade {
+        streams {
+            handshakes_stream<HandshakeMessage>() = subscribe_handshakes;
+        }
+        unit_streams {
+            handshake_request_stream() = subscribe_handshake_request;
+        }
+        publishers {
+            publish_handshake_request_async() = publish_handshake_request;
+            publish_handshake_async(handshake: HandshakeMessage) = publish_handshake;
+        }
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `RegistryFacade`
+    AsyncRegistryFacade: RegistryFacade {
+        streams {
+            snapshot_stream<RegistrySnapshotMessage>() = subscribe_snapshot;
+            component_up_stream<ComponentStatusMessage>() = subscribe_component_up;
+            component_down_stream<ComponentStatusMessage>() = subscribe_component_down;
+        }
+        unit_streams {}
+        publishers {
+            publish_snapshot_request_async() = publish_snapshot_request;
+        }
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `RegistryBackendFacade`
+    AsyncRegistryBackendFacade: RegistryBackendFacade {
+        streams {}
+        unit_streams {
+            snapshot_request_stream() = subscribe_snapshot_request;
+        }
+        publishers {
+            publish_snapshot_async(snapshot: RegistrySnapshotMessage) = publish_snapshot;
+            publish_component_up_async(status: ComponentStatusMessage) = publish_component_up;
+            publish_component_down_async(status: ComponentStatusMessage) = publish_component_down;
+        }
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `SiteFacade`
+    AsyncSiteFacade: SiteFacade {
+        streams {
+            sites_stream<SitesMessage>() = subscribe_sites;
+        }
+        unit_streams {}
+        publishers {
+            publish_register_site_async(site: SiteInfoMessage) = publish_register_site;
+            publish_unregister_site_async(site: SiteMessage) = publish_unregister_site;
+            publish_sites_request_async() = publish_sites_request;
+        }
+    }
+);
+
+async_facade!(
+    /// Async counterpart of `SiteBackendFacade`
+    AsyncSiteBackendFacade: SiteBackendFacade {
+        streams {
+            register_site_stream<SiteInfoMessage>() = subscribe_register_site;
+            unregister_site_stream<SiteMessage>() = subscribe_unregister_site;
+        }
+        unit_streams {
+            sites_request_stream() = subscribe_sites_request;
+        }
+        publishers {
+            publish_sites_async(sites: SitesMessage) = publish_sites;
+        }
+    }
+);
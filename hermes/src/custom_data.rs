@@ -0,0 +1,129 @@
+//! The typed custom data of the sessions, so that skills written in different languages can share
+//! structured session state.
+//!
+//! The `custom_data` of the dialogue messages is an opaque string, the typed data is stored in it
+//! as the JSON of an envelope, `{"version": 1, "data": ...}`. The version of the envelope is bumped
+//! when its layout changes, so that the data written by a newer skill is rejected instead of being
+//! misread.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::errors::{HermesError, HermesResult};
+use crate::ontology::*;
+
+/// The version of the envelope written by this crate
+pub const CUSTOM_DATA_VERSION: u32 = 1;
+
+#[derive(Debug, Deserialize, Serialize)]
+struct CustomDataEnvelope<T> {
+    version: u32,
+    data: T,
+}
+
+/// Wrap some data in an envelope, to be used as the custom data of a session
+pub fn encode_custom_data<T: Serialize>(data: &T) -> HermesResult<String> {
+    Ok(serde_json::to_string(&CustomDataEnvelope {
+        version: CUSTOM_DATA_VERSION,
+        data,
+    })?)
+}
+
+/// Unwrap the data of a custom data encoded with `encode_custom_data`, failing with a serialization
+/// error if it is not an envelope, if its version is not supported or if its data is not a `T`
+pub fn decode_custom_data<T: DeserializeOwned>(custom_data: &str) -> HermesResult<T> {
+    let envelope: CustomDataEnvelope<Value> = serde_json::from_str(custom_data)?;
+    if envelope.version > CUSTOM_DATA_VERSION {
+        return Err(HermesError::serialization(format!(
+            "Unsupported custom data version {}, the latest supported one is {}",
+            envelope.version, CUSTOM_DATA_VERSION
+        )));
+    }
+    Ok(serde_json::from_value(envelope.data)?)
+}
+
+macro_rules! impl_custom_data_as {
+    ($($message:ty),*) => {
+        $(
+            impl $message {
+                /// The custom data of the session decoded with `decode_custom_data`, if any
+                pub fn custom_data_as<T: DeserializeOwned>(&self) -> HermesResult<Option<T>> {
+                    self.custom_data.as_ref().map(|it| decode_custom_data(it)).transpose()
+                }
+            }
+        )*
+    };
+}
+
+impl_custom_data_as!(
+    IntentMessage,
+    IntentNotRecognizedMessage,
+    SessionStartedMessage,
+    SessionQueuedMessage,
+    SessionEndedMessage,
+    SessionState
+);
+
+impl StartSessionMessage {
+    /// Set the custom data of the session, encoded with `encode_custom_data`
+    pub fn set_custom_data<T: Serialize>(&mut self, data: &T) -> HermesResult<()> {
+        self.custom_data = Some(encode_custom_data(data)?);
+        Ok(())
+    }
+}
+
+impl ContinueSessionMessage {
+    /// Replace the custom data of the session, encoded with `encode_custom_data`
+    pub fn set_custom_data<T: Serialize>(&mut self, data: &T) -> HermesResult<()> {
+        self.custom_data = Some(encode_custom_data(data)?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Order {
+        pizzas: u32,
+        kind: Option<String>,
+    }
+
+    fn session_ended(custom_data: Option<&str>) -> SessionEndedMessage {
+        SessionEndedMessage {
+            session_id: "some session".into(),
+            custom_data: custom_data.map(str::to_string),
+            termination: SessionTerminationType::Nominal,
+            site_id: "kitchen".into(),
+        }
+    }
+
+    #[test]
+    fn custom_data_is_wrapped_in_a_versioned_envelope() {
+        let order = Order { pizzas: 2, kind: None };
+        let custom_data = encode_custom_data(&order).unwrap();
+        assert_eq!(custom_data, r#"{"version":1,"data":{"pizzas":2,"kind":null}}"#);
+        assert_eq!(decode_custom_data::<Order>(&custom_data).unwrap(), order);
+        assert_eq!(
+            session_ended(Some(&custom_data)).custom_data_as::<Order>().unwrap(),
+            Some(order)
+        );
+        assert_eq!(session_ended(None).custom_data_as::<Order>().unwrap(), None);
+    }
+
+    #[test]
+    fn invalid_custom_data_is_rejected() {
+        let decode = |custom_data| {
+            matches!(
+                decode_custom_data::<Order>(custom_data),
+                Err(HermesError::Serialization(_))
+            )
+        };
+        assert!(decode("some raw string"));
+        assert!(decode(r#"{"pizzas":2}"#));
+        assert!(decode(r#"{"version":1,"data":"margherita"}"#));
+        assert!(decode(r#"{"version":2,"data":{"pizzas":2}}"#));
+    }
+}
@@ -1,11 +1,83 @@
+use std::error::Error;
+use std::fmt;
 use std::sync::PoisonError;
+use std::time::Duration;
 
-#[derive(Debug, Fail)]
-#[fail(display = "Can't lock thread")]
-pub struct PoisonLock;
+use crate::validation::ValidationErrors;
 
-impl<T> From<PoisonError<T>> for PoisonLock {
+/// The errors returned by the facades
+#[derive(Debug)]
+pub enum HermesError {
+    /// The handler can't reach its transport, like an MQTT client whose connection is gone
+    NotConnected(String),
+    /// A message could not be serialized or deserialized
+    Serialization(Box<Error + Send + Sync>),
+    /// A thread panicked while holding a lock of the handler
+    PoisonedLock,
+    /// An argument is invalid, like a topic or a configuration value
+    InvalidArgument(String),
+    /// A message breaks the invariants of the ontology, see `Validate`
+    InvalidMessage(ValidationErrors),
+    /// No response to a request was received in time
+    RequestTimeout { request_id: String, timeout: Duration },
+    /// Any other error, coming from the transport or the environment
+    Other(Box<Error + Send + Sync>),
+}
+
+pub type HermesResult<T> = Result<T, HermesError>;
+
+impl HermesError {
+    /// Wrap any error that doesn't fall in one of the other kinds
+    pub fn other<E: Into<Box<Error + Send + Sync>>>(error: E) -> Self {
+        HermesError::Other(error.into())
+    }
+
+    /// Wrap an error raised while (de)serializing a message
+    pub fn serialization<E: Into<Box<Error + Send + Sync>>>(error: E) -> Self {
+        HermesError::Serialization(error.into())
+    }
+}
+
+impl fmt::Display for HermesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HermesError::NotConnected(reason) => write!(f, "Not connected: {}", reason),
+            HermesError::Serialization(e) => write!(f, "Could not (de)serialize message: {}", e),
+            HermesError::PoisonedLock => write!(f, "Can't lock thread"),
+            HermesError::InvalidArgument(reason) => write!(f, "Invalid argument: {}", reason),
+            HermesError::InvalidMessage(errors) => write!(f, "Invalid message: {}", errors),
+            HermesError::RequestTimeout { request_id, timeout } => {
+                write!(f, "No response to request {} after {:?}", request_id, timeout)
+            }
+            HermesError::Other(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for HermesError {
+    fn source(&self) -> Option<&(Error + 'static)> {
+        match self {
+            HermesError::Serialization(e) | HermesError::Other(e) => Some(&**e),
+            HermesError::InvalidMessage(errors) => Some(errors),
+            _ => None,
+        }
+    }
+}
+
+impl<T> From<PoisonError<T>> for HermesError {
     fn from(_: PoisonError<T>) -> Self {
-        Self {}
+        HermesError::PoisonedLock
+    }
+}
+
+impl From<serde_json::Error> for HermesError {
+    fn from(e: serde_json::Error) -> Self {
+        HermesError::serialization(e)
+    }
+}
+
+impl From<std::io::Error> for HermesError {
+    fn from(e: std::io::Error) -> Self {
+        HermesError::other(e)
     }
 }
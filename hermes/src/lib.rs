@@ -1,21 +1,35 @@
 extern crate base64;
 extern crate chrono;
-#[macro_use]
-extern crate failure;
+extern crate futures;
+#[cfg(feature = "json-schema")]
+extern crate schemars;
 extern crate semver;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 extern crate serde_json;
 extern crate snips_nlu_ontology;
+extern crate uuid;
 
+pub mod asynchronous;
+pub mod custom_data;
 pub mod errors;
 pub mod ontology;
-
+pub mod protocol;
+pub mod request;
+#[cfg(feature = "json-schema")]
+pub mod schema;
+pub mod slots;
+pub mod validation;
+
+pub use crate::asynchronous::*;
+pub use crate::custom_data::*;
 pub use crate::errors::*;
 pub use crate::ontology::*;
-
-use failure::Fallible;
+pub use crate::protocol::*;
+pub use crate::request::*;
+pub use crate::slots::*;
+pub use crate::validation::*;
 
 /// A struct wrapping a callback with one argument, create one with the `new` method
 pub struct Callback<T> {
@@ -57,58 +71,107 @@ impl Callback0 {
     }
 }
 
+/// A handle on a subscription made through one of the facades. The handler registered with the
+/// subscription will keep being called as long as this handle is alive, drop it or call
+/// `unsubscribe` to stop receiving messages.
+#[must_use = "dropping a Subscription immediately unsubscribes its handler, use `detach` to keep it"]
+pub struct Subscription {
+    unsubscriber: Option<Callback0>,
+}
+
+impl Subscription {
+    /// Create a new subscription handle, `unsubscriber` will be called exactly once when the
+    /// subscription is cancelled
+    pub fn new<F: 'static>(unsubscriber: F) -> Subscription
+    where
+        F: Fn() -> () + Send + Sync,
+    {
+        Subscription {
+            unsubscriber: Some(Callback0::new(unsubscriber)),
+        }
+    }
+
+    /// Stop receiving messages on this subscription, this is the same as dropping the handle
+    pub fn unsubscribe(self) {}
+
+    /// Keep the handler registered for the whole life of the protocol handler, the subscription
+    /// can't be cancelled afterwards
+    pub fn detach(mut self) {
+        if let Some(unsubscriber) = self.unsubscriber.take() {
+            // the unsubscriber may own resources whose drop would cancel the subscription
+            std::mem::forget(unsubscriber)
+        }
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Some(unsubscriber) = self.unsubscriber.take() {
+            unsubscriber.call()
+        }
+    }
+}
+
+impl std::fmt::Debug for Subscription {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Subscription")
+            .field("active", &self.unsubscriber.is_some())
+            .finish()
+    }
+}
+
 /// A generic facade used to interact with a component
 pub trait ComponentFacade: Send + Sync {
-    fn publish_version_request(&self) -> Fallible<()>;
-    fn subscribe_version(&self, handler: Callback<VersionMessage>) -> Fallible<()>;
-    fn subscribe_error(&self, handler: Callback<ErrorMessage>) -> Fallible<()>;
+    fn publish_version_request(&self) -> HermesResult<()>;
+    fn subscribe_version(&self, handler: Callback<VersionMessage>) -> HermesResult<Subscription>;
+    fn subscribe_error(&self, handler: Callback<ErrorMessage>) -> HermesResult<Subscription>;
 }
 
 /// A generic facade used to interact with a component
 pub trait IdentifiableComponentFacade: Send + Sync {
-    fn publish_version_request(&self, id: String) -> Fallible<()>;
-    fn subscribe_version(&self, id: String, handler: Callback<VersionMessage>) -> Fallible<()>;
-    fn subscribe_error(&self, id: String, handler: Callback<ErrorMessage>) -> Fallible<()>;
+    fn publish_version_request(&self, id: String) -> HermesResult<()>;
+    fn subscribe_version(&self, id: String, handler: Callback<VersionMessage>) -> HermesResult<Subscription>;
+    fn subscribe_error(&self, id: String, handler: Callback<ErrorMessage>) -> HermesResult<Subscription>;
 }
 
 /// A generic facade all components must use to publish their errors and versions (when requested)
 pub trait ComponentBackendFacade: Send + Sync {
-    fn subscribe_version_request(&self, handler: Callback0) -> Fallible<()>;
-    fn publish_version(&self, version: VersionMessage) -> Fallible<()>;
-    fn publish_error(&self, error: ErrorMessage) -> Fallible<()>;
+    fn subscribe_version_request(&self, handler: Callback0) -> HermesResult<Subscription>;
+    fn publish_version(&self, version: VersionMessage) -> HermesResult<()>;
+    fn publish_error(&self, error: ErrorMessage) -> HermesResult<()>;
 }
 
 /// A generic facade all components must use to publish their errors and versions (when requested)
 pub trait IdentifiableComponentBackendFacade: Send + Sync {
-    fn subscribe_version_request(&self, id: String, handler: Callback0) -> Fallible<()>;
-    fn publish_version(&self, id: String, version: VersionMessage) -> Fallible<()>;
-    fn publish_error(&self, id: String, error: ErrorMessage) -> Fallible<()>;
+    fn subscribe_version_request(&self, id: String, handler: Callback0) -> HermesResult<Subscription>;
+    fn publish_version(&self, id: String, version: VersionMessage) -> HermesResult<()>;
+    fn publish_error(&self, id: String, error: ErrorMessage) -> HermesResult<()>;
 }
 
 /// A facade to interact with a component that can be toggled on an off at a specific site
 pub trait ToggleableFacade: Send + Sync {
-    fn publish_toggle_on(&self) -> Fallible<()>;
-    fn publish_toggle_off(&self) -> Fallible<()>;
+    fn publish_toggle_on(&self) -> HermesResult<()>;
+    fn publish_toggle_off(&self) -> HermesResult<()>;
 }
 
 /// The facade a component that can be toggled on an off at a specific site must use to receive
 /// its orders
 pub trait ToggleableBackendFacade: Send + Sync {
-    fn subscribe_toggle_on(&self, handler: Callback0) -> Fallible<()>;
-    fn subscribe_toggle_off(&self, handler: Callback0) -> Fallible<()>;
+    fn subscribe_toggle_on(&self, handler: Callback0) -> HermesResult<Subscription>;
+    fn subscribe_toggle_off(&self, handler: Callback0) -> HermesResult<Subscription>;
 }
 
 /// A facade to interact with a component that can be toggled on an off at a specific site
 pub trait IdentifiableToggleableFacade: Send + Sync {
-    fn publish_toggle_on(&self, site: SiteMessage) -> Fallible<()>;
-    fn publish_toggle_off(&self, site: SiteMessage) -> Fallible<()>;
+    fn publish_toggle_on(&self, site: SiteMessage) -> HermesResult<()>;
+    fn publish_toggle_off(&self, site: SiteMessage) -> HermesResult<()>;
 }
 
 /// The facade a component that can be toggled on an off at a specific site must use to receive
 /// its orders
 pub trait IdentifiableToggleableBackendFacade: Send + Sync {
-    fn subscribe_toggle_on(&self, handler: Callback<SiteMessage>) -> Fallible<()>;
-    fn subscribe_toggle_off(&self, handler: Callback<SiteMessage>) -> Fallible<()>;
+    fn subscribe_toggle_on(&self, handler: Callback<SiteMessage>) -> HermesResult<Subscription>;
+    fn subscribe_toggle_off(&self, handler: Callback<SiteMessage>) -> HermesResult<Subscription>;
 }
 
 //
@@ -117,27 +180,31 @@ pub trait IdentifiableToggleableBackendFacade: Send + Sync {
 
 /// Facade used to interact with the voice activity component
 pub trait VoiceActivityFacade: IdentifiableComponentFacade {
-    fn subscribe_vad_up(&self, site_id: String, handler: Callback<VadUpMessage>) -> Fallible<()>;
-    fn subscribe_vad_down(&self, site_id: String, handler: Callback<VadDownMessage>) -> Fallible<()>;
-    fn subscribe_all_vad_up(&self, handler: Callback<VadUpMessage>) -> Fallible<()>;
-    fn subscribe_all_vad_down(&self, handler: Callback<VadDownMessage>) -> Fallible<()>;
+    fn subscribe_vad_up(&self, site_id: String, handler: Callback<VadUpMessage>) -> HermesResult<Subscription>;
+    fn subscribe_vad_down(&self, site_id: String, handler: Callback<VadDownMessage>) -> HermesResult<Subscription>;
+    fn subscribe_all_vad_up(&self, handler: Callback<VadUpMessage>) -> HermesResult<Subscription>;
+    fn subscribe_all_vad_down(&self, handler: Callback<VadDownMessage>) -> HermesResult<Subscription>;
 }
 
 /// Facade the voice activity component must use to publish its results
 pub trait VoiceActivityBackendFacade: IdentifiableComponentBackendFacade {
-    fn publish_vad_up(&self, vad_up: VadUpMessage) -> Fallible<()>;
-    fn publish_vad_down(&self, vad_down: VadDownMessage) -> Fallible<()>;
+    fn publish_vad_up(&self, vad_up: VadUpMessage) -> HermesResult<()>;
+    fn publish_vad_down(&self, vad_down: VadDownMessage) -> HermesResult<()>;
 }
 
 /// The facade to interact with the hotword component
 pub trait HotwordFacade: IdentifiableComponentFacade + IdentifiableToggleableFacade {
-    fn subscribe_detected(&self, site_id: String, handler: Callback<HotwordDetectedMessage>) -> Fallible<()>;
-    fn subscribe_all_detected(&self, handler: Callback<HotwordDetectedMessage>) -> Fallible<()>;
+    fn subscribe_detected(
+        &self,
+        site_id: String,
+        handler: Callback<HotwordDetectedMessage>,
+    ) -> HermesResult<Subscription>;
+    fn subscribe_all_detected(&self, handler: Callback<HotwordDetectedMessage>) -> HermesResult<Subscription>;
 }
 
 /// The facade the hotword feature must use receive its orders and publish detected hotwords
 pub trait HotwordBackendFacade: IdentifiableComponentBackendFacade + IdentifiableToggleableBackendFacade {
-    fn publish_detected(&self, site_id: String, message: HotwordDetectedMessage) -> Fallible<()>;
+    fn publish_detected(&self, site_id: String, message: HotwordDetectedMessage) -> HermesResult<()>;
 }
 
 /// The facade used to toggle on and of the sound feedback at a specific site
@@ -148,118 +215,230 @@ pub trait SoundFeedbackBackendFacade: IdentifiableToggleableBackendFacade {}
 
 /// The facade to interact with the automatic speech recognition component
 pub trait AsrFacade: ComponentFacade + ToggleableFacade {
-    fn publish_start_listening(&self, start: AsrStartListeningMessage) -> Fallible<()>;
-    fn publish_stop_listening(&self, site: SiteMessage) -> Fallible<()>;
-    fn publish_reload(&self) -> Fallible<()>;
-    fn subscribe_text_captured(&self, handler: Callback<TextCapturedMessage>) -> Fallible<()>;
-    fn subscribe_partial_text_captured(&self, handler: Callback<TextCapturedMessage>) -> Fallible<()>;
+    fn publish_start_listening(&self, start: AsrStartListeningMessage) -> HermesResult<()>;
+    fn publish_stop_listening(&self, site: SiteMessage) -> HermesResult<()>;
+    fn publish_reload(&self) -> HermesResult<()>;
+    fn subscribe_text_captured(&self, handler: Callback<TextCapturedMessage>) -> HermesResult<Subscription>;
+    fn subscribe_partial_text_captured(&self, handler: Callback<TextCapturedMessage>) -> HermesResult<Subscription>;
 }
 
 /// The facade the automatic speech recognition must use to receive its orders and publish
 /// recognized text
 pub trait AsrBackendFacade: ComponentBackendFacade + ToggleableBackendFacade {
-    fn subscribe_start_listening(&self, handler: Callback<AsrStartListeningMessage>) -> Fallible<()>;
-    fn subscribe_stop_listening(&self, handler: Callback<SiteMessage>) -> Fallible<()>;
-    fn subscribe_reload(&self, handler: Callback0) -> Fallible<()>;
-    fn publish_text_captured(&self, text_captured: TextCapturedMessage) -> Fallible<()>;
-    fn publish_partial_text_captured(&self, text_captured: TextCapturedMessage) -> Fallible<()>;
+    fn subscribe_start_listening(&self, handler: Callback<AsrStartListeningMessage>) -> HermesResult<Subscription>;
+    fn subscribe_stop_listening(&self, handler: Callback<SiteMessage>) -> HermesResult<Subscription>;
+    fn subscribe_reload(&self, handler: Callback0) -> HermesResult<Subscription>;
+    fn publish_text_captured(&self, text_captured: TextCapturedMessage) -> HermesResult<()>;
+    fn publish_partial_text_captured(&self, text_captured: TextCapturedMessage) -> HermesResult<()>;
 }
 
 /// The facade to interact with the text to speech component
 pub trait TtsFacade: ComponentFacade {
-    fn publish_say(&self, to_say: SayMessage) -> Fallible<()>;
-    fn subscribe_say_finished(&self, handler: Callback<SayFinishedMessage>) -> Fallible<()>;
-    fn publish_register_sound(&self, sound: RegisterSoundMessage) -> Fallible<()>;
+    fn publish_say(&self, to_say: SayMessage) -> HermesResult<()>;
+    fn subscribe_say_finished(&self, handler: Callback<SayFinishedMessage>) -> HermesResult<Subscription>;
+    fn publish_register_sound(&self, sound: RegisterSoundMessage) -> HermesResult<()>;
 }
 
 /// The facade the text to speech must use to receive its orders and advertise when it has finished
 pub trait TtsBackendFacade: ComponentBackendFacade {
-    fn publish_say_finished(&self, status: SayFinishedMessage) -> Fallible<()>;
-    fn subscribe_say(&self, handler: Callback<SayMessage>) -> Fallible<()>;
-    fn subscribe_register_sound(&self, handler: Callback<RegisterSoundMessage>) -> Fallible<()>;
+    fn publish_say_finished(&self, status: SayFinishedMessage) -> HermesResult<()>;
+    fn subscribe_say(&self, handler: Callback<SayMessage>) -> HermesResult<Subscription>;
+    fn subscribe_register_sound(&self, handler: Callback<RegisterSoundMessage>) -> HermesResult<Subscription>;
 }
 
 /// The facade to interact with the natural language understanding component
 pub trait NluFacade: ComponentFacade {
-    fn publish_query(&self, query: NluQueryMessage) -> Fallible<()>;
-    fn publish_partial_query(&self, query: NluSlotQueryMessage) -> Fallible<()>;
-    fn publish_reload(&self) -> Fallible<()>;
-    fn subscribe_slot_parsed(&self, handler: Callback<NluSlotMessage>) -> Fallible<()>;
-    fn subscribe_intent_parsed(&self, handler: Callback<NluIntentMessage>) -> Fallible<()>;
-    fn subscribe_intent_not_recognized(&self, handler: Callback<NluIntentNotRecognizedMessage>) -> Fallible<()>;
+    fn publish_query(&self, query: NluQueryMessage) -> HermesResult<()>;
+    fn publish_partial_query(&self, query: NluSlotQueryMessage) -> HermesResult<()>;
+    fn publish_reload(&self) -> HermesResult<()>;
+    fn subscribe_slot_parsed(&self, handler: Callback<NluSlotMessage>) -> HermesResult<Subscription>;
+    fn subscribe_intent_parsed(&self, handler: Callback<NluIntentMessage>) -> HermesResult<Subscription>;
+    fn subscribe_intent_not_recognized(
+        &self,
+        handler: Callback<NluIntentNotRecognizedMessage>,
+    ) -> HermesResult<Subscription>;
 }
 
 /// The facade the natural language understanding must use to receive its orders and publish
 /// its results
 pub trait NluBackendFacade: ComponentBackendFacade {
-    fn subscribe_query(&self, handler: Callback<NluQueryMessage>) -> Fallible<()>;
-    fn subscribe_partial_query(&self, handler: Callback<NluSlotQueryMessage>) -> Fallible<()>;
-    fn subscribe_reload(&self, handler: Callback0) -> Fallible<()>;
-    fn publish_slot_parsed(&self, slot: NluSlotMessage) -> Fallible<()>;
-    fn publish_intent_parsed(&self, intent: NluIntentMessage) -> Fallible<()>;
-    fn publish_intent_not_recognized(&self, status: NluIntentNotRecognizedMessage) -> Fallible<()>;
+    fn subscribe_query(&self, handler: Callback<NluQueryMessage>) -> HermesResult<Subscription>;
+    fn subscribe_partial_query(&self, handler: Callback<NluSlotQueryMessage>) -> HermesResult<Subscription>;
+    fn subscribe_reload(&self, handler: Callback0) -> HermesResult<Subscription>;
+    fn publish_slot_parsed(&self, slot: NluSlotMessage) -> HermesResult<()>;
+    fn publish_intent_parsed(&self, intent: NluIntentMessage) -> HermesResult<()>;
+    fn publish_intent_not_recognized(&self, status: NluIntentNotRecognizedMessage) -> HermesResult<()>;
 }
 
 /// The facade to interact with the audio server
 pub trait AudioServerFacade: IdentifiableComponentFacade + IdentifiableToggleableFacade {
-    fn publish_play_bytes(&self, bytes: PlayBytesMessage) -> Fallible<()>;
-    fn subscribe_play_finished(&self, site_id: String, handler: Callback<PlayFinishedMessage>) -> Fallible<()>;
-    fn subscribe_all_play_finished(&self, handler: Callback<PlayFinishedMessage>) -> Fallible<()>;
-    fn subscribe_audio_frame(&self, site_id: String, handler: Callback<AudioFrameMessage>) -> Fallible<()>;
-    fn publish_replay_request(&self, request: ReplayRequestMessage) -> Fallible<()>;
-    fn subscribe_replay_response(&self, site_id: String, handler: Callback<AudioFrameMessage>) -> Fallible<()>;
+    fn publish_play_bytes(&self, bytes: PlayBytesMessage) -> HermesResult<()>;
+    fn subscribe_play_finished(
+        &self,
+        site_id: String,
+        handler: Callback<PlayFinishedMessage>,
+    ) -> HermesResult<Subscription>;
+    fn subscribe_all_play_finished(&self, handler: Callback<PlayFinishedMessage>) -> HermesResult<Subscription>;
+    fn subscribe_audio_frame(
+        &self,
+        site_id: String,
+        handler: Callback<AudioFrameMessage>,
+    ) -> HermesResult<Subscription>;
+    fn publish_replay_request(&self, request: ReplayRequestMessage) -> HermesResult<()>;
+    fn subscribe_replay_response(
+        &self,
+        site_id: String,
+        handler: Callback<AudioFrameMessage>,
+    ) -> HermesResult<Subscription>;
 }
 
 /// The facade the audio server must use to receive its orders and advertise when it has finished
 pub trait AudioServerBackendFacade: IdentifiableComponentBackendFacade + IdentifiableToggleableBackendFacade {
-    fn subscribe_play_bytes(&self, site_id: String, handler: Callback<PlayBytesMessage>) -> Fallible<()>;
-    fn subscribe_all_play_bytes(&self, handler: Callback<PlayBytesMessage>) -> Fallible<()>;
-    fn publish_play_finished(&self, status: PlayFinishedMessage) -> Fallible<()>;
-    fn publish_audio_frame(&self, frame: AudioFrameMessage) -> Fallible<()>;
-    fn subscribe_replay_request(&self, site_id: String, handler: Callback<ReplayRequestMessage>) -> Fallible<()>;
-    fn publish_replay_response(&self, frame: AudioFrameMessage) -> Fallible<()>;
+    fn subscribe_play_bytes(&self, site_id: String, handler: Callback<PlayBytesMessage>) -> HermesResult<Subscription>;
+    fn subscribe_all_play_bytes(&self, handler: Callback<PlayBytesMessage>) -> HermesResult<Subscription>;
+    fn publish_play_finished(&self, status: PlayFinishedMessage) -> HermesResult<()>;
+    fn publish_audio_frame(&self, frame: AudioFrameMessage) -> HermesResult<()>;
+    fn subscribe_replay_request(
+        &self,
+        site_id: String,
+        handler: Callback<ReplayRequestMessage>,
+    ) -> HermesResult<Subscription>;
+    fn publish_replay_response(&self, frame: AudioFrameMessage) -> HermesResult<()>;
 }
 
 /// The facade to use to interact with the dialogue manager, this is the principal interface that a
 /// lambda should use
 pub trait DialogueFacade: ComponentFacade + ToggleableFacade {
-    fn subscribe_session_queued(&self, handler: Callback<SessionQueuedMessage>) -> Fallible<()>;
-    fn subscribe_session_started(&self, handler: Callback<SessionStartedMessage>) -> Fallible<()>;
-    fn subscribe_intent(&self, intent_name: String, handler: Callback<IntentMessage>) -> Fallible<()>;
-    fn subscribe_intents(&self, handler: Callback<IntentMessage>) -> Fallible<()>;
-    fn subscribe_intent_not_recognized(&self, handler: Callback<IntentNotRecognizedMessage>) -> Fallible<()>;
-    fn subscribe_session_ended(&self, handler: Callback<SessionEndedMessage>) -> Fallible<()>;
-    fn publish_start_session(&self, start_session: StartSessionMessage) -> Fallible<()>;
-    fn publish_continue_session(&self, continue_session: ContinueSessionMessage) -> Fallible<()>;
-    fn publish_end_session(&self, end_session: EndSessionMessage) -> Fallible<()>;
-    fn publish_configure(&self, config: DialogueConfigureMessage) -> Fallible<()>;
+    fn subscribe_session_queued(&self, handler: Callback<SessionQueuedMessage>) -> HermesResult<Subscription>;
+    fn subscribe_session_started(&self, handler: Callback<SessionStartedMessage>) -> HermesResult<Subscription>;
+    fn subscribe_intent(&self, intent_name: String, handler: Callback<IntentMessage>) -> HermesResult<Subscription>;
+    fn subscribe_intents(&self, handler: Callback<IntentMessage>) -> HermesResult<Subscription>;
+    fn subscribe_intent_not_recognized(
+        &self,
+        handler: Callback<IntentNotRecognizedMessage>,
+    ) -> HermesResult<Subscription>;
+    fn subscribe_session_ended(&self, handler: Callback<SessionEndedMessage>) -> HermesResult<Subscription>;
+    fn publish_start_session(&self, start_session: StartSessionMessage) -> HermesResult<()>;
+    fn publish_continue_session(&self, continue_session: ContinueSessionMessage) -> HermesResult<()>;
+    fn publish_end_session(&self, end_session: EndSessionMessage) -> HermesResult<()>;
+    fn publish_configure(&self, config: DialogueConfigureMessage) -> HermesResult<()>;
+    fn publish_session_state_request(&self) -> HermesResult<()>;
+    fn subscribe_session_state(&self, handler: Callback<SessionStateMessage>) -> HermesResult<Subscription>;
 }
 
 /// The facade the dialogue manager must use to interact with the lambdas
 pub trait DialogueBackendFacade: ComponentBackendFacade + ToggleableBackendFacade {
-    fn publish_session_queued(&self, status: SessionQueuedMessage) -> Fallible<()>;
-    fn publish_session_started(&self, status: SessionStartedMessage) -> Fallible<()>;
-    fn publish_intent(&self, intent: IntentMessage) -> Fallible<()>;
-    fn publish_intent_not_recognized(&self, intent_not_recognized: IntentNotRecognizedMessage) -> Fallible<()>;
-    fn publish_session_ended(&self, status: SessionEndedMessage) -> Fallible<()>;
-    fn subscribe_start_session(&self, handler: Callback<StartSessionMessage>) -> Fallible<()>;
-    fn subscribe_continue_session(&self, handler: Callback<ContinueSessionMessage>) -> Fallible<()>;
-    fn subscribe_end_session(&self, handler: Callback<EndSessionMessage>) -> Fallible<()>;
-    fn subscribe_configure(&self, handler: Callback<DialogueConfigureMessage>) -> Fallible<()>;
+    fn publish_session_queued(&self, status: SessionQueuedMessage) -> HermesResult<()>;
+    fn publish_session_started(&self, status: SessionStartedMessage) -> HermesResult<()>;
+    fn publish_intent(&self, intent: IntentMessage) -> HermesResult<()>;
+    fn publish_intent_not_recognized(&self, intent_not_recognized: IntentNotRecognizedMessage) -> HermesResult<()>;
+    fn publish_session_ended(&self, status: SessionEndedMessage) -> HermesResult<()>;
+    fn subscribe_start_session(&self, handler: Callback<StartSessionMessage>) -> HermesResult<Subscription>;
+    fn subscribe_continue_session(&self, handler: Callback<ContinueSessionMessage>) -> HermesResult<Subscription>;
+    fn subscribe_end_session(&self, handler: Callback<EndSessionMessage>) -> HermesResult<Subscription>;
+    fn subscribe_configure(&self, handler: Callback<DialogueConfigureMessage>) -> HermesResult<Subscription>;
+    fn subscribe_session_state_request(&self, handler: Callback0) -> HermesResult<Subscription>;
+    fn publish_session_state(&self, state: SessionStateMessage) -> HermesResult<()>;
+}
+
+/// Helpers publishing typed custom data on the dialogue facade, see `encode_custom_data`
+pub trait DialogueFacadeExt: DialogueFacade {
+    /// Start a session whose custom data is `data`
+    fn publish_start_session_with_data<T: serde::Serialize>(
+        &self,
+        mut start_session: StartSessionMessage,
+        data: &T,
+    ) -> HermesResult<()> {
+        start_session.set_custom_data(data)?;
+        self.publish_start_session(start_session)
+    }
+
+    /// Continue a session, replacing its custom data with `data`
+    fn publish_continue_session_with_data<T: serde::Serialize>(
+        &self,
+        mut continue_session: ContinueSessionMessage,
+        data: &T,
+    ) -> HermesResult<()> {
+        continue_session.set_custom_data(data)?;
+        self.publish_continue_session(continue_session)
+    }
 }
 
+impl<F: DialogueFacade + ?Sized> DialogueFacadeExt for F {}
+
 /// The facade to interact with the injection component
 pub trait InjectionFacade: ComponentFacade {
-    fn publish_injection_request(&self, request: InjectionRequestMessage) -> Fallible<()>;
-    fn publish_injection_status_request(&self) -> Fallible<()>;
-    fn subscribe_injection_status(&self, handler: Callback<InjectionStatusMessage>) -> Fallible<()>;
+    fn publish_injection_request(&self, request: InjectionRequestMessage) -> HermesResult<()>;
+    fn publish_injection_status_request(&self) -> HermesResult<()>;
+    fn subscribe_injection_status(&self, handler: Callback<InjectionStatusMessage>) -> HermesResult<Subscription>;
 }
 
 /// The facade the injecter must use to receive its orders and advertise when it has finished
 pub trait InjectionBackendFacade: ComponentBackendFacade {
-    fn subscribe_injection_request(&self, handler: Callback<InjectionRequestMessage>) -> Fallible<()>;
-    fn subscribe_injection_status_request(&self, handler: Callback0) -> Fallible<()>;
-    fn publish_injection_status(&self, status: InjectionStatusMessage) -> Fallible<()>;
+    fn subscribe_injection_request(&self, handler: Callback<InjectionRequestMessage>) -> HermesResult<Subscription>;
+    fn subscribe_injection_status_request(&self, handler: Callback0) -> HermesResult<Subscription>;
+    fn publish_injection_status(&self, status: InjectionStatusMessage) -> HermesResult<()>;
+}
+
+/// The state of the connection of a protocol handler to the bus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// Messages are exchanged with the bus
+    Connected,
+    /// The connection has been lost, or has not been established yet
+    Disconnected,
+    /// The connection has been lost and the handler is trying to restore it
+    Reconnecting,
+}
+
+/// The facade to follow the connection of the handler to the bus, the subscriptions are restored
+/// by the handler once it is connected again
+pub trait ConnectionFacade: Send + Sync {
+    fn state(&self) -> HermesResult<ConnectionState>;
+    fn subscribe_state_changed(&self, handler: Callback<ConnectionState>) -> HermesResult<Subscription>;
+}
+
+/// The facade the components use to advertise the versions of the protocol they use and support,
+/// see `CompatibilityChecker` to compare them
+pub trait HandshakeFacade: Send + Sync {
+    fn publish_handshake_request(&self) -> HermesResult<()>;
+    fn subscribe_handshake_request(&self, handler: Callback0) -> HermesResult<Subscription>;
+    fn publish_handshake(&self, handshake: HandshakeMessage) -> HermesResult<()>;
+    fn subscribe_handshakes(&self, handler: Callback<HandshakeMessage>) -> HermesResult<Subscription>;
+}
+
+/// The facade to follow the components alive on the bus, as tracked by the registry
+pub trait RegistryFacade: Send + Sync {
+    fn publish_snapshot_request(&self) -> HermesResult<()>;
+    fn subscribe_snapshot(&self, handler: Callback<RegistrySnapshotMessage>) -> HermesResult<Subscription>;
+    fn subscribe_component_up(&self, handler: Callback<ComponentStatusMessage>) -> HermesResult<Subscription>;
+    fn subscribe_component_down(&self, handler: Callback<ComponentStatusMessage>) -> HermesResult<Subscription>;
+}
+
+/// The facade the registry must use to advertise the components that appeared and disappeared
+pub trait RegistryBackendFacade: Send + Sync {
+    fn subscribe_snapshot_request(&self, handler: Callback0) -> HermesResult<Subscription>;
+    fn publish_snapshot(&self, snapshot: RegistrySnapshotMessage) -> HermesResult<()>;
+    fn publish_component_up(&self, status: ComponentStatusMessage) -> HermesResult<()>;
+    fn publish_component_down(&self, status: ComponentStatusMessage) -> HermesResult<()>;
+}
+
+/// The facade the satellites use to describe their site, so that the other components can pick the
+/// language and the features to use on each site
+pub trait SiteFacade: Send + Sync {
+    fn publish_register_site(&self, site: SiteInfoMessage) -> HermesResult<()>;
+    fn publish_unregister_site(&self, site: SiteMessage) -> HermesResult<()>;
+    fn publish_sites_request(&self) -> HermesResult<()>;
+    fn subscribe_sites(&self, handler: Callback<SitesMessage>) -> HermesResult<Subscription>;
+}
+
+/// The facade the site registry must use to keep the list of the sites, registering a site again
+/// updates it
+pub trait SiteBackendFacade: Send + Sync {
+    fn subscribe_register_site(&self, handler: Callback<SiteInfoMessage>) -> HermesResult<Subscription>;
+    fn subscribe_unregister_site(&self, handler: Callback<SiteMessage>) -> HermesResult<Subscription>;
+    fn subscribe_sites_request(&self, handler: Callback0) -> HermesResult<Subscription>;
+    fn publish_sites(&self, sites: SitesMessage) -> HermesResult<()>;
 }
 
 pub trait HermesProtocolHandler: Send + Sync + std::fmt::Display {
@@ -281,4 +460,10 @@ pub trait HermesProtocolHandler: Send + Sync + std::fmt::Display {
     fn audio_server_backend(&self) -> Box<AudioServerBackendFacade>;
     fn dialogue_backend(&self) -> Box<DialogueBackendFacade>;
     fn injection_backend(&self) -> Box<InjectionBackendFacade>;
+    fn connection(&self) -> Box<ConnectionFacade>;
+    fn handshake(&self) -> Box<HandshakeFacade>;
+    fn registry(&self) -> Box<RegistryFacade>;
+    fn registry_backend(&self) -> Box<RegistryBackendFacade>;
+    fn site(&self) -> Box<SiteFacade>;
+    fn site_backend(&self) -> Box<SiteBackendFacade>;
 }
@@ -1,6 +1,8 @@
 use super::HermesMessage;
+use crate::validation::{Validate, ValidationErrors, Validator};
 
 #[derive(Debug, Clone, Default, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct AsrStartListeningMessage {
     /// The site that must be listened too
@@ -13,14 +15,30 @@ pub struct AsrStartListeningMessage {
 
 impl<'de> HermesMessage<'de> for AsrStartListeningMessage {}
 
+impl Validate for AsrStartListeningMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new().not_empty("siteId", &self.site_id).finish()
+    }
+}
+
 #[derive(Debug, Clone, Default, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct AsrDecodingDuration {
     pub start: f32,
     pub end: f32,
 }
 
+impl Validate for AsrDecodingDuration {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new()
+            .check("end", self.start <= self.end, "must not be before start")
+            .finish()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct AsrToken {
     /// The value of the token
@@ -36,7 +54,22 @@ pub struct AsrToken {
     pub time: AsrDecodingDuration,
 }
 
+impl Validate for AsrToken {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new()
+            .unit_interval("confidence", self.confidence)
+            .check(
+                "rangeEnd",
+                self.range_start <= self.range_end,
+                "must not be before rangeStart",
+            )
+            .nested("time", &self.time)
+            .finish()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct TextCapturedMessage {
     /// The text captured
@@ -54,3 +87,14 @@ pub struct TextCapturedMessage {
 }
 
 impl<'de> HermesMessage<'de> for TextCapturedMessage {}
+
+impl Validate for TextCapturedMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new()
+            .unit_interval("likelihood", self.likelihood)
+            .nested("tokens", &self.tokens)
+            .check("seconds", self.seconds >= 0.0, "must not be negative")
+            .not_empty("siteId", &self.site_id)
+            .finish()
+    }
+}
@@ -1,6 +1,8 @@
 use super::HermesMessage;
+use crate::validation::{Validate, ValidationErrors, Validator};
 
 #[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct PlayBytesMessage {
     /// An id for the request, it will be passed back in the `PlayFinishedMessage`
@@ -9,6 +11,7 @@ pub struct PlayBytesMessage {
     /// Note that serde json serialization is provided but in practice most handler impl will want
     /// to avoid the base64 encoding/decoding and give this a special treatment
     #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
+    #[cfg_attr(feature = "json-schema", schemars(with = "crate::schema::Base64"))]
     pub wav_bytes: Vec<u8>,
     /// The site where the bytes should be played
     pub site_id: String,
@@ -16,13 +19,24 @@ pub struct PlayBytesMessage {
 
 impl<'de> HermesMessage<'de> for PlayBytesMessage {}
 
+impl Validate for PlayBytesMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new()
+            .not_empty("id", &self.id)
+            .not_empty("siteId", &self.site_id)
+            .finish()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct AudioFrameMessage {
     /// The bytes of the wav frame (should be a regular wav with header)
     /// Note that serde json serialization is provided but in practice most handler impl will want
     /// to avoid the base64 encoding/decoding and give this a special treatment
     #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
+    #[cfg_attr(feature = "json-schema", schemars(with = "crate::schema::Base64"))]
     pub wav_frame: Vec<u8>,
     /// The site this frame originates from
     pub site_id: String,
@@ -30,7 +44,14 @@ pub struct AudioFrameMessage {
 
 impl<'de> HermesMessage<'de> for AudioFrameMessage {}
 
+impl Validate for AudioFrameMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new().not_empty("siteId", &self.site_id).finish()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct ReplayRequestMessage {
     /// An id for the request, it will be passed back in the replayed frames headers.
@@ -43,7 +64,17 @@ pub struct ReplayRequestMessage {
 
 impl<'de> HermesMessage<'de> for ReplayRequestMessage {}
 
+impl Validate for ReplayRequestMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new()
+            .not_empty("requestId", &self.request_id)
+            .not_empty("siteId", &self.site_id)
+            .finish()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct PlayFinishedMessage {
     /// The id of the `PlayBytesMessage` which bytes finished playing
@@ -54,6 +85,15 @@ pub struct PlayFinishedMessage {
 
 impl<'de> HermesMessage<'de> for PlayFinishedMessage {}
 
+impl Validate for PlayFinishedMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new()
+            .not_empty("id", &self.id)
+            .not_empty("siteId", &self.site_id)
+            .finish()
+    }
+}
+
 fn as_base64<S>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error>
 where
     S: serde::Serializer,
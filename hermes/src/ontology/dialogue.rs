@@ -1,8 +1,11 @@
 use super::asr::AsrToken;
-use super::nlu::{NluIntentClassifierResult, NluSlot};
+use super::nlu::{check_alternatives, NluIntentAlternative, NluIntentClassifierResult, NluSlot};
 use super::HermesMessage;
+use crate::validation::{Validate, ValidationErrors, Validator};
+use chrono::prelude::*;
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct IntentMessage {
     /// The session in which this intent was detected
@@ -21,11 +24,29 @@ pub struct IntentMessage {
     pub intent: NluIntentClassifierResult,
     /// The detected slots, if any
     pub slots: Vec<NluSlot>,
+    /// The alternative resolutions of the input by decreasing confidence, if the dialogue manager
+    /// requested them
+    pub alternatives: Option<Vec<NluIntentAlternative>>,
 }
 
 impl<'de> HermesMessage<'de> for IntentMessage {}
 
+impl Validate for IntentMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut validator = Validator::new();
+        validator
+            .not_empty("sessionId", &self.session_id)
+            .not_empty("siteId", &self.site_id)
+            .nested("asrTokens", &self.asr_tokens)
+            .nested("intent", &self.intent)
+            .nested("slots", &self.slots);
+        check_alternatives(&mut validator, &self.alternatives);
+        validator.finish()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct IntentNotRecognizedMessage {
     /// The session in which no intent was recognized
@@ -42,7 +63,18 @@ pub struct IntentNotRecognizedMessage {
 
 impl<'de> HermesMessage<'de> for IntentNotRecognizedMessage {}
 
+impl Validate for IntentNotRecognizedMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new()
+            .not_empty("sessionId", &self.session_id)
+            .not_empty("siteId", &self.site_id)
+            .unit_interval("confidenceScore", self.confidence_score)
+            .finish()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(tag = "type", rename_all = "camelCase")]
 pub enum SessionInit {
     /// The session expects a response from the user. Users responses will
@@ -74,6 +106,7 @@ fn boolean_default_true() -> bool {
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct StartSessionMessage {
     /// The way this session was created
@@ -89,7 +122,29 @@ pub struct StartSessionMessage {
 
 impl<'de> HermesMessage<'de> for StartSessionMessage {}
 
+impl Validate for StartSessionMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut validator = Validator::new();
+        if let Some(site_id) = &self.site_id {
+            validator.not_empty("siteId", site_id);
+        }
+        if let SessionInit::Action {
+            intent_filter: Some(intent_filter),
+            ..
+        } = &self.init
+        {
+            validator.check(
+                "init.intentFilter",
+                intent_filter.iter().all(|it| !it.is_empty()),
+                "must not contain empty intent names",
+            );
+        }
+        validator.finish()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct SessionStartedMessage {
     /// The id of the session that was started
@@ -106,7 +161,17 @@ pub struct SessionStartedMessage {
 
 impl<'de> HermesMessage<'de> for SessionStartedMessage {}
 
+impl Validate for SessionStartedMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new()
+            .not_empty("sessionId", &self.session_id)
+            .not_empty("siteId", &self.site_id)
+            .finish()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct SessionQueuedMessage {
     /// The id of the session that was started
@@ -119,7 +184,17 @@ pub struct SessionQueuedMessage {
 
 impl<'de> HermesMessage<'de> for SessionQueuedMessage {}
 
+impl Validate for SessionQueuedMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new()
+            .not_empty("sessionId", &self.session_id)
+            .not_empty("siteId", &self.site_id)
+            .finish()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct ContinueSessionMessage {
     /// The id of the session this action applies to
@@ -148,7 +223,23 @@ pub struct ContinueSessionMessage {
 
 impl<'de> HermesMessage<'de> for ContinueSessionMessage {}
 
+impl Validate for ContinueSessionMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut validator = Validator::new();
+        validator.not_empty("sessionId", &self.session_id);
+        if self.slot.is_some() {
+            validator.check(
+                "intentFilter",
+                self.intent_filter.as_ref().map(Vec::len) == Some(1),
+                "must contain a single intent when a slot is requested",
+            );
+        }
+        validator.finish()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct EndSessionMessage {
     /// The id of the session to end
@@ -159,7 +250,14 @@ pub struct EndSessionMessage {
 
 impl<'de> HermesMessage<'de> for EndSessionMessage {}
 
+impl Validate for EndSessionMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new().not_empty("sessionId", &self.session_id).finish()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(tag = "reason", rename_all = "camelCase")]
 pub enum SessionTerminationType {
     /// The session ended as expected
@@ -177,6 +275,7 @@ pub enum SessionTerminationType {
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct SessionEndedMessage {
     /// The id of the session that was terminated
@@ -191,7 +290,17 @@ pub struct SessionEndedMessage {
 
 impl<'de> HermesMessage<'de> for SessionEndedMessage {}
 
+impl Validate for SessionEndedMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new()
+            .not_empty("sessionId", &self.session_id)
+            .not_empty("siteId", &self.site_id)
+            .finish()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct DialogueConfigureMessage {
     /// The site on which this configuration applies, if None the configuration will be applied to
@@ -203,7 +312,18 @@ pub struct DialogueConfigureMessage {
 
 impl<'de> HermesMessage<'de> for DialogueConfigureMessage {}
 
+impl Validate for DialogueConfigureMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut validator = Validator::new();
+        if let Some(site_id) = &self.site_id {
+            validator.not_empty("siteId", site_id);
+        }
+        validator.nested("intents", &self.intents).finish()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct DialogueConfigureIntent {
     /// The name of the intent that should be configured.
@@ -211,3 +331,67 @@ pub struct DialogueConfigureIntent {
     /// Whether this intent should be activated on not.
     pub enable: Option<bool>,
 }
+
+impl Validate for DialogueConfigureIntent {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new().not_empty("intentId", &self.intent_id).finish()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct SessionState {
+    /// The id of the session
+    pub session_id: String,
+    /// The custom data that was given at the session creation, or updated when continuing it
+    pub custom_data: Option<String>,
+    /// The list of intent names the parsing of the user response is currently restricted to, if any
+    pub intent_filter: Option<Vec<String>>,
+    /// When the session was started, `None` for the queued sessions
+    pub start_time: Option<DateTime<Utc>>,
+}
+
+impl Validate for SessionState {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new().not_empty("sessionId", &self.session_id).finish()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct SiteSessionState {
+    /// The site concerned
+    pub site_id: String,
+    /// The session running on the site, if any
+    pub active_session: Option<SessionState>,
+    /// The sessions waiting for the site to be free, in the order they will be started
+    pub queued_sessions: Vec<SessionState>,
+}
+
+impl Validate for SiteSessionState {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new()
+            .not_empty("siteId", &self.site_id)
+            .nested("activeSession", &self.active_session)
+            .nested("queuedSessions", &self.queued_sessions)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct SessionStateMessage {
+    /// The sites having an active or queued session
+    pub sites: Vec<SiteSessionState>,
+}
+
+impl<'de> HermesMessage<'de> for SessionStateMessage {}
+
+impl Validate for SessionStateMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new().nested("sites", &self.sites).finish()
+    }
+}
@@ -1,6 +1,8 @@
 use super::HermesMessage;
+use crate::validation::{Validate, ValidationErrors, Validator};
 
 #[derive(Debug, Clone, PartialEq, PartialOrd, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub enum HotwordModelType {
     Universal,
@@ -8,6 +10,7 @@ pub enum HotwordModelType {
 }
 
 #[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct HotwordDetectedMessage {
     /// The site where the hotword was triggered
@@ -28,3 +31,19 @@ pub struct HotwordDetectedMessage {
 }
 
 impl<'de> HermesMessage<'de> for HotwordDetectedMessage {}
+
+impl Validate for HotwordDetectedMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut validator = Validator::new();
+        validator
+            .not_empty("siteId", &self.site_id)
+            .not_empty("modelId", &self.model_id);
+        if let Some(sensitivity) = self.current_sensitivity {
+            validator.unit_interval("currentSensitivity", sensitivity);
+        }
+        if let (Some(detection), Some(end)) = (self.detection_signal_ms, self.end_signal_ms) {
+            validator.check("endSignalMs", detection <= end, "must not be before detectionSignalMs");
+        }
+        validator.finish()
+    }
+}
@@ -1,4 +1,5 @@
 use super::HermesMessage;
+use crate::validation::Validate;
 use chrono::prelude::*;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashMap;
@@ -8,6 +9,7 @@ type Entity = String;
 type Pronunciation = String;
 
 #[derive(Debug, Clone, PartialEq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub enum InjectionKind {
     /// Add to current assistant
@@ -52,7 +54,31 @@ impl Serialize for EntityValue {
     }
 }
 
+/// Entity values are serialized as a `[value, weight]` pair, the weight being optional when
+/// deserializing
+#[cfg(feature = "json-schema")]
+impl schemars::JsonSchema for EntityValue {
+    fn schema_name() -> String {
+        "EntityValue".into()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        use schemars::schema::{Schema, SchemaObject, SubschemaValidation};
+        Schema::Object(SchemaObject {
+            subschemas: Some(Box::new(SubschemaValidation {
+                any_of: Some(vec![
+                    gen.subschema_for::<(String, u32)>(),
+                    gen.subschema_for::<String>(),
+                ]),
+                ..Default::default()
+            })),
+            ..Default::default()
+        })
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct InjectionRequestMessage {
     /// List of operations to execute in the order of the list on a model
@@ -68,7 +94,10 @@ pub struct InjectionRequestMessage {
 
 impl<'de> HermesMessage<'de> for InjectionRequestMessage {}
 
+impl Validate for InjectionRequestMessage {}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct InjectionStatusMessage {
     /// Date of the latest injection
@@ -77,6 +106,8 @@ pub struct InjectionStatusMessage {
 
 impl<'de> HermesMessage<'de> for InjectionStatusMessage {}
 
+impl Validate for InjectionStatusMessage {}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -4,12 +4,16 @@ use semver;
 
 use serde::{Deserialize, Serialize};
 
+use crate::validation::{Validate, ValidationErrors, Validator};
+
 pub mod asr;
 pub mod audio_server;
 pub mod dialogue;
 pub mod hotword;
 pub mod injection;
 pub mod nlu;
+pub mod registry;
+pub mod site;
 pub mod tts;
 pub mod vad;
 
@@ -19,12 +23,15 @@ pub use self::dialogue::*;
 pub use self::hotword::*;
 pub use self::injection::*;
 pub use self::nlu::*;
+pub use self::registry::*;
+pub use self::site::*;
 pub use self::tts::*;
 pub use self::vad::*;
 
-pub trait HermesMessage<'de>: fmt::Debug + Deserialize<'de> + Serialize {}
+pub trait HermesMessage<'de>: fmt::Debug + Deserialize<'de> + Serialize + Validate {}
 
 #[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct SiteMessage {
     /// The site concerned
@@ -44,16 +51,62 @@ impl Default for SiteMessage {
 
 impl<'de> HermesMessage<'de> for SiteMessage {}
 
+impl Validate for SiteMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new().not_empty("siteId", &self.site_id).finish()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct VersionMessage {
     /// The version of the component
+    #[cfg_attr(feature = "json-schema", schemars(with = "String"))]
     pub version: semver::Version,
 }
 
 impl<'de> HermesMessage<'de> for VersionMessage {}
 
+impl Validate for VersionMessage {}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct HandshakeMessage {
+    /// The name of the component, like `snips-nlu`
+    pub component: String,
+    /// The site the component runs on, if it is bound to one
+    pub site_id: Option<String>,
+    /// The version of the protocol of the messages the component publishes
+    #[cfg_attr(feature = "json-schema", schemars(with = "String"))]
+    pub protocol_version: semver::Version,
+    /// The versions of the protocol of the messages the component can receive, like `^1.0`
+    #[cfg_attr(feature = "json-schema", schemars(with = "String"))]
+    pub supported_versions: semver::VersionReq,
+}
+
+impl<'de> HermesMessage<'de> for HandshakeMessage {}
+
+impl Validate for HandshakeMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut validator = Validator::new();
+        validator.not_empty("component", &self.component);
+        if let Some(site_id) = &self.site_id {
+            validator.not_empty("siteId", site_id);
+        }
+        validator
+            .check(
+                "supportedVersions",
+                self.supported_versions.matches(&self.protocol_version),
+                "must include the protocolVersion",
+            )
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct ErrorMessage {
     /// An optional session id if there is a related session
@@ -66,6 +119,12 @@ pub struct ErrorMessage {
 
 impl<'de> HermesMessage<'de> for ErrorMessage {}
 
+impl Validate for ErrorMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new().not_empty("error", &self.error).finish()
+    }
+}
+
 fn as_base64<S>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error>
 where
     S: serde::Serializer,
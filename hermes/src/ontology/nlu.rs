@@ -1,7 +1,9 @@
 use super::asr::AsrToken;
 use super::HermesMessage;
+use crate::validation::{Validate, ValidationErrors, Validator};
 
 #[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct NluQueryMessage {
     /// The text to run the NLU on
@@ -15,11 +17,20 @@ pub struct NluQueryMessage {
     pub id: Option<String>,
     /// An optional session id if there is a related session
     pub session_id: Option<String>,
+    /// The number of alternative resolutions to return along with the best one, none if not set
+    pub intent_alternatives: Option<usize>,
 }
 
 impl<'de> HermesMessage<'de> for NluQueryMessage {}
 
+impl Validate for NluQueryMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new().nested("asrTokens", &self.asr_tokens).finish()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct NluSlotQueryMessage {
     /// The text to run the slot detection on
@@ -39,7 +50,18 @@ pub struct NluSlotQueryMessage {
 
 impl<'de> HermesMessage<'de> for NluSlotQueryMessage {}
 
+impl Validate for NluSlotQueryMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new()
+            .nested("asrTokens", &self.asr_tokens)
+            .not_empty("intentName", &self.intent_name)
+            .not_empty("slotName", &self.slot_name)
+            .finish()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct NluSlotMessage {
     /// The id of the `NluSlotQueryMessage` that was processed
@@ -56,7 +78,17 @@ pub struct NluSlotMessage {
 
 impl<'de> HermesMessage<'de> for NluSlotMessage {}
 
+impl Validate for NluSlotMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new()
+            .not_empty("intentName", &self.intent_name)
+            .nested("slot", &self.slot)
+            .finish()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct NluIntentNotRecognizedMessage {
     /// The id of the `NluQueryMessage` that was processed
@@ -71,14 +103,40 @@ pub struct NluIntentNotRecognizedMessage {
 
 impl<'de> HermesMessage<'de> for NluIntentNotRecognizedMessage {}
 
+impl Validate for NluIntentNotRecognizedMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new()
+            .unit_interval("confidenceScore", self.confidence_score)
+            .finish()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct NluSlot {
     #[serde(flatten)]
+    #[cfg_attr(feature = "json-schema", schemars(with = "crate::schema::Slot"))]
     pub nlu_slot: snips_nlu_ontology::Slot,
 }
 
+impl Validate for NluSlot {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut validator = Validator::new();
+        validator.not_empty("slotName", &self.nlu_slot.slot_name).check(
+            "range",
+            self.nlu_slot.range.start <= self.nlu_slot.range.end,
+            "must not end before its start",
+        );
+        if let Some(confidence_score) = self.nlu_slot.confidence_score {
+            validator.unit_interval("confidenceScore", confidence_score);
+        }
+        validator.finish()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct NluIntentClassifierResult {
     /// Name of the intent that was found
@@ -87,7 +145,57 @@ pub struct NluIntentClassifierResult {
     pub confidence_score: f32,
 }
 
+impl Validate for NluIntentClassifierResult {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new()
+            .not_empty("intentName", &self.intent_name)
+            .unit_interval("confidenceScore", self.confidence_score)
+            .finish()
+    }
+}
+
+/// An alternative resolution of an input, less likely than the one it comes with
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct NluIntentAlternative {
+    /// Name of the intent, `None` for the resolution matching no intent
+    pub intent_name: Option<String>,
+    /// The confidence score
+    pub confidence_score: f32,
+    /// The slots detected for this intent, if any
+    pub slots: Vec<NluSlot>,
+}
+
+impl Validate for NluIntentAlternative {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut validator = Validator::new();
+        if let Some(intent_name) = &self.intent_name {
+            validator.not_empty("intentName", intent_name);
+        }
+        validator
+            .unit_interval("confidenceScore", self.confidence_score)
+            .nested("slots", &self.slots)
+            .finish()
+    }
+}
+
+/// Check that the alternatives of a resolution are ranked by decreasing confidence
+pub(crate) fn check_alternatives(validator: &mut Validator, alternatives: &Option<Vec<NluIntentAlternative>>) {
+    if let Some(alternatives) = alternatives {
+        validator.check(
+            "alternatives",
+            alternatives
+                .windows(2)
+                .all(|it| it[0].confidence_score >= it[1].confidence_score),
+            "must be sorted by decreasing confidence",
+        );
+    }
+    validator.nested("alternatives", alternatives);
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct NluIntentMessage {
     /// The id of the `NluQueryMessage` that was processed
@@ -100,6 +208,18 @@ pub struct NluIntentMessage {
     pub slots: Vec<NluSlot>,
     /// An optional session id if there is a related session
     pub session_id: Option<String>,
+    /// The alternative resolutions of the input by decreasing confidence, when requested in the
+    /// `NluQueryMessage`
+    pub alternatives: Option<Vec<NluIntentAlternative>>,
 }
 
 impl<'de> HermesMessage<'de> for NluIntentMessage {}
+
+impl Validate for NluIntentMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut validator = Validator::new();
+        validator.nested("intent", &self.intent).nested("slots", &self.slots);
+        check_alternatives(&mut validator, &self.alternatives);
+        validator.finish()
+    }
+}
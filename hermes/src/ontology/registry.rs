@@ -0,0 +1,87 @@
+use super::HermesMessage;
+use crate::validation::{Validate, ValidationErrors, Validator};
+use chrono::prelude::*;
+
+/// The components of the platform that answer version requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum HermesComponent {
+    VoiceActivity,
+    Hotword,
+    Asr,
+    Tts,
+    Nlu,
+    DialogueManager,
+    AudioServer,
+    Injection,
+}
+
+impl HermesComponent {
+    pub const ALL: [HermesComponent; 8] = [
+        HermesComponent::VoiceActivity,
+        HermesComponent::Hotword,
+        HermesComponent::Asr,
+        HermesComponent::Tts,
+        HermesComponent::Nlu,
+        HermesComponent::DialogueManager,
+        HermesComponent::AudioServer,
+        HermesComponent::Injection,
+    ];
+
+    /// Whether the component runs on each site, and is identified by the site it runs on
+    pub fn is_identifiable(self) -> bool {
+        matches!(
+            self,
+            HermesComponent::VoiceActivity | HermesComponent::Hotword | HermesComponent::AudioServer
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ComponentStatusMessage {
+    /// The component concerned
+    pub component: HermesComponent,
+    /// The site the component runs on, for the components running on each site
+    pub site_id: Option<String>,
+    /// The version of the component, as it answered the last version request
+    #[cfg_attr(feature = "json-schema", schemars(with = "String"))]
+    pub version: semver::Version,
+    /// The last time the component answered a version request
+    pub last_seen: DateTime<Utc>,
+}
+
+impl<'de> HermesMessage<'de> for ComponentStatusMessage {}
+
+impl Validate for ComponentStatusMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut validator = Validator::new();
+        match &self.site_id {
+            Some(site_id) => validator.not_empty("siteId", site_id),
+            None => validator.check(
+                "siteId",
+                !self.component.is_identifiable(),
+                "must be set for the components running on each site",
+            ),
+        };
+        validator.finish()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct RegistrySnapshotMessage {
+    /// The components currently alive
+    pub components: Vec<ComponentStatusMessage>,
+}
+
+impl<'de> HermesMessage<'de> for RegistrySnapshotMessage {}
+
+impl Validate for RegistrySnapshotMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new().nested("components", &self.components).finish()
+    }
+}
@@ -0,0 +1,120 @@
+use super::HermesMessage;
+use crate::validation::{Validate, ValidationErrors, Validator};
+
+/// The hardware available on a site
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase", default)]
+pub struct SiteCapabilities {
+    /// Whether the site can play sounds and speech
+    pub has_speaker: bool,
+    /// Whether the site can capture audio
+    pub has_microphone: bool,
+    /// Whether the site can display things
+    pub has_screen: bool,
+}
+
+impl Default for SiteCapabilities {
+    fn default() -> Self {
+        Self {
+            has_speaker: true,
+            has_microphone: true,
+            has_screen: false,
+        }
+    }
+}
+
+/// The format of the audio captured and played on a site
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct AudioFormat {
+    /// The sample rate, in Hz
+    pub sample_rate: u32,
+    /// The number of channels
+    pub channels: u16,
+    /// The number of bits of each sample
+    pub bits_per_sample: u16,
+}
+
+impl Default for AudioFormat {
+    fn default() -> Self {
+        Self {
+            sample_rate: 16000,
+            channels: 1,
+            bits_per_sample: 16,
+        }
+    }
+}
+
+impl Validate for AudioFormat {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new()
+            .check("sampleRate", self.sample_rate > 0, "must be positive")
+            .check("channels", self.channels > 0, "must be positive")
+            .check("bitsPerSample", self.bits_per_sample > 0, "must be positive")
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct SiteInfoMessage {
+    /// The site concerned
+    pub site_id: String,
+    /// The room the site is in, like `kitchen`
+    pub room_name: Option<String>,
+    /// The language the users speak on the site, like `en`
+    pub language: Option<String>,
+    /// The hardware available on the site
+    #[serde(default)]
+    pub capabilities: SiteCapabilities,
+    /// The format of the audio of the site, if it is not the default one
+    pub audio_format: Option<AudioFormat>,
+}
+
+impl SiteInfoMessage {
+    /// A site with the default capabilities and no other information
+    pub fn new<S: Into<String>>(site_id: S) -> Self {
+        Self {
+            site_id: site_id.into(),
+            room_name: None,
+            language: None,
+            capabilities: SiteCapabilities::default(),
+            audio_format: None,
+        }
+    }
+}
+
+impl<'de> HermesMessage<'de> for SiteInfoMessage {}
+
+impl Validate for SiteInfoMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut validator = Validator::new();
+        validator.not_empty("siteId", &self.site_id);
+        if let Some(room_name) = &self.room_name {
+            validator.not_empty("roomName", room_name);
+        }
+        if let Some(language) = &self.language {
+            validator.not_empty("language", language);
+        }
+        validator.nested("audioFormat", &self.audio_format).finish()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct SitesMessage {
+    /// The sites currently registered
+    pub sites: Vec<SiteInfoMessage>,
+}
+
+impl<'de> HermesMessage<'de> for SitesMessage {}
+
+impl Validate for SitesMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new().nested("sites", &self.sites).finish()
+    }
+}
@@ -1,6 +1,8 @@
 use super::HermesMessage;
+use crate::validation::{Validate, ValidationErrors, Validator};
 
 #[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct SayMessage {
     /// The text to say
@@ -17,7 +19,17 @@ pub struct SayMessage {
 
 impl<'de> HermesMessage<'de> for SayMessage {}
 
+impl Validate for SayMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new()
+            .not_empty("text", &self.text)
+            .not_empty("siteId", &self.site_id)
+            .finish()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct SayFinishedMessage {
     /// The id of the `SayMessage` which was has been said
@@ -28,14 +40,27 @@ pub struct SayFinishedMessage {
 
 impl<'de> HermesMessage<'de> for SayFinishedMessage {}
 
+impl Validate for SayFinishedMessage {}
+
 #[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct RegisterSoundMessage {
     /// The sound to register encoded as a wav.
     #[serde(serialize_with = "super::as_base64", deserialize_with = "super::from_base64")]
+    #[cfg_attr(feature = "json-schema", schemars(with = "crate::schema::Base64"))]
     pub wav_sound: Vec<u8>,
     /// The id this sound should be registered under
     pub sound_id: String,
 }
 
 impl<'de> HermesMessage<'de> for RegisterSoundMessage {}
+
+impl Validate for RegisterSoundMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new()
+            .check("wavSound", !self.wav_sound.is_empty(), "must not be empty")
+            .not_empty("soundId", &self.sound_id)
+            .finish()
+    }
+}
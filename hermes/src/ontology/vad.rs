@@ -1,6 +1,8 @@
 use super::HermesMessage;
+use crate::validation::{Validate, ValidationErrors, Validator};
 
 #[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct VadUpMessage {
     /// The site concerned
@@ -11,7 +13,14 @@ pub struct VadUpMessage {
 
 impl<'de> HermesMessage<'de> for VadUpMessage {}
 
+impl Validate for VadUpMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new().not_empty("siteId", &self.site_id).finish()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct VadDownMessage {
     /// The site concerned
@@ -21,3 +30,9 @@ pub struct VadDownMessage {
 }
 
 impl<'de> HermesMessage<'de> for VadDownMessage {}
+
+impl Validate for VadDownMessage {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Validator::new().not_empty("siteId", &self.site_id).finish()
+    }
+}
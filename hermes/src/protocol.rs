@@ -0,0 +1,167 @@
+//! The version of the protocol, that is of the topics and of the schema of the messages of the
+//! ontology, and the checks of its compatibility with the one of the other components.
+//!
+//! The minor version is bumped when messages or optional fields are added, as components using
+//! an older protocol ignore what they don't know of, and the major version when a field is removed,
+//! renamed or made mandatory. The components advertise the versions they use and support with a
+//! `HandshakeMessage`, so that the ones running on satellites can be upgraded one at a time.
+
+use std::cmp::Ordering;
+
+use semver::{Version, VersionReq};
+
+use crate::ontology::HandshakeMessage;
+
+/// The version of the protocol implemented by this crate
+pub const PROTOCOL_VERSION: &str = "1.0.0";
+
+/// The version of the protocol implemented by this crate
+pub fn protocol_version() -> Version {
+    Version::parse(PROTOCOL_VERSION).expect("PROTOCOL_VERSION is a valid version")
+}
+
+/// The versions of the protocol whose messages this crate can read: the ones with the same major
+/// version, whatever their minor version
+pub fn supported_protocol_versions() -> VersionReq {
+    VersionReq::parse(&format!("^{}", protocol_version().major)).expect("the caret requirement is valid")
+}
+
+impl HandshakeMessage {
+    /// The handshake of a component using the protocol implemented by this crate
+    pub fn new<S: Into<String>>(component: S, site_id: Option<String>) -> Self {
+        Self {
+            component: component.into(),
+            site_id,
+            protocol_version: protocol_version(),
+            supported_versions: supported_protocol_versions(),
+        }
+    }
+}
+
+/// How the messages of a component relate to the ones of this side
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compatibility {
+    /// Both sides use the same version of the protocol
+    Same,
+    /// The component uses an older version, the fields it doesn't know of are missing from its
+    /// messages
+    Older,
+    /// The component uses a newer version, the fields of its messages this side doesn't know of are
+    /// ignored
+    Newer,
+    /// The messages of one of the sides can't be read by the other
+    Incompatible,
+}
+
+impl Compatibility {
+    /// Whether the messages can be exchanged, maybe with some fields missing or ignored
+    pub fn is_compatible(self) -> bool {
+        self != Compatibility::Incompatible
+    }
+}
+
+/// Compares the protocol of this side with the one advertised by the other components
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompatibilityChecker {
+    protocol_version: Version,
+    supported_versions: VersionReq,
+}
+
+impl Default for CompatibilityChecker {
+    /// A checker for the protocol implemented by this crate
+    fn default() -> Self {
+        Self::new(protocol_version(), supported_protocol_versions())
+    }
+}
+
+impl CompatibilityChecker {
+    pub fn new(protocol_version: Version, supported_versions: VersionReq) -> Self {
+        Self {
+            protocol_version,
+            supported_versions,
+        }
+    }
+
+    pub fn check(&self, handshake: &HandshakeMessage) -> Compatibility {
+        if !self.supported_versions.matches(&handshake.protocol_version)
+            || !handshake.supported_versions.matches(&self.protocol_version)
+        {
+            return Compatibility::Incompatible;
+        }
+        // the patch versions don't change the schema of the messages
+        let schema = |version: &Version| (version.major, version.minor);
+        match schema(&handshake.protocol_version).cmp(&schema(&self.protocol_version)) {
+            Ordering::Less => Compatibility::Older,
+            Ordering::Equal => Compatibility::Same,
+            Ordering::Greater => Compatibility::Newer,
+        }
+    }
+
+    /// A description of what to expect from the messages of a component, if they are not the same
+    /// as the ones of this side
+    pub fn warning(&self, handshake: &HandshakeMessage) -> Option<String> {
+        let component = match &handshake.site_id {
+            Some(site_id) => format!("{} on site {}", handshake.component, site_id),
+            None => handshake.component.clone(),
+        };
+        let (version, local_version) = (&handshake.protocol_version, &self.protocol_version);
+        match self.check(handshake) {
+            Compatibility::Same => None,
+            Compatibility::Older => Some(format!(
+                "{} uses the protocol {}, older than {}: the fields added since are missing from its messages",
+                component, version, local_version
+            )),
+            Compatibility::Newer => Some(format!(
+                "{} uses the protocol {}, newer than {}: the fields added since are ignored",
+                component, version, local_version
+            )),
+            Compatibility::Incompatible => Some(format!(
+                "{} uses the protocol {} and supports {}, incompatible with the protocol {} supporting {}",
+                component, version, handshake.supported_versions, local_version, self.supported_versions
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handshake(protocol_version: &str, supported_versions: &str) -> HandshakeMessage {
+        HandshakeMessage {
+            component: "snips-nlu".into(),
+            site_id: None,
+            protocol_version: Version::parse(protocol_version).unwrap(),
+            supported_versions: VersionReq::parse(supported_versions).unwrap(),
+        }
+    }
+
+    #[test]
+    fn protocol_version_is_valid() {
+        let handshake = HandshakeMessage::new("snips-nlu", None);
+        assert_eq!(handshake.protocol_version.to_string(), PROTOCOL_VERSION);
+        assert!(handshake.supported_versions.matches(&handshake.protocol_version));
+    }
+
+    #[test]
+    fn minor_versions_are_compatible() {
+        let checker = CompatibilityChecker::new(Version::new(1, 2, 0), VersionReq::parse("^1").unwrap());
+        assert_eq!(checker.check(&handshake("1.2.3", "^1")), Compatibility::Same);
+        assert_eq!(checker.check(&handshake("1.1.0", "^1")), Compatibility::Older);
+        assert_eq!(checker.check(&handshake("1.3.0", "^1")), Compatibility::Newer);
+        assert!(checker.warning(&handshake("1.2.0", "^1")).is_none());
+        assert_eq!(
+            checker.warning(&handshake("1.3.0", "^1")).unwrap(),
+            "snips-nlu uses the protocol 1.3.0, newer than 1.2.0: the fields added since are ignored"
+        );
+    }
+
+    #[test]
+    fn both_sides_must_support_the_version_of_the_other() {
+        let checker = CompatibilityChecker::new(Version::new(1, 2, 0), VersionReq::parse("^1").unwrap());
+        assert_eq!(checker.check(&handshake("2.0.0", "^2")), Compatibility::Incompatible);
+        assert_eq!(checker.check(&handshake("1.3.0", ">=1.3")), Compatibility::Incompatible);
+        assert_eq!(checker.check(&handshake("2.0.0", ">=1")), Compatibility::Incompatible);
+        assert!(!Compatibility::Incompatible.is_compatible());
+    }
+}
@@ -0,0 +1,122 @@
+//! Request/response helpers built on top of the facades.
+//!
+//! They take care of giving an id to the request, waiting for the reply carrying the same id and
+//! cancelling the subscriptions made to receive it. A `HermesError::RequestTimeout` error is
+//! returned when no reply arrived in time.
+
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+
+use crate::*;
+
+/// The outcome of a query made to the natural language understanding component
+#[derive(Debug, Clone, PartialEq)]
+pub enum NluResult {
+    IntentParsed(NluIntentMessage),
+    IntentNotRecognized(NluIntentNotRecognizedMessage),
+}
+
+/// Generate a new unique id for a request
+pub fn new_request_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+fn request_id(id: &mut Option<String>) -> String {
+    id.get_or_insert_with(new_request_id).clone()
+}
+
+/// A request waiting for the first reply carrying its id
+struct PendingRequest<T> {
+    request_id: String,
+    rx: mpsc::Receiver<T>,
+    tx: Mutex<mpsc::Sender<T>>,
+}
+
+impl<T: Send + 'static> PendingRequest<T> {
+    fn new(request_id: String) -> Self {
+        let (tx, rx) = mpsc::channel();
+        Self {
+            request_id,
+            rx,
+            tx: Mutex::new(tx),
+        }
+    }
+
+    /// A callback forwarding the messages carrying the id of this request, mapped with `f`
+    fn callback<M, F>(&self, id: F, f: fn(M) -> T) -> HermesResult<Callback<M>>
+    where
+        M: Clone + 'static,
+        F: Fn(&M) -> Option<&str> + Send + Sync + 'static,
+    {
+        let tx = self.tx.lock()?.clone();
+        let tx = Mutex::new(tx);
+        let request_id = self.request_id.clone();
+        Ok(Callback::new(move |message: &M| {
+            if id(message) == Some(&request_id) {
+                // the receiver is gone once the request is over, nothing to do
+                let _ = tx.lock().map(|tx| tx.send(f(message.clone())));
+            }
+        }))
+    }
+
+    fn wait(self, timeout: Duration) -> HermesResult<T> {
+        let request_id = self.request_id;
+        self.rx
+            .recv_timeout(timeout)
+            .map_err(|_| HermesError::RequestTimeout { request_id, timeout })
+    }
+}
+
+/// Request/response helpers for the natural language understanding component
+pub trait NluRequestFacade: NluFacade {
+    /// Send a query and wait for its result, an id is generated if the query has none
+    fn query(&self, mut query: NluQueryMessage, timeout: Duration) -> HermesResult<NluResult> {
+        let request = PendingRequest::new(request_id(&mut query.id));
+        let _parsed = self.subscribe_intent_parsed(
+            request.callback(|it: &NluIntentMessage| it.id.as_deref(), NluResult::IntentParsed)?,
+        )?;
+        let _not_recognized = self.subscribe_intent_not_recognized(request.callback(
+            |it: &NluIntentNotRecognizedMessage| it.id.as_deref(),
+            NluResult::IntentNotRecognized,
+        )?)?;
+        self.publish_query(query)?;
+        request.wait(timeout)
+    }
+}
+
+impl<T: NluFacade + ?Sized> NluRequestFacade for T {}
+
+/// Request/response helpers for the text to speech component
+pub trait TtsRequestFacade: TtsFacade {
+    /// Say something and wait for the text to speech to be done with it, an id is generated if
+    /// the message has none
+    fn say_and_wait(&self, mut to_say: SayMessage, timeout: Duration) -> HermesResult<SayFinishedMessage> {
+        let request = PendingRequest::new(request_id(&mut to_say.id));
+        let _finished =
+            self.subscribe_say_finished(request.callback(|it: &SayFinishedMessage| it.id.as_deref(), |it| it)?)?;
+        self.publish_say(to_say)?;
+        request.wait(timeout)
+    }
+}
+
+impl<T: TtsFacade + ?Sized> TtsRequestFacade for T {}
+
+/// Request/response helpers for the audio server
+pub trait AudioServerRequestFacade: AudioServerFacade {
+    /// Play some sound and wait for the audio server to be done with it, an id is generated if
+    /// the message has an empty one
+    fn play_and_wait(&self, mut bytes: PlayBytesMessage, timeout: Duration) -> HermesResult<PlayFinishedMessage> {
+        if bytes.id.is_empty() {
+            bytes.id = new_request_id();
+        }
+        let request = PendingRequest::new(bytes.id.clone());
+        let _finished = self.subscribe_play_finished(
+            bytes.site_id.clone(),
+            request.callback(|it: &PlayFinishedMessage| Some(it.id.as_str()), |it| it)?,
+        )?;
+        self.publish_play_bytes(bytes)?;
+        request.wait(timeout)
+    }
+}
+
+impl<T: AudioServerFacade + ?Sized> AudioServerRequestFacade for T {}
@@ -0,0 +1,141 @@
+//! Support for the JSON schemas of the ontology, for the types that don't derive them.
+//!
+//! The types here mirror the serialization of the snips NLU ontology, they are only used to
+//! describe the messages embedding its slots.
+
+use std::ops::Range;
+
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Schema, SchemaObject};
+use schemars::JsonSchema;
+
+/// Binary data, serialized as a base64 string
+pub struct Base64;
+
+impl JsonSchema for Base64 {
+    fn schema_name() -> String {
+        "Base64".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        let mut schema = SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            ..Default::default()
+        };
+        schema.extensions.insert("contentEncoding".into(), "base64".into());
+        schema.into()
+    }
+
+    fn is_referenceable() -> bool {
+        false
+    }
+}
+
+#[derive(JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Slot {
+    pub raw_value: String,
+    pub value: SlotValue,
+    pub range: Range<usize>,
+    pub entity: String,
+    pub slot_name: String,
+    pub confidence_score: Option<f32>,
+}
+
+#[derive(JsonSchema)]
+#[serde(tag = "kind")]
+pub enum SlotValue {
+    Custom(StringValue),
+    Number(NumberValue),
+    Ordinal(OrdinalValue),
+    Percentage(PercentageValue),
+    InstantTime(InstantTimeValue),
+    TimeInterval(TimeIntervalValue),
+    AmountOfMoney(AmountOfMoneyValue),
+    Temperature(TemperatureValue),
+    Duration(DurationValue),
+    MusicAlbum(StringValue),
+    MusicArtist(StringValue),
+    MusicTrack(StringValue),
+    City(StringValue),
+    Country(StringValue),
+    Region(StringValue),
+}
+
+#[derive(JsonSchema)]
+pub struct StringValue {
+    pub value: String,
+}
+
+#[derive(JsonSchema)]
+pub struct NumberValue {
+    pub value: f64,
+}
+
+#[derive(JsonSchema)]
+pub struct OrdinalValue {
+    pub value: i64,
+}
+
+#[derive(JsonSchema)]
+pub struct PercentageValue {
+    pub value: f64,
+}
+
+#[derive(JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct InstantTimeValue {
+    pub value: String,
+    pub grain: Grain,
+    pub precision: Precision,
+}
+
+#[derive(JsonSchema)]
+pub struct TimeIntervalValue {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[derive(JsonSchema)]
+pub struct AmountOfMoneyValue {
+    pub value: f32,
+    pub precision: Precision,
+    pub unit: Option<String>,
+}
+
+#[derive(JsonSchema)]
+pub struct TemperatureValue {
+    pub value: f32,
+    pub unit: Option<String>,
+}
+
+#[derive(JsonSchema)]
+pub struct DurationValue {
+    pub years: i64,
+    pub quarters: i64,
+    pub months: i64,
+    pub weeks: i64,
+    pub days: i64,
+    pub hours: i64,
+    pub minutes: i64,
+    pub seconds: i64,
+    pub precision: Precision,
+}
+
+#[derive(JsonSchema)]
+pub enum Grain {
+    Year,
+    Quarter,
+    Month,
+    Week,
+    Day,
+    Hour,
+    Minute,
+    Second,
+}
+
+#[derive(JsonSchema)]
+pub enum Precision {
+    Approximate,
+    Exact,
+}
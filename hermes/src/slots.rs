@@ -0,0 +1,416 @@
+//! Typed accessors to the values of the slots of the intents.
+//!
+//! The values of the slots are the ones of the snips NLU ontology, `FromSlot` converts them to
+//! plain Rust and `chrono` types, failing with a `SlotError` telling apart the missing slots from
+//! the slots holding another kind of value.
+
+use std::error::Error;
+use std::fmt;
+
+use chrono::{DateTime, Duration, FixedOffset};
+pub use snips_nlu_ontology::{AmountOfMoneyValue, SlotValue};
+
+use crate::ontology::{IntentMessage, NluSlot};
+
+/// The errors raised when reading the value of a slot
+#[derive(Debug, Clone, PartialEq)]
+pub enum SlotError {
+    /// The intent has no slot with this name
+    Missing { slot_name: String },
+    /// The slot holds another kind of value than the one expected
+    WrongType {
+        slot_name: String,
+        expected: &'static str,
+        actual: String,
+    },
+    /// The value of the slot has the expected kind but can't be converted, like a malformed date
+    InvalidValue { slot_name: String, reason: String },
+}
+
+pub type SlotResult<T> = Result<T, SlotError>;
+
+/// The bounds of a time interval, a missing bound leaving it open
+pub type TimeInterval = (Option<DateTime<FixedOffset>>, Option<DateTime<FixedOffset>>);
+
+impl fmt::Display for SlotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SlotError::Missing { slot_name } => write!(f, "Missing slot {}", slot_name),
+            SlotError::WrongType {
+                slot_name,
+                expected,
+                actual,
+            } => write!(f, "Slot {} holds a {} value, expected {}", slot_name, actual, expected),
+            SlotError::InvalidValue { slot_name, reason } => {
+                write!(f, "Invalid value of slot {}: {}", slot_name, reason)
+            }
+        }
+    }
+}
+
+impl Error for SlotError {}
+
+/// A type the value of a slot can be converted to
+pub trait FromSlot: Sized {
+    fn from_slot(slot: &NluSlot) -> SlotResult<Self>;
+}
+
+impl NluSlot {
+    /// The value of the slot converted to a `T`
+    pub fn value_as<T: FromSlot>(&self) -> SlotResult<T> {
+        T::from_slot(self)
+    }
+
+    fn wrong_type(&self, expected: &'static str) -> SlotError {
+        SlotError::WrongType {
+            slot_name: self.nlu_slot.slot_name.clone(),
+            expected,
+            actual: kind(&self.nlu_slot.value).into(),
+        }
+    }
+
+    fn invalid_value<S: Into<String>>(&self, reason: S) -> SlotError {
+        SlotError::InvalidValue {
+            slot_name: self.nlu_slot.slot_name.clone(),
+            reason: reason.into(),
+        }
+    }
+
+    fn parse_time(&self, time: &str) -> SlotResult<DateTime<FixedOffset>> {
+        DateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S %:z")
+            .or_else(|_| DateTime::parse_from_rfc3339(time))
+            .map_err(|e| self.invalid_value(format!("{} is not a valid time: {}", time, e)))
+    }
+}
+
+/// The kind of a value, as serialized by the snips NLU ontology
+fn kind(value: &SlotValue) -> &'static str {
+    match value {
+        SlotValue::Custom(_) => "Custom",
+        SlotValue::Number(_) => "Number",
+        SlotValue::Ordinal(_) => "Ordinal",
+        SlotValue::Percentage(_) => "Percentage",
+        SlotValue::InstantTime(_) => "InstantTime",
+        SlotValue::TimeInterval(_) => "TimeInterval",
+        SlotValue::AmountOfMoney(_) => "AmountOfMoney",
+        SlotValue::Temperature(_) => "Temperature",
+        SlotValue::Duration(_) => "Duration",
+        SlotValue::MusicAlbum(_) => "MusicAlbum",
+        SlotValue::MusicArtist(_) => "MusicArtist",
+        SlotValue::MusicTrack(_) => "MusicTrack",
+        SlotValue::City(_) => "City",
+        SlotValue::Country(_) => "Country",
+        SlotValue::Region(_) => "Region",
+    }
+}
+
+impl FromSlot for SlotValue {
+    fn from_slot(slot: &NluSlot) -> SlotResult<Self> {
+        Ok(slot.nlu_slot.value.clone())
+    }
+}
+
+/// The value of the custom entities and of the builtin ones holding a name, like a city
+impl FromSlot for String {
+    fn from_slot(slot: &NluSlot) -> SlotResult<Self> {
+        match &slot.nlu_slot.value {
+            SlotValue::Custom(it)
+            | SlotValue::MusicAlbum(it)
+            | SlotValue::MusicArtist(it)
+            | SlotValue::MusicTrack(it)
+            | SlotValue::City(it)
+            | SlotValue::Country(it)
+            | SlotValue::Region(it) => Ok(it.value.clone()),
+            _ => Err(slot.wrong_type("a string")),
+        }
+    }
+}
+
+/// The value of the numbers, ordinals and percentages
+impl FromSlot for f64 {
+    fn from_slot(slot: &NluSlot) -> SlotResult<Self> {
+        match &slot.nlu_slot.value {
+            SlotValue::Number(it) => Ok(it.value),
+            SlotValue::Percentage(it) => Ok(it.value),
+            SlotValue::Ordinal(it) => Ok(it.value as f64),
+            _ => Err(slot.wrong_type("a number")),
+        }
+    }
+}
+
+/// The value of the ordinals and of the numbers without fractional part
+impl FromSlot for i64 {
+    fn from_slot(slot: &NluSlot) -> SlotResult<Self> {
+        match &slot.nlu_slot.value {
+            SlotValue::Ordinal(it) => Ok(it.value),
+            SlotValue::Number(it) if it.value.fract() == 0. => Ok(it.value as i64),
+            SlotValue::Number(it) => Err(slot.invalid_value(format!("{} is not an integer", it.value))),
+            _ => Err(slot.wrong_type("an integer")),
+        }
+    }
+}
+
+impl FromSlot for DateTime<FixedOffset> {
+    fn from_slot(slot: &NluSlot) -> SlotResult<Self> {
+        match &slot.nlu_slot.value {
+            SlotValue::InstantTime(it) => slot.parse_time(&it.value),
+            _ => Err(slot.wrong_type("an instant time")),
+        }
+    }
+}
+
+impl FromSlot for TimeInterval {
+    fn from_slot(slot: &NluSlot) -> SlotResult<Self> {
+        match &slot.nlu_slot.value {
+            SlotValue::TimeInterval(it) => Ok((
+                it.from.as_ref().map(|it| slot.parse_time(it)).transpose()?,
+                it.to.as_ref().map(|it| slot.parse_time(it)).transpose()?,
+            )),
+            _ => Err(slot.wrong_type("a time interval")),
+        }
+    }
+}
+
+/// The length of a duration, the calendar units being approximated: a month is 30 days, a quarter
+/// 3 months and a year 365 days
+impl FromSlot for Duration {
+    fn from_slot(slot: &NluSlot) -> SlotResult<Self> {
+        match &slot.nlu_slot.value {
+            SlotValue::Duration(it) => {
+                const DAY: i64 = 24 * 3600;
+                let units = [
+                    (it.years, 365 * DAY),
+                    (it.quarters, 90 * DAY),
+                    (it.months, 30 * DAY),
+                    (it.weeks, 7 * DAY),
+                    (it.days, DAY),
+                    (it.hours, 3600),
+                    (it.minutes, 60),
+                    (it.seconds, 1),
+                ];
+                units
+                    .iter()
+                    .try_fold(0i64, |total, (count, seconds)| {
+                        count.checked_mul(*seconds).and_then(|it| total.checked_add(it))
+                    })
+                    .and_then(|seconds| seconds.checked_mul(1000))
+                    .map(Duration::milliseconds)
+                    .ok_or_else(|| slot.invalid_value("the duration is out of range"))
+            }
+            _ => Err(slot.wrong_type("a duration")),
+        }
+    }
+}
+
+impl FromSlot for AmountOfMoneyValue {
+    fn from_slot(slot: &NluSlot) -> SlotResult<Self> {
+        match &slot.nlu_slot.value {
+            SlotValue::AmountOfMoney(it) => Ok(it.clone()),
+            _ => Err(slot.wrong_type("an amount of money")),
+        }
+    }
+}
+
+impl IntentMessage {
+    /// The first slot named `slot_name`, if any
+    pub fn slot(&self, slot_name: &str) -> Option<&NluSlot> {
+        self.slots.iter().find(|it| it.nlu_slot.slot_name == slot_name)
+    }
+
+    /// The slots named `slot_name`, a slot can be filled several times like in "red or blue"
+    pub fn slots_named<'a>(&'a self, slot_name: &'a str) -> impl Iterator<Item = &'a NluSlot> + 'a {
+        self.slots.iter().filter(move |it| it.nlu_slot.slot_name == slot_name)
+    }
+
+    /// The value of the first slot named `slot_name` converted to a `T`
+    pub fn slot_value_as<T: FromSlot>(&self, slot_name: &str) -> SlotResult<T> {
+        self.slot(slot_name)
+            .ok_or_else(|| SlotError::Missing {
+                slot_name: slot_name.into(),
+            })?
+            .value_as()
+    }
+
+    /// The value of the instant time slot named `slot_name`
+    pub fn instant_time(&self, slot_name: &str) -> SlotResult<DateTime<FixedOffset>> {
+        self.slot_value_as(slot_name)
+    }
+
+    /// The bounds of the time interval slot named `slot_name`
+    pub fn time_interval(&self, slot_name: &str) -> SlotResult<TimeInterval> {
+        self.slot_value_as(slot_name)
+    }
+
+    /// The value of the duration slot named `slot_name`
+    pub fn duration(&self, slot_name: &str) -> SlotResult<Duration> {
+        self.slot_value_as(slot_name)
+    }
+
+    /// The value of the amount of money slot named `slot_name`
+    pub fn amount_of_money(&self, slot_name: &str) -> SlotResult<AmountOfMoneyValue> {
+        self.slot_value_as(slot_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::TimeZone;
+    use snips_nlu_ontology::*;
+
+    use crate::ontology::NluIntentClassifierResult;
+
+    fn slot(slot_name: &str, value: SlotValue) -> NluSlot {
+        NluSlot {
+            nlu_slot: Slot {
+                raw_value: "some value".into(),
+                value,
+                range: 0..10,
+                entity: "some entity".into(),
+                slot_name: slot_name.into(),
+                confidence_score: None,
+            },
+        }
+    }
+
+    fn intent(slots: Vec<NluSlot>) -> IntentMessage {
+        IntentMessage {
+            session_id: "some session".into(),
+            custom_data: None,
+            site_id: "kitchen".into(),
+            input: "some input".into(),
+            asr_tokens: None,
+            asr_confidence: None,
+            intent: NluIntentClassifierResult {
+                intent_name: "some intent".into(),
+                confidence_score: 0.9,
+            },
+            slots,
+            alternatives: None,
+        }
+    }
+
+    #[test]
+    fn slots_can_be_found_by_name() {
+        let intent = intent(vec![
+            slot("color", SlotValue::Custom("red".into())),
+            slot("count", SlotValue::Number(NumberValue { value: 2. })),
+            slot("color", SlotValue::Custom("blue".into())),
+        ]);
+        assert_eq!(intent.slot_value_as::<String>("color"), Ok("red".to_string()));
+        let colors: SlotResult<Vec<String>> = intent.slots_named("color").map(NluSlot::value_as).collect();
+        assert_eq!(colors, Ok(vec!["red".to_string(), "blue".to_string()]));
+        assert_eq!(intent.slot_value_as::<f64>("count"), Ok(2.));
+        assert_eq!(intent.slot_value_as::<i64>("count"), Ok(2));
+        assert!(intent.slot("size").is_none());
+    }
+
+    #[test]
+    fn missing_and_wrongly_typed_slots_are_errors() {
+        let intent = intent(vec![
+            slot("color", SlotValue::Custom("red".into())),
+            slot("count", SlotValue::Number(NumberValue { value: 2.5 })),
+        ]);
+        assert_eq!(
+            intent.slot_value_as::<f64>("size"),
+            Err(SlotError::Missing {
+                slot_name: "size".into()
+            })
+        );
+        assert_eq!(
+            intent.slot_value_as::<f64>("color"),
+            Err(SlotError::WrongType {
+                slot_name: "color".into(),
+                expected: "a number",
+                actual: "Custom".into(),
+            })
+        );
+        assert_eq!(
+            intent.slot_value_as::<i64>("count").unwrap_err().to_string(),
+            "Invalid value of slot count: 2.5 is not an integer"
+        );
+    }
+
+    #[test]
+    fn time_slots_are_converted_to_chrono_types() {
+        let intent = intent(vec![
+            slot(
+                "when",
+                SlotValue::InstantTime(InstantTimeValue {
+                    value: "2019-06-15 18:30:00 +02:00".into(),
+                    grain: Grain::Minute,
+                    precision: Precision::Exact,
+                }),
+            ),
+            slot(
+                "period",
+                SlotValue::TimeInterval(TimeIntervalValue {
+                    from: Some("2019-06-15 18:00:00 +02:00".into()),
+                    to: None,
+                }),
+            ),
+            slot(
+                "how_long",
+                SlotValue::Duration(DurationValue {
+                    years: 0,
+                    quarters: 0,
+                    months: 0,
+                    weeks: 1,
+                    days: 1,
+                    hours: 2,
+                    minutes: 0,
+                    seconds: 30,
+                    precision: Precision::Exact,
+                }),
+            ),
+            slot(
+                "forever",
+                SlotValue::Duration(DurationValue {
+                    years: i64::MAX / 2,
+                    quarters: 0,
+                    months: 0,
+                    weeks: 0,
+                    days: 0,
+                    hours: 0,
+                    minutes: 0,
+                    seconds: 0,
+                    precision: Precision::Approximate,
+                }),
+            ),
+            slot(
+                "broken",
+                SlotValue::InstantTime(InstantTimeValue {
+                    value: "tomorrow".into(),
+                    grain: Grain::Day,
+                    precision: Precision::Approximate,
+                }),
+            ),
+        ]);
+        let offset = FixedOffset::east(2 * 3600);
+        assert_eq!(
+            intent.instant_time("when"),
+            Ok(offset.ymd(2019, 6, 15).and_hms(18, 30, 0))
+        );
+        assert_eq!(
+            intent.time_interval("period"),
+            Ok((Some(offset.ymd(2019, 6, 15).and_hms(18, 0, 0)), None))
+        );
+        assert_eq!(
+            intent.duration("how_long"),
+            Ok(Duration::days(8) + Duration::hours(2) + Duration::seconds(30))
+        );
+        assert_eq!(
+            intent.duration("forever").unwrap_err().to_string(),
+            "Invalid value of slot forever: the duration is out of range"
+        );
+        assert!(matches!(
+            intent.instant_time("broken"),
+            Err(SlotError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            intent.amount_of_money("when"),
+            Err(SlotError::WrongType { .. })
+        ));
+    }
+}
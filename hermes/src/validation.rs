@@ -0,0 +1,267 @@
+//! Checks of the invariants of the messages of the ontology that serde can't enforce.
+//!
+//! The handlers can validate the messages they publish and receive according to their
+//! `ValidationMode`. The fields of the errors are named like in the serialized messages, as they
+//! may be reported to components written in other languages.
+
+use std::fmt;
+
+/// An invalid field of a message
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    /// The path of the field, like `tokens[2].confidence`
+    pub field: String,
+    /// Why its value is invalid
+    pub reason: String,
+}
+
+/// All the invalid fields of a message
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ValidationErrors(pub Vec<ValidationError>);
+
+/// A message whose invariants can be checked
+pub trait Validate {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Ok(())
+    }
+}
+
+/// Collects the invalid fields of a message, see the implementations of `Validate` in the ontology
+#[derive(Debug, Default)]
+pub struct Validator {
+    errors: Vec<ValidationError>,
+}
+
+impl Validator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Report `field` as invalid with `reason` unless `valid` is true
+    pub fn check(&mut self, field: &str, valid: bool, reason: &str) -> &mut Self {
+        if !valid {
+            self.errors.push(ValidationError {
+                field: field.into(),
+                reason: reason.into(),
+            })
+        }
+        self
+    }
+
+    pub fn not_empty(&mut self, field: &str, value: &str) -> &mut Self {
+        self.check(field, !value.is_empty(), "must not be empty")
+    }
+
+    /// Check a confidence score or a probability, which must be between 0 and 1
+    pub fn unit_interval(&mut self, field: &str, value: f32) -> &mut Self {
+        self.check(field, (0.0..=1.0).contains(&value), "must be between 0 and 1")
+    }
+
+    /// Check a value contained in the message, its errors being reported under `field`
+    pub fn nested<V: Validate>(&mut self, field: &str, value: &V) -> &mut Self {
+        if let Err(ValidationErrors(errors)) = value.validate() {
+            self.errors.extend(errors.into_iter().map(|error| ValidationError {
+                field: if error.field.starts_with('[') {
+                    format!("{}{}", field, error.field)
+                } else {
+                    format!("{}.{}", field, error.field)
+                },
+                reason: error.reason,
+            }))
+        }
+        self
+    }
+
+    pub fn finish(&mut self) -> Result<(), ValidationErrors> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationErrors(std::mem::take(&mut self.errors)))
+        }
+    }
+}
+
+impl<V: Validate> Validate for Option<V> {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        match self {
+            Some(value) => value.validate(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<V: Validate> Validate for Vec<V> {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut validator = Validator::new();
+        for (index, value) in self.iter().enumerate() {
+            validator.nested(&format!("[{}]", index), value);
+        }
+        validator.finish()
+    }
+}
+
+/// How a handler checks the messages it publishes and receives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ValidationMode {
+    /// The messages are not checked
+    #[default]
+    Off,
+    /// The invalid messages are logged, but still published and received
+    Warn,
+    /// The invalid messages are rejected: publishing one fails, and the ones received are dropped
+    /// and reported on the error channel of the component they relate to
+    Strict,
+}
+
+impl ValidationMode {
+    /// Validate a message, unless the validation is off
+    pub fn check<V: Validate>(self, message: &V) -> Result<(), ValidationErrors> {
+        match self {
+            ValidationMode::Off => Ok(()),
+            ValidationMode::Warn | ValidationMode::Strict => message.validate(),
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.field, self.reason)
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let errors: Vec<String> = self.0.iter().map(ToString::to_string).collect();
+        f.write_str(&errors.join(", "))
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    fn invalid_fields<V: Validate>(message: &V) -> Vec<String> {
+        match message.validate() {
+            Ok(()) => vec![],
+            Err(ValidationErrors(errors)) => errors.into_iter().map(|it| it.field).collect(),
+        }
+    }
+
+    fn token(confidence: f32, range_start: usize, range_end: usize) -> AsrToken {
+        AsrToken {
+            value: "hello".into(),
+            confidence,
+            range_start,
+            range_end,
+            time: AsrDecodingDuration { start: 0.0, end: 1.0 },
+        }
+    }
+
+    #[test]
+    fn nested_errors_are_reported_with_their_path() {
+        let message = NluQueryMessage {
+            input: "hello world".into(),
+            asr_tokens: Some(vec![token(0.5, 0, 5), token(1.5, 6, 5)]),
+            intent_filter: None,
+            id: None,
+            session_id: None,
+            intent_alternatives: None,
+        };
+        assert_eq!(
+            invalid_fields(&message),
+            vec!["asrTokens[1].confidence", "asrTokens[1].rangeEnd"]
+        );
+
+        let message = IntentMessage {
+            session_id: "session".into(),
+            custom_data: None,
+            site_id: "".into(),
+            input: "hello".into(),
+            asr_tokens: Some(vec![vec![], vec![token(0.5, 3, 1)]]),
+            asr_confidence: None,
+            intent: NluIntentClassifierResult {
+                intent_name: "hello".into(),
+                confidence_score: -0.1,
+            },
+            slots: vec![],
+            alternatives: None,
+        };
+        assert_eq!(
+            invalid_fields(&message),
+            vec!["siteId", "asrTokens[1][0].rangeEnd", "intent.confidenceScore"]
+        );
+    }
+
+    #[test]
+    fn alternatives_must_be_ranked_by_decreasing_confidence() {
+        let alternative = |intent_name: Option<&str>, confidence_score| NluIntentAlternative {
+            intent_name: intent_name.map(str::to_string),
+            confidence_score,
+            slots: vec![],
+        };
+        let mut message = NluIntentMessage {
+            id: None,
+            input: "hello".into(),
+            intent: NluIntentClassifierResult {
+                intent_name: "hello".into(),
+                confidence_score: 0.8,
+            },
+            slots: vec![],
+            session_id: None,
+            alternatives: Some(vec![alternative(Some("bye"), 0.5), alternative(None, 0.3)]),
+        };
+        assert!(message.validate().is_ok());
+        message.alternatives = Some(vec![alternative(None, 0.3), alternative(Some(""), 0.5)]);
+        assert_eq!(
+            invalid_fields(&message),
+            vec!["alternatives", "alternatives[1].intentName"]
+        );
+    }
+
+    #[test]
+    fn continue_session_with_slot_requires_a_single_intent() {
+        let mut message = ContinueSessionMessage {
+            session_id: "session".into(),
+            text: "which one?".into(),
+            intent_filter: Some(vec!["a".into(), "b".into()]),
+            custom_data: None,
+            send_intent_not_recognized: false,
+            slot: None,
+        };
+        assert!(message.validate().is_ok());
+        message.slot = Some("color".into());
+        assert_eq!(invalid_fields(&message), vec!["intentFilter"]);
+        message.intent_filter = Some(vec!["a".into()]);
+        assert!(message.validate().is_ok());
+    }
+
+    #[test]
+    fn hotword_detection_must_end_after_it_starts() {
+        let mut message = HotwordDetectedMessage {
+            site_id: "default".into(),
+            model_id: "hey_snips".into(),
+            model_version: None,
+            model_type: None,
+            current_sensitivity: Some(0.5),
+            detection_signal_ms: Some(1200),
+            end_signal_ms: Some(1000),
+        };
+        assert_eq!(invalid_fields(&message), vec!["endSignalMs"]);
+        message.end_signal_ms = None;
+        assert!(message.validate().is_ok());
+    }
+
+    #[test]
+    fn validation_can_be_turned_off() {
+        let message = SiteMessage {
+            site_id: "".into(),
+            session_id: None,
+        };
+        assert!(ValidationMode::Off.check(&message).is_ok());
+        let errors = ValidationMode::Strict.check(&message).unwrap_err();
+        assert_eq!(errors.to_string(), "siteId must not be empty");
+    }
+}
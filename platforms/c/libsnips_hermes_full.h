@@ -75,6 +75,26 @@ typedef enum {
    * The function encountered an error, you can retrieve it using the dedicated function
    */
   SNIPS_RESULT_KO = 1,
+  /**
+   * The handler can't reach the broker
+   */
+  SNIPS_RESULT_NOT_CONNECTED = 2,
+  /**
+   * A message could not be serialized or deserialized
+   */
+  SNIPS_RESULT_SERIALIZATION_ERROR = 3,
+  /**
+   * A thread panicked while holding a lock of the handler
+   */
+  SNIPS_RESULT_POISONED_LOCK = 4,
+  /**
+   * An argument is invalid, like a topic, an option value or a message breaking the ontology
+   */
+  SNIPS_RESULT_INVALID_ARGUMENT = 5,
+  /**
+   * No response to a request was received in time
+   */
+  SNIPS_RESULT_TIMEOUT = 6,
 } SNIPS_RESULT;
 
 typedef enum {
@@ -316,6 +336,29 @@ typedef struct {
   int count;
 } CNluSlotArray;
 
+/**
+ * An alternative resolution of an input
+ */
+typedef struct {
+  /**
+   * Nullable, name of the intent, null for the resolution matching no intent
+   */
+  const char *intent_name;
+  /**
+   * Between 0 and 1
+   */
+  float confidence_score;
+  /**
+   * Nullable
+   */
+  const CNluSlotArray *slots;
+} CNluIntentAlternative;
+
+typedef struct {
+  const CNluIntentAlternative *const *entries;
+  int count;
+} CNluIntentAlternativeArray;
+
 typedef struct {
   const CAsrTokenArray *const *entries;
   int count;
@@ -342,6 +385,10 @@ typedef struct {
    * Note: this value is optional. Any value not in [0,1] should be ignored.
    */
   float asr_confidence;
+  /**
+   * Nullable, the alternative intents by decreasing confidence
+   */
+  const CNluIntentAlternativeArray *alternatives;
 } CIntentMessage;
 
 typedef struct {
@@ -575,6 +622,10 @@ typedef struct {
    * Nullable
    */
   const char *session_id;
+  /**
+   * Nullable, the alternative intents by decreasing confidence
+   */
+  const CNluIntentAlternativeArray *alternatives;
 } CNluIntentMessage;
 
 typedef struct {
@@ -608,6 +659,10 @@ typedef struct {
    * Nullable
    */
   const char *session_id;
+  /**
+   * The number of alternative intents requested, -1 if none
+   */
+  int intent_alternatives;
 } CNluQueryMessage;
 
 typedef struct {
@@ -702,6 +757,29 @@ typedef struct {
   uint64_t patch;
 } CVersionMessage;
 
+typedef struct {
+  /**
+   * A hermes topic, the parts depending on the message like the site id or the intent name can
+   * be anything as the policy applies to all the topics of its kind, e.g.
+   * `hermes/audioServer/+/audioFrame`
+   */
+  const char *topic;
+  /**
+   * The MQTT quality of service: 0 for at most once, 1 for at least once, 2 for exactly once
+   */
+  unsigned char qos;
+  /**
+   * Boolean indicating if the messages are retained by the broker. It is interpreted as a
+   * boolean, 0 meaning false, all other values meaning true
+   */
+  unsigned char retain;
+} CPublishPolicy;
+
+typedef struct {
+  const CPublishPolicy *const *entries;
+  int count;
+} CPublishPolicyArray;
+
 /**
  * A struct representing the configuration of the MQTT client
  */
@@ -743,6 +821,21 @@ typedef struct {
    * interpreted as a boolean, 0 meaning false, all other values meaning true
    */
   unsigned char tls_disable_root_store;
+  /**
+   * How the messages are published on some kinds of topics, overriding the default policies.
+   * Nullable
+   */
+  CPublishPolicyArray *publish_policies;
+  /**
+   * Prefix of all the topics, like `tenant42` to use `tenant42/hermes/tts/say`, for several
+   * assistants to share a broker. Nullable
+   */
+  char *topic_prefix;
+  /**
+   * How the messages are serialized when published: 0 for JSON, 1 for MessagePack, 2 for CBOR.
+   * The messages received are decoded whatever their codec
+   */
+  unsigned char payload_codec;
 } CMqttOptions;
 
 typedef struct {
@@ -18,6 +18,26 @@ typedef enum {
    * The function encountered an error, you can retrieve it using the dedicated function
    */
   SNIPS_RESULT_KO = 1,
+  /**
+   * The handler can't reach the broker
+   */
+  SNIPS_RESULT_NOT_CONNECTED = 2,
+  /**
+   * A message could not be serialized or deserialized
+   */
+  SNIPS_RESULT_SERIALIZATION_ERROR = 3,
+  /**
+   * A thread panicked while holding a lock of the handler
+   */
+  SNIPS_RESULT_POISONED_LOCK = 4,
+  /**
+   * An argument is invalid, like a topic, an option value or a message breaking the ontology
+   */
+  SNIPS_RESULT_INVALID_ARGUMENT = 5,
+  /**
+   * No response to a request was received in time
+   */
+  SNIPS_RESULT_TIMEOUT = 6,
 } SNIPS_RESULT;
 
 typedef struct {
@@ -45,6 +65,29 @@ typedef struct {
   void *user_data;
 } CTtsFacade;
 
+typedef struct {
+  /**
+   * A hermes topic, the parts depending on the message like the site id or the intent name can
+   * be anything as the policy applies to all the topics of its kind, e.g.
+   * `hermes/audioServer/+/audioFrame`
+   */
+  const char *topic;
+  /**
+   * The MQTT quality of service: 0 for at most once, 1 for at least once, 2 for exactly once
+   */
+  unsigned char qos;
+  /**
+   * Boolean indicating if the messages are retained by the broker. It is interpreted as a
+   * boolean, 0 meaning false, all other values meaning true
+   */
+  unsigned char retain;
+} CPublishPolicy;
+
+typedef struct {
+  const CPublishPolicy *const *entries;
+  int count;
+} CPublishPolicyArray;
+
 /**
  * A struct representing the configuration of the MQTT client
  */
@@ -86,8 +129,29 @@ typedef struct {
    * interpreted as a boolean, 0 meaning false, all other values meaning true
    */
   unsigned char tls_disable_root_store;
+  /**
+   * How the messages are published on some kinds of topics, overriding the default policies.
+   * Nullable
+   */
+  CPublishPolicyArray *publish_policies;
+  /**
+   * Prefix of all the topics, like `tenant42` to use `tenant42/hermes/tts/say`, for several
+   * assistants to share a broker. Nullable
+   */
+  char *topic_prefix;
+  /**
+   * How the messages are serialized when published: 0 for JSON, 1 for MessagePack, 2 for CBOR.
+   * The messages received are decoded whatever their codec
+   */
+  unsigned char payload_codec;
 } CMqttOptions;
 
+/**
+ * Unwrap the JSON of the data of a custom data of a session, the result must be freed
+ * with `hermes_drop_custom_data_json`
+ */
+SNIPS_RESULT hermes_decode_custom_data_json(const char *custom_data, const char **data);
+
 SNIPS_RESULT hermes_destroy_mqtt_protocol_handler(CProtocolHandler *handler);
 
 SNIPS_RESULT hermes_dialogue_publish_configure_json(const CDialogueFacade *facade,
@@ -99,6 +163,8 @@ SNIPS_RESULT hermes_dialogue_publish_continue_session_json(const CDialogueFacade
 SNIPS_RESULT hermes_dialogue_publish_end_session_json(const CDialogueFacade *facade,
                                                       const char *message);
 
+SNIPS_RESULT hermes_dialogue_publish_session_state_request_json(const CDialogueFacade *facade);
+
 SNIPS_RESULT hermes_dialogue_publish_start_session_json(const CDialogueFacade *facade,
                                                         const char *message);
 
@@ -121,6 +187,14 @@ SNIPS_RESULT hermes_dialogue_subscribe_session_queued_json(const CDialogueFacade
 SNIPS_RESULT hermes_dialogue_subscribe_session_started_json(const CDialogueFacade *facade,
                                                             void (*handler)(const char*, void*));
 
+SNIPS_RESULT hermes_dialogue_subscribe_session_state_json(const CDialogueFacade *facade,
+                                                          void (*handler)(const char*, void*));
+
+/**
+ * Free a string returned by the custom data functions
+ */
+SNIPS_RESULT hermes_drop_custom_data_json(char *json);
+
 SNIPS_RESULT hermes_drop_dialogue_facade(const CDialogueFacade *cstruct);
 
 SNIPS_RESULT hermes_drop_injection_facade(const CInjectionFacade *cstruct);
@@ -131,6 +205,12 @@ SNIPS_RESULT hermes_drop_tts_facade(const CTtsFacade *cstruct);
 
 SNIPS_RESULT hermes_enable_debug_logs(void);
 
+/**
+ * Wrap the JSON of some data in the envelope of the custom data of the sessions, the
+ * result must be freed with `hermes_drop_custom_data_json`
+ */
+SNIPS_RESULT hermes_encode_custom_data_json(const char *data, const char **custom_data);
+
 /**
  * Used to retrieve the last error that happened in this thread. A function encountered an
  * error if its return type is of type SNIPS_RESULT and it returned SNIPS_RESULT_KO
@@ -44,7 +44,10 @@ int main() {
                                 .tls_ca_path = NULL,
                                 .tls_client_key = NULL,
                                 .tls_client_cert = NULL,
-                                .tls_disable_root_store = 0};
+                                .tls_disable_root_store = 0,
+                                .publish_policies = NULL,
+                                .topic_prefix = NULL,
+                                .payload_codec = 0};
 
   check(hermes_protocol_handler_new_mqtt_with_options(&truc, &options,
                                                       "my user data"));
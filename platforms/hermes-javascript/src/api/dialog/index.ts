@@ -31,6 +31,9 @@ export default class Dialog extends ApiSubset {
         },
         configure: {
             fullEventName: 'hermes_dialogue_publish_configure_json'
+        },
+        session_state_request: {
+            fullEventName: 'hermes_dialogue_publish_session_state_request_json'
         }
     }
     publishMessagesList: DialogTypes.publishMessagesList = undefined as any
@@ -56,6 +59,9 @@ export default class Dialog extends ApiSubset {
         },
         session_started: {
             fullEventName: 'hermes_dialogue_subscribe_session_started_json'
+        },
+        session_state: {
+            fullEventName: 'hermes_dialogue_subscribe_session_state_json'
         }
     }
     subscribeMessagesList: DialogTypes.subscribeMessagesList = undefined as any
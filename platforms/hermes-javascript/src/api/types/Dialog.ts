@@ -7,7 +7,8 @@ import {
     SessionEndedMessage,
     SessionQueuedMessage,
     SessionStartedMessage,
-    DialogueConfigureMessage
+    DialogueConfigureMessage,
+    SessionStateMessage
 } from './messages'
 
 export namespace DialogTypes {
@@ -15,14 +16,16 @@ export namespace DialogTypes {
         start_session: StartSessionMessage,
         continue_session: ContinueSessionMessage,
         end_session: EndSessionMessage,
-        configure: DialogueConfigureMessage
+        configure: DialogueConfigureMessage,
+        session_state_request: null
     }
     export type subscribeMessagesList = {
         intents: IntentMessage,
         intent_not_recognized: IntentNotRecognizedMessage,
         session_ended: SessionEndedMessage,
         session_queued: SessionQueuedMessage,
-        session_started: SessionStartedMessage
+        session_started: SessionStartedMessage,
+        session_state: SessionStateMessage
     } & {
         // Workaround for intents that have a dynamic key
         [key: string]: IntentMessage
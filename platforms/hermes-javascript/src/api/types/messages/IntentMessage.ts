@@ -87,6 +87,12 @@ export type NluSlot<T extends slotType = slotType> = {
         never
 }
 
+export type NluIntentAlternative = {
+    intentName?: string,
+    confidenceScore: number,
+    slots: NluSlot[]
+}
+
 export type IntentMessage = {
     sessionId: string,
     siteId: string,
@@ -109,5 +115,6 @@ export type IntentMessage = {
             }
         }[]?
     ],
-    slots: NluSlot[]
+    slots: NluSlot[],
+    alternatives?: NluIntentAlternative[]
 }
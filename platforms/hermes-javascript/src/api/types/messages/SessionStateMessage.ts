@@ -0,0 +1,15 @@
+export type SessionState = {
+    sessionId: string,
+    customData?: string,
+    intentFilter?: string[],
+    // Null for the queued sessions
+    startTime?: string
+}
+
+export type SessionStateMessage = {
+    sites: {
+        siteId: string,
+        activeSession?: SessionState,
+        queuedSessions: SessionState[]
+    }[]
+}
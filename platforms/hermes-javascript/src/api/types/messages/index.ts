@@ -13,3 +13,4 @@ export * from './InjectionRequestMessage'
 export * from './InjectionStatusMessage'
 export * from './RegisterSoundMessage'
 export * from './DialogueConfigureMessage'
+export * from './SessionStateMessage'
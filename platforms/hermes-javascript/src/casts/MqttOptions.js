@@ -10,10 +10,20 @@ class IntentMessage extends Casteable {
     }
 
     forge() {
-        return super.forge(this.type, {
+        const options = super.forge(this.type, {
             tls_ca_file: tls_ca_file => tls_ca_file && new StringArray(tls_ca_file).forge() || ref.NULL,
             tls_ca_path: tls_ca_path => tls_ca_path && new StringArray(tls_ca_path).forge() || ref.NULL
         })
+        // the default publish policies are used
+        options.publish_policies = ref.NULL
+        if(!this.topic_prefix) {
+            options.topic_prefix = ref.NULL
+        }
+        // payloads are published in JSON unless told otherwise
+        if(!this.payload_codec) {
+            options.payload_codec = 0
+        }
+        return options
     }
 }
 
@@ -34,6 +34,8 @@ module.exports.library = libraryPath => ffi.Library(libraryPath, {
     hermes_dialogue_publish_start_session_json: [ 'int', [ 'void *', 'char *' ]],
     // Configure the dialogue
     hermes_dialogue_publish_configure_json: [ 'int', [ 'void *', 'char *' ]],
+    // Request the state of the sessions active or queued on each site
+    hermes_dialogue_publish_session_state_request_json: [ 'int', [ 'void *' ]],
     // Callback - Subscribe to intents detected
     hermes_dialogue_subscribe_intent_json: [ 'int', [ 'void *', 'char *', 'void *' ]],
     hermes_dialogue_subscribe_intents_json: [ 'int', [ 'void *', 'void *' ]],
@@ -44,6 +46,8 @@ module.exports.library = libraryPath => ffi.Library(libraryPath, {
     hermes_dialogue_subscribe_session_queued_json: [ 'int', [ 'void *', 'void *' ]],
     // Callback - hotword or custom message
     hermes_dialogue_subscribe_session_started_json: [ 'int', [ 'void *', 'void *' ]],
+    // Callback - state of the sessions, as requested
+    hermes_dialogue_subscribe_session_state_json: [ 'int', [ 'void *', 'void *' ]],
 
     /* Injection */
 
@@ -11,6 +11,17 @@ const CStringArray = Struct({
   size: coerce('int')
 })
 
+const CPublishPolicy = Struct({
+  topic: coerce('char *'),
+  qos: coerce('uchar'),
+  retain: coerce('uchar')
+})
+
+const CPublishPolicyArray = Struct({
+  entries: pointer(pointer(CPublishPolicy)),
+  count: coerce('int')
+})
+
 const CMqttOptions = Struct({
   broker_address: coerce('char *'),
   username: coerce('char *'),
@@ -21,10 +32,15 @@ const CMqttOptions = Struct({
   tls_client_key: coerce('char *'),
   tls_client_cert: coerce('char *'),
   tls_disable_root_store: coerce('uchar'),
+  publish_policies: pointer(CPublishPolicyArray),
+  topic_prefix: coerce('char *'),
+  payload_codec: coerce('uchar'),
 })
 
 const misc = {
   CMqttOptions,
+  CPublishPolicy,
+  CPublishPolicyArray,
   CStringArray
 }
 
@@ -127,6 +127,15 @@ it('[dialog] should publish a configure event', () => {
     facadePublication: 'configure'
   })
 })
+it('[dialog] should publish a session state request event', () => {
+  return setupPublisherJsonTest({
+    client,
+    facade: dialog,
+    json: null,
+    hermesTopic: 'hermes/dialogueManager/sessionStateRequest',
+    facadePublication: 'session_state_request'
+  })
+})
 
 // Injection
 
@@ -261,6 +270,16 @@ it('[dialog] should receive and parse an intent not recognized event', () => {
   })
 })
 
+it('[dialog] should receive and parse a session state event', () => {
+  return setupSubscriberJsonTest({
+    client,
+    facade: dialog,
+    json: require('./messages/SessionState.json'),
+    hermesTopic: 'hermes/dialogueManager/sessionState',
+    facadeSubscription: 'session_state'
+  })
+})
+
 it('[dialog] should receive events related to any intent', () => {
   return setupSubscriberJsonTest({
     client,
@@ -16,6 +16,17 @@ class CProtocolHandler(Structure):
     _fields_ = [("handler", c_void_p)]
 
 
+class CPublishPolicy(Structure):
+    _fields_ = [("topic", c_char_p),
+                ("qos", c_uint8),
+                ("retain", c_uint8)]
+
+
+class CPublishPolicyArray(Structure):
+    _fields_ = [("entries", POINTER(POINTER(CPublishPolicy))),
+                ("count", c_int32)]
+
+
 class CMqttOptions(Structure):
     _fields_ = [("broker_address", c_char_p),
                 ("username", c_char_p),
@@ -25,7 +36,10 @@ class CMqttOptions(Structure):
                 ("tls_ca_path", POINTER(CStringArray)),
                 ("tls_client_key", c_char_p),
                 ("tls_client_cert", c_char_p),
-                ("tls_disable_root_store", c_uint8)]
+                ("tls_disable_root_store", c_uint8),
+                ("publish_policies", POINTER(CPublishPolicyArray)),
+                ("topic_prefix", c_char_p),
+                ("payload_codec", c_uint8)]
 
     @classmethod
     def build(cls, broker_address, username, password, tls_hostname, tls_ca_file, tls_ca_path, tls_client_key,